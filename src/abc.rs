@@ -0,0 +1,160 @@
+// Desc: Artificial Bee Colony (Karaboga): employed, onlooker and scout phases
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::comparator::Score;
+use crate::config::Config;
+use crate::convergence::TracePoint;
+use crate::optimizer::{self, Optimizer, Outcome, Tracker};
+use crate::options::RunOptions;
+use crate::sampling;
+
+// A food source: a candidate solution and the cycles since it last improved
+struct Source {
+    x: Vec<f64>,
+    score: Score,
+    trials: usize,
+}
+
+pub struct Abc<'a> {
+    tracker: Tracker<'a>,
+    rng: StdRng,
+    limit: usize, // failed trials after which a scout abandons a source
+    sources: Vec<Source>,
+}
+
+impl<'a> Abc<'a> {
+    // `particles` food sources, so the colony has as many employed bees and
+    // as many onlookers; `limit` defaults to sources * dimensions
+    pub fn new(
+        config: Config,
+        options: &'a RunOptions,
+        limit: Option<usize>,
+    ) -> Result<Self, String> {
+        let limit = limit.unwrap_or(config.particles * config.dimensions);
+        let mut abc = Abc {
+            rng: optimizer::seeded_rng(&config),
+            tracker: Tracker::new(config, options)?,
+            limit,
+            sources: Vec::new(),
+        };
+        for id in 0..abc.tracker.config.particles {
+            let source = abc.random_source(id);
+            abc.sources.push(source);
+        }
+        Ok(abc)
+    }
+
+    fn random_source(&mut self, id: usize) -> Source {
+        let config = &self.tracker.config;
+        let x: Vec<f64> = sampling::point(config, &mut self.rng);
+        let score = self.tracker.evaluate(id, &x);
+        Source {
+            x,
+            score,
+            trials: 0,
+        }
+    }
+
+    // Moves one random coordinate of source `i` relative to another random
+    // source and keeps the move if it improves the source
+    fn explore(&mut self, i: usize, id: usize) {
+        let config = &self.tracker.config;
+        let n = self.sources.len();
+        let k = (i + self.rng.gen_range(1..n)) % n;
+        let pos = self.rng.gen_range(0..config.dimensions);
+        let phi: f64 = self.rng.gen_range(-1.0..=1.0);
+
+        let mut v = self.sources[i].x.clone();
+        let (lower, upper) = config.bounds_of(pos);
+        let moved = v[pos] + phi * (v[pos] - self.sources[k].x[pos]);
+        v[pos] = config.snap(pos, moved.clamp(lower, upper));
+
+        let score = self.tracker.evaluate(id, &v);
+        let source = &mut self.sources[i];
+        if self.tracker.config.comparison.better(score, source.score) {
+            source.x = v;
+            source.score = score;
+            source.trials = 0;
+        } else {
+            source.trials += 1;
+        }
+    }
+
+    // Roulette-wheel choice of a source, weighted by the classic ABC fitness
+    fn choose_source(&mut self) -> usize {
+        let quality: Vec<f64> = self
+            .sources
+            .iter()
+            .map(|s| match s.score.fitness {
+                f if f >= 0.0 => 1.0 / (1.0 + f),
+                f if f < 0.0 => 1.0 - f,
+                _ => 0.0, // NaN
+            })
+            .collect();
+        let total: f64 = quality.iter().sum();
+        if total.partial_cmp(&0.0) != Some(std::cmp::Ordering::Greater) || !total.is_finite() {
+            return self.rng.gen_range(0..self.sources.len());
+        }
+
+        let mut r = self.rng.gen_range(0.0..total);
+        for (i, q) in quality.iter().enumerate() {
+            if r < *q {
+                return i;
+            }
+            r -= q;
+        }
+        self.sources.len() - 1
+    }
+}
+
+impl Optimizer for Abc<'_> {
+    fn step(&mut self) {
+        let n = self.sources.len();
+
+        // employed bees: one neighbour move per source
+        for i in 0..n {
+            self.explore(i, i);
+        }
+
+        // onlookers: neighbour moves on sources chosen by quality
+        for id in n..2 * n {
+            let i = self.choose_source();
+            self.explore(i, id);
+        }
+
+        // scout: the most exhausted source past the limit is abandoned
+        let (worn, trials) = self
+            .sources
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (i, s.trials))
+            .max_by_key(|&(_, trials)| trials)
+            .expect("colony has food sources");
+        if trials > self.limit {
+            self.sources[worn] = self.random_source(2 * n);
+        }
+
+        self.tracker.end_iteration();
+    }
+
+    fn is_finished(&self) -> bool {
+        self.tracker.is_finished()
+    }
+
+    fn stop(&mut self, reason: &'static str) {
+        self.tracker.stop(reason);
+    }
+
+    fn best(&self) -> f64 {
+        self.tracker.best()
+    }
+
+    fn progress(&self) -> TracePoint {
+        self.tracker.progress()
+    }
+
+    fn finish(self: Box<Self>) -> Outcome {
+        self.tracker.finish(&[])
+    }
+}
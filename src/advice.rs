@@ -0,0 +1,149 @@
+// Desc: End-of-run suggestions for PSO runs: rules over the convergence
+// history (best-so-far, diversity, share of the swarm on a bound) that spot
+// the usual problems, a run cut short while still improving, a swarm pinned
+// to the bounds, one that collapsed early or one that never converged, and
+// name the setting to change. The rules are heuristics: each suggestion says
+// what it saw, so the user can judge whether it applies.
+use crate::config::Config;
+use crate::convergence::TracePoint;
+use crate::schedule::Param;
+use crate::topology::Topology;
+
+const TAIL: f64 = 0.1; // share of the run checked for late improvement
+const LATE_IMPROVEMENT: f64 = 1e-3; // relative improvement over the tail that counts
+const SATURATED: f64 = 0.25; // mean share of the swarm on a bound
+const COLLAPSED: f64 = 1e-3; // final diversity relative to the initial one
+const SPREAD: f64 = 0.5; // final diversity relative to the initial one of an unconverged swarm
+const SOLVED: f64 = 1e-6; // distance to a known optimum that needs no advice
+
+pub fn advise(config: &Config, history: &[TracePoint], stopped_early: bool) -> Vec<String> {
+    let mut advice = Vec::new();
+    let (Some(first), Some(last)) = (history.first(), history.last()) else {
+        return advice;
+    };
+    if history.len() < 10 {
+        return advice;
+    }
+    let best: Vec<f64> = history.iter().map(|p| config.internal(p.best)).collect();
+    let end = best[best.len() - 1];
+    let solved = config
+        .optimum()
+        .is_some_and(|optimum| (end - config.internal(optimum)).abs() < SOLVED);
+    let scale = end.abs().max(f64::MIN_POSITIVE);
+
+    // still improving when the iterations ran out
+    let tail = ((history.len() as f64 * TAIL) as usize).max(5);
+    let gain = best[best.len() - 1 - tail] - end;
+    if !stopped_early && !solved && gain.is_finite() && gain / scale > LATE_IMPROVEMENT {
+        advice.push(format!(
+            "Increase iterations: the best still improved by {} over the last {} of {} \
+             iterations",
+            gain,
+            tail,
+            history.len()
+        ));
+    }
+
+    let saturation = history.iter().map(|p| p.at_bounds).sum::<f64>() / history.len() as f64;
+    if saturation > SATURATED {
+        let seen = format!(
+            "on average {:.0}% of the swarm sat on a bound",
+            saturation * 100.0
+        );
+        if config.vmax.is_none() && config.vmax_fraction.is_none() {
+            advice.push(format!(
+                "Limit the velocities (e.g. vmax_fraction = 0.2): {}",
+                seen
+            ));
+        } else {
+            advice.push(format!(
+                "Reduce vmax or use boundary = {{ type = \"reflect\" }}: {}",
+                seen
+            ));
+        }
+    }
+
+    // the iteration from which on the best is within the improvement that
+    // counts of its final value
+    let settled = best
+        .iter()
+        .position(|&b| b - end <= LATE_IMPROVEMENT * scale)
+        .unwrap_or(0);
+    let late = settled < history.len() / 2;
+    if !solved && late && first.diversity > 0.0 {
+        let relative = last.diversity / first.diversity;
+        if relative < COLLAPSED {
+            let seen = format!(
+                "the swarm collapsed and the best improved by less than {}% after iteration {} of {}",
+                LATE_IMPROVEMENT * 100.0,
+                history[settled].iteration,
+                history.len()
+            );
+            match config.topology {
+                Topology::Global => advice.push(format!(
+                    "Switch to a ring topology (topology = {{ type = \"ring\" }}), which keeps \
+                     the swarm diverse for longer: {}",
+                    seen
+                )),
+                _ => advice.push(format!(
+                    "Use more particles, or stagnation escapes ([escape]): {}",
+                    seen
+                )),
+            }
+        } else if relative > SPREAD && matches!(config.w, Param::Value(w) if w >= 0.8) {
+            advice.push(format!(
+                "Lower the inertia (w = 0.7, or a linear decay from 0.9 to 0.4): the swarm \
+                 is still {:.0}% as spread out as at the start and the best improved by less \
+                 than {}% after iteration {}",
+                relative * 100.0,
+                LATE_IMPROVEMENT * 100.0,
+                history[settled].iteration
+            ));
+        }
+    }
+    advice
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(best: impl Fn(usize) -> f64, diversity: impl Fn(usize) -> f64) -> Vec<TracePoint> {
+        (0..100)
+            .map(|i| TracePoint {
+                iteration: i,
+                best: best(i),
+                diversity: diversity(i),
+                ..TracePoint::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn the_rules_name_what_they_saw() {
+        let mut config = Config::default();
+        config.function = crate::functions::Function::Quadratic;
+
+        let improving = history(|i| 100.0 / (i + 1) as f64, |_| 1.0);
+        let advice = advise(&config, &improving, false);
+        assert!(advice[0].starts_with("Increase iterations"), "{:?}", advice);
+        assert!(advise(&config, &improving, true).is_empty());
+
+        let collapsed = history(
+            |i| if i < 20 { 50.0 - i as f64 } else { 30.0 },
+            |i| {
+                if i < 20 {
+                    1.0
+                } else {
+                    1e-9
+                }
+            },
+        );
+        let advice = advise(&config, &collapsed, false);
+        assert_eq!(advice.len(), 1);
+        assert!(advice[0].contains("ring topology"), "{:?}", advice);
+
+        // at the known optimum there is nothing to suggest
+        assert!(advise(&config, &history(|_| 0.0, |_| 1e-9), false).is_empty());
+    }
+}
@@ -0,0 +1,638 @@
+// Desc: Embedding API: configure with PsoBuilder, run any objective with Pso
+//
+//   let config = PsoBuilder::new().dimensions(5).bounds(-10.0, 10.0).build()?;
+//   let result = Pso::new(config).run(|x| x.iter().map(|v| v * v).sum());
+//   println!("{} at {:?}", result.best_fitness, result.best_position);
+//
+// `Pso::spawn` starts the run on a thread of its own instead; the returned
+// `PsoHandle` stops it, waits for its result and offers a `SwarmView` to
+// poll the swarm while it runs.
+//
+// Any `ObjectiveFunction` can be minimized (or maximized, with `maximize`),
+// including `Function::Ackley` and the plain functions in `benchmarks`.
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::comparator::Comparator;
+use crate::config::{Config, MultiFidelity, Objective, Stagnation, TargetFitness};
+use crate::constraint::{AdaptivePenalty, Constraint};
+use crate::convergence::TracePoint;
+use crate::functions::ObjectiveFunction;
+use crate::handle::CancelToken;
+use crate::inertia::InertiaSchedule;
+use crate::optimizer::{self, Outcome};
+use crate::options::RunOptions;
+use crate::schedule::{Param, Schedule};
+use crate::view::SwarmView;
+
+// How constraint violations count when personal and global bests are updated
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum ConstraintHandling {
+    #[default]
+    StaticPenalty, // fitness + penalty_factor * violation
+    AdaptivePenalty(AdaptivePenalty), // the same with a penalty factor that adapts
+    FeasibilityRules, // Deb's rules: feasible first, then lower violation, then fitness
+    StochasticRanking {
+        pf: f64,
+    }, // Deb's rules for the bests, the swarm ranked by fitness with probability pf
+}
+
+// Parameters of a run; built and checked by `PsoBuilder`
+#[derive(Clone, Debug)]
+pub struct PsoConfig {
+    pub particles: usize,
+    pub iterations: usize,
+    pub dimensions: usize,
+    pub objective: Objective,
+    pub c1: f64,
+    pub c2: f64,
+    pub inertia: f64,
+    pub inertia_schedule: Option<InertiaSchedule>, // replaces `inertia` when set
+    pub vmax_fraction: Option<f64>, // velocity limit as a fraction of the search range
+    pub lower_bound: f64,
+    pub upper_bound: f64,
+    pub bounds: Option<Vec<(f64, f64)>>, // per dimension, in place of the shared bounds
+    pub penalty_factor: f64,             // added to the fitness of a particle clamped to a bound
+    pub constraint_handling: ConstraintHandling,
+    pub seed: Option<u64>, // for reproducible runs; from entropy when None
+    // Early stops, checked after every iteration; any of them ends the run
+    pub target_fitness: Option<f64>, // stop once gbest is within `target_tolerance` of it
+    pub target_tolerance: f64,
+    pub stagnation_iterations: Option<usize>, // stop after this long without improvement
+    pub stagnation_min_improvement: f64,      // smallest decrease that counts as one
+    pub max_time: Option<Duration>,
+    pub max_evaluations: Option<usize>,
+    // Multi-fidelity screening: every move is evaluated at this level of
+    // `ObjectiveFunction::evaluate_fidelity` first, and accurately only when
+    // it predicts a personal best improvement
+    pub fidelity_level: Option<usize>,
+}
+
+impl Default for PsoConfig {
+    // The binary's built-in defaults
+    fn default() -> Self {
+        PsoConfig::from(&Config::default())
+    }
+}
+
+impl From<&Config> for PsoConfig {
+    fn from(config: &Config) -> Self {
+        let at_start = |p: &Param| p.value(0, config.iterations);
+        PsoConfig {
+            particles: config.particles,
+            iterations: config.iterations,
+            dimensions: config.dimensions,
+            objective: config.objective,
+            c1: at_start(&config.c1),
+            c2: at_start(&config.c2),
+            inertia: at_start(&config.w),
+            inertia_schedule: config.inertia_schedule.clone(),
+            vmax_fraction: config.vmax_fraction,
+            lower_bound: config.lower_bound,
+            upper_bound: config.upper_bound,
+            bounds: config.bounds.clone(),
+            penalty_factor: at_start(&config.penalty_factor),
+            constraint_handling: match (&config.comparison, &config.adaptive_penalty) {
+                (Comparator::FeasibilityFirst, _) => ConstraintHandling::FeasibilityRules,
+                (&Comparator::StochasticRanking { pf }, _) => {
+                    ConstraintHandling::StochasticRanking { pf }
+                }
+                (_, Some(adaptive)) => ConstraintHandling::AdaptivePenalty(adaptive.clone()),
+                _ => ConstraintHandling::StaticPenalty,
+            },
+            seed: config.seed,
+            target_fitness: config.stop.target.as_ref().map(|t| t.fitness),
+            target_tolerance: config.stop.target.as_ref().map_or(0.0, |t| t.tolerance),
+            stagnation_iterations: config.stop.stagnation.as_ref().map(|s| s.iterations),
+            stagnation_min_improvement: config
+                .stop
+                .stagnation
+                .as_ref()
+                .map_or(0.0, |s| s.min_improvement),
+            max_time: config.stop.max_seconds.map(Duration::from_secs_f64),
+            max_evaluations: config.stop.max_evaluations,
+            fidelity_level: config.multi_fidelity.as_ref().map(|mf| mf.components),
+        }
+    }
+}
+
+impl PsoConfig {
+    pub(crate) fn to_config(&self) -> Config {
+        let mut config = Config::default();
+        config.particles = self.particles;
+        config.iterations = self.iterations;
+        config.dimensions = self.dimensions;
+        config.objective = self.objective;
+        config.c1 = Param::Value(self.c1);
+        config.c2 = Param::Value(self.c2);
+        config.w = Param::Value(self.inertia);
+        config.inertia_schedule = self.inertia_schedule.clone();
+        config.vmax_fraction = self.vmax_fraction;
+        config.lower_bound = self.lower_bound;
+        config.upper_bound = self.upper_bound;
+        if let Some(bounds) = self.bounds.as_ref().filter(|b| !b.is_empty()) {
+            config.lower_bound = bounds.iter().map(|b| b.0).fold(f64::INFINITY, f64::min);
+            config.upper_bound = bounds.iter().map(|b| b.1).fold(f64::NEG_INFINITY, f64::max);
+        }
+        config.bounds = self.bounds.clone();
+        config.penalty_factor = Param::Value(self.penalty_factor);
+        match &self.constraint_handling {
+            ConstraintHandling::StaticPenalty => {}
+            ConstraintHandling::AdaptivePenalty(adaptive) => {
+                config.adaptive_penalty = Some(adaptive.clone())
+            }
+            ConstraintHandling::FeasibilityRules => {
+                config.comparison = Comparator::FeasibilityFirst
+            }
+            &ConstraintHandling::StochasticRanking { pf } => {
+                config.comparison = Comparator::StochasticRanking { pf }
+            }
+        }
+        config.seed = self.seed;
+        config.stop.target = self.target_fitness.map(|fitness| TargetFitness {
+            fitness,
+            tolerance: self.target_tolerance,
+        });
+        config.stop.stagnation = self.stagnation_iterations.map(|iterations| Stagnation {
+            iterations,
+            min_improvement: self.stagnation_min_improvement,
+        });
+        config.stop.max_seconds = self.max_time.map(|t| t.as_secs_f64());
+        config.stop.max_evaluations = self.max_evaluations;
+        config.multi_fidelity = self.fidelity_level.map(|components| MultiFidelity {
+            components,
+            low_cost: 0.0,
+        });
+        config
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PsoBuilder {
+    config: PsoConfig,
+}
+
+impl PsoBuilder {
+    pub fn new() -> Self {
+        PsoBuilder::default()
+    }
+
+    pub fn particles(mut self, particles: usize) -> Self {
+        self.config.particles = particles;
+        self
+    }
+
+    pub fn iterations(mut self, iterations: usize) -> Self {
+        self.config.iterations = iterations;
+        self
+    }
+
+    pub fn dimensions(mut self, dimensions: usize) -> Self {
+        self.config.dimensions = dimensions;
+        self
+    }
+
+    // The objective is maximized instead of minimized
+    pub fn maximize(mut self) -> Self {
+        self.config.objective = Objective::Maximize;
+        self
+    }
+
+    pub fn c1(mut self, c1: f64) -> Self {
+        self.config.c1 = c1;
+        self
+    }
+
+    pub fn c2(mut self, c2: f64) -> Self {
+        self.config.c2 = c2;
+        self
+    }
+
+    pub fn inertia(mut self, w: f64) -> Self {
+        self.config.inertia = w;
+        self
+    }
+
+    pub fn inertia_schedule(mut self, schedule: InertiaSchedule) -> Self {
+        self.config.inertia_schedule = Some(schedule);
+        self
+    }
+
+    pub fn vmax_fraction(mut self, fraction: f64) -> Self {
+        self.config.vmax_fraction = Some(fraction);
+        self
+    }
+
+    pub fn bounds(mut self, lower: f64, upper: f64) -> Self {
+        self.config.lower_bound = lower;
+        self.config.upper_bound = upper;
+        self
+    }
+
+    // One [lower, upper] range per dimension; also sets the dimensions
+    pub fn dimension_bounds(mut self, bounds: Vec<(f64, f64)>) -> Self {
+        self.config.dimensions = bounds.len();
+        self.config.bounds = Some(bounds);
+        self
+    }
+
+    pub fn penalty_factor(mut self, penalty: f64) -> Self {
+        self.config.penalty_factor = penalty;
+        self
+    }
+
+    pub fn constraint_handling(mut self, handling: ConstraintHandling) -> Self {
+        self.config.constraint_handling = handling;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.config.seed = Some(seed);
+        self
+    }
+
+    pub fn target_fitness(mut self, fitness: f64, tolerance: f64) -> Self {
+        self.config.target_fitness = Some(fitness);
+        self.config.target_tolerance = tolerance;
+        self
+    }
+
+    pub fn stagnation(mut self, iterations: usize, min_improvement: f64) -> Self {
+        self.config.stagnation_iterations = Some(iterations);
+        self.config.stagnation_min_improvement = min_improvement;
+        self
+    }
+
+    pub fn max_time(mut self, limit: Duration) -> Self {
+        self.config.max_time = Some(limit);
+        self
+    }
+
+    pub fn max_evaluations(mut self, limit: usize) -> Self {
+        self.config.max_evaluations = Some(limit);
+        self
+    }
+
+    pub fn multi_fidelity(mut self, level: usize) -> Self {
+        self.config.fidelity_level = Some(level);
+        self
+    }
+
+    // Checks the parameters with the same rules as a config file
+    pub fn build(self) -> Result<PsoConfig, String> {
+        self.config.to_config().validate()?;
+        Ok(self.config)
+    }
+}
+
+// Outcome of `Pso::run`
+#[derive(Clone, Debug)]
+pub struct PsoResult {
+    pub best_position: Vec<f64>,
+    pub best_fitness: f64,
+    pub history: Vec<f64>,      // gbest after every iteration
+    pub trace: Vec<TracePoint>, // the full state after every iteration
+    pub violation: f64,         // total constraint violation at best_position, 0 when feasible
+    pub evaluations: usize,
+    pub stopped: Option<&'static str>, // stop condition that ended the run early, if any
+}
+
+// Called with the state after every iteration
+pub type IterationObserver = Arc<dyn Fn(&TracePoint) + Send + Sync>;
+
+pub struct Pso {
+    config: Config,
+    observers: Vec<IterationObserver>,
+}
+
+impl Pso {
+    pub fn new(config: PsoConfig) -> Self {
+        let mut config = config.to_config();
+        config.prepare();
+        Pso {
+            config,
+            observers: Vec::new(),
+        }
+    }
+
+    // Calls `observer` after every iteration, e.g. to watch diversity and
+    // velocities live
+    pub fn on_iteration(mut self, observer: impl Fn(&TracePoint) + Send + Sync + 'static) -> Self {
+        self.observers.push(Arc::new(observer));
+        self
+    }
+
+    // Adds a constraint the solution must satisfy besides the bounds
+    pub fn constraint(mut self, constraint: impl Constraint + 'static) -> Self {
+        self.config.constraints.push(Arc::new(constraint));
+        self
+    }
+
+    // Minimizes (or maximizes) `objective` over the configured box, without
+    // console or file output
+    pub fn run(&self, objective: impl ObjectiveFunction + 'static) -> PsoResult {
+        let mut config = self.config.clone();
+        config.objective_fn = Some(Arc::new(objective));
+        execute(config, &self.observers, &CancelToken::default())
+    }
+
+    // Like `run`, on a background thread; the handle stops the run and
+    // waits for its result
+    pub fn spawn(&self, objective: impl ObjectiveFunction + 'static) -> PsoHandle {
+        let view = SwarmView::new();
+        let mut config = self.config.clone();
+        config.objective_fn = Some(Arc::new(objective));
+        config.view = Some(view.clone());
+        let observers = self.observers.clone();
+        let token = CancelToken::default();
+        let cancel = token.clone();
+        let thread = std::thread::spawn(move || execute(config, &observers, &cancel));
+        PsoHandle {
+            token,
+            view,
+            thread,
+        }
+    }
+}
+
+// A run started by `Pso::spawn`
+pub struct PsoHandle {
+    token: CancelToken,
+    view: SwarmView,
+    thread: JoinHandle<PsoResult>,
+}
+
+impl PsoHandle {
+    // Asks the run to stop after its current iteration
+    pub fn stop(&self) {
+        self.token.cancel();
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    // The swarm as of its latest iteration, for other threads to poll
+    pub fn view(&self) -> SwarmView {
+        self.view.clone()
+    }
+
+    // Waits for the run to end and returns its result; a stopped run returns
+    // the best found so far, with `stopped` set to "cancelled"
+    pub fn join(self) -> PsoResult {
+        self.thread.join().expect("optimization thread panicked")
+    }
+}
+
+// Runs `config` until it finishes or `token` is cancelled
+fn execute(config: Config, observers: &[IterationObserver], token: &CancelToken) -> PsoResult {
+    let options = RunOptions::default();
+    let mut history = Vec::with_capacity(config.iterations);
+    // a headless swarm opens no files, ports or threads, so it always starts
+    let mut run = optimizer::create(config.clone(), &options).expect("headless runs start");
+    while !run.is_finished() {
+        if token.is_cancelled() {
+            run.stop("cancelled");
+            break;
+        }
+        run.step();
+        let point = run.progress();
+        history.push(point.best);
+        for observer in observers {
+            observer(&point);
+        }
+    }
+    let Outcome {
+        best_x,
+        best,
+        evaluations,
+        stopped,
+        history: trace,
+        ..
+    } = run.finish();
+
+    PsoResult {
+        violation: config.constraint_violation(&best_x),
+        best_position: best_x,
+        best_fitness: config.reported(best),
+        history,
+        trace,
+        evaluations,
+        stopped,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn seeded_builder_runs_are_reproducible() {
+        let config = PsoBuilder::new()
+            .dimensions(4)
+            .iterations(50)
+            .seed(9)
+            .build()
+            .unwrap();
+        let sphere = |x: &[f64]| x.iter().map(|v| v * v).sum::<f64>();
+        let (a, b) = (
+            Pso::new(config.clone()).run(sphere),
+            Pso::new(config).run(sphere),
+        );
+        assert_eq!(a.history, b.history);
+        assert_eq!(a.best_position, b.best_position);
+        assert_eq!(a.history.len(), 50);
+    }
+
+    #[test]
+    fn spawned_runs_stop_with_the_best_so_far() {
+        let sphere = |x: &[f64]| x.iter().map(|v| v * v).sum::<f64>();
+        let builder = PsoBuilder::new().dimensions(4).seed(2);
+
+        let config = builder.clone().iterations(50).build().unwrap();
+        let joined = Pso::new(config.clone()).spawn(sphere).join();
+        assert_eq!(joined.history, Pso::new(config).run(sphere).history);
+
+        // the run stops long before its budget once the observer has seen it
+        // go, whenever the stop lands
+        let started = Arc::new(std::sync::Barrier::new(2));
+        let seen = Arc::clone(&started);
+        let once = std::sync::Once::new();
+        let handle = Pso::new(builder.iterations(1_000_000).build().unwrap())
+            .on_iteration(move |_| {
+                once.call_once(|| {
+                    seen.wait();
+                })
+            })
+            .spawn(sphere);
+        started.wait();
+        handle.stop();
+        let stopped = handle.join();
+        assert_eq!(stopped.stopped, Some("cancelled"));
+        assert!(!stopped.history.is_empty() && stopped.history.len() < 1_000_000);
+        assert_eq!(stopped.best_fitness, *stopped.history.last().unwrap());
+    }
+
+    #[test]
+    fn a_spawned_run_can_be_watched() {
+        let sphere = |x: &[f64]| x.iter().map(|v| v * v).sum::<f64>();
+        let config = PsoBuilder::new()
+            .particles(12)
+            .dimensions(3)
+            .iterations(1_000_000)
+            .seed(4)
+            .build()
+            .unwrap();
+        let handle = Pso::new(config).spawn(sphere);
+        let view = handle.view();
+        while view.version() == 0 {
+            std::thread::yield_now();
+        }
+        let snapshot = view.latest();
+        assert_eq!(snapshot.positions.len(), 12);
+        assert_eq!(snapshot.gbest, sphere(&snapshot.gbest_x));
+        handle.stop();
+        let result = handle.join();
+        assert!(view.latest().finished);
+        assert_eq!(view.latest().gbest, result.best_fitness);
+    }
+
+    #[test]
+    fn stop_conditions_end_the_run_and_are_reported() {
+        let sphere = |x: &[f64]| x.iter().map(|v| v * v).sum::<f64>();
+        let builder = PsoBuilder::new().dimensions(4).iterations(500).seed(3);
+
+        let target =
+            Pso::new(builder.clone().target_fitness(0.0, 1e-3).build().unwrap()).run(sphere);
+        assert_eq!(target.stopped, Some("target"));
+        assert!(target.best_fitness <= 1e-3);
+        assert!(target.history.len() < 500);
+
+        let limited = Pso::new(builder.clone().max_evaluations(1000).build().unwrap()).run(sphere);
+        assert_eq!(limited.stopped, Some("max_evaluations"));
+        assert!(limited.evaluations >= 1000 && limited.evaluations < 1100);
+
+        let stagnant = Pso::new(builder.clone().stagnation(5, 1.0).build().unwrap()).run(sphere);
+        assert_eq!(stagnant.stopped, Some("stagnation"));
+
+        let full = Pso::new(builder.build().unwrap()).run(sphere);
+        assert_eq!(full.stopped, None);
+        assert_eq!(full.history.len(), 500);
+    }
+
+    #[test]
+    fn every_constraint_handling_finds_the_constrained_minimum() {
+        // min x0^2 + x1^2 subject to x0 + x1 >= 1: 0.5 at (0.5, 0.5)
+        let sphere = |x: &[f64]| x.iter().map(|v| v * v).sum::<f64>();
+        let adaptive = AdaptivePenalty {
+            window: 5,
+            increase: 2.0,
+            decrease: 1.5,
+        };
+        for handling in [
+            ConstraintHandling::StaticPenalty,
+            ConstraintHandling::AdaptivePenalty(adaptive),
+            ConstraintHandling::FeasibilityRules,
+            ConstraintHandling::StochasticRanking { pf: 0.45 },
+        ] {
+            let config = PsoBuilder::new()
+                .dimensions(2)
+                .iterations(300)
+                .seed(11)
+                .penalty_factor(100.0)
+                .constraint_handling(handling.clone())
+                .build()
+                .unwrap();
+            let result = Pso::new(config)
+                .constraint(|x: &[f64]| 1.0 - x[0] - x[1])
+                .run(sphere);
+            // penalties may leave the best marginally infeasible, Deb's rules do not
+            assert!(result.violation < 1e-3, "{:?}", handling);
+            if !matches!(
+                handling,
+                ConstraintHandling::StaticPenalty | ConstraintHandling::AdaptivePenalty(_)
+            ) {
+                assert_eq!(result.violation, 0.0);
+            }
+            assert!((result.best_fitness - 0.5).abs() < 1e-3, "{:?}", handling);
+        }
+    }
+
+    #[test]
+    fn observers_see_every_iteration_with_the_swarm_metrics() {
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = Arc::clone(&seen);
+        let config = PsoBuilder::new().iterations(40).seed(8).build().unwrap();
+        let result = Pso::new(config)
+            .on_iteration(move |point| log.lock().unwrap().push(*point))
+            .run(|x: &[f64]| x.iter().map(|v| v * v).sum());
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 40);
+        assert_eq!(result.trace.len(), 40);
+        for (point, traced) in seen.iter().zip(&result.trace) {
+            assert_eq!(point.iteration, traced.iteration);
+            assert!(point.mean_velocity.unwrap() <= point.max_velocity.unwrap());
+            assert!(point.mean_pbest.unwrap() >= point.best);
+            assert!((0.0..=1.0).contains(&point.at_bounds));
+        }
+    }
+
+    #[test]
+    fn maximization_reports_the_objective_within_each_dimensions_bounds() {
+        // max 10 - (x0 - 3)^2 - (x1 + 1)^2 with x1 in [0.5, 2]: 7.75 at (3, 0.5)
+        let config = PsoBuilder::new()
+            .dimension_bounds(vec![(-10.0, 10.0), (0.5, 2.0)])
+            .maximize()
+            .iterations(200)
+            .seed(4)
+            .build()
+            .unwrap();
+        let result =
+            Pso::new(config).run(|x: &[f64]| 10.0 - (x[0] - 3.0).powi(2) - (x[1] + 1.0).powi(2));
+
+        assert!((result.best_fitness - 7.75).abs() < 1e-6);
+        assert!(result.history.windows(2).all(|w| w[1] >= w[0]));
+        assert!((0.5..=2.0).contains(&result.best_position[1]));
+        assert!((result.best_position[0] - 3.0).abs() < 1e-3);
+    }
+
+    // The sphere, with its first `level` terms scaled up as the cheap
+    // approximation; counts the low-fidelity evaluations
+    struct Layered(Arc<AtomicUsize>);
+
+    impl ObjectiveFunction for Layered {
+        fn evaluate(&self, x: &[f64]) -> f64 {
+            x.iter().map(|v| v * v).sum()
+        }
+
+        fn evaluate_fidelity(&self, x: &[f64], level: usize) -> Option<f64> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            let head: f64 = x[..level].iter().map(|v| v * v).sum();
+            Some(head * x.len() as f64 / level as f64)
+        }
+    }
+
+    #[test]
+    fn multi_fidelity_runs_screen_with_the_objectives_own_low_fidelity() {
+        let builder = PsoBuilder::new()
+            .particles(10)
+            .dimensions(4)
+            .iterations(30)
+            .seed(8)
+            .multi_fidelity(2);
+        let accurate = 10 * 31; // the initial swarm and every move
+
+        let low = Arc::new(AtomicUsize::new(0));
+        let screened = Pso::new(builder.clone().build().unwrap()).run(Layered(low.clone()));
+        assert!(low.load(Ordering::Relaxed) > 0);
+        assert!(screened.evaluations < accurate, "{}", screened.evaluations);
+
+        // without a low fidelity every particle is evaluated accurately
+        let sphere = |x: &[f64]| x.iter().map(|v| v * v).sum::<f64>();
+        let plain = Pso::new(builder.build().unwrap()).run(sphere);
+        assert_eq!(plain.evaluations, accurate);
+    }
+}
@@ -0,0 +1,86 @@
+// Desc: External archive of elite solutions found during a run
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::comparator::{Comparator, Score};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EliteArchive {
+    capacity: usize,
+    comparator: Comparator,
+    entries: Vec<(Vec<f64>, Score)>, // (position, score), best first
+}
+
+impl EliteArchive {
+    pub fn new(capacity: usize, comparator: Comparator) -> Self {
+        EliteArchive {
+            capacity,
+            comparator,
+            entries: Vec::with_capacity(capacity + 1),
+        }
+    }
+
+    // Adds a solution if it beats the worst member; identical positions are kept once
+    pub fn offer(&mut self, x: &[f64], score: Score) {
+        if self.entries.len() == self.capacity
+            && self
+                .entries
+                .last()
+                .is_some_and(|(_, worst)| !self.comparator.better(score, *worst))
+        {
+            return;
+        }
+        if self.entries.iter().any(|(e, _)| e.as_slice() == x) {
+            return;
+        }
+
+        let at = self
+            .entries
+            .partition_point(|(_, s)| self.comparator.ordering(*s, score).is_le());
+        self.entries.insert(at, (x.to_vec(), score));
+        self.entries.truncate(self.capacity);
+    }
+
+    // Re-measures the members' violations, e.g. at a new equality band; a
+    // penalizing comparator moves their fitness along by `penalty` per unit
+    pub fn restate(&mut self, violation: impl Fn(&[f64]) -> f64, penalty: f64) {
+        let penalizes = self.comparator.penalizes();
+        for (x, score) in &mut self.entries {
+            let now = violation(x);
+            if penalizes && score.violation.is_finite() && now.is_finite() {
+                score.fitness += penalty * (now - score.violation);
+            }
+            score.violation = now;
+        }
+        let comparator = &self.comparator;
+        self.entries
+            .sort_by(|(_, a), (_, b)| comparator.ordering(*a, *b));
+    }
+
+    // Orders the members by `comparator` from now on, e.g. at a new epsilon level
+    pub fn rerank(&mut self, comparator: &Comparator) {
+        self.comparator = comparator.clone();
+        let comparator = &self.comparator;
+        self.entries
+            .sort_by(|(_, a), (_, b)| comparator.ordering(*a, *b));
+    }
+
+    pub fn random(&self, rng: &mut impl Rng) -> Option<&[f64]> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        Some(&self.entries[rng.gen_range(0..self.entries.len())].0)
+    }
+
+    pub fn positions(&self) -> Vec<&[f64]> {
+        self.entries.iter().map(|(x, _)| x.as_slice()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn worst(&self) -> Option<f64> {
+        self.entries.last().map(|(_, s)| s.fitness)
+    }
+}
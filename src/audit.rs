@@ -0,0 +1,187 @@
+// Desc: Append-only audit log of every objective evaluation, chained by SHA-256
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use sha2::{Digest, Sha256};
+
+use crate::sink::Sink;
+
+const HEADER: [&str; 7] = [
+    "sequence",
+    "timestamp",
+    "evaluator",
+    "particle",
+    "fitness",
+    "point",
+    "hash",
+];
+const GENESIS: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+// Each record's hash covers the previous record's hash and its own fields, so
+// editing, removing or reordering records breaks the chain from there on.
+fn chain_hash(previous: &str, fields: &[String]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(previous.as_bytes());
+    for field in fields {
+        hasher.update(b",");
+        hasher.update(field.as_bytes());
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+pub struct AuditLog {
+    writer: csv::Writer<Sink>,
+    evaluator: String, // recorded with every evaluation
+    sequence: u64,
+    last_hash: String,
+}
+
+impl AuditLog {
+    // Opens the log for appending; an existing log is verified and continued
+    pub fn open(path: &str, evaluator: &str) -> Result<Self, String> {
+        let exists = std::fs::metadata(path).is_ok_and(|m| m.len() > 0);
+        let (sequence, last_hash) = if exists {
+            verify(path)?
+        } else {
+            (0, GENESIS.to_string())
+        };
+
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(Sink::append(path));
+        if !exists {
+            writer
+                .write_record(HEADER)
+                .map_err(|e| format!("cannot write audit log {}: {}", path, e))?;
+        }
+
+        Ok(AuditLog {
+            writer,
+            evaluator: evaluator.to_string(),
+            sequence,
+            last_hash,
+        })
+    }
+
+    pub fn record(&mut self, particle: Option<usize>, x: &[f64], fitness: f64) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |d| d.as_secs_f64());
+        let point: Vec<String> = x.iter().map(|xi| format!("{:?}", xi)).collect();
+
+        self.sequence += 1;
+        let mut fields = vec![
+            self.sequence.to_string(),
+            format!("{:.6}", timestamp),
+            self.evaluator.clone(),
+            particle.map_or(String::new(), |p| p.to_string()),
+            format!("{:?}", fitness),
+            point.join(";"),
+        ];
+        self.last_hash = chain_hash(&self.last_hash, &fields);
+        fields.push(self.last_hash.clone());
+
+        self.writer
+            .write_record(&fields)
+            .expect("Error writing audit log");
+    }
+}
+
+impl Drop for AuditLog {
+    fn drop(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
+
+// Checks the hash chain of an audit log. Returns the last sequence number and
+// hash, or the first record that does not match.
+pub fn verify(path: &str) -> Result<(u64, String), String> {
+    let mut reader =
+        csv::Reader::from_path(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+
+    let mut sequence = 0;
+    let mut last_hash = GENESIS.to_string();
+    for record in reader.records() {
+        let record = record.map_err(|e| format!("malformed audit log {}: {}", path, e))?;
+        let fields: Vec<String> = record.iter().map(str::to_string).collect();
+        let Some((hash, data)) = fields.split_last().filter(|(_, d)| d.len() == 6) else {
+            return Err(format!(
+                "malformed audit record after sequence {}",
+                sequence
+            ));
+        };
+
+        let expected_sequence = (sequence + 1).to_string();
+        if data[0] != expected_sequence || chain_hash(&last_hash, data) != *hash {
+            return Err(format!(
+                "audit chain broken at record {} of {}",
+                expected_sequence, path
+            ));
+        }
+        sequence += 1;
+        last_hash = hash.clone();
+    }
+    Ok((sequence, last_hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A log of three evaluations by `evaluator`, as its lines
+    fn written(name: &str, evaluator: &str) -> (String, Vec<String>) {
+        let path = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_file(&path);
+        let path = path.to_str().unwrap().to_string();
+        let mut log = AuditLog::open(&path, evaluator).unwrap();
+        for i in 0..3 {
+            log.record(Some(i), &[i as f64, 0.5], i as f64 * 2.0);
+        }
+        drop(log);
+        let lines = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect();
+        (path, lines)
+    }
+
+    #[test]
+    fn an_untouched_chain_verifies_and_names_its_evaluator() {
+        let (path, lines) = written("pso_audit_untouched.csv", "command ./objective --fast");
+        let (sequence, last) = verify(&path).unwrap();
+        assert_eq!(sequence, 3);
+        assert!(lines[3].ends_with(&last));
+        assert!(lines[1..]
+            .iter()
+            .all(|line| line.split(',').nth(2) == Some("command ./objective --fast")));
+
+        // continuing the log keeps the chain
+        let mut log = AuditLog::open(&path, "local rastrigin").unwrap();
+        log.record(None, &[1.0], 1.0);
+        drop(log);
+        assert_eq!(verify(&path).unwrap().0, 4);
+    }
+
+    #[test]
+    fn an_edited_record_breaks_the_chain() {
+        let (path, mut lines) = written("pso_audit_edited.csv", "local sphere");
+        lines[2] = lines[2].replacen(",2.0,", ",1.0,", 1);
+        std::fs::write(&path, lines.join("\n") + "\n").unwrap();
+        let error = verify(&path).unwrap_err();
+        assert!(error.contains("broken at record 2"), "{}", error);
+        assert!(AuditLog::open(&path, "local sphere").is_err());
+    }
+
+    #[test]
+    fn a_removed_record_breaks_the_chain() {
+        let (path, mut lines) = written("pso_audit_removed.csv", "local sphere");
+        lines.remove(2);
+        std::fs::write(&path, lines.join("\n") + "\n").unwrap();
+        let error = verify(&path).unwrap_err();
+        assert!(error.contains("broken at record 2"), "{}", error);
+    }
+}
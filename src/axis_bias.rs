@@ -0,0 +1,66 @@
+// Desc: `axis-bias` experiment: standard vs rotated function, to detect coordinate-aligned search
+
+use crate::config::Config;
+use crate::optimizer;
+use crate::options::RunOptions;
+use crate::stats;
+
+// Final gbest of `runs` headless runs of `config`
+fn final_fitness(config: &Config, runs: usize) -> Result<Vec<f64>, String> {
+    let options = RunOptions::default();
+
+    (0..runs)
+        .map(|_| {
+            let mut run = optimizer::create(config.clone(), &options)?;
+            while !run.is_finished() {
+                run.step();
+            }
+            Ok(run.finish().best)
+        })
+        .collect()
+}
+
+pub fn run(mut config: Config, runs: usize, threshold: f64, seed: u64) -> Result<(), String> {
+    if runs < 2 || threshold.partial_cmp(&1.0) != Some(std::cmp::Ordering::Greater) {
+        return Err("axis-bias needs at least 2 runs and a threshold above 1".to_string());
+    }
+
+    config.rotation_seed = None;
+    config.prepare();
+    let standard = final_fitness(&config, runs)?;
+
+    config.rotation_seed = Some(seed);
+    config.validate()?;
+    config.prepare();
+    let rotated = final_fitness(&config, runs)?;
+
+    let (standard_median, rotated_median) = (stats::median(&standard), stats::median(&rotated));
+    let ratio = rotated_median / standard_median;
+
+    println!(
+        "Axis-bias test on {} ({} dimensions, {} runs each):",
+        config.objective_name(),
+        config.dimensions,
+        runs
+    );
+    println!(
+        "  Standard: median {}, mean {}",
+        standard_median,
+        stats::mean(&standard)
+    );
+    println!(
+        "  Rotated (seed {}): median {}, mean {}",
+        seed,
+        rotated_median,
+        stats::mean(&rotated)
+    );
+    println!("  Degradation ratio (rotated / standard median): {}", ratio);
+    if ratio > threshold {
+        println!(
+            "  Warning: results are {:.1}x worse once rotated; this variant exploits \
+             coordinate alignment",
+            ratio
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,209 @@
+// Desc: `bands` subcommand: per-step median and quantile bands of best-so-far across runs
+use clap::ValueEnum;
+
+use crate::config::Objective;
+use crate::convergence;
+use crate::stats;
+use crate::trajectory::Trajectory;
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Axis {
+    /// Per-iteration gbest logs (`iteration,gbest`, the main CSV output) or
+    /// convergence traces
+    Iteration,
+    /// Improvement logs (`iteration,evaluations,gbest,...`, from --improvements)
+    /// or convergence traces
+    Evaluations,
+}
+
+impl Axis {
+    fn name(self) -> &'static str {
+        match self {
+            Axis::Iteration => "iteration",
+            Axis::Evaluations => "evaluations",
+        }
+    }
+
+    // Columns holding the alignment key and the fitness
+    fn columns(self) -> (usize, usize) {
+        match self {
+            Axis::Iteration => (0, 1),
+            Axis::Evaluations => (1, 2),
+        }
+    }
+
+    // The same in a convergence trace (`algorithm,run,iteration,evaluations,best,...`)
+    fn trace_columns(self) -> (usize, usize) {
+        match self {
+            Axis::Iteration => (2, 4),
+            Axis::Evaluations => (3, 4),
+        }
+    }
+}
+
+// Parses a band given in percent, e.g. `25-75`
+pub fn parse_band(s: &str) -> Result<(f64, f64), String> {
+    let band = s.split_once('-').and_then(|(lo, hi)| {
+        let (lo, hi): (f64, f64) = (lo.trim().parse().ok()?, hi.trim().parse().ok()?);
+        (0.0 <= lo && lo < hi && hi <= 100.0).then_some((lo, hi))
+    });
+    band.ok_or_else(|| format!("expected LO-HI percentages with LO < HI, got `{}`", s))
+}
+
+// Splits a history file into runs: the key column starting over (going down)
+// marks the beginning of another run appended to the same file, as does a
+// change of the algorithm and run columns of a convergence trace.
+fn read_runs(path: &str, axis: Axis) -> Result<Vec<Vec<(u64, f64)>>, String> {
+    let trajectory = Trajectory::open(path)?;
+    let is_trace = trajectory
+        .header()
+        .is_some_and(|header| header == convergence::COLUMNS.join(","));
+    let (key_col, value_col) = if is_trace {
+        axis.trace_columns()
+    } else {
+        axis.columns()
+    };
+
+    let mut runs: Vec<Vec<(u64, f64)>> = Vec::new();
+    let mut current_run: Option<Vec<String>> = None;
+    trajectory.rows(|fields| {
+        if is_trace {
+            let run = fields.get(..2);
+            let same = run.is_some_and(|run| current_run.as_deref().is_some_and(|c| c == run));
+            if let Some(run) = run.filter(|_| !same) {
+                current_run = Some(run.iter().map(|s| s.to_string()).collect());
+                runs.push(Vec::new());
+            }
+        }
+        let parsed = fields
+            .get(key_col)
+            .and_then(|k| k.trim().parse::<u64>().ok())
+            .zip(
+                fields
+                    .get(value_col)
+                    .and_then(|v| v.trim().parse::<f64>().ok()),
+            );
+        let Some((key, value)) = parsed else {
+            return; // header or blank line
+        };
+
+        match runs.last_mut() {
+            Some(run) if run.last().is_none_or(|&(last, _)| key > last) => run.push((key, value)),
+            _ => runs.push(vec![(key, value)]),
+        }
+    })?;
+    runs.retain(|run| !run.is_empty());
+    Ok(runs)
+}
+
+// Every key recorded by any run, with the sorted best-so-far of the runs
+// that have logged something by then; a run's best is carried forward past
+// its last record
+fn best_so_far(runs: &[Vec<(u64, f64)>], objective: Objective) -> Vec<(u64, Vec<f64>)> {
+    let mut keys: Vec<u64> = runs.iter().flatten().map(|&(key, _)| key).collect();
+    keys.sort_unstable();
+    keys.dedup();
+
+    let mut cursors = vec![0; runs.len()];
+    let mut best = vec![objective.sign() * f64::INFINITY; runs.len()];
+    keys.into_iter()
+        .map(|key| {
+            let mut values = Vec::with_capacity(runs.len());
+            for (r, history) in runs.iter().enumerate() {
+                while cursors[r] < history.len() && history[cursors[r]].0 <= key {
+                    best[r] = objective.best(best[r], history[cursors[r]].1);
+                    cursors[r] += 1;
+                }
+                // a run that has not logged anything yet does not count
+                if cursors[r] > 0 {
+                    values.push(best[r]);
+                }
+            }
+            values.sort_by(f64::total_cmp);
+            (key, values)
+        })
+        .collect()
+}
+
+pub fn run(
+    files: &[String],
+    axis: Axis,
+    bands: &[(f64, f64)],
+    objective: Objective,
+    output: &str,
+) -> Result<(), String> {
+    let mut runs = Vec::new();
+    for path in files {
+        runs.extend(read_runs(path, axis)?);
+    }
+    if runs.is_empty() {
+        return Err("no run histories found".to_string());
+    }
+
+    let mut writer =
+        csv::Writer::from_path(output).map_err(|e| format!("cannot create {}: {}", output, e))?;
+    let mut header = vec![
+        axis.name().to_string(),
+        "runs".to_string(),
+        "median".to_string(),
+    ];
+    for (lo, hi) in bands {
+        header.push(format!("q{}", lo));
+        header.push(format!("q{}", hi));
+    }
+    writer.write_record(&header).map_err(|e| e.to_string())?;
+
+    let rows = best_so_far(&runs, objective);
+    for (key, values) in &rows {
+        let mut record = vec![
+            key.to_string(),
+            values.len().to_string(),
+            stats::quantile_sorted(values, 0.5).to_string(),
+        ];
+        for (lo, hi) in bands {
+            record.push(stats::quantile_sorted(values, lo / 100.0).to_string());
+            record.push(stats::quantile_sorted(values, hi / 100.0).to_string());
+        }
+        writer.write_record(&record).map_err(|e| e.to_string())?;
+    }
+    writer.flush().map_err(|e| e.to_string())?;
+
+    println!(
+        "Wrote {} {} row(s) over {} run(s) to {}",
+        rows.len(),
+        axis.name(),
+        runs.len(),
+        output
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finished_runs_carry_their_last_best_forward() {
+        // the second run ends after step 1 and starts out logging at step 1
+        let runs = vec![vec![(0, 8.0), (1, 6.0), (2, 7.0), (3, 2.0)], vec![(1, 4.0)]];
+        let rows = best_so_far(&runs, Objective::Minimize);
+        let keys: Vec<u64> = rows.iter().map(|&(key, _)| key).collect();
+        assert_eq!(keys, [0, 1, 2, 3]);
+        assert_eq!(rows[0].1, [8.0]);
+        assert_eq!(rows[1].1, [4.0, 6.0]);
+        // a worse value does not undo the best so far
+        assert_eq!(rows[2].1, [4.0, 6.0]);
+        assert_eq!(rows[3].1, [2.0, 4.0]);
+    }
+
+    #[test]
+    fn maximized_histories_keep_their_running_maximum() {
+        let runs = vec![vec![(0, 1.0), (1, 5.0), (2, 9.0)]];
+        let medians: Vec<f64> = best_so_far(&runs, Objective::Maximize)
+            .iter()
+            .map(|(_, values)| stats::quantile_sorted(values, 0.5))
+            .collect();
+        assert_eq!(medians, [1.0, 5.0, 9.0]);
+        assert_eq!(best_so_far(&runs, Objective::Minimize)[2].1, [1.0]);
+    }
+}
@@ -0,0 +1,496 @@
+// Desc: Experiment manifests: export to SLURM array jobs and collect the results
+//
+// A manifest names an experiment, a base config, variants overriding parts
+// of it and a number of replicates:
+//
+//   {
+//     "name": "inertia",
+//     "config": { "iterations": 500 },
+//     "variants": { "w07": { "w": 0.7 }, "w09": { "w": 0.9 } },
+//     "runs": 30,
+//     "seed": 1,
+//     "sbatch": ["--time=00:10:00"]
+//   }
+//
+// Replicate r of every variant runs with seed `seed + r`, so variants are
+// compared on the same random streams. The exported tree is
+//
+//   <output>/job.slurm                 array job, one task per line of tasks.txt
+//   <output>/tasks.txt                 task ids, `<variant>/<replicate>`
+//   <output>/runs/<task>/config.json   the task's config
+//   <output>/runs/<task>/result.txt    final gbest, written when the task completes
+//
+// `collect` accepts any tree of such run directories.
+//
+// Executed locally, a manifest skips every task whose config (seed included)
+// already has a completed result anywhere in the tree, so an interrupted
+// batch resumes where it stopped.
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::config::{Config, Objective};
+use crate::handle::CancelToken;
+use crate::options::RunOptions;
+use crate::{multirun, stats};
+
+const RESULT_FILE: &str = "result.txt";
+const BASE_VARIANT: &str = "base"; // the only variant when none are listed
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    name: String,
+    #[serde(default)]
+    config: serde_json::Map<String, Value>,
+    #[serde(default)]
+    variants: BTreeMap<String, Value>,
+    #[serde(default = "default_runs")]
+    runs: usize,
+    #[serde(default)]
+    seed: u64,
+    // directory of the exported tree, relative to the manifest; defaults to `name`
+    output: Option<String>,
+    // extra `#SBATCH` options, e.g. "--time=00:10:00"
+    #[serde(default)]
+    sbatch: Vec<String>,
+}
+
+fn default_runs() -> usize {
+    10
+}
+
+pub struct Task {
+    pub id: String,
+    pub config: Value,
+}
+
+// Deep merge: objects are merged key by key, anything else replaces
+fn merge(base: &mut Value, overrides: &Value) {
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                merge(base.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+        (base, overrides) => *base = overrides.clone(),
+    }
+}
+
+fn is_safe_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+        && name != "."
+        && name != ".."
+}
+
+impl Manifest {
+    pub fn load(path: &str) -> Result<Manifest, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read manifest {}: {}", path, e))?;
+        let manifest: Manifest =
+            serde_json::from_str(&text).map_err(|e| format!("invalid manifest {}: {}", path, e))?;
+        if manifest.runs == 0 {
+            return Err(format!(
+                "invalid manifest {}: runs must be at least 1",
+                path
+            ));
+        }
+        if let Some(name) = manifest.variants.keys().find(|n| !is_safe_name(n)) {
+            return Err(format!(
+                "invalid manifest {}: variant name `{}` may only use letters, digits, - _ .",
+                path, name
+            ));
+        }
+        Ok(manifest)
+    }
+
+    // Where the exported tree goes, next to the manifest
+    pub fn output_dir(&self, manifest_path: &str) -> PathBuf {
+        let dir = Path::new(manifest_path)
+            .parent()
+            .unwrap_or_else(|| Path::new(""));
+        dir.join(self.output.as_deref().unwrap_or(&self.name))
+    }
+
+    // Every (variant, replicate) with its full config, validated
+    pub fn tasks(&self) -> Result<Vec<Task>, String> {
+        let mut variants = self.variants.clone();
+        if variants.is_empty() {
+            variants.insert(BASE_VARIANT.to_string(), Value::Object(Default::default()));
+        }
+
+        let mut tasks = Vec::new();
+        for (variant, overrides) in &variants {
+            let mut config = Value::Object(self.config.clone());
+            merge(&mut config, overrides);
+            Config::from_value(&config)
+                .map_err(|e| format!("variant `{}`: invalid config: {}", variant, e))?;
+            for replicate in 0..self.runs {
+                let mut config = config.clone();
+                config["seed"] = Value::from(self.seed + replicate as u64);
+                tasks.push(Task {
+                    id: format!("{}/{}", variant, replicate),
+                    config,
+                });
+            }
+        }
+        Ok(tasks)
+    }
+}
+
+fn write(path: &Path, contents: &str) -> Result<(), String> {
+    std::fs::write(path, contents).map_err(|e| format!("cannot write {}: {}", path.display(), e))
+}
+
+fn create_dir(path: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(path).map_err(|e| format!("cannot create {}: {}", path.display(), e))
+}
+
+// Content address of a task: a hash of its config, which includes the seed.
+// serde_json orders object keys, so equal configs serialize alike.
+fn key_of(config: &Value) -> String {
+    Sha256::digest(config.to_string().as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn read_config(dir: &Path) -> Option<Value> {
+    let text = std::fs::read_to_string(dir.join("config.json")).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+// Completed results under `out`, by the content address of their config
+fn completed(out: &Path) -> BTreeMap<String, PathBuf> {
+    let mut runs = Vec::new();
+    if out.is_dir() {
+        run_dirs(out, &mut runs);
+    }
+    runs.into_iter()
+        .filter(|run| read_result(&run.join(RESULT_FILE)).is_ok())
+        .filter_map(|run| read_config(&run).map(|c| (key_of(&c), run.join(RESULT_FILE))))
+        .collect()
+}
+
+// Writes the task configs and the task list under `out`. A result left by a
+// different config of the same task is removed.
+fn write_tasks(out: &Path, tasks: &[Task]) -> Result<(), String> {
+    let mut list = String::new();
+    for task in tasks {
+        let dir = out.join("runs").join(&task.id);
+        create_dir(&dir)?;
+        if read_config(&dir).is_some_and(|old| key_of(&old) != key_of(&task.config)) {
+            let _ = std::fs::remove_file(dir.join(RESULT_FILE));
+        }
+        let json = serde_json::to_string_pretty(&task.config).expect("Error serializing config");
+        write(&dir.join("config.json"), &json)?;
+        list.push_str(&task.id);
+        list.push('\n');
+    }
+    write(&out.join("tasks.txt"), &list)
+}
+
+// Runs the tasks of a manifest on this machine, skipping those already
+// completed (or `force`d to rerun everything), until `token` is cancelled
+pub fn execute(
+    manifest_path: &str,
+    jobs: usize,
+    force: bool,
+    token: &CancelToken,
+) -> Result<(), String> {
+    let manifest = Manifest::load(manifest_path)?;
+    let tasks = manifest.tasks()?;
+    let out = manifest.output_dir(manifest_path);
+    let index = if force {
+        BTreeMap::new()
+    } else {
+        completed(&out)
+    };
+    write_tasks(&out, &tasks)?;
+
+    let (mut done, mut reused) = (0, 0);
+    let mut pending = Vec::new();
+    for task in &tasks {
+        let result = out.join("runs").join(&task.id).join(RESULT_FILE);
+        match index.get(&key_of(&task.config)) {
+            Some(_) if !force && read_result(&result).is_ok() => done += 1,
+            // the same (config, seed) ran under another task id
+            Some(other) => {
+                std::fs::copy(other, &result)
+                    .map_err(|e| format!("cannot copy {}: {}", other.display(), e))?;
+                reused += 1;
+            }
+            None => {
+                let _ = std::fs::remove_file(&result);
+                let config = Config::from_value(&task.config)
+                    .map_err(|e| format!("task {}: invalid config: {}", task.id, e))?;
+                pending.push((task.id.clone(), config, result));
+            }
+        }
+    }
+    println!(
+        "{}: {} task(s), {} already completed, {} reused from identical runs, {} to run",
+        manifest.name,
+        tasks.len(),
+        done,
+        reused,
+        pending.len()
+    );
+
+    let options = RunOptions::default();
+    let labelled: Vec<(String, Config)> = pending
+        .iter()
+        .map(|(id, config, _)| (format!("Task {}", id), config.clone()))
+        .collect();
+    let results = multirun::run_all(&labelled, &options, jobs, token, |i, record| {
+        let path = &pending[i].2;
+        if let Err(e) = multirun::save_results(&path.to_string_lossy(), &[record.best]) {
+            eprintln!("Warning: {}", e);
+        }
+    });
+
+    if token.is_cancelled() {
+        let finished = results.iter().flatten().count();
+        println!(
+            "Cancelled: rerun the same command to resume ({} of {} task(s) left)",
+            pending.len() - finished,
+            pending.len()
+        );
+    }
+    println!("Aggregate with: pso_rastrigin collect {}", out.display());
+    Ok(())
+}
+
+// Writes the task configs, the task list and an array job script that runs
+// task $SLURM_ARRAY_TASK_ID with this binary
+pub fn export_slurm(manifest_path: &str) -> Result<(), String> {
+    let manifest = Manifest::load(manifest_path)?;
+    let tasks = manifest.tasks()?;
+
+    let out = manifest.output_dir(manifest_path);
+    create_dir(&out.join("logs"))?;
+    let out = out
+        .canonicalize()
+        .map_err(|e| format!("cannot resolve {}: {}", out.display(), e))?;
+    write_tasks(&out, &tasks)?;
+
+    let exe = std::env::current_exe()
+        .map_err(|e| format!("cannot locate the pso_rastrigin binary: {}", e))?;
+    let mut script = String::new();
+    writeln!(script, "#!/bin/bash").unwrap();
+    writeln!(script, "#SBATCH --job-name={}", manifest.name).unwrap();
+    writeln!(script, "#SBATCH --array=0-{}", tasks.len() - 1).unwrap();
+    writeln!(script, "#SBATCH --output={}/logs/%A_%a.out", out.display()).unwrap();
+    for option in &manifest.sbatch {
+        writeln!(script, "#SBATCH {}", option).unwrap();
+    }
+    writeln!(script, "set -euo pipefail").unwrap();
+    writeln!(
+        script,
+        "TASK=$(sed -n \"$((SLURM_ARRAY_TASK_ID + 1))p\" \"{}/tasks.txt\")",
+        out.display()
+    )
+    .unwrap();
+    writeln!(script, "cd \"{}/runs/$TASK\"", out.display()).unwrap();
+    writeln!(
+        script,
+        "exec \"{}\" --config config.json --results {}",
+        exe.display(),
+        RESULT_FILE
+    )
+    .unwrap();
+    let job = out.join("job.slurm");
+    write(&job, &script)?;
+
+    println!(
+        "Exported {} task(s) of {} to {}",
+        tasks.len(),
+        manifest.name,
+        out.display()
+    );
+    println!("Submit with: sbatch {}", job.display());
+    println!(
+        "Then aggregate with: pso_rastrigin collect {}",
+        out.display()
+    );
+    Ok(())
+}
+
+// Run directories under `dir`: those holding a task config or a result
+fn run_dirs(dir: &Path, found: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        eprintln!("Warning: cannot read {}, skipped", dir.display());
+        return;
+    };
+    let mut children: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+    children.sort();
+    if dir.join("config.json").is_file() || dir.join(RESULT_FILE).is_file() {
+        found.push(dir.to_path_buf());
+    }
+    for child in children.into_iter().filter(|c| c.is_dir()) {
+        run_dirs(&child, found);
+    }
+}
+
+// The variant a run belongs to: its parent directory relative to the root,
+// without the `runs` level of an exported tree
+fn variant_of(root: &Path, run: &Path) -> String {
+    let parent = run.parent().unwrap_or(run);
+    let relative = parent.strip_prefix(root).unwrap_or(parent);
+    let relative = relative.strip_prefix("runs").unwrap_or(relative);
+    match relative.to_str() {
+        Some("") | None => BASE_VARIANT.to_string(),
+        Some(name) => name.to_string(),
+    }
+}
+
+fn read_result(path: &Path) -> Result<f64, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut values = text.lines().filter(|l| !l.trim().is_empty());
+    let value: f64 = match (values.next(), values.next()) {
+        (Some(line), None) => line.trim().parse().map_err(|_| "not a number")?,
+        _ => return Err("expected exactly one value".to_string()),
+    };
+    if !value.is_finite() {
+        return Err(format!("non-finite result {}", value));
+    }
+    Ok(value)
+}
+
+// Per-variant results found in a tree, with the number of runs skipped
+#[derive(Default)]
+struct Collected {
+    values: Vec<f64>,
+    skipped: usize,
+    objective: Option<Objective>, // of the runs' configs; minimized without one
+}
+
+// Walks a results tree, skipping incomplete and corrupt runs with a warning,
+// and prints (and optionally writes as CSV) one statistics row per variant
+pub fn collect(dir: &str, output: Option<&str>) -> Result<(), String> {
+    let root = Path::new(dir);
+    if !root.is_dir() {
+        return Err(format!("{} is not a directory", dir));
+    }
+
+    let mut runs = Vec::new();
+    run_dirs(root, &mut runs);
+    let mut variants: BTreeMap<String, Collected> = BTreeMap::new();
+    for run in &runs {
+        let collected = variants.entry(variant_of(root, run)).or_default();
+        let path = run.join(RESULT_FILE);
+        if !path.is_file() {
+            eprintln!(
+                "Warning: {} is incomplete (no {}), skipped",
+                run.display(),
+                RESULT_FILE
+            );
+            collected.skipped += 1;
+            continue;
+        }
+        // the direction decides which end of the results is the best
+        let objective = read_config(run)
+            .and_then(|c| Config::from_value(&c).ok())
+            .map(|c| c.objective);
+        if let (Some(seen), Some(objective)) = (collected.objective, objective) {
+            if seen != objective {
+                return Err(format!(
+                    "variant {} mixes minimized and maximized runs",
+                    variant_of(root, run)
+                ));
+            }
+        }
+        collected.objective = collected.objective.or(objective);
+        match read_result(&path) {
+            Ok(value) => collected.values.push(value),
+            Err(e) => {
+                eprintln!("Warning: {} is corrupt ({}), skipped", path.display(), e);
+                collected.skipped += 1;
+            }
+        }
+    }
+
+    // tasks that never started leave no directory behind
+    if let Ok(list) = std::fs::read_to_string(root.join("tasks.txt")) {
+        for id in list.lines().filter(|l| !l.is_empty()) {
+            let run = root.join("runs").join(id);
+            if !run.is_dir() {
+                eprintln!("Warning: task {} has no run directory, skipped", id);
+                variants.entry(variant_of(root, &run)).or_default().skipped += 1;
+            }
+        }
+    }
+
+    let completed: usize = variants.values().map(|c| c.values.len()).sum();
+    if completed == 0 {
+        return Err(format!("no completed runs under {}", dir));
+    }
+
+    let mut rng = rand::thread_rng();
+    let mut table = csv::Writer::from_writer(Vec::new());
+    table
+        .write_record([
+            "variant",
+            "runs",
+            "skipped",
+            "mean",
+            "mean_ci_low",
+            "mean_ci_high",
+            "median",
+            "median_ci_low",
+            "median_ci_high",
+            "std",
+            "best",
+            "worst",
+        ])
+        .expect("Error writing table");
+    println!(
+        "{:<16} {:>5} {:>7} {:>14} {:>14} {:>14} {:>14} {:>14}",
+        "variant", "runs", "skipped", "mean", "median", "std", "best", "worst"
+    );
+    for (variant, collected) in &variants {
+        if collected.values.is_empty() {
+            println!(
+                "{:<16} {:>5} {:>7}   (no completed runs)",
+                variant, 0, collected.skipped
+            );
+            continue;
+        }
+        let s = stats::Summary::of(&collected.values, 10000, 0.95, &mut rng);
+        let s = s.toward(collected.objective.unwrap_or_default());
+        println!(
+            "{:<16} {:>5} {:>7} {:>14.6e} {:>14.6e} {:>14.6e} {:>14.6e} {:>14.6e}",
+            variant, s.runs, collected.skipped, s.mean, s.median, s.std, s.best, s.worst
+        );
+        let row = [
+            variant.clone(),
+            s.runs.to_string(),
+            collected.skipped.to_string(),
+            s.mean.to_string(),
+            s.mean_ci.0.to_string(),
+            s.mean_ci.1.to_string(),
+            s.median.to_string(),
+            s.median_ci.0.to_string(),
+            s.median_ci.1.to_string(),
+            s.std.to_string(),
+            s.best.to_string(),
+            s.worst.to_string(),
+        ];
+        table.write_record(&row).expect("Error writing table");
+    }
+    let skipped: usize = variants.values().map(|c| c.skipped).sum();
+    println!("\n{} completed run(s), {} skipped", completed, skipped);
+
+    if let Some(path) = output {
+        let bytes = table.into_inner().expect("Error writing table");
+        std::fs::write(path, bytes).map_err(|e| format!("cannot write {}: {}", path, e))?;
+    }
+    Ok(())
+}
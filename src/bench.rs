@@ -0,0 +1,278 @@
+// Desc: Benchmark history (`bench run`, `bench history`): the benchmark
+// suite, every built-in function on its canonical domain with seeded runs,
+// stored one JSON line per suite run and tagged with the crate version and
+// commit; the latest entry is checked for quality and speed regressions
+// against a baseline entry. Seeded runs repeat exactly, so any change of the
+// final fitness comes from the code (or the configuration), not from chance.
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Config, Objective};
+use crate::experiment::ExperimentSummary;
+use crate::functions::Function;
+use crate::handle::CancelToken;
+use crate::multirun;
+use crate::options::RunOptions;
+
+const QUALITY_FLOOR: f64 = 1e-8; // fitness differences below this are never a regression
+
+// One suite run
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Entry {
+    pub version: String,
+    pub commit: String,
+    pub timestamp: u64, // seconds since the Unix epoch
+    pub config: BTreeMap<String, String>,
+    pub results: Vec<SuiteResult>,
+}
+
+// The runs of one suite function
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SuiteResult {
+    pub function: String,
+    pub dimensions: usize,
+    pub runs: usize,
+    pub median: f64,
+    pub mean: f64,
+    pub best: f64,
+    pub mean_evaluations: f64,
+    pub seconds: f64, // wall-clock time of all the function's runs
+}
+
+// How far the latest entry may fall behind the baseline before it counts as
+// a regression: relatively to the baseline's median fitness and time
+#[derive(Clone, Copy, Debug)]
+pub struct Tolerances {
+    pub quality: f64,
+    pub time: f64,
+}
+
+// Runs the suite with `base`'s algorithm and parameters, appends the entry to
+// `path` and compares it with the baseline; whether there was no regression
+pub fn run(
+    base: &Config,
+    runs: usize,
+    jobs: Option<u64>,
+    commit: Option<&str>,
+    path: &str,
+    baseline: Option<&str>,
+    tolerances: Tolerances,
+) -> Result<bool, String> {
+    if runs == 0 {
+        return Err("the suite needs at least 1 run per function".to_string());
+    }
+    let mut results = Vec::new();
+    for &function in Function::value_variants() {
+        let mut config = base.clone();
+        let defaults = function.defaults();
+        config.function = function;
+        config.objective = Objective::Minimize;
+        config.lower_bound = defaults.lower_bound;
+        config.upper_bound = defaults.upper_bound;
+        config.dimensions = defaults.dimensions;
+        config.bounds = None;
+        config.seed.get_or_insert(1);
+        config.quiet = true;
+        config
+            .validate()
+            .map_err(|e| format!("suite function {}: {}", function.name(), e))?;
+        config.prepare();
+
+        let start = Instant::now();
+        let token = CancelToken::default();
+        let records = multirun::run(
+            &config,
+            &RunOptions::default(),
+            runs,
+            multirun::jobs(jobs, config.evaluation_threads()),
+            &token,
+        );
+        let summary = ExperimentSummary::of(&records, &config);
+        let result = SuiteResult {
+            function: function.name().to_string(),
+            dimensions: config.dimensions,
+            runs,
+            median: summary.median,
+            mean: summary.mean,
+            best: summary.best,
+            mean_evaluations: summary.mean_evaluations,
+            seconds: start.elapsed().as_secs_f64(),
+        };
+        println!(
+            "  {} ({} dimensions): median {}, {:.3} s",
+            result.function, result.dimensions, result.median, result.seconds
+        );
+        results.push(result);
+    }
+
+    let entry = Entry {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        commit: commit.map_or_else(current_commit, str::to_string),
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs()),
+        config: base
+            .entries()
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect(),
+        results,
+    };
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("cannot open {}: {}", path, e))?;
+    let line = serde_json::to_string(&entry).expect("Error serializing benchmark entry");
+    writeln!(file, "{}", line).map_err(|e| format!("cannot write {}: {}", path, e))?;
+    println!("Recorded {} ({}) in {}", entry.commit, entry.version, path);
+    history(path, baseline, tolerances)
+}
+
+// Lists the entries of `path` and compares the latest with the baseline: the
+// entry whose commit starts with or whose version equals `baseline`, or the
+// first one; whether there was no regression
+pub fn history(path: &str, baseline: Option<&str>, tolerances: Tolerances) -> Result<bool, String> {
+    let entries = load(path)?;
+    let Some(latest) = entries.last() else {
+        return Err(format!("{} has no benchmark entries", path));
+    };
+    println!("Benchmark history ({} entries):", entries.len());
+    for entry in &entries {
+        let medians: Vec<String> = entry
+            .results
+            .iter()
+            .map(|r| format!("{} {}", r.function, r.median))
+            .collect();
+        println!(
+            "  {} ({}, {}): {}",
+            entry.commit,
+            entry.version,
+            entry.timestamp,
+            medians.join(", ")
+        );
+    }
+
+    let reference = match baseline {
+        Some(name) => entries
+            .iter()
+            .find(|e| e.commit.starts_with(name) || e.version == name)
+            .ok_or_else(|| format!("no entry of {} matches the baseline {}", path, name))?,
+        None => &entries[0],
+    };
+    println!(
+        "Latest {} against baseline {} ({}):",
+        latest.commit, reference.commit, reference.version
+    );
+    if reference.config != latest.config {
+        println!(
+            "  Warning: the two entries ran different configurations; their results \
+             are not comparable"
+        );
+    }
+    let regressions = regressions(reference, latest, tolerances);
+    for line in &regressions {
+        println!("  Regression: {}", line);
+    }
+    if regressions.is_empty() {
+        println!("  No regressions");
+    }
+    Ok(regressions.is_empty())
+}
+
+// One description per suite function that got worse than `tolerances` allow
+fn regressions(baseline: &Entry, latest: &Entry, tolerances: Tolerances) -> Vec<String> {
+    let mut found = Vec::new();
+    for now in &latest.results {
+        let Some(before) = baseline
+            .results
+            .iter()
+            .find(|r| r.function == now.function && r.dimensions == now.dimensions)
+        else {
+            continue;
+        };
+        let allowed = tolerances.quality * before.median.abs() + QUALITY_FLOOR;
+        if now.median - before.median > allowed {
+            found.push(format!(
+                "{} median fitness {} -> {}",
+                now.function, before.median, now.median
+            ));
+        }
+        if now.seconds > before.seconds * (1.0 + tolerances.time) {
+            found.push(format!(
+                "{} time {:.3} s -> {:.3} s",
+                now.function, before.seconds, now.seconds
+            ));
+        }
+    }
+    found
+}
+
+fn load(path: &str) -> Result<Vec<Entry>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).map_err(|e| format!("{} line {}: {}", path, i + 1, e))
+        })
+        .collect()
+}
+
+// The short hash of the checked-out commit, "unknown" outside a git checkout
+fn current_commit() -> String {
+    std::process::Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .and_then(|out| String::from_utf8(out.stdout).ok())
+        .map(|hash| hash.trim().to_string())
+        .filter(|hash| !hash.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn regressions_compare_medians_and_times_with_the_baseline() {
+        let entry = |median: f64, seconds: f64| Entry {
+            version: "0.1.0".to_string(),
+            commit: "abc".to_string(),
+            timestamp: 0,
+            config: BTreeMap::new(),
+            results: vec![SuiteResult {
+                function: "rastrigin".to_string(),
+                dimensions: 10,
+                runs: 5,
+                median,
+                mean: median,
+                best: median,
+                mean_evaluations: 1000.0,
+                seconds,
+            }],
+        };
+        let tolerances = Tolerances {
+            quality: 0.1,
+            time: 0.25,
+        };
+        let baseline = entry(2.0, 1.0);
+        assert!(regressions(&baseline, &entry(2.1, 1.2), tolerances).is_empty());
+        assert_eq!(
+            regressions(&baseline, &entry(2.5, 1.0), tolerances).len(),
+            1
+        );
+        assert_eq!(
+            regressions(&baseline, &entry(2.5, 2.0), tolerances).len(),
+            2
+        );
+        // an exact optimum before and a tiny residue now is no regression
+        assert!(regressions(&entry(0.0, 1.0), &entry(1e-12, 1.0), tolerances).is_empty());
+    }
+}
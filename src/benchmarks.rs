@@ -0,0 +1,54 @@
+// Desc: Standard benchmark functions for continuous minimization
+use std::f64::consts::{E, PI};
+
+// 418.9829 * n, the offset that puts Schwefel's minimum at (about) zero
+const SCHWEFEL_OFFSET: f64 = 418.982_887_272_433_9;
+
+pub fn rastrigin(x: &[f64]) -> f64 {
+    let mut sum = 0.0;
+    for xi in x {
+        sum += xi.powi(2) - 10.0 * (2.0 * PI * xi).cos();
+    }
+
+    10.0 * x.len() as f64 + sum
+}
+
+pub fn sphere(x: &[f64]) -> f64 {
+    x.iter().map(|xi| xi * xi).sum()
+}
+
+// Curved narrow valley; minimum 0 at (1, ..., 1)
+pub fn rosenbrock(x: &[f64]) -> f64 {
+    x.windows(2)
+        .map(|w| 100.0 * (w[1] - w[0] * w[0]).powi(2) + (1.0 - w[0]).powi(2))
+        .sum()
+}
+
+// Nearly flat outer region around a deep central funnel; minimum 0 at the origin
+pub fn ackley(x: &[f64]) -> f64 {
+    let n = x.len() as f64;
+    let squares = x.iter().map(|xi| xi * xi).sum::<f64>() / n;
+    let cosines = x.iter().map(|xi| (2.0 * PI * xi).cos()).sum::<f64>() / n;
+    -20.0 * (-0.2 * squares.sqrt()).exp() - cosines.exp() + 20.0 + E
+}
+
+// Many regularly spaced local minima on a wide bowl; minimum 0 at the origin
+pub fn griewank(x: &[f64]) -> f64 {
+    let sum = x.iter().map(|xi| xi * xi).sum::<f64>() / 4000.0;
+    let product: f64 = x
+        .iter()
+        .enumerate()
+        .map(|(i, xi)| (xi / ((i + 1) as f64).sqrt()).cos())
+        .product();
+    sum - product + 1.0
+}
+
+// Deceptive: the second-best minima lie far from the global minimum, which
+// sits near the corner at x_i = 420.9687
+pub fn schwefel(x: &[f64]) -> f64 {
+    x.iter().map(|&xi| schwefel_component(xi)).sum()
+}
+
+pub(crate) fn schwefel_component(xi: f64) -> f64 {
+    SCHWEFEL_OFFSET - xi * xi.abs().sqrt().sin()
+}
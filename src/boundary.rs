@@ -0,0 +1,141 @@
+// Desc: What happens to a particle coordinate that leaves the search box
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// How a coordinate that left its bounds is brought back (or not)
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BoundaryHandling {
+    /// Pin the coordinate to the bound, zero its velocity and add
+    /// `penalty_factor` to the particle's fitness
+    #[default]
+    Clamp,
+    /// Mirror the coordinate back into the box and reverse its velocity
+    Reflect,
+    /// Periodic box: re-enter from the opposite bound, keeping the velocity
+    Wrap,
+    /// Redraw the coordinate uniformly within its bounds and zero its velocity
+    RandomReinit,
+    /// Leave the particle outside and add `factor` times its total distance
+    /// outside the box to its fitness
+    PenaltyOnly { factor: f64 },
+}
+
+// Which bound a coordinate crossed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Crossed {
+    Lower,
+    Upper,
+}
+
+impl Crossed {
+    pub fn name(self) -> &'static str {
+        match self {
+            Crossed::Lower => "lower",
+            Crossed::Upper => "upper",
+        }
+    }
+}
+
+impl BoundaryHandling {
+    // Applies the strategy to coordinate `x` with velocity `v` in [lower, upper];
+    // returns the bound it crossed, if any
+    pub fn apply(
+        &self,
+        x: &mut f64,
+        v: &mut f64,
+        (lower, upper): (f64, f64),
+        rng: &mut impl Rng,
+    ) -> Option<Crossed> {
+        let crossed = if *x < lower {
+            Crossed::Lower
+        } else if *x > upper {
+            Crossed::Upper
+        } else {
+            return None;
+        };
+        let range = upper - lower;
+        match self {
+            BoundaryHandling::Clamp => {
+                *x = x.clamp(lower, upper);
+                *v = 0.0;
+            }
+            BoundaryHandling::Reflect => {
+                // a triangle wave, so overshoots of several ranges still land inside
+                let t = (*x - lower).rem_euclid(2.0 * range);
+                *x = lower + if t <= range { t } else { 2.0 * range - t };
+                *v = -*v;
+            }
+            BoundaryHandling::Wrap => {
+                *x = lower + (*x - lower).rem_euclid(range);
+            }
+            BoundaryHandling::RandomReinit => {
+                *x = rng.gen_range(lower..upper);
+                *v = 0.0;
+            }
+            BoundaryHandling::PenaltyOnly { .. } => {}
+        }
+        Some(crossed)
+    }
+
+    // Fitness penalty of a particle at `x` whose coordinates crossed a bound
+    // `crossings` times; `penalty_factor` is the clamp penalty
+    pub fn penalty(
+        &self,
+        x: &[f64],
+        bounds: impl Fn(usize) -> (f64, f64),
+        crossings: usize,
+        penalty_factor: f64,
+    ) -> f64 {
+        match *self {
+            _ if crossings == 0 => 0.0,
+            BoundaryHandling::Clamp => penalty_factor,
+            BoundaryHandling::PenaltyOnly { factor } => {
+                let outside: f64 = x
+                    .iter()
+                    .enumerate()
+                    .map(|(pos, &xi)| {
+                        let (lower, upper) = bounds(pos);
+                        (lower - xi).max(0.0) + (xi - upper).max(0.0)
+                    })
+                    .sum();
+                factor * outside
+            }
+            _ => 0.0,
+        }
+    }
+
+    // What happened to a coordinate that crossed a bound, for --explain
+    pub fn note(&self, crossed: Crossed) -> String {
+        let bound = crossed.name();
+        match self {
+            BoundaryHandling::Clamp => format!("clamped to {} bound, velocity zeroed", bound),
+            BoundaryHandling::Reflect => format!("reflected off {} bound", bound),
+            BoundaryHandling::Wrap => format!("wrapped past {} bound", bound),
+            BoundaryHandling::RandomReinit => format!("redrawn after crossing {} bound", bound),
+            BoundaryHandling::PenaltyOnly { .. } => format!("outside {} bound, penalized", bound),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            BoundaryHandling::PenaltyOnly { factor } if factor.is_nan() || factor < 0.0 => Err(
+                format!("penalty_only factor must not be negative, got {}", factor),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            BoundaryHandling::Clamp => "clamp with penalty".to_string(),
+            BoundaryHandling::Reflect => "reflect".to_string(),
+            BoundaryHandling::Wrap => "wrap (periodic)".to_string(),
+            BoundaryHandling::RandomReinit => "random re-initialization".to_string(),
+            BoundaryHandling::PenaltyOnly { factor } => {
+                format!("penalty only, {} per unit outside", factor)
+            }
+        }
+    }
+}
@@ -0,0 +1,392 @@
+// Desc: Swarm checkpoints (`--checkpoint`): the whole state of a PSO run,
+// written every few iterations, from which `--resume` continues the run
+// exactly as if it had not stopped; and the `diff-checkpoint` summary of two
+// of them
+//
+// Checkpoints are TOML, which keeps infinite and NaN values, and every f64
+// is written in its shortest form that reads back to the same bits.
+//
+// Every checkpoint starts with its format `version`, and `load` reads all of
+// them, upgrading older ones to the current layout:
+//
+//   1  JSON, the population alone: ids, positions, velocities and personal
+//      bests (non-finite values written as null)
+//   2  TOML, the whole run
+//
+// An upgraded checkpoint lacks the run state its version did not keep: it
+// can start a swarm (`--warm-start`) or be compared, but not resume a run.
+// A format change bumps VERSION and adds the upgrade from the previous one.
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::archive::EliteArchive;
+use crate::config::Config;
+use crate::constraint::PenaltyScale;
+use crate::convergence::TracePoint;
+use crate::diagnostics;
+use crate::events::Event;
+use crate::local_search::Refinements;
+use crate::pso::Particle;
+use crate::stats;
+use crate::stopping::StopState;
+use crate::topology::Neighbourhoods;
+
+pub const VERSION: u32 = 2;
+
+// The success rate before the first iteration
+fn all_succeeded() -> f64 {
+    1.0
+}
+
+#[derive(Deserialize)]
+struct Header {
+    version: u32,
+}
+
+// Version 1 checkpoints
+mod v1 {
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    pub struct Checkpoint {
+        pub iteration: usize,
+        pub evaluations: usize,
+        pub dimensions: usize,
+        pub gbest: Option<f64>,
+        pub gbest_x: Vec<f64>,
+        pub particles: Vec<ParticleState>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct ParticleState {
+        pub id: usize,
+        pub x: Vec<f64>,
+        pub vx: Vec<f64>,
+        pub pbest_x: Vec<f64>,
+        pub pbest: Option<f64>,
+    }
+}
+
+// Position of the run's generator in its stream
+#[derive(Serialize, Deserialize)]
+pub struct RngState {
+    seed: [u8; 32],
+    stream: u64,
+    word_pos: u64,
+}
+
+impl RngState {
+    pub fn of(rng: &ChaCha12Rng) -> Self {
+        RngState {
+            seed: rng.get_seed(),
+            stream: rng.get_stream(),
+            word_pos: u64::try_from(rng.get_word_pos()).expect("generator position fits 64 bits"),
+        }
+    }
+
+    pub fn restore(&self) -> ChaCha12Rng {
+        let mut rng = ChaCha12Rng::from_seed(self.seed);
+        rng.set_stream(self.stream);
+        rng.set_word_pos(self.word_pos as u128);
+        rng
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub version: u32,
+    pub iteration: usize, // iterations completed
+    pub evaluations: usize,
+    pub low_evaluations: usize,
+    #[serde(default)]
+    pub cost: Option<f64>, // of the evaluations; None in checkpoints from before per-evaluation costs
+    pub dimensions: usize, // of the objective; positions may carry strategy coordinates after them
+    pub gbest: f64,
+    pub gbest_violation: f64,
+    pub gbest_x: Vec<f64>,
+    pub config: Vec<(String, String)>, // the run's parameters, which a resumed run must match
+    pub rng: RngState,
+    pub stop: StopState,
+    pub penalty_scale: PenaltyScale,
+    pub active_dims: Vec<Vec<usize>>,
+    #[serde(default)]
+    pub groups: Vec<Vec<usize>>, // block-coordinate groups, as differential grouping found them
+    pub chaos: f64,
+    #[serde(default = "all_succeeded")]
+    pub success_rate: f64,
+    pub initial_diversity: f64,
+    pub dispersed: usize,
+    pub saturated_iterations: usize,
+    pub restarts: usize,
+    #[serde(default)]
+    pub stagnant_iterations: usize,
+    #[serde(default)]
+    pub escape_reference: (f64, f64),
+    #[serde(default)]
+    pub refinements: Refinements,
+    #[serde(default)]
+    pub repairs: usize,
+    pub archive: Option<EliteArchive>,
+    pub neighbourhoods: Option<Neighbourhoods>,
+    pub particles: Vec<Particle>,
+    pub history: Vec<TracePoint>,
+    #[serde(default)]
+    pub events: Vec<Event>,
+    #[serde(skip)]
+    pub upgraded_from: Option<u32>, // the version read, when older than VERSION
+}
+
+impl Checkpoint {
+    // Written next to `path` and renamed over it, so an interrupted write
+    // leaves the previous checkpoint intact
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let text = toml::to_string(self).expect("Error serializing checkpoint");
+        let partial = format!("{}.partial", path);
+        std::fs::write(&partial, text)
+            .and_then(|_| std::fs::rename(&partial, path))
+            .map_err(|e| format!("cannot write checkpoint {}: {}", path, e))
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read checkpoint {}: {}", path, e))?;
+        let invalid = |e: String| format!("invalid checkpoint {}: {}", path, e);
+        // version 1 is JSON, the later ones TOML
+        let header = toml::from_str::<Header>(&text)
+            .map_err(|e| e.to_string())
+            .or_else(|e| serde_json::from_str::<Header>(&text).map_err(|_| e))
+            .map_err(invalid)?;
+        match header.version {
+            1 => serde_json::from_str(&text)
+                .map(Checkpoint::upgrade_v1)
+                .map_err(|e| invalid(e.to_string())),
+            VERSION => toml::from_str(&text).map_err(|e| invalid(e.to_string())),
+            version if version > VERSION => Err(format!(
+                "checkpoint {} has version {}, from a newer build; this one reads versions 1 to {}",
+                path, version, VERSION
+            )),
+            version => Err(format!(
+                "checkpoint {} has unknown version {}",
+                path, version
+            )),
+        }
+    }
+
+    // The population of a version 1 checkpoint, with the run state it did
+    // not keep at its initial values
+    fn upgrade_v1(old: v1::Checkpoint) -> Checkpoint {
+        let unknown = |v: Option<f64>| v.unwrap_or(f64::INFINITY);
+        Checkpoint {
+            version: VERSION,
+            iteration: old.iteration,
+            evaluations: old.evaluations,
+            low_evaluations: 0,
+            cost: None,
+            dimensions: old.dimensions,
+            gbest: unknown(old.gbest),
+            gbest_violation: 0.0,
+            gbest_x: old.gbest_x,
+            config: Vec::new(),
+            rng: RngState::of(&ChaCha12Rng::seed_from_u64(0)),
+            stop: StopState::default(),
+            penalty_scale: PenaltyScale::default(),
+            active_dims: Vec::new(),
+            groups: Vec::new(),
+            chaos: 0.0,
+            success_rate: all_succeeded(),
+            initial_diversity: 0.0,
+            dispersed: 0,
+            saturated_iterations: 0,
+            restarts: 0,
+            stagnant_iterations: 0,
+            escape_reference: (0.0, 0.0),
+            refinements: Refinements::default(),
+            repairs: 0,
+            archive: None,
+            neighbourhoods: None,
+            particles: old
+                .particles
+                .into_iter()
+                .map(|p| Particle {
+                    id: p.id,
+                    x: p.x,
+                    vx: p.vx,
+                    pbest_x: p.pbest_x,
+                    pbest: unknown(p.pbest),
+                    pbest_violation: 0.0,
+                    fitness: None,
+                    pbest_low: None,
+                    coef: None,
+                    stagnation: 0,
+                    gbest_improvements: 0,
+                    gbest_gain: 0.0,
+                })
+                .collect(),
+            history: Vec::new(),
+            events: Vec::new(),
+            upgraded_from: Some(1),
+        }
+    }
+
+    // Positions to start a new swarm from, objective coordinates first
+    pub fn positions_for(&self, config: &Config) -> Result<Vec<Vec<f64>>, String> {
+        if self.dimensions != config.dimensions {
+            return Err(format!(
+                "the checkpoint holds a swarm in {} dimensions, the run has {}",
+                self.dimensions, config.dimensions
+            ));
+        }
+        Ok(self.particles.iter().map(|p| p.x.clone()).collect())
+    }
+
+    pub fn parameters(config: &Config) -> Vec<(String, String)> {
+        config
+            .entries()
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect()
+    }
+
+    // A resumed run must have the parameters of the checkpointed one
+    pub fn check_config(&self, config: &Config) -> Result<(), String> {
+        if let Some(version) = self.upgraded_from {
+            return Err(format!(
+                "a version {} checkpoint holds no run state to resume; --warm-start can start \
+                 a swarm from it",
+                version
+            ));
+        }
+        let current = Checkpoint::parameters(config);
+        let differing = self
+            .config
+            .iter()
+            .zip(&current)
+            .find(|(saved, now)| saved != now);
+        if let Some(((key, saved), (_, now))) = differing {
+            return Err(format!(
+                "the checkpoint was taken with {} = {}, the run has {}",
+                key, saved, now
+            ));
+        }
+        if self.config.len() != current.len() {
+            return Err("the checkpoint was taken with different parameters".to_string());
+        }
+        Ok(())
+    }
+
+    fn positions(&self) -> Vec<&[f64]> {
+        self.particles
+            .iter()
+            .map(|p| &p.x[..self.dimensions])
+            .collect()
+    }
+}
+
+fn distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - y) * (x - y))
+        .sum::<f64>()
+        .sqrt()
+}
+
+// `diff-checkpoint`: how the swarm changed from checkpoint `a` to `b`.
+// Particles are matched by id; objective coordinates only.
+pub fn diff(a_path: &str, b_path: &str) -> Result<(), String> {
+    let (a, b) = (Checkpoint::load(a_path)?, Checkpoint::load(b_path)?);
+    if a.dimensions != b.dimensions || a.particles.len() != b.particles.len() {
+        return Err(format!(
+            "the checkpoints are of different swarms ({} particles in {} dimensions vs {} in {})",
+            a.particles.len(),
+            a.dimensions,
+            b.particles.len(),
+            b.dimensions
+        ));
+    }
+    let d = a.dimensions;
+
+    let moves: Vec<(usize, f64, bool)> = a
+        .particles
+        .iter()
+        .map(|p| {
+            let q = b
+                .particles
+                .iter()
+                .find(|q| q.id == p.id)
+                .ok_or(format!("particle {} is missing from {}", p.id, b_path))?;
+            Ok((p.id, distance(&p.x[..d], &q.x[..d]), q.pbest < p.pbest))
+        })
+        .collect::<Result<_, String>>()?;
+    let displacements: Vec<f64> = moves.iter().map(|&(_, moved, _)| moved).collect();
+    let (farthest, max_move, _) = moves
+        .iter()
+        .copied()
+        .max_by(|x, y| x.1.total_cmp(&y.1))
+        .expect("a swarm has particles");
+    let improved = moves.iter().filter(|&&(_, _, improved)| improved).count();
+    let (diversity_a, diversity_b) = (
+        diagnostics::spread(&a.positions()),
+        diagnostics::spread(&b.positions()),
+    );
+
+    println!(
+        "Iterations: {} -> {} ({:+}), evaluations {} -> {} ({:+})",
+        a.iteration,
+        b.iteration,
+        b.iteration as i64 - a.iteration as i64,
+        a.evaluations,
+        b.evaluations,
+        b.evaluations as i64 - a.evaluations as i64
+    );
+    println!(
+        "gbest: {} -> {} ({:+}), moved {}",
+        a.gbest,
+        b.gbest,
+        b.gbest - a.gbest,
+        distance(&a.gbest_x[..d], &b.gbest_x[..d])
+    );
+    println!(
+        "Diversity: {} -> {} ({:+})",
+        diversity_a,
+        diversity_b,
+        diversity_b - diversity_a
+    );
+    println!(
+        "Particle displacement: mean {}, median {}, max {} (particle {})",
+        stats::mean(&displacements),
+        stats::median(&displacements),
+        max_move,
+        farthest
+    );
+    println!("Personal bests improved: {} of {}", improved, moves.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_1_checkpoints_upgrade_to_the_current_layout() {
+        let path = std::env::temp_dir().join("pso_checkpoint_v1.json");
+        std::fs::write(
+            &path,
+            r#"{"version":1,"iteration":10,"evaluations":330,"dimensions":2,"gbest":0.5,
+               "gbest_x":[0.1,0.2],"particles":[
+               {"id":0,"x":[0.1,0.2],"vx":[0,0],"pbest_x":[0.1,0.2],"pbest":0.5},
+               {"id":1,"x":[1,1],"vx":[0.1,0.1],"pbest_x":[1,1],"pbest":null}]}"#,
+        )
+        .unwrap();
+        let checkpoint = Checkpoint::load(path.to_str().unwrap()).unwrap();
+        assert_eq!(checkpoint.version, VERSION);
+        assert_eq!(checkpoint.upgraded_from, Some(1));
+        assert_eq!(checkpoint.particles[1].pbest, f64::INFINITY);
+
+        let mut config = Config::default();
+        config.dimensions = 2;
+        assert_eq!(checkpoint.positions_for(&config).unwrap()[1], [1.0, 1.0]);
+        // without the run state there is nothing to resume
+        assert!(checkpoint.check_config(&config).is_err());
+    }
+}
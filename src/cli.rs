@@ -0,0 +1,1686 @@
+// Desc: Command-line interface: argument parsing and dispatch of the binary
+use std::io::IsTerminal;
+
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use rand::Rng;
+
+use crate::boundary::BoundaryHandling;
+use crate::config::{self, Config, Objective, OutputPaths};
+use crate::experiment::ExperimentSummary;
+use crate::functions::Function;
+use crate::handle::{CancelToken, RunHandle};
+use crate::optimizer::{Algorithm, Optimizer};
+use crate::options::RunOptions;
+use crate::problem::Problem;
+use crate::pso::{self, Run};
+use crate::topology::Topology;
+use crate::{
+    audit, axis_bias, bands, batch, bench, checkpoint, cluster, comparator, ecdf, init, multirun,
+    output, platform, plot, plugin, power, preflight, privacy, repair, repl, resources,
+    sensitivity, stats, sweep,
+};
+
+#[derive(Clone, Parser)]
+#[command(about = "Rastrigin using Particle Swarm Optimization")]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Load run parameters and output paths from a JSON or TOML config file
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<String>,
+
+    /// Benchmark function to minimize, replacing the config's; bounds and
+    /// dimensions the config leaves out follow the function's canonical domain
+    #[arg(long, value_enum, value_name = "NAME")]
+    pub function: Option<Function>,
+
+    /// Maximize the objective instead of minimizing it
+    #[arg(long)]
+    pub maximize: bool,
+
+    /// Number of particles, replacing the config's
+    #[arg(long, value_name = "N")]
+    pub particles: Option<usize>,
+
+    /// Number of iterations, replacing the config's
+    #[arg(long, value_name = "N")]
+    pub iterations: Option<usize>,
+
+    /// Number of dimensions, replacing the config's
+    #[arg(long = "dims", value_name = "N")]
+    pub dimensions: Option<usize>,
+
+    /// Cognitive weight c1, replacing the config's
+    #[arg(long)]
+    pub c1: Option<f64>,
+
+    /// Social weight c2, replacing the config's
+    #[arg(long)]
+    pub c2: Option<f64>,
+
+    /// Inertia weight w, replacing the config's
+    #[arg(long)]
+    pub inertia: Option<f64>,
+
+    /// Swarm topology with its default neighbourhood size, replacing the config's
+    #[arg(long, value_name = "NAME", value_parser = ["global", "ring", "von_neumann", "random"])]
+    pub topology: Option<String>,
+
+    /// PSO variant (velocity update), replacing the config's
+    #[arg(long, value_name = "NAME", value_parser = ["standard", "spso2011", "constriction", "bare_bones", "qpso"])]
+    pub variant: Option<String>,
+
+    /// Refine gbest with a local search, replacing the config's: every
+    /// --local-search-every iterations, or once when the run ends
+    #[arg(long, value_name = "METHOD", value_parser = ["nelder_mead", "pattern_search"])]
+    pub local_search: Option<String>,
+
+    /// Iterations between the --local-search refinements
+    #[arg(long, value_name = "K", requires = "local_search")]
+    pub local_search_every: Option<usize>,
+
+    /// Objective evaluations each --local-search refinement may spend
+    #[arg(long, value_name = "N", requires = "local_search")]
+    pub local_search_evaluations: Option<usize>,
+
+    /// Lower and upper bound of every dimension, replacing the config's
+    #[arg(long, num_args = 2, value_names = ["LOWER", "UPPER"], allow_negative_numbers = true)]
+    pub bounds: Option<Vec<f64>>,
+
+    /// Seed of the random generator, replacing the config's
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Per-iteration log (gbest, mean fitness, diversity, time, share at the
+    /// bounds, velocities, mean pbest); a CSV log gets its sidecar next to it
+    /// with a .meta suffix
+    #[arg(long, default_value = "test_9.csv", value_name = "FILE")]
+    pub output: String,
+
+    /// Format of the --output file
+    #[arg(long, value_enum, default_value_t = output::Format::Csv)]
+    pub format: output::Format,
+
+    /// Continue an existing CSV log instead of replacing it; only an empty
+    /// file gets the header
+    #[arg(long)]
+    pub append: bool,
+
+    /// Load the variables, their bounds and types, and constraints from a
+    /// TOML or JSON problem file, overriding the config's dimensions and bounds
+    #[arg(long, value_name = "FILE")]
+    pub problem: Option<String>,
+
+    /// Hold a variable at a value while the others are optimized, by name:
+    /// the problem file's names, or x1..xn (repeatable; replaces the config's `fixed`)
+    #[arg(long = "fix", value_name = "NAME=VALUE", value_parser = parse_fixed)]
+    pub fixed: Vec<(String, f64)>,
+
+    /// Attach a key=value tag to every output record (repeatable)
+    #[arg(long = "tag", value_name = "KEY=VALUE", value_parser = parse_tag)]
+    pub tags: Vec<(String, String)>,
+
+    /// Re-disperse particles that share (approximately) the same position
+    #[arg(long)]
+    pub disperse_duplicates: bool,
+
+    /// Grid cell size used to decide that two particles are duplicates
+    #[arg(long, default_value_t = 1e-6, value_name = "TOL")]
+    pub duplicate_tolerance: f64,
+
+    /// Write per-iteration diagnostics (boundary hits per dimension, trace,
+    /// largest eigenvalue and condition number of the position covariance) to this CSV file
+    #[arg(long, value_name = "FILE")]
+    pub diagnostics: Option<String>,
+
+    /// Warn when more than this fraction of the swarm is pinned to a bound
+    #[arg(long, default_value_t = 0.5, value_name = "FRACTION")]
+    pub saturation_warning: f64,
+
+    /// Export a visitation histogram of the whole run to this CSV file
+    #[arg(long, value_name = "FILE")]
+    pub heatmap: Option<String>,
+
+    /// Pair of dimensions (1-based) projected onto the heatmap
+    #[arg(long, num_args = 2, default_values_t = [1, 2], value_name = "DIM")]
+    pub heatmap_dims: Vec<usize>,
+
+    /// Number of bins per heatmap axis
+    #[arg(long, default_value_t = 50)]
+    pub heatmap_bins: usize,
+
+    /// Export a 2D projection of every particle position of the run to this CSV
+    /// file, to follow the swarm in many dimensions
+    #[arg(long, value_name = "FILE")]
+    pub projection: Option<String>,
+
+    /// Axes of the --projection: the two principal components of all
+    /// positions, or two random orthonormal directions
+    #[arg(long, default_value = "pca", value_parser = ["pca", "random"], requires = "projection")]
+    pub projection_method: String,
+
+    /// Log every gbest improvement (iteration, evaluations, delta, particle) to this CSV file
+    #[arg(long, value_name = "FILE")]
+    pub improvements: Option<String>,
+
+    /// Log stop and restart events to this CSV file
+    #[arg(long, value_name = "FILE")]
+    pub events: Option<String>,
+
+    /// Draw the convergence curve (gbest in blue, mean fitness in orange) to this PNG after the run
+    #[arg(long, value_name = "FILE")]
+    pub plot: Option<String>,
+
+    /// Draw the swarm over the objective's contours to one PNG per iteration in this
+    /// directory; 2-dimensional problems only
+    #[arg(long, value_name = "DIR")]
+    pub animate: Option<String>,
+
+    /// Write the swarm state to this TOML file (checkpoint format version 2) every --checkpoint-every iterations and at the end
+    #[arg(long, value_name = "FILE")]
+    pub checkpoint: Option<String>,
+
+    /// Iterations between checkpoints
+    #[arg(long, default_value_t = 100, value_name = "N", requires = "checkpoint",
+          value_parser = clap::value_parser!(u64).range(1..))]
+    pub checkpoint_every: u64,
+
+    /// Continue the run saved in this checkpoint; the config and flags must match the original run.
+    /// The checkpoint does not keep the --heatmap and --projection of the run so far.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["heatmap", "projection"])]
+    pub resume: Option<String>,
+
+    /// Start the swarm at the positions of a checkpoint of any format version, evaluated anew
+    #[arg(long, value_name = "FILE", conflicts_with = "resume")]
+    pub warm_start: Option<String>,
+
+    /// Add Laplace noise with this privacy budget to logged positions and fitness values
+    #[arg(long, value_name = "EPSILON")]
+    pub dp_epsilon: Option<f64>,
+
+    /// Sensitivity used to calibrate the noise (scale = sensitivity / epsilon)
+    #[arg(long, default_value_t = 1.0, requires = "dp_epsilon")]
+    pub dp_sensitivity: f64,
+
+    /// Append every evaluation to this hash-chained audit log (continued if it exists)
+    #[arg(long, value_name = "FILE")]
+    pub audit: Option<String>,
+
+    /// Debug: record every evaluated point, in evaluation order, to this CSV file
+    #[arg(long, value_name = "FILE")]
+    pub trace_evaluations: Option<String>,
+
+    /// Store a hash of each traced point instead of its coordinates
+    #[arg(long, requires = "trace_evaluations")]
+    pub trace_hashed: bool,
+
+    /// Write fitness values in scientific notation with this many digits after the point,
+    /// or `auto` for more decimals as the values approach zero
+    #[arg(long, value_name = "DIGITS|auto", value_parser = output::parse_precision)]
+    pub precision: Option<output::Precision>,
+
+    /// Precision of the console output only, overriding --precision there
+    #[arg(long, value_name = "DIGITS|auto", value_parser = output::parse_precision)]
+    pub console_precision: Option<output::Precision>,
+
+    /// Add a log10(gbest - optimum) column to the CSV output (needs a known optimum)
+    #[arg(long)]
+    pub log_error: bool,
+
+    /// Validate the config, evaluate a few random points and exit without running
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Number of independent runs; more than one runs them headless in parallel
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    pub runs: u64,
+
+    /// Worker threads for --runs (default: number of CPU cores)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pub jobs: Option<u64>,
+
+    /// Move and evaluate the particles in parallel on this many threads. All
+    /// particles then follow the previous iteration's gbest, and a seeded run
+    /// gives the same result for any number of threads.
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pub threads: Option<u64>,
+
+    /// Write the final gbest of every run to this file, one per line, when the runs end
+    #[arg(long, value_name = "FILE")]
+    pub results: Option<String>,
+
+    /// Append a convergence trace (algorithm, run, iteration, evaluations, best,
+    /// diversity, seconds) to this CSV file; the same columns for every algorithm
+    #[arg(long, value_name = "FILE")]
+    pub convergence: Option<String>,
+
+    /// Write the statistics of --runs to this file: JSON if it ends in .json, CSV otherwise
+    #[arg(long, value_name = "FILE")]
+    pub summary: Option<String>,
+
+    /// Write the runtime ECDF of --runs (share of run and target pairs solved by
+    /// each evaluation count, targets 1e2 to 1e-8 above the optimum) to this CSV file
+    #[arg(long, value_name = "FILE")]
+    pub ecdf: Option<String>,
+
+    /// Final solutions of --runs closer than this fraction of the ranges count as one optimum
+    #[arg(long, default_value_t = 0.05, value_name = "FRACTION")]
+    pub cluster_eps: f64,
+
+    /// Runs an optimum needs to be reported (DBSCAN's minimum points); the rest count as noise
+    #[arg(long, default_value_t = 1, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pub cluster_min_runs: u64,
+
+    /// Settings for publishable experiments: synchronous updates, r1 and r2 per
+    /// dimension, the function's canonical bounds, 30 seeded runs stopped by an
+    /// evaluation budget of 10000 per dimension, and their statistics in
+    /// summary.json and runtime ECDF in ecdf.csv (unless set otherwise)
+    #[arg(long)]
+    pub paper_mode: bool,
+
+    // Headless run inside a built-in experiment: no console output, no CSV log
+    #[arg(skip)]
+    pub batch: bool,
+
+    /// Read commands from stdin to pause, inspect and adjust the run
+    #[arg(long)]
+    pub interactive: bool,
+
+    /// Print the inertia, cognitive and social terms of every update (for tiny swarms)
+    #[arg(long)]
+    pub explain: bool,
+
+    /// Serve a live convergence chart and swarm scatter on a local web page
+    #[arg(long)]
+    pub web: bool,
+
+    /// Port of the live web page
+    #[arg(long, default_value_t = 8080, requires = "web")]
+    pub web_port: u16,
+
+    /// Pause this many milliseconds after each iteration so the live page can be followed
+    #[arg(long, default_value_t = 0, value_name = "MS", requires = "web")]
+    pub web_delay: u64,
+}
+
+#[derive(Clone, Subcommand)]
+pub enum Command {
+    /// Print the JSON Schema of the config file format
+    ConfigSchema,
+    /// Write a commented TOML experiment definition to start from
+    InitConfig {
+        /// File to write
+        #[arg(default_value = "experiment.toml")]
+        file: String,
+        /// Replace the file if it exists
+        #[arg(long)]
+        force: bool,
+    },
+    /// List the algorithms and the features each supports
+    Algorithms,
+    /// Estimate the runs per configuration needed to detect a difference, from pilot results
+    Power {
+        /// File with one final fitness per line from pilot runs of the first configuration
+        baseline: String,
+        /// File with one final fitness per line from pilot runs of the second configuration
+        candidate: String,
+        /// Significance level of the two-sided test
+        #[arg(long, default_value_t = 0.05)]
+        alpha: f64,
+        /// Desired probability of detecting the effect
+        #[arg(long, default_value_t = 0.8)]
+        power: f64,
+        /// Standardized effect size (Cohen's d) to detect; defaults to the observed one
+        #[arg(long)]
+        effect_size: Option<f64>,
+    },
+    /// Summarize final fitness across runs with bootstrap confidence intervals
+    Summarize {
+        /// Files with one final fitness per line, one file per configuration
+        #[arg(required = true)]
+        files: Vec<String>,
+        /// Confidence level of the bootstrap intervals
+        #[arg(long, default_value_t = 0.95)]
+        confidence: f64,
+        /// Number of bootstrap resamples
+        #[arg(long, default_value_t = 10000)]
+        resamples: usize,
+        /// The values are of a maximized objective: the largest is the best
+        #[arg(long)]
+        maximize: bool,
+    },
+    /// Compare results on the standard and a rotated version of the function
+    AxisBias {
+        /// Config of the runs; defaults to the built-in parameters
+        #[arg(long, value_name = "FILE")]
+        config: Option<String>,
+        /// Runs per version of the function
+        #[arg(long, default_value_t = 10)]
+        runs: usize,
+        /// Flag the variant when the rotated median is this many times worse
+        #[arg(long, default_value_t = 2.0)]
+        threshold: f64,
+        /// Seed of the random rotation
+        #[arg(long, default_value_t = 1)]
+        rotation_seed: u64,
+    },
+    /// Run from many seeds and report how much the final result depends on the start
+    Sensitivity {
+        /// Config of the runs; defaults to the built-in parameters
+        #[arg(long, value_name = "FILE")]
+        config: Option<String>,
+        /// Problem file of the runs
+        #[arg(long, value_name = "FILE")]
+        problem: Option<String>,
+        /// Runs, each with its own seed and so its own initial swarm
+        #[arg(long, default_value_t = 20)]
+        starts: usize,
+        /// Final solutions closer than this fraction of the ranges count as the same
+        #[arg(long, default_value_t = 0.05)]
+        eps: f64,
+        /// Worker threads (default: number of CPU cores)
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+        jobs: Option<u64>,
+        /// CSV file to write each start's seed, result, cluster and position to
+        #[arg(long, short, value_name = "FILE")]
+        output: Option<String>,
+    },
+    /// Print the random numbers a particle draws at an iteration of an rng_streams run
+    Rng {
+        /// Seed of the run
+        #[arg(long)]
+        seed: u64,
+        /// Iteration, counted from 0 as in --explain
+        #[arg(long)]
+        iteration: usize,
+        /// Particle index
+        #[arg(long)]
+        particle: usize,
+        /// Numbers to print
+        #[arg(long, default_value_t = 8)]
+        count: usize,
+    },
+    /// Check the hash chain of an audit log
+    AuditVerify {
+        /// Audit log written with --audit
+        file: String,
+    },
+    /// Export per-step median and quantile bands of best-so-far across runs
+    Bands {
+        /// Run histories; runs appended to the same file are split where the step count restarts
+        #[arg(required = true)]
+        files: Vec<String>,
+        /// Align histories by iteration (gbest logs) or evaluation count (improvement logs)
+        #[arg(long, value_enum, default_value_t = bands::Axis::Iteration)]
+        by: bands::Axis,
+        /// Quantile band in percent (repeatable)
+        #[arg(long = "band", value_name = "LO-HI", value_parser = bands::parse_band,
+              default_values = ["25-75", "5-95"])]
+        bands: Vec<(f64, f64)>,
+        /// CSV file to write the bands to
+        #[arg(long, short, value_name = "FILE")]
+        output: String,
+        /// The histories are of a maximized objective: best-so-far is a running maximum
+        #[arg(long)]
+        maximize: bool,
+    },
+    /// Run the tasks of an experiment manifest, skipping completed ones, or export them
+    Batch {
+        /// JSON manifest: base config, variants, replicates
+        manifest: String,
+        /// Write per-task configs and a SLURM array job script instead of running
+        #[arg(long)]
+        export_slurm: bool,
+        /// Worker threads (default: number of CPU cores)
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+        jobs: Option<u64>,
+        /// Rerun tasks that already have a result
+        #[arg(long, conflicts_with = "export_slurm")]
+        force: bool,
+    },
+    /// Aggregate a results tree, skipping incomplete or corrupt runs
+    Collect {
+        /// Directory written by `batch --export-slurm`, or any tree of run directories
+        dir: String,
+        /// CSV file to write the per-variant statistics table to
+        #[arg(long, short, value_name = "FILE")]
+        output: Option<String>,
+    },
+    /// Run every combination of the given parameter values and rank them
+    Sweep {
+        /// Base config of the runs; defaults to the built-in parameters
+        #[arg(long, value_name = "FILE")]
+        config: Option<String>,
+        /// Values of c1: a list (1,1.5,2) or a range START:END:STEP; the config's when left out
+        #[arg(long, value_name = "VALUES")]
+        c1: Option<String>,
+        /// Values of c2, in the same forms
+        #[arg(long, value_name = "VALUES")]
+        c2: Option<String>,
+        /// Values of the inertia weight w, in the same forms
+        #[arg(long, value_name = "VALUES")]
+        inertia: Option<String>,
+        /// Swarm sizes, in the same forms
+        #[arg(long, value_name = "VALUES")]
+        particles: Option<String>,
+        /// Runs per combination
+        #[arg(long, default_value_t = 10)]
+        runs: usize,
+        /// Worker threads (default: number of CPU cores)
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+        jobs: Option<u64>,
+        /// CSV file to write the ranked table to
+        #[arg(long, short, value_name = "FILE")]
+        output: Option<String>,
+    },
+    /// Summarize how the swarm changed between two checkpoints
+    DiffCheckpoint {
+        /// Earlier checkpoint written with --checkpoint
+        a: String,
+        /// Later checkpoint of the same swarm
+        b: String,
+    },
+    /// List the plugin libraries found and what each registers
+    Plugins,
+    /// Record benchmark-suite results by version and commit, and check them for regressions
+    Bench {
+        #[command(subcommand)]
+        action: BenchCommand,
+    },
+}
+
+#[derive(Clone, Subcommand)]
+pub enum BenchCommand {
+    /// Run the suite, append its results to the history and compare them with the baseline
+    Run {
+        /// Config of the runs; the suite sets the function, bounds and dimensions
+        #[arg(long, value_name = "FILE")]
+        config: Option<String>,
+        /// Seeded runs per suite function
+        #[arg(long, default_value_t = 10)]
+        runs: usize,
+        /// Worker threads (default: number of CPU cores)
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+        jobs: Option<u64>,
+        /// Commit to tag the results with; defaults to the checked-out one
+        #[arg(long)]
+        commit: Option<String>,
+        #[command(flatten)]
+        comparison: BenchComparison,
+    },
+    /// List the recorded results and compare the latest with the baseline
+    History {
+        #[command(flatten)]
+        comparison: BenchComparison,
+    },
+}
+
+#[derive(Clone, clap::Args)]
+pub struct BenchComparison {
+    /// History file, one JSON line per suite run
+    #[arg(long, value_name = "FILE", default_value = "bench_history.jsonl")]
+    history: String,
+    /// Entry to compare with, by commit prefix or version; defaults to the first
+    #[arg(long, value_name = "REF")]
+    baseline: Option<String>,
+    /// Relative increase of a median final fitness that counts as a regression
+    #[arg(long, default_value_t = 0.1)]
+    quality_tolerance: f64,
+    /// Relative increase of a function's run time that counts as a regression
+    #[arg(long, default_value_t = 0.25)]
+    time_tolerance: f64,
+}
+
+impl BenchComparison {
+    fn tolerances(&self) -> bench::Tolerances {
+        bench::Tolerances {
+            quality: self.quality_tolerance,
+            time: self.time_tolerance,
+        }
+    }
+}
+
+impl Cli {
+    // Config fields set by command-line flags, applied over the config file
+    fn overrides(&self) -> serde_json::Map<String, serde_json::Value> {
+        let mut fields = serde_json::Map::new();
+        let mut set = |key: &str, value: serde_json::Value| {
+            fields.insert(key.to_string(), value);
+        };
+        if let Some(function) = self.function {
+            set("function", function.name().into());
+        }
+        if let Some(particles) = self.particles {
+            set("particles", particles.into());
+        }
+        if let Some(iterations) = self.iterations {
+            set("iterations", iterations.into());
+        }
+        if let Some(dimensions) = self.dimensions {
+            set("dimensions", dimensions.into());
+        }
+        if let Some(c1) = self.c1 {
+            set("c1", c1.into());
+        }
+        if let Some(c2) = self.c2 {
+            set("c2", c2.into());
+        }
+        if let Some(w) = self.inertia {
+            set("w", w.into());
+        }
+        if let Some(topology) = &self.topology {
+            set("topology", serde_json::json!({ "type": topology }));
+        }
+        if let Some(variant) = &self.variant {
+            set("velocity_update", variant.as_str().into());
+        }
+        if let Some(method) = &self.local_search {
+            let mut search = serde_json::json!({ "method": method });
+            if let Some(every) = self.local_search_every {
+                search["every"] = every.into();
+            }
+            if let Some(evaluations) = self.local_search_evaluations {
+                search["evaluations"] = evaluations.into();
+            }
+            set("local_search", search);
+        }
+        if self.maximize {
+            set("objective", "maximize".into());
+        }
+        if let Some(bounds) = &self.bounds {
+            set("lower_bound", bounds[0].into());
+            set("upper_bound", bounds[1].into());
+            set("bounds", serde_json::Value::Null);
+        }
+        if let Some(seed) = self.seed {
+            set("seed", seed.into());
+        }
+        if !self.fixed.is_empty() {
+            let fixed: serde_json::Map<String, serde_json::Value> = self
+                .fixed
+                .iter()
+                .map(|(name, value)| (name.clone(), (*value).into()))
+                .collect();
+            set("fixed", fixed.into());
+        }
+        fields
+    }
+
+    // The options of the run the flags ask for
+    pub(crate) fn options(&self) -> RunOptions {
+        RunOptions {
+            batch: self.batch,
+            output: self.output.clone(),
+            format: self.format,
+            append: self.append,
+            precision: self.precision,
+            console_precision: self.console_precision,
+            tags: self.tags.clone(),
+            log_error: self.log_error,
+            explain: self.explain,
+            convergence: self.convergence.clone(),
+            diagnostics: self.diagnostics.clone(),
+            improvements: self.improvements.clone(),
+            events: self.events.clone(),
+            trace_evaluations: self.trace_evaluations.clone(),
+            trace_hashed: self.trace_hashed,
+            audit: self.audit.clone(),
+            checkpoint: self.checkpoint.clone(),
+            checkpoint_every: self.checkpoint_every,
+            resume: self.resume.clone(),
+            warm_start: self.warm_start.clone(),
+            heatmap: self.heatmap.clone(),
+            heatmap_dims: self.heatmap_dims.clone(),
+            heatmap_bins: self.heatmap_bins,
+            projection: self.projection.clone(),
+            projection_method: self.projection_method.clone(),
+            animate: self.animate.clone(),
+            web: self.web,
+            web_port: self.web_port,
+            web_delay: self.web_delay,
+            threads: self.threads,
+            saturation_warning: self.saturation_warning,
+            disperse_duplicates: self.disperse_duplicates,
+            duplicate_tolerance: self.duplicate_tolerance,
+            dp_epsilon: self.dp_epsilon,
+            dp_sensitivity: self.dp_sensitivity,
+        }
+    }
+}
+
+fn parse_fixed(s: &str) -> Result<(String, f64), String> {
+    let (name, value) = parse_tag(s)?;
+    let value = value
+        .parse()
+        .map_err(|_| format!("expected a number after `{}=`, got `{}`", name, value))?;
+    Ok((name, value))
+}
+
+fn parse_tag(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got `{}`", s)),
+    }
+}
+
+// Ctrl-C cancels `token`, so runs stop after the current iteration and still
+// report; a second Ctrl-C exits at once
+fn cancel_on_interrupt(token: CancelToken) {
+    let installed = platform::on_interrupt(move || {
+        if token.is_cancelled() {
+            std::process::exit(platform::INTERRUPTED_EXIT);
+        }
+        token.cancel();
+    });
+    if let Err(e) = installed {
+        eprintln!("Warning: cannot install the Ctrl-C handler: {}", e);
+    }
+}
+
+// An external objective given up during the run fails the command
+fn exit_on_objective_failure(config: &Config) {
+    if let Some(e) = config.objective_failure() {
+        eprintln!("Error: {}", e);
+        std::process::exit(2);
+    }
+}
+
+// A token that Ctrl-C cancels
+fn interrupt_token() -> CancelToken {
+    let token = CancelToken::default();
+    cancel_on_interrupt(token.clone());
+    token
+}
+
+pub fn main() {
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    match &cli.command {
+        Some(Command::Algorithms) => {
+            for algorithm in Algorithm::catalogue() {
+                println!(
+                    "{:<16} {}",
+                    algorithm.name(),
+                    algorithm.capabilities().names().join(", ")
+                );
+            }
+            println!("{:<16} what all of its stages support", "pipeline");
+            return;
+        }
+        Some(Command::ConfigSchema) => {
+            println!("{}", config::schema_json());
+            return;
+        }
+        Some(Command::InitConfig { file, force }) => {
+            if let Err(e) = config::write_template(file, *force) {
+                eprintln!("Error: {}", e);
+                std::process::exit(2);
+            }
+            println!("Wrote {}", file);
+            return;
+        }
+        Some(Command::Power {
+            baseline,
+            candidate,
+            alpha,
+            power,
+            effect_size,
+        }) => {
+            if let Err(e) = power::run(baseline, candidate, *alpha, *power, *effect_size) {
+                eprintln!("Error: {}", e);
+                std::process::exit(2);
+            }
+            return;
+        }
+        Some(Command::Summarize {
+            files,
+            confidence,
+            resamples,
+            maximize,
+        }) => {
+            if let Err(e) = summarize(files, *confidence, *resamples, direction(*maximize)) {
+                eprintln!("Error: {}", e);
+                std::process::exit(2);
+            }
+            return;
+        }
+        Some(Command::Sensitivity {
+            config,
+            problem,
+            starts,
+            eps,
+            jobs,
+            output,
+        }) => {
+            let result = load_config(
+                config.as_deref(),
+                serde_json::Map::new(),
+                problem.as_deref(),
+            )
+            .and_then(|c| {
+                sensitivity::run(
+                    &c,
+                    *starts,
+                    *eps,
+                    *jobs,
+                    output.as_deref(),
+                    &interrupt_token(),
+                )
+            });
+            if let Err(e) = result {
+                eprintln!("Error: {}", e);
+                std::process::exit(2);
+            }
+            return;
+        }
+        Some(Command::Rng {
+            seed,
+            iteration,
+            particle,
+            count,
+        }) => {
+            print_stream(*seed, *iteration, *particle, *count);
+            return;
+        }
+        Some(Command::AxisBias {
+            config,
+            runs,
+            threshold,
+            rotation_seed,
+        }) => {
+            let result = load_config(config.as_deref(), serde_json::Map::new(), None)
+                .and_then(|c| axis_bias::run(c, *runs, *threshold, *rotation_seed));
+            if let Err(e) = result {
+                eprintln!("Error: {}", e);
+                std::process::exit(2);
+            }
+            return;
+        }
+        Some(Command::AuditVerify { file }) => {
+            match audit::verify(file) {
+                Ok((records, _)) => println!("{}: {} record(s), chain intact", file, records),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        Some(Command::Bands {
+            files,
+            by,
+            bands,
+            output,
+            maximize,
+        }) => {
+            if let Err(e) = bands::run(files, *by, bands, direction(*maximize), output) {
+                eprintln!("Error: {}", e);
+                std::process::exit(2);
+            }
+            return;
+        }
+        Some(Command::Batch {
+            manifest,
+            export_slurm,
+            jobs,
+            force,
+        }) => {
+            let result = if *export_slurm {
+                batch::export_slurm(manifest)
+            } else {
+                batch::execute(
+                    manifest,
+                    multirun::jobs(*jobs, 1),
+                    *force,
+                    &interrupt_token(),
+                )
+            };
+            if let Err(e) = result {
+                eprintln!("Error: {}", e);
+                std::process::exit(2);
+            }
+            return;
+        }
+        Some(Command::Collect { dir, output }) => {
+            if let Err(e) = batch::collect(dir, output.as_deref()) {
+                eprintln!("Error: {}", e);
+                std::process::exit(2);
+            }
+            return;
+        }
+        Some(Command::Sweep {
+            config,
+            c1,
+            c2,
+            inertia,
+            particles,
+            runs,
+            jobs,
+            output,
+        }) => {
+            let values =
+                |spec: &Option<String>| spec.as_deref().map_or(Ok(Vec::new()), sweep::parse_values);
+            let grid = values(c1).and_then(|c1| {
+                Ok(sweep::SweepGrid {
+                    c1,
+                    c2: values(c2)?,
+                    w: values(inertia)?,
+                    particles: particles
+                        .as_deref()
+                        .map_or(Ok(Vec::new()), sweep::parse_counts)?,
+                })
+            });
+            let result = grid.and_then(|grid| {
+                let config = load_config(config.as_deref(), serde_json::Map::new(), None)?;
+                sweep::run(
+                    &config,
+                    &grid,
+                    *runs,
+                    *jobs,
+                    output.as_deref(),
+                    &interrupt_token(),
+                )
+            });
+            if let Err(e) = result {
+                eprintln!("Error: {}", e);
+                std::process::exit(2);
+            }
+            return;
+        }
+        Some(Command::Plugins) => {
+            list_plugins();
+            return;
+        }
+        Some(Command::Bench { action }) => {
+            let result = match action {
+                BenchCommand::Run {
+                    config,
+                    runs,
+                    jobs,
+                    commit,
+                    comparison: c,
+                } => load_config(config.as_deref(), serde_json::Map::new(), None).and_then(
+                    |config| {
+                        let baseline = c.baseline.as_deref();
+                        let tolerances = c.tolerances();
+                        bench::run(
+                            &config,
+                            *runs,
+                            *jobs,
+                            commit.as_deref(),
+                            &c.history,
+                            baseline,
+                            tolerances,
+                        )
+                    },
+                ),
+                BenchCommand::History { comparison: c } => {
+                    bench::history(&c.history, c.baseline.as_deref(), c.tolerances())
+                }
+            };
+            match result {
+                Ok(true) => {}
+                Ok(false) => std::process::exit(1),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(2);
+                }
+            }
+            return;
+        }
+        Some(Command::DiffCheckpoint { a, b }) => {
+            if let Err(e) = checkpoint::diff(a, b) {
+                eprintln!("Error: {}", e);
+                std::process::exit(2);
+            }
+            return;
+        }
+        None => {}
+    }
+
+    let mut config = load_config(
+        cli.config.as_deref(),
+        cli.overrides(),
+        cli.problem.as_deref(),
+    )
+    .unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        std::process::exit(2);
+    });
+    let log_given = matches.value_source("output") != Some(ValueSource::DefaultValue);
+    apply_output_paths(&mut cli, &config.output, log_given);
+    if cli.paper_mode {
+        if let Err(e) = paper_mode(&mut config, &mut cli) {
+            eprintln!("Error: {}", e);
+            std::process::exit(2);
+        }
+    }
+
+    if cli
+        .heatmap_dims
+        .iter()
+        .any(|&d| d == 0 || d > config.dimensions)
+        || cli.heatmap_bins == 0
+    {
+        eprintln!(
+            "Error: heatmap dimensions must be between 1 and {} and bins must be positive",
+            config.dimensions
+        );
+        std::process::exit(2);
+    }
+
+    if cli
+        .dp_epsilon
+        .is_some_and(|e| e.partial_cmp(&0.0) != Some(std::cmp::Ordering::Greater))
+        || cli.dp_sensitivity.partial_cmp(&0.0) != Some(std::cmp::Ordering::Greater)
+    {
+        eprintln!("Error: --dp-epsilon and --dp-sensitivity must be positive");
+        std::process::exit(2);
+    }
+
+    if (cli.plot.is_some() || cli.animate.is_some()) && !cfg!(feature = "plot") {
+        eprintln!(
+            "Error: --plot and --animate need the `plot` feature \
+             (rebuild with `cargo build --features plot`)"
+        );
+        std::process::exit(2);
+    }
+    if cli.animate.is_some() && config.dimensions != 2 {
+        eprintln!(
+            "Error: --animate needs a 2-dimensional problem, not {} dimensions",
+            config.dimensions
+        );
+        std::process::exit(2);
+    }
+
+    if cli.log_error && config.optimum().is_none() {
+        eprintln!(
+            "Error: --log-error needs a known optimum, which {} does not have",
+            config.objective_name()
+        );
+        std::process::exit(2);
+    }
+
+    if cli.threads.is_some() && !config.algorithm.capabilities().batch_evaluation {
+        eprintln!(
+            "Error: --threads needs an algorithm that evaluates in batches, not {}",
+            config.algorithm.name()
+        );
+        std::process::exit(2);
+    }
+    if cli.threads.is_some() && cli.explain {
+        eprintln!("Error: --threads cannot be combined with --explain");
+        std::process::exit(2);
+    }
+
+    // an objective that runs threads of its own multiplies the workers
+    if config.objective_threads.is_some() {
+        let runs = if cli.runs > 1 {
+            cli.jobs.unwrap_or(1) as usize
+        } else {
+            1
+        };
+        let busy = runs * cli.threads.unwrap_or(1) as usize * config.evaluation_threads();
+        if busy > multirun::cores() {
+            eprintln!(
+                "Warning: the evaluations run {} threads on {} core(s); lower --threads, \
+                 --jobs or objective_threads to avoid oversubscribing them",
+                busy,
+                multirun::cores()
+            );
+        }
+    }
+
+    if config.algorithm != Algorithm::Pso {
+        let swarm_only = [
+            (cli.interactive, "--interactive"),
+            (cli.web, "--web"),
+            (cli.explain, "--explain"),
+            (cli.diagnostics.is_some(), "--diagnostics"),
+            (cli.heatmap.is_some(), "--heatmap"),
+            (cli.projection.is_some(), "--projection"),
+            (cli.improvements.is_some(), "--improvements"),
+            (cli.events.is_some(), "--events"),
+            (cli.checkpoint.is_some(), "--checkpoint"),
+            (cli.resume.is_some(), "--resume"),
+            (cli.warm_start.is_some(), "--warm-start"),
+            (cli.animate.is_some(), "--animate"),
+            (cli.disperse_duplicates, "--disperse-duplicates"),
+        ];
+        if let Some((_, flag)) = swarm_only.iter().find(|(given, _)| *given) {
+            eprintln!(
+                "Error: {} needs the pso algorithm, not {}",
+                flag,
+                config.algorithm.name()
+            );
+            std::process::exit(2);
+        }
+    }
+    // every stage opens its own evaluation hooks, which would restart the logs
+    if matches!(config.algorithm, Algorithm::Pipeline { .. }) {
+        let per_stage = [
+            (cli.audit.is_some(), "--audit"),
+            (cli.trace_evaluations.is_some(), "--trace-evaluations"),
+        ];
+        if let Some((_, flag)) = per_stage.iter().find(|(given, _)| *given) {
+            eprintln!("Error: {} cannot be combined with a pipeline", flag);
+            std::process::exit(2);
+        }
+    }
+
+    if cli.summary.is_some() && cli.runs == 1 {
+        eprintln!("Error: --summary needs --runs with more than one run");
+        std::process::exit(2);
+    }
+    if !(cli.cluster_eps > 0.0 && cli.cluster_eps.is_finite()) {
+        eprintln!(
+            "Error: --cluster-eps must be positive, got {}",
+            cli.cluster_eps
+        );
+        std::process::exit(2);
+    }
+    if cli.ecdf.is_some() && (cli.runs == 1 || config.optimum().is_none()) {
+        eprintln!("Error: --ecdf needs --runs with more than one run and a known optimum");
+        std::process::exit(2);
+    }
+
+    println!("Rastrigin using Particle Swarm Optimization");
+    println!("===========================================\n");
+
+    print_params(&config, &cli);
+    if cli.dry_run {
+        let ok = preflight::run(&config);
+        std::process::exit(if ok { 0 } else { 1 });
+    }
+
+    let options = cli.options();
+    if cli.runs > 1 {
+        let start = std::time::Instant::now();
+        run_many(&config, &cli, &options);
+        print_resource_usage(start.elapsed());
+        return;
+    }
+
+    output::start(&config, &options);
+
+    let start = std::time::Instant::now();
+    let outcome = if cli.interactive {
+        let mut run = Run::new(config.clone(), &options).unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            std::process::exit(2);
+        });
+        repl::drive(&mut run);
+        Box::new(run).finish()
+    } else {
+        // Ctrl-C stops after the current iteration and still reports the
+        // best solution; a second Ctrl-C exits at once
+        let handle = RunHandle::start(config.clone(), options.clone()).unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            std::process::exit(2);
+        });
+        cancel_on_interrupt(handle.token());
+        handle.join()
+    };
+    output::close();
+    exit_on_objective_failure(&config);
+    if let Some(path) = &cli.plot {
+        if let Err(e) = plot::convergence(path, &outcome.history, &outcome.events) {
+            eprintln!("Warning: {}", e);
+        }
+    }
+    if cli.format == output::Format::Json {
+        output::save_json(&config, &options, &outcome, privacy::from_options(&options));
+    }
+    if let Some(path) = &cli.results {
+        if let Err(e) = multirun::save_results(path, &[config.reported(outcome.best)]) {
+            eprintln!("Error: {}", e);
+            std::process::exit(2);
+        }
+    }
+
+    if cli.web && std::io::stdin().is_terminal() {
+        println!("\nPress Enter to stop the live view server");
+        let _ = std::io::stdin().read_line(&mut String::new());
+    }
+
+    print_resource_usage(start.elapsed());
+}
+
+// The config's output paths, for the flags not given
+fn apply_output_paths(cli: &mut Cli, paths: &OutputPaths, log_given: bool) {
+    if let (Some(log), false) = (&paths.log, log_given) {
+        cli.output = log.clone();
+    }
+    let defaults = [
+        (&mut cli.results, &paths.results),
+        (&mut cli.convergence, &paths.convergence),
+        (&mut cli.summary, &paths.summary),
+        (&mut cli.ecdf, &paths.ecdf),
+    ];
+    for (flag, path) in defaults {
+        if flag.is_none() {
+            flag.clone_from(path);
+        }
+    }
+}
+
+// `--paper-mode`: fills in what the config and flags leave open with the
+// settings of a reproducible benchmark study
+fn paper_mode(config: &mut Config, cli: &mut Cli) -> Result<(), String> {
+    if config.problem.is_none() && config.objective_fn.is_none() {
+        let canonical = config.function.defaults();
+        if config.bounds.is_some() {
+            return Err(format!(
+                "--paper-mode uses the canonical bounds of {}, not per-dimension bounds",
+                config.function.name()
+            ));
+        }
+        if (config.lower_bound, config.upper_bound)
+            != (canonical.lower_bound, canonical.upper_bound)
+        {
+            return Err(format!(
+                "--paper-mode uses the canonical bounds of {}, [{}, {}], not [{}, {}]",
+                config.function.name(),
+                canonical.lower_bound,
+                canonical.upper_bound,
+                config.lower_bound,
+                config.upper_bound
+            ));
+        }
+    }
+    if config.algorithm.runs(|a| *a == Algorithm::Pso) {
+        config.synchronous = true;
+        config.random_per_dimension = true;
+    }
+    config.seed.get_or_insert(1);
+    // enough iterations that the budget, not the iteration count, ends a PSO run
+    let budget = *config
+        .stop
+        .max_evaluations
+        .get_or_insert(10_000 * config.dimensions);
+    config.iterations = config.iterations.max(budget.div_ceil(config.particles));
+    config.validate()?;
+
+    if cli.runs == 1 {
+        cli.runs = 30;
+    }
+    cli.summary
+        .get_or_insert_with(|| "summary.json".to_string());
+    if config.optimum().is_some() {
+        cli.ecdf.get_or_insert_with(|| "ecdf.csv".to_string());
+    }
+    Ok(())
+}
+
+fn load_config(
+    path: Option<&str>,
+    overrides: serde_json::Map<String, serde_json::Value>,
+    problem: Option<&str>,
+) -> Result<Config, String> {
+    // `fixed` names the problem's variables, so with a problem file the
+    // config is only checked once the problem is applied
+    let mut config = match path {
+        Some(path) => Config::read_with(path, overrides)?,
+        None => Config::parse(&serde_json::Value::Object(overrides))?,
+    };
+    match problem {
+        Some(problem) => {
+            let problem = Problem::load(problem, &config)?;
+            config.apply_problem(problem)?;
+        }
+        None => {
+            let invalid = |e: String| match path {
+                Some(path) => format!("invalid config file {}: {}", path, e),
+                None => e,
+            };
+            config.validate().map_err(invalid)?;
+            config.prepare();
+        }
+    }
+    Ok(config)
+}
+
+// `--runs`: parallel headless runs, then the statistics of their results
+fn run_many(config: &Config, cli: &Cli, options: &RunOptions) {
+    let per_run = [
+        (cli.interactive, "--interactive"),
+        (cli.web, "--web"),
+        (cli.explain, "--explain"),
+        (cli.diagnostics.is_some(), "--diagnostics"),
+        (cli.heatmap.is_some(), "--heatmap"),
+        (cli.projection.is_some(), "--projection"),
+        (cli.improvements.is_some(), "--improvements"),
+        (cli.events.is_some(), "--events"),
+        (cli.checkpoint.is_some(), "--checkpoint"),
+        (cli.resume.is_some(), "--resume"),
+        (cli.plot.is_some(), "--plot"),
+        (cli.animate.is_some(), "--animate"),
+        (cli.audit.is_some(), "--audit"),
+        (cli.trace_evaluations.is_some(), "--trace-evaluations"),
+    ];
+    if let Some((_, flag)) = per_run.iter().find(|(given, _)| *given) {
+        eprintln!("Error: {} cannot be combined with --runs", flag);
+        std::process::exit(2);
+    }
+
+    let runs = cli.runs as usize;
+    let jobs = multirun::jobs(
+        cli.jobs,
+        config.evaluation_threads() * cli.threads.unwrap_or(1) as usize,
+    );
+    println!("\nExecuting {} runs on {} thread(s)", runs, jobs.min(runs));
+
+    let token = interrupt_token();
+
+    let records = multirun::run(config, options, runs, jobs, &token);
+    exit_on_objective_failure(config);
+    if records.len() < runs && !token.is_cancelled() {
+        eprintln!(
+            "Error: {} of {} runs could not start",
+            runs - records.len(),
+            runs
+        );
+        std::process::exit(2);
+    }
+    if records.len() < runs {
+        println!(
+            "Cancelled: {} of {} runs completed; the {} cut short or not started are left \
+             out of the results and statistics",
+            records.len(),
+            runs,
+            runs - records.len()
+        );
+    }
+    let values: Vec<f64> = records.iter().map(|r| r.best).collect();
+    if let Some(path) = &cli.results {
+        if let Err(e) = multirun::save_results(path, &values) {
+            eprintln!("Error: {}", e);
+            std::process::exit(2);
+        }
+    }
+    if !values.is_empty() {
+        println!("\nSummary of the final gbest:");
+        let statistics = stats::Summary::of(&values, 10000, 0.95, &mut rand::thread_rng());
+        statistics.toward(config.objective).print();
+        let summary = ExperimentSummary::of(&records, config);
+        summary.print();
+        let min_runs = cli.cluster_min_runs as usize;
+        let (clusters, noise) = cluster::of_runs(config, &records, cli.cluster_eps, min_runs);
+        cluster::print(&records, &clusters, &noise, cli.cluster_eps);
+        if let Some(path) = &cli.summary {
+            if let Err(e) = summary.save(path) {
+                eprintln!("Error: {}", e);
+                std::process::exit(2);
+            }
+        }
+        if let (Some(path), Some(optimum)) = (&cli.ecdf, config.optimum()) {
+            if let Err(e) = ecdf::save(path, &records, optimum, config.objective) {
+                eprintln!("Error: {}", e);
+                std::process::exit(2);
+            }
+        }
+    }
+}
+
+// `rng`: the first uniform draws of a particle's stream; with the standard
+// update the first two are its r1 and r2 (of the first dimension with
+// random_per_dimension, whose later pairs follow)
+fn print_stream(seed: u64, iteration: usize, particle: usize, count: usize) {
+    let mut rng = pso::particle_stream(seed, iteration, particle);
+    println!(
+        "Stream of particle {} at iteration {} (seed {}):",
+        particle, iteration, seed
+    );
+    for k in 1..=count {
+        println!("  {}: {}", k, rng.gen::<f64>());
+    }
+}
+
+// `plugins`: every library in the plugin directories with its registrations
+fn list_plugins() {
+    let libraries = plugin::discover();
+    if libraries.is_empty() {
+        println!(
+            "No plugin libraries in the plugin directories ({} and ./plugins)",
+            plugin::DIRECTORIES
+        );
+        return;
+    }
+    for path in libraries {
+        let path = path.to_string_lossy();
+        match plugin::registrations(&path) {
+            Ok((objectives, optimizers)) => {
+                println!("{}:", path);
+                println!("  Objectives: {}", objectives.join(", "));
+                println!("  Optimizers: {}", optimizers.join(", "));
+            }
+            Err(e) => eprintln!("Warning: {}", e),
+        }
+    }
+}
+
+// The objective direction of a subcommand's --maximize flag
+fn direction(maximize: bool) -> Objective {
+    if maximize {
+        Objective::Maximize
+    } else {
+        Objective::Minimize
+    }
+}
+
+fn summarize(
+    files: &[String],
+    confidence: f64,
+    resamples: usize,
+    objective: Objective,
+) -> Result<(), String> {
+    if !(confidence > 0.0 && confidence < 1.0) || resamples == 0 {
+        return Err("confidence must be within (0, 1) and resamples positive".to_string());
+    }
+
+    let mut rng = rand::thread_rng();
+    for path in files {
+        let values = power::read_results(path)?;
+        println!("{}:", path);
+        stats::Summary::of(&values, resamples, confidence, &mut rng)
+            .toward(objective)
+            .print();
+    }
+    Ok(())
+}
+
+fn print_params(config: &Config, cli: &Cli) {
+    println!("Parameters:");
+    println!("  Function: {}", config.objective_name());
+    if config.algorithm != Algorithm::Pso {
+        println!("  Algorithm: {}", config.algorithm.describe());
+    }
+    println!("  Number of particles: {}", config.particles);
+    println!("  Number of iterations: {}", config.iterations);
+    println!("  Number of dimensions: {}", config.dimensions);
+    if config.algorithm.runs(|a| *a == Algorithm::Pso) {
+        match &config.inertia_schedule {
+            Some(inertia) => println!("  Inertia weight: {}", inertia.describe()),
+            None => println!("  Inertia weight: {}", config.w.describe()),
+        }
+        println!("  Cognitive weight: {}", config.c1.describe());
+        println!("  Social weight: {}", config.c2.describe());
+    }
+    match &config.problem {
+        Some(problem) => {
+            println!(
+                "  Problem: {} ({} variable(s), {} integer, {} constraint(s))",
+                problem.path,
+                problem.variables.len(),
+                problem.integer_variables(),
+                problem.constraints.len()
+            );
+            for (pos, v) in problem.variables.iter().enumerate() {
+                match config.fixed_value(pos) {
+                    Some(fixed) => println!("    {}: fixed at {}", v.name, fixed),
+                    None => println!(
+                        "    {}: [{}, {}] {:?}",
+                        v.name, v.lower_bound, v.upper_bound, v.kind
+                    ),
+                }
+            }
+            for c in &problem.constraints {
+                println!("    subject to {}", c.text);
+            }
+        }
+        None => match &config.bounds {
+            Some(bounds) => {
+                let pairs: Vec<String> = bounds
+                    .iter()
+                    .map(|(lower, upper)| format!("[{}, {}]", lower, upper))
+                    .collect();
+                println!("  Bounds per dimension: {}", pairs.join(" "));
+            }
+            None => println!(
+                "  Lower and Upper bounds: [{}, {}]",
+                config.lower_bound, config.upper_bound
+            ),
+        },
+    }
+    if config.problem.is_none() && !config.fixed.is_empty() {
+        let fixed: Vec<String> = config
+            .fixed
+            .iter()
+            .map(|(name, value)| format!("{} = {}", name, value))
+            .collect();
+        println!("  Fixed: {}", fixed.join(", "));
+    }
+    if config.objective == Objective::Maximize {
+        println!("  Objective: maximized");
+    }
+    if let Some(r) = config.deterministic {
+        println!("  Deterministic r1 = r2 = {}", r);
+    }
+    if let Some(seed) = config.seed {
+        println!("  Seed: {}", seed);
+    }
+    if config.velocity_init != init::VelocityInit::FullRange {
+        println!(
+            "  Velocity initialization: {}",
+            config.velocity_init.describe()
+        );
+    }
+    if config.boundary != BoundaryHandling::Clamp {
+        println!("  Boundary handling: {}", config.boundary.describe());
+    }
+    if config.topology != Topology::Global {
+        println!("  Topology: {}", config.topology.describe());
+    }
+    if config.comparison != comparator::Comparator::Strict {
+        println!("  Comparison: {}", config.comparison.describe());
+    }
+    if let Some(adaptive) = &config.adaptive_penalty {
+        println!("  Adaptive penalty: {}", adaptive.describe());
+    }
+    if let Some(tolerance) = &config.equality_tolerance {
+        println!("  Equality tolerance: {}", tolerance.describe());
+    }
+    if config.linear_repair {
+        match repair::Polytope::of(config) {
+            Some(polytope) => println!(
+                "  Linear repair: {} constraint(s) projected onto, {} nonlinear penalized",
+                polytope.linear(),
+                polytope.nonlinear
+            ),
+            None => println!("  Linear repair: no linear constraints, nothing to project onto"),
+        }
+    }
+    if let Some(seed) = config.rotation_seed {
+        println!("  Rotated search space (seed {})", seed);
+    }
+    if config.velocity_update != config::VelocityUpdate::Standard {
+        println!("  Velocity update: {}", config.velocity_update.name());
+    }
+    if let Some(d) = &config.random_coefficients {
+        println!("  r1, r2 ~ {}", d.describe());
+    }
+    if let Some(vmax) = &config.vmax {
+        println!("  Velocity limit: {}", vmax.describe());
+    }
+    if let Some(f) = config.vmax_fraction {
+        println!("  Velocity limit: {} of each range", f);
+    }
+    if let Some(schedule) = &config.constriction {
+        println!("  Constriction: {}", schedule.describe());
+    }
+    if let Some(sa) = &config.self_adaptive {
+        println!(
+            "  Self-adaptive coefficients: w in {:?}, c1 in {:?}, c2 in {:?}",
+            sa.w, sa.c1, sa.c2
+        );
+    }
+    if let Some(sub) = &config.dimension_subset {
+        println!(
+            "  Dimension subset: {} of {} per {}, redrawn every {} iteration(s)",
+            sub.size,
+            config.dimensions,
+            if sub.shared { "swarm" } else { "particle" },
+            sub.resample_every
+        );
+    }
+    if let Some(block) = &config.block_coordinate {
+        if block.groups.is_empty() {
+            println!(
+                "  Block-coordinate groups: differential grouping, {} iteration(s) each",
+                block.iterations_per_group
+            );
+        } else {
+            println!(
+                "  Block-coordinate groups: {:?}, {} iteration(s) each",
+                block.groups, block.iterations_per_group
+            );
+        }
+    }
+    if let Some(h) = &config.heterogeneous {
+        println!(
+            "  Heterogeneous coefficients: w ~ {}, c1 ~ {}, c2 ~ {}",
+            h.w.describe(),
+            h.c1.describe(),
+            h.c2.describe()
+        );
+    }
+    if let Some(rate) = config.max_evaluations_per_second {
+        println!("  Evaluation rate limit: {} per second", rate);
+    }
+    if let Some(threads) = config.objective_threads {
+        println!("  Threads per evaluation: {}", threads);
+    }
+    if let Some(cost) = config.evaluation_cost {
+        println!("  Cost per evaluation: {}", cost);
+    }
+    if let Some(archive) = &config.archive {
+        println!(
+            "  Elite archive: {} members, injected with probability {}",
+            archive.size, archive.injection_probability
+        );
+        if archive.warm_restart {
+            println!("  Restarts sample from a Gaussian fitted to the archive");
+        }
+    }
+    if config.generational {
+        println!("  Generational (mu+lambda) replacement of positions");
+    }
+    if config.synchronous {
+        println!("  Synchronous updates: the bests change between iterations");
+    }
+    if config.random_per_dimension {
+        println!("  r1 and r2 drawn per dimension");
+    }
+    if config.rng_streams {
+        println!("  Random numbers from per-(iteration, particle) streams");
+    }
+    if let Some(mf) = &config.multi_fidelity {
+        println!(
+            "  Multi-fidelity: {} of {} terms at low fidelity (cost {})",
+            mf.components, config.dimensions, mf.low_cost
+        );
+    }
+    if let Some(noise) = &config.noise {
+        println!(
+            "  Objective noise: {}, {:?} sampling (seed {})",
+            noise
+                .distribution
+                .as_ref()
+                .map_or_else(|| "drawn by the objective".to_string(), |d| d.describe()),
+            noise.sampling,
+            noise.seed.unwrap_or(0)
+        );
+    }
+    if let Some(budget) = config.stop.cost_budget {
+        println!("  Cost budget: {}", budget);
+    }
+    if let Some(collapse) = &config.stop.velocity_collapse {
+        println!(
+            "  Velocity collapse: {:?} when mean speed < {} for {} iteration(s)",
+            collapse.action, collapse.threshold, collapse.iterations
+        );
+    }
+    if let Some(m) = &config.escape.gbest_mutation {
+        println!(
+            "  Escape: every {} stagnant iteration(s), move the {} worst particle(s) to gbest mutated by {} of the range",
+            m.after, m.particles, m.sigma
+        );
+    }
+    if let Some(o) = &config.escape.opposition {
+        println!(
+            "  Escape: every {} stagnant iteration(s), re-seed at opposite points",
+            o.after
+        );
+    }
+    if let Some(r) = &config.escape.partial_restart {
+        println!(
+            "  Escape: every {} stagnant iteration(s), re-initialize all but the {} best particle(s)",
+            r.after, r.keep
+        );
+    }
+    if let Some(l) = &config.local_search {
+        let when = match l.every {
+            Some(every) => format!("every {} iteration(s)", every),
+            None => "at the end of the run".to_string(),
+        };
+        println!(
+            "  Local search: {} from gbest {}, {} evaluations each, step {} of the range",
+            l.method.name(),
+            when,
+            l.evaluations,
+            l.step
+        );
+    }
+    if let Some(target) = &config.stop.target {
+        println!(
+            "  Target fitness: {} (tolerance {})",
+            target.fitness, target.tolerance
+        );
+    }
+    if let Some(stagnation) = &config.stop.stagnation {
+        println!(
+            "  Stagnation: stop after {} iteration(s) without improving by more than {}",
+            stagnation.iterations, stagnation.min_improvement
+        );
+    }
+    if let Some(seconds) = config.stop.max_seconds {
+        println!("  Time limit: {} s", seconds);
+    }
+    if let Some(evaluations) = config.stop.max_evaluations {
+        println!("  Evaluation limit: {}", evaluations);
+    }
+    if let Some(epsilon) = cli.dp_epsilon {
+        println!(
+            "  Log noise: Laplace, epsilon {}, sensitivity {} (audit log stays exact)",
+            epsilon, cli.dp_sensitivity
+        );
+    }
+    for (key, value) in &cli.tags {
+        println!("  Tag {}: {}", key, value);
+    }
+    println!();
+}
+
+fn print_resource_usage(wall_time: std::time::Duration) {
+    println!("\nResources:");
+    println!("  Wall-clock time: {:.3} s", wall_time.as_secs_f64());
+    match resources::current() {
+        Some(usage) => {
+            println!(
+                "  CPU time: {:.3} s (user {:.3} s, system {:.3} s)",
+                usage.cpu_time().as_secs_f64(),
+                usage.user_time.as_secs_f64(),
+                usage.system_time.as_secs_f64()
+            );
+            println!(
+                "  Peak RSS: {:.1} MiB",
+                usage.peak_rss_bytes as f64 / (1024.0 * 1024.0)
+            );
+        }
+        None => println!("  CPU time and peak RSS: unavailable on this platform"),
+    }
+}
@@ -0,0 +1,196 @@
+// Desc: DBSCAN clustering of final solutions, to tell the distinct optima
+// that several runs ended in apart. Positions are compared in the unit box:
+// each coordinate divided by its range, so `eps` is a fraction of the ranges.
+use crate::config::Config;
+use crate::experiment::RunRecord;
+
+// The members of one cluster, by index into the clustered points, and the
+// best of them
+#[derive(Clone, Debug)]
+pub struct Cluster {
+    pub members: Vec<usize>,
+    pub best: usize,
+}
+
+// Clusters of the objective coordinates of `points`, best cluster first by
+// the fitness of its best member (lower `fitness` is better), and the points
+// left as noise. With `min_points` = 1 every point belongs to a cluster.
+pub fn clusters(
+    config: &Config,
+    points: &[Vec<f64>],
+    fitness: &[f64],
+    eps: f64,
+    min_points: usize,
+) -> (Vec<Cluster>, Vec<usize>) {
+    let scaled: Vec<Vec<f64>> = points
+        .iter()
+        .map(|x| {
+            (0..config.dimensions)
+                .map(|pos| {
+                    let (lower, upper) = config.bounds_of(pos);
+                    (x[pos] - lower) / (upper - lower)
+                })
+                .collect()
+        })
+        .collect();
+    let labels = dbscan(&scaled, eps, min_points);
+
+    let count = labels.iter().flatten().max().map_or(0, |k| k + 1);
+    let mut members: Vec<Vec<usize>> = vec![Vec::new(); count];
+    let mut noise = Vec::new();
+    for (i, label) in labels.into_iter().enumerate() {
+        match label {
+            Some(k) => members[k].push(i),
+            None => noise.push(i),
+        }
+    }
+    let mut clusters: Vec<Cluster> = members
+        .into_iter()
+        .map(|members| Cluster {
+            best: members
+                .iter()
+                .copied()
+                .min_by(|&a, &b| fitness[a].total_cmp(&fitness[b]))
+                .expect("clusters have members"),
+            members,
+        })
+        .collect();
+    clusters.sort_by(|a, b| fitness[a.best].total_cmp(&fitness[b.best]));
+    (clusters, noise)
+}
+
+// Clusters of the final positions of `records`, ranked by their final fitness
+pub fn of_runs(
+    config: &Config,
+    records: &[RunRecord],
+    eps: f64,
+    min_points: usize,
+) -> (Vec<Cluster>, Vec<usize>) {
+    let positions: Vec<Vec<f64>> = records.iter().map(|r| r.position.clone()).collect();
+    // the records hold the objective's own values; clusters rank minimized ones
+    let minimized: Vec<f64> = records.iter().map(|r| config.internal(r.best)).collect();
+    clusters(config, &positions, &minimized, eps, min_points)
+}
+
+// The distinct optima of `records`, one line per cluster, best first
+pub fn print(records: &[RunRecord], clusters: &[Cluster], noise: &[usize], eps: f64) {
+    println!(
+        "  Distinct optima (within {} of the ranges): {}",
+        eps,
+        clusters.len()
+    );
+    for (k, c) in clusters.iter().enumerate() {
+        let best = &records[c.best];
+        println!(
+            "    Optimum {}: {} run(s), best fitness {} at {:?}",
+            k + 1,
+            c.members.len(),
+            best.best,
+            best.position
+        );
+    }
+    if !noise.is_empty() {
+        println!("    Runs in no cluster (noise): {}", noise.len());
+    }
+}
+
+// Cluster label per point, in order of discovery, None for noise: a point with
+// at least `min_points` points (itself included) within `eps` is a core
+// point, and a cluster is what its core points reach
+fn dbscan(points: &[Vec<f64>], eps: f64, min_points: usize) -> Vec<Option<usize>> {
+    let neighbours = |i: usize| -> Vec<usize> {
+        (0..points.len())
+            .filter(|&j| distance(&points[i], &points[j]) <= eps)
+            .collect()
+    };
+    let mut labels: Vec<Option<usize>> = vec![None; points.len()];
+    let mut visited = vec![false; points.len()];
+    let mut next = 0;
+    for i in 0..points.len() {
+        if visited[i] {
+            continue;
+        }
+        visited[i] = true;
+        let around = neighbours(i);
+        if around.len() < min_points {
+            continue;
+        }
+        labels[i] = Some(next);
+        let mut frontier = around;
+        while let Some(j) = frontier.pop() {
+            if labels[j].is_none() {
+                labels[j] = Some(next);
+            }
+            if visited[j] {
+                continue;
+            }
+            visited[j] = true;
+            let reach = neighbours(j);
+            if reach.len() >= min_points {
+                frontier.extend(reach);
+            }
+        }
+        next += 1;
+    }
+    labels
+}
+
+fn distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - y) * (x - y))
+        .sum::<f64>()
+        .sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearby_solutions_share_a_cluster() {
+        let mut config = Config::default();
+        config.dimensions = 2;
+        config.lower_bound = 0.0;
+        config.upper_bound = 10.0;
+        let points = vec![
+            vec![1.0, 1.0],
+            vec![8.0, 8.0],
+            vec![1.2, 1.0],
+            vec![8.1, 7.9],
+            vec![5.0, 0.0],
+        ];
+        let fitness = [3.0, 1.0, 2.0, 4.0, 0.5];
+        let (found, noise) = clusters(&config, &points, &fitness, 0.05, 1);
+        let members: Vec<&[usize]> = found.iter().map(|c| c.members.as_slice()).collect();
+        assert_eq!(members, [&[4][..], &[1, 3], &[0, 2]]);
+        assert_eq!(found[2].best, 2);
+        assert!(noise.is_empty());
+
+        // the lone solution is noise once a cluster needs two
+        let (found, noise) = clusters(&config, &points, &fitness, 0.05, 2);
+        assert_eq!(found.len(), 2);
+        assert_eq!(noise, [4]);
+    }
+
+    #[test]
+    fn the_largest_optimum_ranks_first_when_maximizing() {
+        let mut config = Config::default();
+        config.dimensions = 1;
+        config.objective = crate::config::Objective::Maximize;
+        let record = |x: f64, best: f64| RunRecord {
+            best,
+            evaluations: 0,
+            iterations: 0,
+            converged_at: 0,
+            stopped: None,
+            trace: Vec::new(),
+            position: vec![x],
+        };
+        let records = [record(-4.0, 1.0), record(4.0, 9.0), record(4.01, 8.0)];
+        let (found, _) = of_runs(&config, &records, 0.05, 1);
+        assert_eq!(found[0].members, [1, 2]);
+        assert_eq!(found[0].best, 1);
+        assert_eq!(found[1].members, [0]);
+    }
+}
@@ -0,0 +1,252 @@
+// Desc: CMA-ES (Hansen): samples from a Gaussian whose mean, step size and
+// covariance adapt to the selected samples
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand_distr::StandardNormal;
+
+use crate::comparator::Score;
+use crate::config::Config;
+use crate::convergence::TracePoint;
+use crate::linalg;
+use crate::optimizer::{self, Optimizer, Outcome, Tracker};
+use crate::options::RunOptions;
+use crate::sampling;
+use crate::selection;
+
+// Strategy constants of the default CMA-ES, fixed by the dimension and the
+// number of parents
+struct Constants {
+    weights: Vec<f64>, // recombination weights of the mu best samples
+    mu_eff: f64,
+    c_sigma: f64,
+    d_sigma: f64,
+    c_c: f64,
+    c_1: f64,
+    c_mu: f64,
+    chi_n: f64, // expected length of an n-dimensional standard normal vector
+}
+
+impl Constants {
+    fn new(n: usize, lambda: usize) -> Self {
+        let mu = (lambda / 2).max(1);
+        let raw: Vec<f64> = (0..mu)
+            .map(|i| ((lambda as f64 + 1.0) / 2.0).ln() - ((i + 1) as f64).ln())
+            .collect();
+        let total: f64 = raw.iter().sum();
+        let weights: Vec<f64> = raw.iter().map(|w| w / total).collect();
+        let mu_eff = 1.0 / weights.iter().map(|w| w * w).sum::<f64>();
+
+        let n = n as f64;
+        let c_sigma = (mu_eff + 2.0) / (n + mu_eff + 5.0);
+        let c_1 = 2.0 / ((n + 1.3).powi(2) + mu_eff);
+        Constants {
+            weights,
+            mu_eff,
+            c_sigma,
+            d_sigma: 1.0 + 2.0 * (((mu_eff - 1.0) / (n + 1.0)).sqrt() - 1.0).max(0.0) + c_sigma,
+            c_c: (4.0 + mu_eff / n) / (n + 4.0 + 2.0 * mu_eff / n),
+            c_1,
+            c_mu: (1.0 - c_1)
+                .min(2.0 * (mu_eff - 2.0 + 1.0 / mu_eff) / ((n + 2.0).powi(2) + mu_eff)),
+            chi_n: n.sqrt() * (1.0 - 1.0 / (4.0 * n) + 1.0 / (21.0 * n * n)),
+        }
+    }
+}
+
+// One candidate of a generation: the standard normal draw, the step it maps
+// to and the candidate's score
+struct Sample {
+    z: Vec<f64>,
+    y: Vec<f64>,
+    score: Score,
+}
+
+pub struct CmaEs<'a> {
+    tracker: Tracker<'a>,
+    rng: StdRng,
+    k: Constants,
+    mean: Vec<f64>,
+    sigma: f64,
+    cov: Vec<Vec<f64>>,
+    factor: Vec<Vec<f64>>, // Cholesky factor of `cov`
+    p_sigma: Vec<f64>,
+    p_c: Vec<f64>,
+    generation: usize,
+}
+
+impl<'a> CmaEs<'a> {
+    // `particles` samples per generation. Without `start` the search begins at
+    // a random point with a diagonal covariance of the squared ranges; with
+    // one start point it begins there, and with several it also takes their
+    // covariance (with `sigma` = 1) as the initial shape.
+    pub fn new(
+        config: Config,
+        options: &'a RunOptions,
+        sigma: f64,
+        start: Option<&[Vec<f64>]>,
+    ) -> Result<Self, String> {
+        let mut rng: StdRng = optimizer::seeded_rng(&config);
+        let n = config.dimensions;
+        let ranges: Vec<f64> = (0..n)
+            .map(|pos| {
+                let (lower, upper) = config.bounds_of(pos);
+                upper - lower
+            })
+            .collect();
+        let diagonal = |scale: f64| -> Vec<Vec<f64>> {
+            (0..n)
+                .map(|i| {
+                    let mut row = vec![0.0; n];
+                    row[i] = scale * ranges[i] * ranges[i];
+                    row
+                })
+                .collect()
+        };
+
+        let (mean, sigma, cov) = match start {
+            Some(points) if points.len() > 1 => {
+                let samples: Vec<&[f64]> = points.iter().map(Vec::as_slice).collect();
+                let mut cov = linalg::covariance(&samples, &linalg::mean(&samples));
+                // keeps the shape usable when the points span few directions
+                for (i, row) in cov.iter_mut().enumerate() {
+                    row[i] += 1e-12 * ranges[i] * ranges[i];
+                }
+                (points[0].clone(), 1.0, cov)
+            }
+            Some(points) if !points.is_empty() => (points[0].clone(), sigma, diagonal(1.0)),
+            _ => {
+                let mean = (0..n)
+                    .map(|pos| sampling::coordinate(&config, pos, &mut rng))
+                    .collect();
+                (mean, sigma, diagonal(1.0))
+            }
+        };
+        let factor = linalg::cholesky(&cov).unwrap_or_else(|| diagonal(1e-12));
+        let lambda = config.particles.max(2);
+        Ok(CmaEs {
+            tracker: Tracker::new(config, options)?,
+            rng,
+            k: Constants::new(n, lambda),
+            mean,
+            sigma,
+            cov,
+            factor,
+            p_sigma: vec![0.0; n],
+            p_c: vec![0.0; n],
+            generation: 0,
+        })
+    }
+}
+
+impl Optimizer for CmaEs<'_> {
+    fn step(&mut self) {
+        let config = &self.tracker.config;
+        let n = config.dimensions;
+        let lambda = config.particles.max(2);
+
+        // z ~ N(0, I), y = A z with A A^T = C, x = mean + sigma y; the
+        // candidate is evaluated clamped, the update uses the unclamped step
+        let mut samples = Vec::with_capacity(lambda);
+        for id in 0..lambda {
+            let z: Vec<f64> = (0..n).map(|_| self.rng.sample(StandardNormal)).collect();
+            let y: Vec<f64> = self
+                .factor
+                .iter()
+                .map(|row| row.iter().zip(&z).map(|(a, zi)| a * zi).sum())
+                .collect();
+            let config = &self.tracker.config;
+            let x: Vec<f64> = (0..n)
+                .map(|pos| {
+                    let (lower, upper) = config.bounds_of(pos);
+                    let xi = self.mean[pos] + self.sigma * y[pos];
+                    config.snap(pos, xi.clamp(lower, upper))
+                })
+                .collect();
+            let score = self.tracker.evaluate(id, &x);
+            samples.push(Sample { z, y, score });
+        }
+        let scores: Vec<Score> = samples.iter().map(|s| s.score).collect();
+        let comparator = &self.tracker.config.comparison;
+        let order = selection::ranked_indices(&scores, scores.len(), comparator, &mut self.rng);
+        let mut samples: Vec<Option<Sample>> = samples.into_iter().map(Some).collect();
+        let samples: Vec<Sample> = order
+            .into_iter()
+            .map(|k| samples[k].take().expect("each sample ranked once"))
+            .collect();
+
+        let k = &self.k;
+        let selected = &samples[..k.weights.len()];
+        let (mut z_w, mut y_w) = (vec![0.0; n], vec![0.0; n]);
+        for (s, w) in selected.iter().zip(&k.weights) {
+            for (zw, z) in z_w.iter_mut().zip(&s.z) {
+                *zw += w * z;
+            }
+            for (yw, y) in y_w.iter_mut().zip(&s.y) {
+                *yw += w * y;
+            }
+        }
+
+        let config = &self.tracker.config;
+        for (pos, (m, y)) in self.mean.iter_mut().zip(&y_w).enumerate() {
+            let (lower, upper) = config.bounds_of(pos);
+            *m = (*m + self.sigma * y).clamp(lower, upper);
+        }
+
+        // evolution paths; the Cholesky factor stands in for C^(1/2), so
+        // C^(-1/2) y_w is z_w
+        let cs = k.c_sigma;
+        for (p, z) in self.p_sigma.iter_mut().zip(&z_w) {
+            *p = (1.0 - cs) * *p + (cs * (2.0 - cs) * k.mu_eff).sqrt() * z;
+        }
+        let ps_norm = self.p_sigma.iter().map(|p| p * p).sum::<f64>().sqrt();
+        self.generation += 1;
+        let decay = 1.0 - (1.0 - cs).powi(2 * self.generation as i32);
+        let h_sigma = ps_norm / decay.sqrt() < (1.4 + 2.0 / (n as f64 + 1.0)) * k.chi_n;
+        let h = if h_sigma { 1.0 } else { 0.0 };
+        let cc = k.c_c;
+        for (p, y) in self.p_c.iter_mut().zip(&y_w) {
+            *p = (1.0 - cc) * *p + h * (cc * (2.0 - cc) * k.mu_eff).sqrt() * y;
+        }
+
+        // rank-one and rank-mu covariance update
+        let keep = 1.0 - k.c_1 - k.c_mu + (1.0 - h) * k.c_1 * cc * (2.0 - cc);
+        for i in 0..n {
+            for j in 0..n {
+                let rank_mu: f64 = selected
+                    .iter()
+                    .zip(&k.weights)
+                    .map(|(s, w)| w * s.y[i] * s.y[j])
+                    .sum();
+                self.cov[i][j] =
+                    keep * self.cov[i][j] + k.c_1 * self.p_c[i] * self.p_c[j] + k.c_mu * rank_mu;
+            }
+        }
+        if let Some(factor) = linalg::cholesky(&self.cov) {
+            self.factor = factor;
+        }
+        self.sigma *= ((cs / k.d_sigma) * (ps_norm / k.chi_n - 1.0)).exp();
+
+        self.tracker.end_iteration();
+    }
+
+    fn is_finished(&self) -> bool {
+        self.tracker.is_finished()
+    }
+
+    fn stop(&mut self, reason: &'static str) {
+        self.tracker.stop(reason);
+    }
+
+    fn best(&self) -> f64 {
+        self.tracker.best()
+    }
+
+    fn progress(&self) -> TracePoint {
+        self.tracker.progress()
+    }
+
+    fn finish(self: Box<Self>) -> Outcome {
+        let notes = [format!("Final step size: {}", self.sigma)];
+        self.tracker.finish(&notes)
+    }
+}
@@ -0,0 +1,301 @@
+// Desc: Fitness comparison shared by every best-so-far and selection decision
+use std::cmp::Ordering;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::schedule::{Param, Schedule};
+
+// What a candidate is judged on: its fitness (the objective, plus the bound
+// penalty) and its total constraint violation (0 when feasible)
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Score {
+    pub fitness: f64,
+    pub violation: f64,
+}
+
+/// Criterion of a lexicographic comparison
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Criterion {
+    /// The objective value
+    Fitness,
+    /// The total constraint violation
+    Violation,
+}
+
+/// How two candidates are compared
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Comparator {
+    /// Lower penalized fitness (fitness + penalty_factor * violation) wins
+    #[default]
+    Strict,
+    /// Like strict, but only an improvement by more than `epsilon` counts; ties keep the incumbent
+    Tolerant { epsilon: f64 },
+    /// Deb's rules: feasible beats infeasible, then lower violation, then lower fitness; no penalty
+    FeasibilityFirst,
+    /// Criteria in order, each deciding unless the two differ by at most `epsilon`; no penalty
+    Lexicographic {
+        criteria: Vec<Criterion>,
+        #[serde(default)]
+        epsilon: f64,
+    },
+    /// Runarsson and Yao's stochastic ranking: populations are ranked by
+    /// bubble-sort sweeps that compare neighbours by fitness when both are
+    /// feasible or with probability `pf`, by violation otherwise; an
+    /// incumbent is only replaced under Deb's rules. No penalty
+    StochasticRanking {
+        #[serde(default = "default_pf")]
+        pf: f64,
+    },
+    /// Takahama's epsilon-constrained method: violations up to the epsilon
+    /// level count as feasible, so such candidates compare by fitness, the
+    /// others by violation first; the level follows `epsilon` over the run,
+    /// typically down to 0. No penalty
+    EpsilonConstraint {
+        epsilon: Param,
+        /// Level of the current iteration, set by the run
+        #[serde(skip)]
+        #[schemars(skip)]
+        level: f64,
+    },
+}
+
+fn default_pf() -> f64 {
+    0.45
+}
+
+impl Comparator {
+    // Moves the epsilon level to `iteration`'s; returns whether the comparator
+    // has one, and so whether comparisons may have changed
+    pub fn advance(&mut self, iteration: usize, iterations: usize) -> bool {
+        match self {
+            Comparator::EpsilonConstraint { epsilon, level } => {
+                *level = epsilon.value(iteration, iterations).max(0.0);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // The violation of `s` as the comparator weighs it: nothing up to the epsilon level
+    fn effective_violation(&self, s: Score) -> f64 {
+        match self {
+            Comparator::EpsilonConstraint { level, .. } if s.violation <= *level => 0.0,
+            _ => s.violation,
+        }
+    }
+
+    // Whether the comparator folds violations into the fitness as a penalty
+    pub fn penalizes(&self) -> bool {
+        matches!(self, Comparator::Strict | Comparator::Tolerant { .. })
+    }
+
+    // The score of a candidate; penalty-based comparators fold the violation
+    // into the fitness with the current penalty factor
+    pub fn score(&self, fitness: f64, violation: f64, penalty_factor: f64) -> Score {
+        let fitness = if self.penalizes() && violation > 0.0 {
+            fitness + penalty_factor * violation
+        } else {
+            fitness
+        };
+        Score { fitness, violation }
+    }
+
+    // Whether `a` replaces the incumbent `b`
+    pub fn better(&self, a: Score, b: Score) -> bool {
+        match self {
+            Comparator::Strict => a.fitness < b.fitness,
+            Comparator::Tolerant { epsilon } => a.fitness < b.fitness - epsilon,
+            Comparator::FeasibilityFirst | Comparator::StochasticRanking { .. } => {
+                match (a.violation > 0.0, b.violation > 0.0) {
+                    (false, false) => a.fitness < b.fitness,
+                    (false, true) => true,
+                    (true, false) => false,
+                    (true, true) => a.violation < b.violation,
+                }
+            }
+            Comparator::EpsilonConstraint { .. } => {
+                let (va, vb) = (self.effective_violation(a), self.effective_violation(b));
+                va < vb || (va == vb && a.fitness < b.fitness)
+            }
+            Comparator::Lexicographic { criteria, epsilon } => {
+                for criterion in criteria {
+                    let (x, y) = match criterion {
+                        Criterion::Fitness => (a.fitness, b.fitness),
+                        Criterion::Violation => (a.violation, b.violation),
+                    };
+                    if x < y - epsilon {
+                        return true;
+                    }
+                    if y < x - epsilon {
+                        return false;
+                    }
+                }
+                false
+            }
+        }
+    }
+
+    // Total order for sorting, best first; NaN fitness sorts last. Tolerances
+    // are ignored here, as ties within epsilon are not transitive.
+    pub fn ordering(&self, a: Score, b: Score) -> Ordering {
+        match (a.fitness.is_nan(), b.fitness.is_nan()) {
+            (true, false) => return Ordering::Greater,
+            (false, true) => return Ordering::Less,
+            (true, true) => return Ordering::Equal,
+            (false, false) => {}
+        }
+        let by = |criterion: &Criterion| match criterion {
+            Criterion::Fitness => a.fitness.total_cmp(&b.fitness),
+            Criterion::Violation => a.violation.total_cmp(&b.violation),
+        };
+        match self {
+            Comparator::Strict | Comparator::Tolerant { .. } => by(&Criterion::Fitness),
+            Comparator::FeasibilityFirst | Comparator::StochasticRanking { .. } => {
+                let infeasible = (a.violation > 0.0).cmp(&(b.violation > 0.0));
+                let criteria = if a.violation > 0.0 && b.violation > 0.0 {
+                    Criterion::Violation
+                } else {
+                    Criterion::Fitness
+                };
+                infeasible.then_with(|| by(&criteria))
+            }
+            Comparator::Lexicographic { criteria, .. } => criteria
+                .iter()
+                .fold(Ordering::Equal, |order, c| order.then_with(|| by(c))),
+            Comparator::EpsilonConstraint { .. } => self
+                .effective_violation(a)
+                .total_cmp(&self.effective_violation(b))
+                .then_with(|| by(&Criterion::Fitness)),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Comparator::Tolerant { epsilon } | Comparator::Lexicographic { epsilon, .. }
+                if !epsilon.is_finite() || *epsilon < 0.0 =>
+            {
+                Err(format!(
+                    "comparison epsilon must be finite and >= 0, got {}",
+                    epsilon
+                ))
+            }
+            Comparator::Lexicographic { criteria, .. }
+                if criteria.is_empty() || (criteria.len() == 2 && criteria[0] == criteria[1]) =>
+            {
+                Err("lexicographic comparison needs distinct criteria".to_string())
+            }
+            Comparator::StochasticRanking { pf } if !(0.0..=1.0).contains(pf) => Err(format!(
+                "stochastic ranking pf must be within [0, 1], got {}",
+                pf
+            )),
+            Comparator::EpsilonConstraint { epsilon, .. } => {
+                epsilon.validate()?;
+                // the level at the start and at the end of the run
+                let (start, end) = (epsilon.value(0, 2), epsilon.value(1, 2));
+                if !(start.is_finite() && start >= 0.0 && end >= 0.0) {
+                    return Err(format!(
+                        "the epsilon level must be finite and >= 0, got {}",
+                        epsilon.describe()
+                    ));
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Comparator::Strict => "strict".to_string(),
+            Comparator::Tolerant { epsilon } => format!("tolerant (epsilon {})", epsilon),
+            Comparator::FeasibilityFirst => "feasibility-first".to_string(),
+            Comparator::Lexicographic { criteria, epsilon } => {
+                let names: Vec<String> = criteria
+                    .iter()
+                    .map(|c| format!("{:?}", c).to_lowercase())
+                    .collect();
+                format!("lexicographic [{}] (epsilon {})", names.join(", "), epsilon)
+            }
+            Comparator::StochasticRanking { pf } => format!("stochastic ranking (pf {})", pf),
+            Comparator::EpsilonConstraint { epsilon, .. } => {
+                format!("epsilon-constrained (epsilon {})", epsilon.describe())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schedule::{Linear, ScheduleSpec};
+
+    fn score(fitness: f64, violation: f64) -> Score {
+        Score { fitness, violation }
+    }
+
+    #[test]
+    fn strict_penalizes_violation() {
+        let c = Comparator::Strict;
+        assert_eq!(c.score(1.0, 0.5, 10.0), score(6.0, 0.5));
+        assert!(c.better(score(1.0, 0.0), score(2.0, 0.0)));
+        assert!(!c.better(score(2.0, 0.0), score(2.0, 0.0)));
+    }
+
+    #[test]
+    fn tolerant_keeps_incumbent_on_ties() {
+        let c = Comparator::Tolerant { epsilon: 0.1 };
+        assert!(!c.better(score(1.95, 0.0), score(2.0, 0.0)));
+        assert!(c.better(score(1.85, 0.0), score(2.0, 0.0)));
+    }
+
+    #[test]
+    fn feasibility_first_prefers_feasible_points() {
+        let c = Comparator::FeasibilityFirst;
+        assert_eq!(c.score(1.0, 0.5, 10.0), score(1.0, 0.5));
+        assert!(c.better(score(100.0, 0.0), score(1.0, 0.1)));
+        assert!(c.better(score(100.0, 0.1), score(1.0, 0.2)));
+        assert!(c.better(score(1.0, 0.0), score(2.0, 0.0)));
+        assert!(!c.better(score(1.0, 0.2), score(100.0, 0.0)));
+    }
+
+    #[test]
+    fn lexicographic_falls_through_ties() {
+        let c = Comparator::Lexicographic {
+            criteria: vec![Criterion::Violation, Criterion::Fitness],
+            epsilon: 0.01,
+        };
+        assert!(c.better(score(5.0, 0.001), score(6.0, 0.0)));
+        assert!(!c.better(score(5.0, 0.5), score(6.0, 0.0)));
+        assert!(!c.better(score(6.0, 0.0), score(6.0, 0.0)));
+    }
+
+    #[test]
+    fn epsilon_level_admits_violations_until_it_shrinks() {
+        let epsilon = Param::Schedule(ScheduleSpec::Linear(Linear {
+            start: 1.0,
+            end: 0.0,
+        }));
+        let mut c = Comparator::EpsilonConstraint {
+            epsilon,
+            level: 0.0,
+        };
+        assert!(c.advance(0, 11));
+        assert!(c.better(score(1.0, 0.5), score(2.0, 0.0)));
+        c.advance(10, 11);
+        assert!(!c.better(score(1.0, 0.5), score(2.0, 0.0)));
+        assert!(c.better(score(3.0, 0.2), score(1.0, 0.5)));
+        assert!(!Comparator::Strict.advance(0, 11));
+    }
+
+    #[test]
+    fn ordering_sorts_nan_last() {
+        let c = Comparator::Strict;
+        let mut scores = [score(f64::NAN, 0.0), score(2.0, 0.0), score(1.0, 0.0)];
+        scores.sort_by(|&a, &b| c.ordering(a, b));
+        assert_eq!(scores[0].fitness, 1.0);
+        assert!(scores[2].fitness.is_nan());
+    }
+}
@@ -0,0 +1,1896 @@
+// Desc: Run configuration, loadable from a JSON file
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::boundary::BoundaryHandling;
+use crate::comparator::Comparator;
+use crate::constraint::{self, AdaptivePenalty, Constraint, ConstraintKind};
+use crate::distribution::Distribution;
+use crate::functions::{Function, ObjectiveFunction};
+use crate::inertia::InertiaSchedule;
+use crate::init::VelocityInit;
+use crate::noise::{self, EvalContext, NoiseStream};
+use crate::optimizer::Algorithm;
+use crate::problem::{Expression, Problem, VariableType};
+use crate::rotation;
+use crate::schedule::{Param, Schedule};
+use crate::topology::Topology;
+use crate::view::SwarmView;
+
+// Objective supplied through the library API
+pub type ObjectiveFn = Arc<dyn ObjectiveFunction>;
+
+// Constraint supplied through the library API
+pub type ConstraintFn = Arc<dyn Constraint>;
+
+const PARTICLES: usize = 30; // number of particles
+const ITERATIONS: usize = 100; // number of iterations
+const C1: f64 = 1.3; // acceleration constant c1 (cognitive component)
+const C2: f64 = 1.1; // acceleration constant c2 (social component)
+const W: f64 = 0.9; // inertia weight
+const PENALTY_FACTOR: f64 = 10000.0; // penalty for particles out-of-the-bounds
+
+/// PSO run configuration. Every field is optional and falls back to its default.
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Objective function
+    pub function: Function,
+    /// Whether the objective is minimized or maximized
+    pub objective: Objective,
+    /// Optimization algorithm; the swarm settings from `c1` on only apply to pso
+    pub algorithm: Algorithm,
+    /// Evaluate the function on a randomly rotated space, drawn from this seed
+    pub rotation_seed: Option<u64>,
+    #[serde(skip)]
+    #[schemars(skip)]
+    rotation: Option<Arc<Vec<Vec<f64>>>>, // built from rotation_seed by `prepare`
+    #[serde(skip)]
+    #[schemars(skip)]
+    pub problem: Option<Arc<Problem>>, // set from `--problem` by `apply_problem`
+    #[serde(skip)]
+    #[schemars(skip)]
+    pub objective_fn: Option<ObjectiveFn>, // supplied by library callers, replaces everything else
+    #[serde(skip)]
+    #[schemars(skip)]
+    pub constraints: Vec<ConstraintFn>, // supplied by library callers, with the problem's
+    #[serde(skip)]
+    #[schemars(skip)]
+    pub view: Option<SwarmView>, // a library caller's PsoHandle watches the swarm through it
+    #[serde(skip)]
+    #[schemars(skip)]
+    pub quiet: bool, // pipeline stage: no iteration log or report, the pipeline prints them
+    #[serde(skip)]
+    #[schemars(skip)]
+    pub run: usize, // run number within --runs, for the convergence trace
+    /// Seed of the swarm's random generator, for reproducible runs; from entropy when left out
+    pub seed: Option<u64>,
+    /// Number of particles in the swarm
+    #[schemars(range(min = 1))]
+    pub particles: usize,
+    /// Number of iterations to run
+    pub iterations: usize,
+    /// Number of dimensions of the search space; defaults to the function's usual size
+    #[schemars(range(min = 1))]
+    pub dimensions: usize,
+    /// Acceleration constant c1 (cognitive component), a number or a schedule
+    pub c1: Param,
+    /// Acceleration constant c2 (social component), a number or a schedule
+    pub c2: Param,
+    /// Inertia weight, a number or a schedule
+    pub w: Param,
+    /// Inertia strategy that replaces `w`, including chaotic and diversity-adaptive ones
+    pub inertia_schedule: Option<InertiaSchedule>,
+    /// Maximum absolute velocity per dimension, a number or a schedule
+    pub vmax: Option<Param>,
+    /// Maximum absolute velocity per dimension as a fraction of that dimension's range
+    #[schemars(range(min = 0.0, max = 1.0))]
+    pub vmax_fraction: Option<f64>,
+    /// Lower bound of the search space, shared by all dimensions; defaults to the function's canonical domain
+    pub lower_bound: f64,
+    /// Upper bound of the search space, shared by all dimensions; defaults to the function's canonical domain
+    pub upper_bound: f64,
+    /// Bounds of every dimension as [lower, upper] pairs, in place of the shared ones
+    pub bounds: Option<Vec<(f64, f64)>>,
+    /// Variables held at a value while the others are optimized, by name: the problem file's names, or x1..xn
+    pub fixed: BTreeMap<String, f64>,
+    #[serde(skip)]
+    #[schemars(skip)]
+    fixed_at: Vec<Option<f64>>, // per coordinate, from `fixed` and the problem's fixed variables, by `prepare`
+    /// Fitness penalty added to particles clamped to a bound, a number or a schedule
+    pub penalty_factor: Param,
+    /// What happens to a coordinate that leaves its bounds
+    pub boundary: BoundaryHandling,
+    /// How candidates are compared when personal and global bests are updated
+    pub comparison: Comparator,
+    /// Adapt the penalty factor on constraint violations to the global best's feasibility
+    pub adaptive_penalty: Option<AdaptivePenalty>,
+    /// Tolerance band of every equality constraint, met where |h(x)| <= the band, in place of each one's own: a number or a schedule, e.g. shrinking exponentially from 1e-2 to 1e-6
+    pub equality_tolerance: Option<Param>,
+    #[serde(skip)]
+    #[schemars(skip)]
+    equality_band: Option<f64>, // equality_tolerance at the current iteration, set by `advance`
+    /// Project particles that violate linear constraints onto the nearest point satisfying all of them within the bounds, instead of penalizing them
+    pub linear_repair: bool,
+    /// Deterministic PSO: fix both r1 and r2 to this value instead of drawing them
+    #[schemars(range(min = 0.0, max = 1.0))]
+    pub deterministic: Option<f64>,
+    /// Velocity update rule
+    pub velocity_update: VelocityUpdate,
+    /// How initial (and re-dispersed) velocities are drawn
+    pub velocity_init: VelocityInit,
+    /// Neighbourhood structure: whose personal bests each particle's social term follows
+    pub topology: Topology,
+    /// Distribution r1 and r2 are drawn from instead of uniform [0, 1)
+    pub random_coefficients: Option<Distribution>,
+    /// Annealed constriction factor that scales the whole velocity update
+    pub constriction: Option<Param>,
+    /// Heterogeneous swarm: draw w, c1 and c2 per particle instead of sharing them
+    pub heterogeneous: Option<Heterogeneous>,
+    /// Self-adaptive swarm: w, c1 and c2 are extra position coordinates that evolve with each particle
+    pub self_adaptive: Option<SelfAdaptive>,
+    /// Sparse updates: move only a random subset of the dimensions of each particle per iteration
+    pub dimension_subset: Option<DimensionSubset>,
+    /// Block-coordinate PSO: optimize one group of variables at a time, cycling through the groups
+    pub block_coordinate: Option<BlockCoordinate>,
+    /// Wait between objective evaluations so that at most this many run per second,
+    /// counted over all the runs and threads of the process
+    pub max_evaluations_per_second: Option<f64>,
+    /// Threads one objective evaluation uses itself (e.g. a multithreaded
+    /// simulation); parallel runs and --threads leave that many cores per evaluation
+    pub objective_threads: Option<u64>,
+    /// Cost charged per objective evaluation (e.g. dollars per cloud simulation),
+    /// unless the objective reports the cost of each evaluation itself
+    pub evaluation_cost: Option<f64>,
+    /// Elite archive whose members occasionally replace gbest in the social term
+    pub archive: Option<Archive>,
+    /// (mu+lambda) replacement: after moving, the best `particles` of the old and new positions survive
+    pub generational: bool,
+    /// Synchronous updates: the global and neighbourhood bests the particles follow change only between iterations
+    pub synchronous: bool,
+    /// Draw r1 and r2 for every dimension instead of once per particle
+    pub random_per_dimension: bool,
+    /// Draw each particle's random numbers of an iteration from a stream keyed by the seed, iteration and particle, which `rng` replays
+    pub rng_streams: bool,
+    /// Screen particles with a cheap low-fidelity objective before the accurate one
+    pub multi_fidelity: Option<MultiFidelity>,
+    /// Observation noise on every objective evaluation, added to its value or drawn
+    /// by the objective itself, to study noisy problems
+    pub noise: Option<Noise>,
+    /// Conditions that end (or restart) the run before `iterations`; the first one met fires
+    pub stop: StopConditions,
+    /// Ways out of a stagnating swarm, each fired after its number of iterations without a gbest improvement
+    pub escape: Escape,
+    /// Local search polishing gbest, every few iterations or when the run ends
+    pub local_search: Option<LocalSearch>,
+    /// Files a command-line run writes; the matching flags take precedence
+    pub output: OutputPaths,
+}
+
+/// Output files of a command-line run, each the default of its flag
+#[derive(Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputPaths {
+    /// Per-iteration log (`--output`)
+    pub log: Option<String>,
+    /// Final gbest of every run (`--results`)
+    pub results: Option<String>,
+    /// Convergence trace (`--convergence`)
+    pub convergence: Option<String>,
+    /// Statistics of the runs (`--summary`)
+    pub summary: Option<String>,
+    /// Runtime ECDF of the runs (`--ecdf`)
+    pub ecdf: Option<String>,
+}
+
+/// Direction of the optimization. Runs always minimize: a maximized
+/// objective is negated when evaluated, and reported with its own sign.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Objective {
+    #[default]
+    Minimize,
+    Maximize,
+}
+
+impl Objective {
+    // Factor from the objective's values to the minimized ones, and back
+    pub fn sign(self) -> f64 {
+        match self {
+            Objective::Minimize => 1.0,
+            Objective::Maximize => -1.0,
+        }
+    }
+
+    // How much worse `value` is than `reference`, negative when it is better
+    pub fn shortfall(self, value: f64, reference: f64) -> f64 {
+        self.sign() * (value - reference)
+    }
+
+    // The better of two of the objective's values
+    pub fn best(self, a: f64, b: f64) -> f64 {
+        match self {
+            Objective::Minimize => a.min(b),
+            Objective::Maximize => a.max(b),
+        }
+    }
+}
+
+/// How a particle's velocity is updated (the PSO variant)
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VelocityUpdate {
+    /// Per-dimension update w*v + c1*r1*(pbest - x) + c2*r2*(gbest - x); biased toward the axes
+    Standard,
+    /// Rotation-invariant SPSO-2011 update: the new point is drawn uniformly in a hypersphere
+    /// around the centre of x, x + c1*(pbest - x) and x + c2*(gbest - x); r1 and r2 are unused
+    Spso2011,
+    /// Clerc's constriction factor: chi * (v + c1*r1*(pbest - x) + c2*r2*(gbest - x)) with chi
+    /// derived from phi = c1 + c2 > 4 (e.g. 2.05 each); w is unused
+    Constriction,
+    /// Bare-bones PSO: each coordinate is drawn from a Gaussian with mean (pbest + gbest) / 2
+    /// and standard deviation |pbest - gbest|; no velocity or coefficients
+    BareBones,
+    /// Quantum-behaved PSO: each coordinate is drawn around a random point between pbest and
+    /// gbest, at a distance of w * |mbest - x| * ln(1/u) with mbest the mean personal best;
+    /// w (a number or schedule) is the contraction-expansion coefficient
+    Qpso,
+}
+
+impl VelocityUpdate {
+    pub fn name(self) -> &'static str {
+        match self {
+            VelocityUpdate::Standard => "standard",
+            VelocityUpdate::Spso2011 => "spso2011",
+            VelocityUpdate::Constriction => "constriction",
+            VelocityUpdate::BareBones => "bare_bones",
+            VelocityUpdate::Qpso => "qpso",
+        }
+    }
+}
+
+/// Per-particle coefficient distributions for a heterogeneous swarm
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Heterogeneous {
+    pub w: Distribution,
+    pub c1: Distribution,
+    pub c2: Distribution,
+    /// Redraw a particle's coefficients after its pbest has not improved for this many iterations
+    #[schemars(range(min = 1))]
+    pub resample_after: Option<usize>,
+}
+
+/// Ranges of the strategy coordinates of a self-adaptive swarm
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct SelfAdaptive {
+    pub w: (f64, f64),
+    pub c1: (f64, f64),
+    pub c2: (f64, f64),
+}
+
+impl Default for SelfAdaptive {
+    fn default() -> Self {
+        SelfAdaptive {
+            w: (0.2, 1.0),
+            c1: (0.5, 2.5),
+            c2: (0.5, 2.5),
+        }
+    }
+}
+
+/// Random-subset (dropout-style) update of the dimensions
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct DimensionSubset {
+    /// Number of dimensions updated per particle
+    #[schemars(range(min = 1))]
+    pub size: usize,
+    /// Draw one subset for the whole swarm instead of one per particle
+    #[serde(default)]
+    pub shared: bool,
+    /// Draw new subsets every this many iterations
+    #[serde(default = "default_resample_every")]
+    #[schemars(range(min = 1))]
+    pub resample_every: usize,
+    /// Re-evaluate only the updated dimensions (needs a decomposable function)
+    #[serde(default)]
+    pub partial_evaluation: bool,
+}
+
+fn default_resample_every() -> usize {
+    1
+}
+
+/// User-declared variable groups for block-coordinate optimization
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct BlockCoordinate {
+    /// Groups of dimensions (1-based, like x1, x2, ...) optimized in turn;
+    /// leave empty to discover them with differential grouping
+    #[serde(default)]
+    pub groups: Vec<Vec<usize>>,
+    /// Detect the groups automatically before the run when `groups` is empty
+    pub differential_grouping: Option<DifferentialGrouping>,
+    /// Iterations spent on a group before moving to the next one
+    #[schemars(range(min = 1))]
+    pub iterations_per_group: usize,
+    /// On each switch, set every particle's frozen variables to the global best's values
+    #[serde(default = "default_share_context")]
+    pub share_context: bool,
+}
+
+/// Settings of the differential grouping analysis pass
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct DifferentialGrouping {
+    /// Interaction threshold on the change of fitness differences
+    pub epsilon: f64,
+    /// Maximum objective evaluations spent on the analysis
+    pub max_evaluations: Option<usize>,
+}
+
+impl Default for DifferentialGrouping {
+    fn default() -> Self {
+        DifferentialGrouping {
+            epsilon: 1e-3,
+            max_evaluations: None,
+        }
+    }
+}
+
+fn default_share_context() -> bool {
+    true
+}
+
+/// External archive of the best personal bests seen so far
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Archive {
+    /// Number of elite solutions kept
+    #[schemars(range(min = 1))]
+    pub size: usize,
+    /// Chance per particle and iteration of being guided by a random archive member instead of gbest
+    #[schemars(range(min = 0.0, max = 1.0))]
+    pub injection_probability: f64,
+    /// On restart, sample the new particles from a Gaussian fitted to the
+    /// archive members (their mean and covariance) instead of uniformly
+    #[serde(default)]
+    pub warm_restart: bool,
+}
+
+/// Two-level objective: every particle is evaluated at low fidelity and only
+/// those the cheap model predicts to beat their personal best are promoted to
+/// the accurate (high-fidelity) evaluation
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct MultiFidelity {
+    /// Fidelity level of the cheap evaluation: the terms of the sum a built-in
+    /// function computes (it must be decomposable), passed on as is to the
+    /// objectives of library callers
+    #[schemars(range(min = 1))]
+    pub components: usize,
+    /// Cost charged per low-fidelity evaluation; `evaluation_cost` applies to high fidelity
+    #[serde(default)]
+    pub low_cost: f64,
+}
+
+/// Noise added to the objective value of every evaluation, or drawn by the
+/// objective itself from the noise stream each evaluation is given
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Noise {
+    /// Distribution of the added noise (symmetric around its location); leave
+    /// it out for an objective that draws its own noise from the streams
+    pub distribution: Option<Distribution>,
+    /// How the noise of the candidates of one iteration relates
+    #[serde(default)]
+    pub sampling: NoiseSampling,
+    /// Seed of the noise streams; drawn at random when left out
+    pub seed: Option<u64>,
+}
+
+/// Variance reduction for comparisons between the candidates of an iteration
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum NoiseSampling {
+    /// Every evaluation draws its own noise
+    #[default]
+    Independent,
+    /// Common random numbers: all evaluations of an iteration share one noise draw
+    Common,
+    /// Particles 2k and 2k+1 of an iteration get mirrored draws of one noise value
+    Antithetic,
+}
+
+/// Early stop and restart triggers, checked after every iteration
+#[derive(Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct StopConditions {
+    /// The swarm has frozen: mean velocity magnitude stayed below a threshold
+    pub velocity_collapse: Option<VelocityCollapse>,
+    /// Stop once the spent evaluation cost reaches this budget (checked after each iteration)
+    pub cost_budget: Option<f64>,
+    /// Stop once a feasible solution is within `tolerance` of the target fitness
+    pub target: Option<TargetFitness>,
+    /// Stop when the best fitness has not improved for a number of iterations
+    pub stagnation: Option<Stagnation>,
+    /// Stop once the run has taken this many seconds of wall-clock time
+    pub max_seconds: Option<f64>,
+    /// Stop once this many objective evaluations were spent (checked after each iteration)
+    pub max_evaluations: Option<usize>,
+}
+
+/// Good-enough fitness, typically the known optimum of a benchmark
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TargetFitness {
+    pub fitness: f64,
+    /// How far above the target still counts as reached
+    #[serde(default)]
+    #[schemars(range(min = 0.0))]
+    pub tolerance: f64,
+}
+
+/// No-progress trigger on the best fitness found so far
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Stagnation {
+    /// Consecutive iterations without improvement before the run stops
+    #[schemars(range(min = 1))]
+    pub iterations: usize,
+    /// Smallest decrease of the best fitness that counts as an improvement
+    #[serde(default)]
+    #[schemars(range(min = 0.0))]
+    pub min_improvement: f64,
+}
+
+/// What to do when a stop condition is met
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum StopAction {
+    /// End the run
+    Stop,
+    /// Re-initialize the particles, keeping the global best found so far
+    Restart,
+}
+
+/// Stagnation escapes. Each fires every `after` consecutive iterations
+/// without a gbest improvement, so longer thresholds escalate; when several
+/// fire together they act in the order below
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct Escape {
+    /// Smallest decrease of gbest since the last improvement that counts as one
+    #[schemars(range(min = 0.0))]
+    pub min_improvement: f64,
+    /// Craziness: move the worst particles to Gaussian mutations of gbest
+    pub gbest_mutation: Option<GbestMutation>,
+    /// Opposition-based re-seeding: move every particle to the opposite point
+    /// of the bounds when that beats its personal best
+    pub opposition: Option<Opposition>,
+    /// Re-initialize every particle but the ones with the best personal bests
+    pub partial_restart: Option<PartialRestart>,
+}
+
+impl Default for Escape {
+    fn default() -> Self {
+        Escape {
+            min_improvement: 1e-6,
+            gbest_mutation: None,
+            opposition: None,
+            partial_restart: None,
+        }
+    }
+}
+
+impl Escape {
+    pub fn any(&self) -> bool {
+        self.gbest_mutation.is_some() || self.opposition.is_some() || self.partial_restart.is_some()
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct GbestMutation {
+    /// Iterations without improvement that trigger it
+    #[schemars(range(min = 1))]
+    pub after: usize,
+    /// Standard deviation of the mutation, as a fraction of each dimension's range
+    #[serde(default = "default_mutation_sigma")]
+    pub sigma: f64,
+    /// How many of the worst particles move
+    #[serde(default = "default_mutated_particles")]
+    #[schemars(range(min = 1))]
+    pub particles: usize,
+}
+
+fn default_mutation_sigma() -> f64 {
+    0.1
+}
+
+fn default_mutated_particles() -> usize {
+    1
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Opposition {
+    /// Iterations without improvement that trigger it
+    #[schemars(range(min = 1))]
+    pub after: usize,
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PartialRestart {
+    /// Iterations without improvement that trigger it
+    #[schemars(range(min = 1))]
+    pub after: usize,
+    /// Particles with the best personal bests kept as they are
+    pub keep: usize,
+}
+
+/// Refinement of gbest by a local search started at it; its evaluations
+/// count towards the run's totals
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct LocalSearch {
+    pub method: LocalMethod,
+    /// Iterations between refinements; left out, gbest is refined once, when the run ends
+    #[schemars(range(min = 1))]
+    pub every: Option<usize>,
+    /// Objective evaluations one refinement may spend
+    #[serde(default = "default_local_evaluations")]
+    #[schemars(range(min = 1))]
+    pub evaluations: usize,
+    /// Initial simplex edge or pattern step, as a fraction of each range
+    #[serde(default = "default_local_step")]
+    pub step: f64,
+}
+
+fn default_local_evaluations() -> usize {
+    200
+}
+
+fn default_local_step() -> f64 {
+    0.01
+}
+
+/// Local search of a refinement
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LocalMethod {
+    /// Nelder-Mead simplex around gbest
+    NelderMead,
+    /// Hooke-Jeeves pattern search from gbest
+    PatternSearch,
+}
+
+impl LocalMethod {
+    pub fn name(self) -> &'static str {
+        match self {
+            LocalMethod::NelderMead => "nelder_mead",
+            LocalMethod::PatternSearch => "pattern_search",
+        }
+    }
+}
+
+/// Velocity-collapse trigger, independent of fitness stagnation
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct VelocityCollapse {
+    /// Mean over particles of the velocity's Euclidean norm
+    pub threshold: f64,
+    /// Consecutive iterations below the threshold before the trigger fires
+    #[serde(default = "default_collapse_iterations")]
+    #[schemars(range(min = 1))]
+    pub iterations: usize,
+    #[serde(default = "default_stop_action")]
+    pub action: StopAction,
+}
+
+fn default_collapse_iterations() -> usize {
+    10
+}
+
+fn default_stop_action() -> StopAction {
+    StopAction::Stop
+}
+
+// Number of strategy coordinates appended to the position in self-adaptive mode
+pub const STRATEGY_DIMENSIONS: usize = 3;
+
+impl Default for Config {
+    fn default() -> Self {
+        let function = Function::Rastrigin;
+        let defaults = function.defaults();
+        Config {
+            function,
+            objective: Objective::Minimize,
+            algorithm: Algorithm::Pso,
+            rotation_seed: None,
+            rotation: None,
+            problem: None,
+            objective_fn: None,
+            constraints: Vec::new(),
+            view: None,
+            quiet: false,
+            run: 1,
+            seed: None,
+            particles: PARTICLES,
+            iterations: ITERATIONS,
+            dimensions: defaults.dimensions,
+            c1: Param::Value(C1),
+            c2: Param::Value(C2),
+            w: Param::Value(W),
+            inertia_schedule: None,
+            boundary: BoundaryHandling::Clamp,
+            vmax: None,
+            vmax_fraction: None,
+            lower_bound: defaults.lower_bound,
+            upper_bound: defaults.upper_bound,
+            bounds: None,
+            fixed: BTreeMap::new(),
+            fixed_at: Vec::new(),
+            penalty_factor: Param::Value(PENALTY_FACTOR),
+            comparison: Comparator::Strict,
+            adaptive_penalty: None,
+            equality_tolerance: None,
+            equality_band: None,
+            linear_repair: false,
+            deterministic: None,
+            velocity_update: VelocityUpdate::Standard,
+            velocity_init: VelocityInit::FullRange,
+            topology: Topology::Global,
+            random_coefficients: None,
+            constriction: None,
+            heterogeneous: None,
+            self_adaptive: None,
+            dimension_subset: None,
+            block_coordinate: None,
+            max_evaluations_per_second: None,
+            objective_threads: None,
+            evaluation_cost: None,
+            archive: None,
+            generational: false,
+            synchronous: false,
+            random_per_dimension: false,
+            rng_streams: false,
+            multi_fidelity: None,
+            noise: None,
+            stop: StopConditions::default(),
+            escape: Escape::default(),
+            local_search: None,
+            output: OutputPaths::default(),
+        }
+    }
+}
+
+impl Config {
+    // JSON, or TOML when the path ends in .toml
+    pub fn load(path: &str) -> Result<Config, String> {
+        Config::load_with(path, serde_json::Map::new())
+    }
+
+    // Like `load`, with `overrides` (from command-line flags) replacing the
+    // file's fields before the function's defaults are applied
+    pub fn load_with(
+        path: &str,
+        overrides: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<Config, String> {
+        let mut config = Config::read_with(path, overrides)?;
+        config
+            .validate()
+            .map_err(|e| format!("invalid config file {}: {}", path, e))?;
+        config.prepare();
+        Ok(config)
+    }
+
+    // Like `load_with`, neither validated nor prepared
+    pub fn read_with(
+        path: &str,
+        overrides: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<Config, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read config file {}: {}", path, e))?;
+        let parsed = if path.ends_with(".toml") {
+            toml::from_str(&text).map_err(|e| e.to_string())
+        } else if path.ends_with(".yaml") || path.ends_with(".yml") {
+            Err("YAML is not supported, write the config as TOML or JSON".to_string())
+        } else {
+            serde_json::from_str(&text).map_err(|e| e.to_string())
+        };
+        let mut value: serde_json::Value =
+            parsed.map_err(|e| format!("invalid config file {}: {}", path, e))?;
+        if let Some(fields) = value.as_object_mut() {
+            fields.extend(overrides);
+        }
+        Config::parse(&value).map_err(|e| format!("invalid config file {}: {}", path, e))
+    }
+
+    // A config from its JSON form, validated and prepared
+    pub fn from_value(value: &serde_json::Value) -> Result<Config, String> {
+        let mut config = Config::parse(value)?;
+        config.validate()?;
+        config.prepare();
+        Ok(config)
+    }
+
+    // A config from its JSON form with the function's defaults, neither
+    // validated nor prepared
+    pub fn parse(value: &serde_json::Value) -> Result<Config, String> {
+        let mut config = Config::deserialize(value).map_err(|e| e.to_string())?;
+
+        // fields left out follow the selected function, not Rastrigin's defaults
+        let given = |key: &str| value.get(key).is_some();
+        let defaults = config.function.defaults();
+        if !given("lower_bound") {
+            config.lower_bound = defaults.lower_bound;
+        }
+        if !given("upper_bound") {
+            config.upper_bound = defaults.upper_bound;
+        }
+        if !given("dimensions") {
+            config.dimensions = defaults.dimensions;
+        }
+        // per-dimension bounds give the dimensions, and the shared bounds
+        // become the box around them, for the views
+        if let Some(bounds) = config.bounds.as_ref().filter(|b| !b.is_empty()) {
+            if !given("dimensions") {
+                config.dimensions = bounds.len();
+            }
+            config.lower_bound = bounds.iter().map(|b| b.0).fold(f64::INFINITY, f64::min);
+            config.upper_bound = bounds.iter().map(|b| b.1).fold(f64::NEG_INFINITY, f64::max);
+        }
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.particles == 0 {
+            return Err("particles must be at least 1".to_string());
+        }
+        if self.dimensions == 0 {
+            return Err("dimensions must be at least 1".to_string());
+        }
+        if let Some(bounds) = &self.bounds {
+            if bounds.len() != self.dimensions {
+                return Err(format!(
+                    "bounds has {} pairs for {} dimensions",
+                    bounds.len(),
+                    self.dimensions
+                ));
+            }
+            let inverted = bounds.iter().position(|(lower, upper)| {
+                lower.partial_cmp(upper) != Some(std::cmp::Ordering::Less)
+            });
+            if let Some(i) = inverted {
+                return Err(format!(
+                    "bounds of dimension {}: lower ({}) must be below upper ({})",
+                    i + 1,
+                    bounds[i].0,
+                    bounds[i].1
+                ));
+            }
+        }
+        if self.lower_bound.partial_cmp(&self.upper_bound) != Some(std::cmp::Ordering::Less) {
+            return Err(format!(
+                "lower_bound ({}) must be below upper_bound ({})",
+                self.lower_bound, self.upper_bound
+            ));
+        }
+        let names: Vec<String> = (0..self.dimensions)
+            .map(|pos| self.variable_name(pos))
+            .collect();
+        for (name, &value) in &self.fixed {
+            let pos = names
+                .iter()
+                .position(|n| n == name)
+                .ok_or(format!("fixed: no variable `{}`", name))?;
+            let (lower, upper) = self.bounds_of(pos);
+            if !(lower..=upper).contains(&value) {
+                return Err(format!(
+                    "fixed: {} = {} is outside its bounds [{}, {}]",
+                    name, value, lower, upper
+                ));
+            }
+            let integer = self
+                .problem
+                .as_ref()
+                .is_some_and(|p| p.variables[pos].kind == VariableType::Integer);
+            if integer && value.fract() != 0.0 {
+                return Err(format!(
+                    "fixed: {} = {} is not an integer, as its variable is",
+                    name, value
+                ));
+            }
+        }
+        if self.free_dimensions() == 0 {
+            return Err("every variable is fixed, none is left to optimize".to_string());
+        }
+        self.algorithm.validate()?;
+        if self.algorithm.runs(|a| matches!(a, Algorithm::Abc { .. })) && self.particles < 2 {
+            return Err("abc needs at least 2 particles (food sources)".to_string());
+        }
+        if !self.algorithm.runs(|a| *a == Algorithm::Pso) {
+            let swarm_only = [
+                (self.topology != Topology::Global, "topology"),
+                (self.boundary != BoundaryHandling::Clamp, "boundary"),
+                (self.inertia_schedule.is_some(), "inertia_schedule"),
+                (self.vmax_fraction.is_some(), "vmax_fraction"),
+                (self.heterogeneous.is_some(), "heterogeneous"),
+                (self.self_adaptive.is_some(), "self_adaptive"),
+                (self.dimension_subset.is_some(), "dimension_subset"),
+                (self.block_coordinate.is_some(), "block_coordinate"),
+                (self.archive.is_some(), "archive"),
+                (self.adaptive_penalty.is_some(), "adaptive_penalty"),
+                (self.linear_repair, "linear_repair"),
+                (
+                    self.velocity_update != VelocityUpdate::Standard,
+                    "velocity_update",
+                ),
+                (self.generational, "generational"),
+                (self.synchronous, "synchronous"),
+                (self.random_per_dimension, "random_per_dimension"),
+                (self.rng_streams, "rng_streams"),
+                (self.multi_fidelity.is_some(), "multi_fidelity"),
+                (
+                    self.stop.velocity_collapse.is_some(),
+                    "stop.velocity_collapse",
+                ),
+                (self.stop.cost_budget.is_some(), "stop.cost_budget"),
+                (self.escape.any(), "escape"),
+                (self.local_search.is_some(), "local_search"),
+            ];
+            if let Some((_, name)) = swarm_only.iter().find(|(given, _)| *given) {
+                return Err(format!(
+                    "{} only applies to the pso algorithm, not {}",
+                    name,
+                    self.algorithm.name()
+                ));
+            }
+        }
+        if let Some(problem) = &self.problem {
+            let capabilities = self.algorithm.capabilities();
+            let unsupported = [
+                (
+                    !problem.constraints.is_empty() && !capabilities.constraints,
+                    "constraints",
+                ),
+                (
+                    problem.integer_variables() > 0 && !capabilities.discrete,
+                    "integer variables",
+                ),
+            ];
+            if let Some((_, what)) = unsupported.iter().find(|(given, _)| *given) {
+                return Err(format!(
+                    "{} cannot handle the {} of problem {}",
+                    self.algorithm.name(),
+                    what,
+                    problem.path
+                ));
+            }
+        }
+        if !self.constraints.is_empty() && !self.algorithm.capabilities().constraints {
+            return Err(format!(
+                "{} cannot handle constraints",
+                self.algorithm.name()
+            ));
+        }
+        self.comparison.validate()?;
+        if let Some(tolerance) = &self.equality_tolerance {
+            tolerance.validate()?;
+            let (start, end) = (
+                tolerance.value(0, self.iterations),
+                tolerance.value(self.iterations, self.iterations),
+            );
+            if !(start >= 0.0 && end >= 0.0) {
+                return Err(format!(
+                    "equality_tolerance must not be negative, it goes from {} to {}",
+                    start, end
+                ));
+            }
+        }
+        if let Some(adaptive) = &self.adaptive_penalty {
+            if !self.comparison.penalizes() {
+                return Err(format!(
+                    "adaptive_penalty needs a penalty-based comparison, not {}",
+                    self.comparison.describe()
+                ));
+            }
+            adaptive.validate()?;
+        }
+        self.velocity_init.validate()?;
+        self.topology.validate()?;
+        self.boundary.validate()?;
+        if self.velocity_update == VelocityUpdate::Constriction {
+            if self.constriction.is_some() {
+                return Err(
+                    "constriction and the constriction velocity update cannot be combined"
+                        .to_string(),
+                );
+            }
+            let last = self.iterations.saturating_sub(1);
+            let phi =
+                |it: usize| self.c1.value(it, self.iterations) + self.c2.value(it, self.iterations);
+            if let Some(phi) = [phi(0), phi(last)].into_iter().find(|phi| *phi <= 4.0) {
+                return Err(format!(
+                    "the constriction velocity update needs c1 + c2 > 4, got {}",
+                    phi
+                ));
+            }
+        }
+        if let Some(r) = self.deterministic {
+            if !(0.0..=1.0).contains(&r) {
+                return Err(format!("deterministic r ({}) must be within [0, 1]", r));
+            }
+        }
+        if self.rng_streams && self.seed.is_none() {
+            return Err("rng_streams keys its streams by the seed: set seed".to_string());
+        }
+        if let Some(d) = &self.random_coefficients {
+            if self.deterministic.is_some() {
+                return Err("deterministic and random_coefficients cannot be combined".to_string());
+            }
+            d.validate()?;
+        }
+        if let Some(inertia) = &self.inertia_schedule {
+            inertia.validate()?;
+        }
+        if let Some(f) = self.vmax_fraction {
+            if self.vmax.is_some() {
+                return Err("vmax and vmax_fraction cannot be combined".to_string());
+            }
+            if !(f > 0.0 && f <= 1.0) {
+                return Err(format!("vmax_fraction must be within (0, 1], got {}", f));
+            }
+        }
+        for param in [&self.c1, &self.c2, &self.w, &self.penalty_factor]
+            .into_iter()
+            .chain(self.vmax.as_ref())
+            .chain(self.constriction.as_ref())
+        {
+            param.validate()?;
+        }
+        if let Some(h) = &self.heterogeneous {
+            h.w.validate()?;
+            h.c1.validate()?;
+            h.c2.validate()?;
+            if h.resample_after == Some(0) {
+                return Err("heterogeneous.resample_after must be at least 1".to_string());
+            }
+        }
+        if let Some(sa) = &self.self_adaptive {
+            if self.heterogeneous.is_some() {
+                return Err("heterogeneous and self_adaptive cannot be combined".to_string());
+            }
+            for (name, (lo, hi)) in [("w", sa.w), ("c1", sa.c1), ("c2", sa.c2)] {
+                if lo.partial_cmp(&hi) != Some(std::cmp::Ordering::Less) {
+                    return Err(format!("self_adaptive.{} range must have min < max", name));
+                }
+            }
+        }
+        if let Some(sub) = &self.dimension_subset {
+            if sub.size == 0 || sub.size > self.free_dimensions() {
+                return Err(format!(
+                    "dimension_subset.size must be between 1 and {}, the variables that are not fixed",
+                    self.free_dimensions()
+                ));
+            }
+            if sub.resample_every == 0 {
+                return Err("dimension_subset.resample_every must be at least 1".to_string());
+            }
+            if sub.partial_evaluation && !self.is_decomposable() {
+                return Err("dimension_subset.partial_evaluation needs a decomposable, \
+                     unrotated function"
+                    .to_string());
+            }
+        }
+        if let Some(block) = &self.block_coordinate {
+            if self.dimension_subset.is_some() {
+                return Err("dimension_subset and block_coordinate cannot be combined".to_string());
+            }
+            if block.groups.is_empty() && block.differential_grouping.is_none() {
+                return Err("block_coordinate needs groups or differential_grouping".to_string());
+            }
+            if block.groups.iter().any(|g| g.is_empty()) {
+                return Err("block_coordinate.groups must not contain empty groups".to_string());
+            }
+            if let Some(&d) = block
+                .groups
+                .iter()
+                .flatten()
+                .find(|&&d| d == 0 || d > self.dimensions)
+            {
+                return Err(format!(
+                    "block_coordinate dimension {} is outside 1..={}",
+                    d, self.dimensions
+                ));
+            }
+            if block.iterations_per_group == 0 {
+                return Err("block_coordinate.iterations_per_group must be at least 1".to_string());
+            }
+        }
+        if let Some(rate) = self.max_evaluations_per_second {
+            if rate.partial_cmp(&0.0) != Some(std::cmp::Ordering::Greater) || rate.is_infinite() {
+                return Err("max_evaluations_per_second must be positive and finite".to_string());
+            }
+        }
+        if self.objective_threads == Some(0) {
+            return Err("objective_threads must be at least 1".to_string());
+        }
+        if let Some(cost) = self.evaluation_cost {
+            if cost.partial_cmp(&0.0) == Some(std::cmp::Ordering::Less) || !cost.is_finite() {
+                return Err("evaluation_cost must be a non-negative number".to_string());
+            }
+        }
+        if let Some(mf) = &self.multi_fidelity {
+            if self.objective_fn.is_none() && !self.is_decomposable() {
+                return Err(
+                    "multi_fidelity needs an objective with a low fidelity: a decomposable, \
+                     unrotated built-in function"
+                        .to_string(),
+                );
+            }
+            if mf.components == 0 || mf.components > self.dimensions {
+                return Err(format!(
+                    "multi_fidelity.components must be between 1 and {}",
+                    self.dimensions
+                ));
+            }
+            if mf.low_cost.partial_cmp(&0.0) == Some(std::cmp::Ordering::Less)
+                || !mf.low_cost.is_finite()
+            {
+                return Err("multi_fidelity.low_cost must be a non-negative number".to_string());
+            }
+        }
+        if let Some(archive) = &self.archive {
+            if archive.size == 0 || !(0.0..=1.0).contains(&archive.injection_probability) {
+                return Err(
+                    "archive needs a positive size and an injection_probability within [0, 1]"
+                        .to_string(),
+                );
+            }
+        }
+        if let Some(noise) = &self.noise {
+            if let Some(distribution) = &noise.distribution {
+                distribution.validate()?;
+            }
+            if self.multi_fidelity.is_some()
+                || self
+                    .dimension_subset
+                    .as_ref()
+                    .is_some_and(|sub| sub.partial_evaluation)
+            {
+                return Err(
+                    "noise cannot be combined with multi_fidelity or partial_evaluation"
+                        .to_string(),
+                );
+            }
+        }
+        if self.generational && self.multi_fidelity.is_some() {
+            return Err("generational and multi_fidelity cannot be combined".to_string());
+        }
+        if let Some(budget) = self.stop.cost_budget {
+            if budget.partial_cmp(&0.0) != Some(std::cmp::Ordering::Greater) {
+                return Err("stop.cost_budget must be positive".to_string());
+            }
+        }
+        if let Some(collapse) = &self.stop.velocity_collapse {
+            if collapse.threshold.partial_cmp(&0.0) != Some(std::cmp::Ordering::Greater)
+                || collapse.iterations == 0
+            {
+                return Err(
+                    "stop.velocity_collapse needs a positive threshold and iterations".to_string(),
+                );
+            }
+        }
+        if let Some(m) = &self.escape.gbest_mutation {
+            if m.after == 0
+                || m.sigma.partial_cmp(&0.0) != Some(std::cmp::Ordering::Greater)
+                || m.particles == 0
+                || m.particles > self.particles
+            {
+                return Err(format!(
+                    "escape.gbest_mutation needs positive after and sigma, and 1 to {} particles",
+                    self.particles
+                ));
+            }
+        }
+        if self.escape.min_improvement.is_nan() || self.escape.min_improvement < 0.0 {
+            return Err("escape.min_improvement must be non-negative".to_string());
+        }
+        if self
+            .escape
+            .opposition
+            .as_ref()
+            .is_some_and(|o| o.after == 0)
+        {
+            return Err("escape.opposition.after must be positive".to_string());
+        }
+        if let Some(r) = &self.escape.partial_restart {
+            if r.after == 0 || r.keep >= self.particles {
+                return Err(format!(
+                    "escape.partial_restart needs a positive after and keep below the {} particles",
+                    self.particles
+                ));
+            }
+        }
+        if let Some(l) = &self.local_search {
+            if l.every == Some(0) || l.evaluations == 0 || !(l.step > 0.0 && l.step <= 1.0) {
+                return Err(
+                    "local_search needs a positive every and evaluations, and a step within (0, 1]"
+                        .to_string(),
+                );
+            }
+        }
+        if let Some(target) = &self.stop.target {
+            if !target.fitness.is_finite() || target.tolerance.is_nan() || target.tolerance < 0.0 {
+                return Err(
+                    "stop.target needs a finite fitness and a non-negative tolerance".to_string(),
+                );
+            }
+        }
+        if let Some(stagnation) = &self.stop.stagnation {
+            if stagnation.iterations == 0
+                || stagnation.min_improvement.is_nan()
+                || stagnation.min_improvement < 0.0
+            {
+                return Err(
+                    "stop.stagnation needs positive iterations and a non-negative min_improvement"
+                        .to_string(),
+                );
+            }
+        }
+        if let Some(seconds) = self.stop.max_seconds {
+            if !(seconds > 0.0 && seconds.is_finite()) {
+                return Err("stop.max_seconds must be positive and finite".to_string());
+            }
+        }
+        if self.stop.max_evaluations == Some(0) {
+            return Err("stop.max_evaluations must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    // Builds the derived state (the rotation matrix, the fixed coordinates)
+    // after a change of the settings
+    pub fn prepare(&mut self) {
+        self.advance(0);
+        self.fixed_at = (0..self.dimensions)
+            .map(|pos| {
+                let declared = self.problem.as_ref().and_then(|p| p.variables[pos].fixed);
+                self.fixed
+                    .get(&self.variable_name(pos))
+                    .copied()
+                    .or(declared)
+            })
+            .collect();
+        self.rotation = self
+            .rotation_seed
+            .map(|seed| Arc::new(rotation::random_orthogonal(self.dimensions, seed)));
+        // fixed here so the effective configuration reproduces the noise
+        if let Some(noise) = self.noise.as_mut() {
+            noise.seed.get_or_insert_with(rand::random);
+        }
+    }
+
+    // Cores one objective evaluation keeps busy
+    pub fn evaluation_threads(&self) -> usize {
+        self.objective_threads.unwrap_or(1) as usize
+    }
+
+    // The objective as configured: the function, on the rotated space if requested
+    pub fn objective(&self, x: &[f64]) -> f64 {
+        self.objective_on(x, None).0
+    }
+
+    // The objective of an evaluation on the noise `stream`, which callers'
+    // objectives and external programs may draw their own noise from, and
+    // the cost they report for it
+    fn objective_on(&self, x: &[f64], stream: Option<NoiseStream>) -> (f64, Option<f64>) {
+        if let Some(f) = &self.objective_fn {
+            return f.evaluate_with_cost(x, stream);
+        }
+        let rotated;
+        let x = match &self.rotation {
+            Some(matrix) => {
+                rotated = rotation::rotate(matrix, x);
+                &rotated
+            }
+            None => x,
+        };
+        let problem = self.problem.as_deref();
+        if let Some(command) = problem.and_then(|p| p.command.as_ref()) {
+            // a failed batch scores the worst value in the objective's own sign
+            return command
+                .evaluate(x, stream)
+                .unwrap_or((self.objective.sign() * f64::INFINITY, None));
+        }
+        let value = if let Some(plugin) = problem.and_then(|p| p.plugin.as_ref()) {
+            plugin.evaluate(x)
+        } else if let Some(portfolio) = problem.and_then(|p| p.portfolio.as_ref()) {
+            portfolio.evaluate(x)
+        } else if let Some(fit) = problem.and_then(|p| p.fit.as_ref()) {
+            fit.evaluate(x)
+        } else {
+            match (problem.and_then(|p| p.model.as_ref()), self.expression()) {
+                (Some(model), _) => model.evaluate(x),
+                (None, Some(expression)) => expression.evaluate(x),
+                (None, None) => self.function.evaluate(x),
+            }
+        };
+        (value, None)
+    }
+
+    // Low-fidelity value of `x` at `level`, from a caller's objective that has
+    // one or a decomposable built-in function; None for the other objectives
+    pub fn low_fidelity(&self, x: &[f64], level: usize) -> Option<f64> {
+        if let Some(f) = &self.objective_fn {
+            return f.evaluate_fidelity(x, level);
+        }
+        if !self.is_decomposable() {
+            return None;
+        }
+        ObjectiveFunction::evaluate_fidelity(&self.function, x, level)
+    }
+
+    // Whether a problem file or the caller replaces the function with its own objective
+    fn custom_objective(&self) -> bool {
+        self.objective_fn.is_some()
+            || self.problem.as_ref().is_some_and(|p| {
+                p.model.is_some()
+                    || p.expression.is_some()
+                    || p.command.is_some()
+                    || p.plugin.is_some()
+                    || p.portfolio.is_some()
+                    || p.fit.is_some()
+            })
+    }
+
+    // Observed value of the `evaluation`-th evaluation (1-based), at `at`:
+    // the objective plus the configured noise; and its cost, as the objective
+    // reported it or evaluation_cost
+    pub fn evaluate(&self, x: &[f64], at: EvalContext, evaluation: usize) -> (f64, f64) {
+        let noise = self.noise.as_ref();
+        let stream = noise.map(|n| noise::stream_of(n, at, evaluation));
+        let (fitness, cost) = self.objective_on(x, stream);
+        let added = match (noise.and_then(|n| n.distribution.as_ref()), stream) {
+            (Some(distribution), Some(stream)) => noise::sample(distribution, stream),
+            _ => 0.0,
+        };
+        let cost = cost.unwrap_or(self.evaluation_cost.unwrap_or(0.0));
+        (self.internal(fitness + added), cost)
+    }
+
+    // A value of the objective as the runs minimize it: negated when maximizing
+    pub fn internal(&self, value: f64) -> f64 {
+        self.objective.sign() * value
+    }
+
+    // A minimized value back in the objective's own sign, for the outputs
+    pub fn reported(&self, fitness: f64) -> f64 {
+        self.objective.sign() * fitness
+    }
+
+    // Objective expression of the problem file, which replaces the function
+    fn expression(&self) -> Option<&Expression> {
+        self.problem.as_ref()?.expression.as_ref()
+    }
+
+    pub fn objective_name(&self) -> &str {
+        if self.objective_fn.is_some() {
+            return "custom";
+        }
+        let problem = self.problem.as_deref();
+        if let Some(command) = problem.and_then(|p| p.command.as_ref()) {
+            return &command.program;
+        }
+        if let Some(plugin) = problem.and_then(|p| p.plugin.as_ref()) {
+            return &plugin.name;
+        }
+        if let Some(portfolio) = problem.and_then(|p| p.portfolio.as_ref()) {
+            return &portfolio.name;
+        }
+        if let Some(fit) = problem.and_then(|p| p.fit.as_ref()) {
+            return &fit.name;
+        }
+        match (problem.and_then(|p| p.model.as_ref()), self.expression()) {
+            (Some(model), _) => &model.path,
+            (None, Some(expression)) => &expression.text,
+            (None, None) => self.function.name(),
+        }
+    }
+
+    // What evaluates the objective, for the audit log: the command line of an
+    // external program, the library of a plugin, or the in-process objective
+    pub fn evaluator(&self) -> String {
+        let problem = self.problem.as_deref();
+        if self.objective_fn.is_some() {
+            return "library caller".to_string();
+        }
+        if let Some(command) = problem.and_then(|p| p.command.as_ref()) {
+            return format!("command {}", command.command_line());
+        }
+        if let Some(plugin) = problem.and_then(|p| p.plugin.as_ref()) {
+            return format!("plugin {}", plugin.library);
+        }
+        format!("local {}", self.objective_name())
+    }
+
+    // Why the objective can no longer be evaluated: an external program given
+    // up after failing too many batches in a row
+    pub fn objective_failure(&self) -> Option<String> {
+        let problem = self.problem.as_deref();
+        problem.and_then(|p| p.command.as_ref())?.failure()
+    }
+
+    // Known optimum of the objective, if any; the benchmarks only know their minimum
+    pub fn optimum(&self) -> Option<f64> {
+        match self.problem.as_ref().and_then(|p| p.optimum) {
+            Some(optimum) => Some(optimum),
+            None if self.custom_objective() || self.objective == Objective::Maximize => None,
+            None => self.function.optimum(),
+        }
+    }
+
+    // Whether the objective is a sum of per-dimension terms; rotation mixes
+    // them and problem-file objectives are not analysed
+    pub fn is_decomposable(&self) -> bool {
+        self.rotation_seed.is_none() && !self.custom_objective() && self.function.is_decomposable()
+    }
+
+    // Total cost of the objective evaluations, which cost `spent` together,
+    // and of this many low-fidelity ones
+    pub fn cost_of(&self, spent: f64, low_evaluations: usize) -> f64 {
+        let low_cost = self.multi_fidelity.as_ref().map_or(0.0, |mf| mf.low_cost);
+        spent + low_evaluations as f64 * low_cost
+    }
+
+    // Length of the position vector: the objective's dimensions plus any
+    // strategy coordinates, which the objective never sees
+    pub fn search_dimensions(&self) -> usize {
+        match self.self_adaptive {
+            Some(_) => self.dimensions + STRATEGY_DIMENSIONS,
+            None => self.dimensions,
+        }
+    }
+
+    // Search range of one coordinate of the position vector
+    pub fn bounds_of(&self, pos: usize) -> (f64, f64) {
+        match (&self.self_adaptive, &self.problem) {
+            (Some(sa), _) if pos >= self.dimensions => [sa.w, sa.c1, sa.c2][pos - self.dimensions],
+            (_, Some(problem)) => {
+                let v = &problem.variables[pos];
+                (v.lower_bound, v.upper_bound)
+            }
+            _ => match &self.bounds {
+                Some(bounds) if pos < self.dimensions => bounds[pos],
+                _ => (self.lower_bound, self.upper_bound),
+            },
+        }
+    }
+
+    // Takes the objective, dimensions and variable bounds from a problem file.
+    // The shared bounds become the box around all variables, for the views.
+    pub fn apply_problem(&mut self, problem: Problem) -> Result<(), String> {
+        if let Some(function) = problem.objective {
+            self.function = function;
+        }
+        self.dimensions = problem.variables.len();
+        self.lower_bound = problem
+            .variables
+            .iter()
+            .map(|v| v.lower_bound)
+            .fold(f64::INFINITY, f64::min);
+        self.upper_bound = problem
+            .variables
+            .iter()
+            .map(|v| v.upper_bound)
+            .fold(f64::NEG_INFINITY, f64::max);
+        self.bounds = None;
+        self.problem = Some(Arc::new(problem));
+        self.validate()?;
+        self.prepare();
+        Ok(())
+    }
+
+    // Name of objective coordinate `pos`: its problem variable's, or x1..xn
+    pub fn variable_name(&self, pos: usize) -> String {
+        match &self.problem {
+            Some(problem) => problem.variables[pos].name.clone(),
+            None => format!("x{}", pos + 1),
+        }
+    }
+
+    // Objective coordinates that are not fixed, by `fixed` or the problem file
+    pub fn free_dimensions(&self) -> usize {
+        (0..self.dimensions)
+            .filter(|&pos| {
+                let declared = self.problem.as_ref().and_then(|p| p.variables[pos].fixed);
+                declared.is_none() && !self.fixed.contains_key(&self.variable_name(pos))
+            })
+            .count()
+    }
+
+    // The value coordinate `pos` is held at, if it is fixed
+    pub fn fixed_value(&self, pos: usize) -> Option<f64> {
+        self.fixed_at.get(pos).copied().flatten()
+    }
+
+    // Coordinate `pos` moved to the nearest value its variable type allows;
+    // a fixed coordinate only allows its value
+    pub fn snap(&self, pos: usize, x: f64) -> f64 {
+        if let Some(fixed) = self.fixed_value(pos) {
+            return fixed;
+        }
+        match &self.problem {
+            Some(problem) if pos < self.dimensions => problem.snap(pos, x),
+            _ => x,
+        }
+    }
+
+    // Total constraint violation of the objective coordinates, 0 when feasible
+    pub fn constraint_violation(&self, x: &[f64]) -> f64 {
+        let (inequalities, equalities) = self.violations(x);
+        inequalities + equalities
+    }
+
+    // Violations of the inequality and of the equality constraints, apart;
+    // equalities are met within the equality band when there is one, or
+    // else within their own tolerance
+    pub fn violations(&self, x: &[f64]) -> (f64, f64) {
+        let x = &x[..self.dimensions];
+        let declared = self.problem.iter().flat_map(|p| &p.constraints);
+        declared
+            .map(|c| c as &dyn Constraint)
+            .chain(self.constraints.iter().map(|c| c.as_ref()))
+            .fold((0.0, 0.0), |(g, h), c| {
+                match (c.kind(), self.equality_band) {
+                    (ConstraintKind::Inequality, _) => (g + c.violation(x), h),
+                    (ConstraintKind::Equality { .. }, Some(band)) => {
+                        (g, h + constraint::excess(c.value(x).abs() - band))
+                    }
+                    (ConstraintKind::Equality { .. }, None) => (g, h + c.violation(x)),
+                }
+            })
+    }
+
+    // Whether the problem file or the caller attached any constraint
+    pub fn is_constrained(&self) -> bool {
+        !self.constraints.is_empty()
+            || self
+                .problem
+                .as_ref()
+                .is_some_and(|p| !p.constraints.is_empty())
+    }
+
+    // The inequality and equality violations at `x` for the logs, None
+    // without constraints or before there is a point
+    pub fn logged_violations(&self, x: &[f64]) -> (Option<f64>, Option<f64>) {
+        if !self.is_constrained() || x.len() < self.dimensions {
+            return (None, None);
+        }
+        let (inequalities, equalities) = self.violations(x);
+        (Some(inequalities), Some(equalities))
+    }
+
+    // Moves the scheduled parts of the constraint handling, the comparator's
+    // epsilon level and the equality band, to `iteration`; whether scores
+    // may have changed
+    pub fn advance(&mut self, iteration: usize) -> bool {
+        let leveled = self.comparison.advance(iteration, self.iterations);
+        self.equality_band = self
+            .equality_tolerance
+            .as_ref()
+            .map(|t| t.value(iteration, self.iterations).max(0.0));
+        leveled || matches!(self.equality_tolerance, Some(Param::Schedule(_)))
+    }
+
+    // Key/value view of the parameters, used by the sidecar file
+    pub fn entries(&self) -> Vec<(&'static str, String)> {
+        let mut entries = vec![
+            ("function", self.objective_name().to_string()),
+            ("algorithm", self.algorithm.describe()),
+            ("particles", self.particles.to_string()),
+            ("iterations", self.iterations.to_string()),
+            ("dimensions", self.dimensions.to_string()),
+            ("c1", self.c1.describe()),
+            ("c2", self.c2.describe()),
+            ("w", self.w.describe()),
+            ("lower_bound", self.lower_bound.to_string()),
+            ("upper_bound", self.upper_bound.to_string()),
+            ("penalty_factor", self.penalty_factor.describe()),
+        ];
+        if self.objective == Objective::Maximize {
+            entries.push(("objective", "maximize".to_string()));
+        }
+        if let Some(bounds) = &self.bounds {
+            let pairs: Vec<String> = bounds
+                .iter()
+                .map(|(lower, upper)| format!("[{},{}]", lower, upper))
+                .collect();
+            entries.push(("bounds", pairs.join(" ")));
+        }
+        if !self.fixed.is_empty() {
+            let fixed: Vec<String> = self
+                .fixed
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect();
+            entries.push(("fixed", fixed.join(" ")));
+        }
+        if let Some(seed) = self.seed {
+            entries.push(("seed", seed.to_string()));
+        }
+        if self.comparison != Comparator::Strict {
+            entries.push(("comparison", self.comparison.describe()));
+        }
+        if let Some(adaptive) = &self.adaptive_penalty {
+            entries.push(("adaptive_penalty", adaptive.describe()));
+        }
+        if let Some(tolerance) = &self.equality_tolerance {
+            entries.push(("equality_tolerance", tolerance.describe()));
+        }
+        if self.linear_repair {
+            entries.push(("linear_repair", "true".to_string()));
+        }
+        if !self.constraints.is_empty() {
+            entries.push(("constraints", self.constraints.len().to_string()));
+        }
+        if let Some(seed) = self.rotation_seed {
+            entries.push(("rotation_seed", seed.to_string()));
+        }
+        if let Some(problem) = &self.problem {
+            entries.push(("problem", problem.path.clone()));
+            if let Some(optimum) = problem.optimum {
+                entries.push(("problem.optimum", optimum.to_string()));
+            }
+            let variables: Vec<String> = problem
+                .variables
+                .iter()
+                .map(|v| {
+                    format!(
+                        "{}:{:?}[{},{}]",
+                        v.name, v.kind, v.lower_bound, v.upper_bound
+                    )
+                    .to_lowercase()
+                })
+                .collect();
+            entries.push(("problem.variables", variables.join(" ")));
+            if !problem.constraints.is_empty() {
+                let constraints: Vec<&str> = problem
+                    .constraints
+                    .iter()
+                    .map(|c| c.text.as_str())
+                    .collect();
+                entries.push(("problem.constraints", constraints.join("; ")));
+            }
+        }
+        if let Some(r) = self.deterministic {
+            entries.push(("deterministic", r.to_string()));
+        }
+        if self.velocity_update != VelocityUpdate::Standard {
+            entries.push(("velocity_update", self.velocity_update.name().to_string()));
+        }
+        if self.velocity_init != VelocityInit::FullRange {
+            entries.push(("velocity_init", self.velocity_init.describe()));
+        }
+        if self.topology != Topology::Global {
+            entries.push(("topology", self.topology.describe()));
+        }
+        if let Some(d) = &self.random_coefficients {
+            entries.push(("random_coefficients", d.describe()));
+        }
+        if self.boundary != BoundaryHandling::Clamp {
+            entries.push(("boundary", self.boundary.describe()));
+        }
+        if let Some(inertia) = &self.inertia_schedule {
+            entries.push(("inertia_schedule", inertia.describe()));
+        }
+        if let Some(f) = self.vmax_fraction {
+            entries.push(("vmax_fraction", f.to_string()));
+        }
+        if let Some(vmax) = &self.vmax {
+            entries.push(("vmax", vmax.describe()));
+        }
+        if let Some(schedule) = &self.constriction {
+            entries.push(("constriction", schedule.describe()));
+        }
+        if let Some(h) = &self.heterogeneous {
+            entries.push(("heterogeneous.w", h.w.describe()));
+            entries.push(("heterogeneous.c1", h.c1.describe()));
+            entries.push(("heterogeneous.c2", h.c2.describe()));
+            if let Some(n) = h.resample_after {
+                entries.push(("heterogeneous.resample_after", n.to_string()));
+            }
+        }
+        if let Some(sa) = &self.self_adaptive {
+            entries.push(("self_adaptive.w", format!("{:?}", sa.w)));
+            entries.push(("self_adaptive.c1", format!("{:?}", sa.c1)));
+            entries.push(("self_adaptive.c2", format!("{:?}", sa.c2)));
+        }
+        if let Some(sub) = &self.dimension_subset {
+            entries.push(("dimension_subset.size", sub.size.to_string()));
+            entries.push(("dimension_subset.shared", sub.shared.to_string()));
+            entries.push((
+                "dimension_subset.resample_every",
+                sub.resample_every.to_string(),
+            ));
+            entries.push((
+                "dimension_subset.partial_evaluation",
+                sub.partial_evaluation.to_string(),
+            ));
+        }
+        if let Some(block) = &self.block_coordinate {
+            entries.push(("block_coordinate.groups", format!("{:?}", block.groups)));
+            entries.push((
+                "block_coordinate.iterations_per_group",
+                block.iterations_per_group.to_string(),
+            ));
+            entries.push((
+                "block_coordinate.share_context",
+                block.share_context.to_string(),
+            ));
+        }
+        if let Some(rate) = self.max_evaluations_per_second {
+            entries.push(("max_evaluations_per_second", rate.to_string()));
+        }
+        if let Some(threads) = self.objective_threads {
+            entries.push(("objective_threads", threads.to_string()));
+        }
+        if let Some(cost) = self.evaluation_cost {
+            entries.push(("evaluation_cost", cost.to_string()));
+        }
+        if let Some(archive) = &self.archive {
+            entries.push(("archive.size", archive.size.to_string()));
+            entries.push((
+                "archive.injection_probability",
+                archive.injection_probability.to_string(),
+            ));
+            if archive.warm_restart {
+                entries.push(("archive.warm_restart", "true".to_string()));
+            }
+        }
+        if self.generational {
+            entries.push(("generational", "true".to_string()));
+        }
+        if self.synchronous {
+            entries.push(("synchronous", "true".to_string()));
+        }
+        if self.rng_streams {
+            entries.push(("rng_streams", "true".to_string()));
+        }
+        if self.random_per_dimension {
+            entries.push(("random_per_dimension", "true".to_string()));
+        }
+        if let Some(noise) = &self.noise {
+            if let Some(distribution) = &noise.distribution {
+                entries.push(("noise.distribution", distribution.describe()));
+            }
+            entries.push((
+                "noise.sampling",
+                format!("{:?}", noise.sampling).to_lowercase(),
+            ));
+            if let Some(seed) = noise.seed {
+                entries.push(("noise.seed", seed.to_string()));
+            }
+        }
+        if let Some(mf) = &self.multi_fidelity {
+            entries.push(("multi_fidelity.components", mf.components.to_string()));
+            entries.push(("multi_fidelity.low_cost", mf.low_cost.to_string()));
+        }
+        if let Some(budget) = self.stop.cost_budget {
+            entries.push(("stop.cost_budget", budget.to_string()));
+        }
+        if let Some(collapse) = &self.stop.velocity_collapse {
+            entries.push((
+                "stop.velocity_collapse.threshold",
+                collapse.threshold.to_string(),
+            ));
+            entries.push((
+                "stop.velocity_collapse.iterations",
+                collapse.iterations.to_string(),
+            ));
+            entries.push((
+                "stop.velocity_collapse.action",
+                format!("{:?}", collapse.action).to_lowercase(),
+            ));
+        }
+        if self.escape.any() {
+            let min = self.escape.min_improvement;
+            entries.push(("escape.min_improvement", min.to_string()));
+        }
+        if let Some(m) = &self.escape.gbest_mutation {
+            entries.push(("escape.gbest_mutation.after", m.after.to_string()));
+            entries.push(("escape.gbest_mutation.sigma", m.sigma.to_string()));
+            entries.push(("escape.gbest_mutation.particles", m.particles.to_string()));
+        }
+        if let Some(o) = &self.escape.opposition {
+            entries.push(("escape.opposition.after", o.after.to_string()));
+        }
+        if let Some(r) = &self.escape.partial_restart {
+            entries.push(("escape.partial_restart.after", r.after.to_string()));
+            entries.push(("escape.partial_restart.keep", r.keep.to_string()));
+        }
+        if let Some(l) = &self.local_search {
+            entries.push(("local_search.method", l.method.name().to_string()));
+            if let Some(every) = l.every {
+                entries.push(("local_search.every", every.to_string()));
+            }
+            entries.push(("local_search.evaluations", l.evaluations.to_string()));
+            entries.push(("local_search.step", l.step.to_string()));
+        }
+        if let Some(target) = &self.stop.target {
+            entries.push(("stop.target.fitness", target.fitness.to_string()));
+            entries.push(("stop.target.tolerance", target.tolerance.to_string()));
+        }
+        if let Some(stagnation) = &self.stop.stagnation {
+            entries.push((
+                "stop.stagnation.iterations",
+                stagnation.iterations.to_string(),
+            ));
+            entries.push((
+                "stop.stagnation.min_improvement",
+                stagnation.min_improvement.to_string(),
+            ));
+        }
+        if let Some(seconds) = self.stop.max_seconds {
+            entries.push(("stop.max_seconds", seconds.to_string()));
+        }
+        if let Some(evaluations) = self.stop.max_evaluations {
+            entries.push(("stop.max_evaluations", evaluations.to_string()));
+        }
+        entries
+    }
+}
+
+pub fn schema_json() -> String {
+    let schema = schemars::schema_for!(Config);
+    serde_json::to_string_pretty(&schema).expect("Error serializing config schema")
+}
+
+// Commented TOML experiment definition written by `init-config`
+pub const TEMPLATE: &str = include_str!("config_template.toml");
+
+pub fn write_template(path: &str, force: bool) -> Result<(), String> {
+    if !force && std::path::Path::new(path).exists() {
+        return Err(format!("{} exists (use --force to replace it)", path));
+    }
+    std::fs::write(path, TEMPLATE).map_err(|e| format!("cannot write {}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schedule::ScheduleSpec;
+
+    #[test]
+    fn the_template_is_a_valid_config() {
+        let value: serde_json::Value = toml::from_str(TEMPLATE).unwrap();
+        let config = Config::from_value(&value).unwrap();
+        assert_eq!(config.particles, 30);
+        assert_eq!(config.output.log.as_deref(), Some("test_9.csv"));
+    }
+
+    #[test]
+    fn the_equality_band_follows_its_schedule() {
+        let mut config = Config {
+            dimensions: 1,
+            iterations: 10,
+            equality_tolerance: Some(Param::Schedule(ScheduleSpec::Linear(
+                crate::schedule::Linear {
+                    start: 1.0,
+                    end: 0.0,
+                },
+            ))),
+            ..Config::default()
+        };
+        config.constraints = vec![
+            Arc::new(|x: &[f64]| x[0] - 2.0),
+            Arc::new(crate::constraint::Equality::new(|x: &[f64]| x[0], 0.5)),
+        ];
+        config.prepare();
+        // |h| = 0.75 is within the band at first, and 0.75 past it once the band has closed
+        assert_eq!(config.violations(&[0.75]), (0.0, 0.0));
+        assert!(config.advance(10));
+        assert_eq!(config.violations(&[0.75]), (0.0, 0.75));
+        assert_eq!(config.violations(&[3.0]), (1.0, 3.0));
+    }
+
+    #[test]
+    fn a_failed_command_scores_the_worst_value_when_maximizing() {
+        let path = std::env::temp_dir().join("pso_failing_command.toml");
+        std::fs::write(
+            &path,
+            "dimensions = 2\n[command]\nprogram = \"false\"\ntimeout = 5.0\n",
+        )
+        .unwrap();
+        let mut config = Config {
+            objective: Objective::Maximize,
+            ..Config::default()
+        };
+        let problem = crate::problem::Problem::load(path.to_str().unwrap(), &config).unwrap();
+        config.apply_problem(problem).unwrap();
+        // the program exits without a reply; the run must not take that for a maximum
+        let (fitness, _) = config.evaluate(&[0.0, 0.0], EvalContext::particle(0, 0), 1);
+        assert_eq!(fitness, f64::INFINITY);
+    }
+
+    #[test]
+    fn external_programs_are_sent_the_noise_streams() {
+        // answers 1 to the mirrored candidate of a pair, -1 to the others, 0 without streams
+        let script = std::env::temp_dir().join("pso_noise_streams.sh");
+        std::fs::write(
+            &script,
+            "while read line; do case \"$line\" in\n\
+             *'\"mirrored\":true'*) echo '[1]' ;;\n\
+             *'\"noise\"'*) echo '[-1]' ;;\n\
+             *) echo '[0]' ;;\n\
+             esac; done\n",
+        )
+        .unwrap();
+        let path = std::env::temp_dir().join("pso_noise_command.toml");
+        std::fs::write(
+            &path,
+            format!(
+                "dimensions = 2\n[command]\nprogram = \"sh\"\nargs = [{:?}]\n",
+                script.to_str().unwrap()
+            ),
+        )
+        .unwrap();
+        let mut config = Config::default();
+        let problem = crate::problem::Problem::load(path.to_str().unwrap(), &config).unwrap();
+        config.apply_problem(problem).unwrap();
+        assert_eq!(
+            config
+                .evaluate(&[0.0, 0.0], EvalContext::particle(1, 0), 1)
+                .0,
+            0.0
+        );
+
+        config.noise = Some(Noise {
+            distribution: None,
+            sampling: NoiseSampling::Antithetic,
+            seed: Some(3),
+        });
+        assert_eq!(
+            config
+                .evaluate(&[0.0, 0.0], EvalContext::particle(1, 0), 2)
+                .0,
+            -1.0
+        );
+        assert_eq!(
+            config
+                .evaluate(&[0.0, 0.0], EvalContext::particle(1, 1), 3)
+                .0,
+            1.0
+        );
+    }
+
+    #[test]
+    fn external_programs_may_report_the_cost_of_each_evaluation() {
+        // the first candidate costs what its reply says, the second evaluation_cost
+        let path = std::env::temp_dir().join("pso_costed_command.toml");
+        std::fs::write(
+            &path,
+            r#"dimensions = 1
+[command]
+program = "sh"
+args = ["-c", '''read l; echo '[{"value":2,"cost":0.25}]'; read l; echo '[3]' ''']
+"#,
+        )
+        .unwrap();
+        let mut config = Config::default();
+        let problem = crate::problem::Problem::load(path.to_str().unwrap(), &config).unwrap();
+        config.apply_problem(problem).unwrap();
+        config.evaluation_cost = Some(1.5);
+        let at = EvalContext::particle(0, 0);
+        assert_eq!(config.evaluate(&[0.0], at, 1), (2.0, 0.25));
+        assert_eq!(config.evaluate(&[0.0], at, 2), (3.0, 1.5));
+    }
+}
@@ -0,0 +1,231 @@
+// Desc: Constraints beyond the bounds, g(x) <= 0 or h(x) = 0, linear or
+// not, and the adaptive penalty on their violation
+//
+//   let pso = Pso::new(config)
+//       .constraint(|x: &[f64]| x[0] + x[1] - 1.0)             // x0 + x1 <= 1
+//       .constraint(Equality::new(|x: &[f64]| x[0] - x[1], 1e-4)); // x0 == x1
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConstraintKind {
+    Inequality,                  // satisfied where g(x) <= 0
+    Equality { tolerance: f64 }, // satisfied where |h(x)| <= tolerance
+}
+
+// A linear g(x) or h(x), a·x - b: satisfied where a·x <= b, or a·x == b for
+// an equality. Linear constraints can be repaired by projection
+// (`linear_repair`) instead of penalized.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Linear {
+    pub a: Vec<f64>,
+    pub b: f64,
+}
+
+// A constraint on the objective's coordinates; a plain closure is an
+// inequality g(x) <= 0
+pub trait Constraint: Send + Sync {
+    // g(x) or h(x)
+    fn value(&self, x: &[f64]) -> f64;
+
+    fn kind(&self) -> ConstraintKind {
+        ConstraintKind::Inequality
+    }
+
+    // g(x) or h(x) over `dimensions` coordinates as a·x - b when it is known
+    // to be linear; a plain closure is opaque
+    fn linear(&self, _dimensions: usize) -> Option<Linear> {
+        None
+    }
+
+    // How far `x` is from satisfying the constraint, 0 when it does; a NaN
+    // (e.g. sqrt of a negative) counts as infeasible
+    fn violation(&self, x: &[f64]) -> f64 {
+        let v = self.value(x);
+        excess(match self.kind() {
+            ConstraintKind::Inequality => v,
+            ConstraintKind::Equality { tolerance } => v.abs() - tolerance,
+        })
+    }
+}
+
+// The violation of g(x) <= 0 given g(x), or of |h(x)| <= tolerance given
+// |h(x)| - tolerance; a NaN counts as infinitely infeasible
+pub fn excess(v: f64) -> f64 {
+    if v.is_nan() {
+        f64::INFINITY
+    } else {
+        v.max(0.0)
+    }
+}
+
+impl<F> Constraint for F
+where
+    F: Fn(&[f64]) -> f64 + Send + Sync,
+{
+    fn value(&self, x: &[f64]) -> f64 {
+        self(x)
+    }
+}
+
+// Equality constraint h(x) = 0, met within `tolerance`
+pub struct Equality<F> {
+    h: F,
+    tolerance: f64,
+}
+
+impl<F> Equality<F>
+where
+    F: Fn(&[f64]) -> f64 + Send + Sync,
+{
+    pub fn new(h: F, tolerance: f64) -> Self {
+        Equality { h, tolerance }
+    }
+}
+
+impl<F> Constraint for Equality<F>
+where
+    F: Fn(&[f64]) -> f64 + Send + Sync,
+{
+    fn value(&self, x: &[f64]) -> f64 {
+        (self.h)(x)
+    }
+
+    fn kind(&self) -> ConstraintKind {
+        ConstraintKind::Equality {
+            tolerance: self.tolerance,
+        }
+    }
+}
+
+/// Adaptive penalty (Bean and Hadj-Alouane): the penalty per unit of
+/// constraint violation grows while the global best stays infeasible and
+/// shrinks while it stays feasible
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct AdaptivePenalty {
+    /// Consecutive iterations the global best must stay (in)feasible before the penalty changes
+    #[serde(default = "default_window")]
+    #[schemars(range(min = 1))]
+    pub window: usize,
+    /// Factor the penalty is multiplied by after `window` infeasible iterations
+    #[serde(default = "default_increase")]
+    pub increase: f64,
+    /// Factor the penalty is divided by after `window` feasible iterations
+    #[serde(default = "default_decrease")]
+    pub decrease: f64,
+}
+
+fn default_window() -> usize {
+    5
+}
+
+fn default_increase() -> f64 {
+    2.0
+}
+
+fn default_decrease() -> f64 {
+    1.5
+}
+
+impl AdaptivePenalty {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.window == 0 {
+            return Err("adaptive_penalty.window must be at least 1".to_string());
+        }
+        for (name, factor) in [("increase", self.increase), ("decrease", self.decrease)] {
+            if !factor.is_finite() || factor <= 1.0 {
+                return Err(format!(
+                    "adaptive_penalty.{} must be finite and above 1, got {}",
+                    name, factor
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "x{} after {} infeasible iterations, /{} after {} feasible ones",
+            self.increase, self.window, self.decrease, self.window
+        )
+    }
+}
+
+// Multiplier of `penalty_factor` on constraint violations, adapted after
+// every iteration; stays 1 without an adaptive penalty
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PenaltyScale {
+    pub value: f64,
+    feasible_for: usize,   // iterations the global best has been feasible
+    infeasible_for: usize, // iterations it has been infeasible
+}
+
+impl Default for PenaltyScale {
+    fn default() -> Self {
+        PenaltyScale {
+            value: 1.0,
+            feasible_for: 0,
+            infeasible_for: 0,
+        }
+    }
+}
+
+impl PenaltyScale {
+    // Records whether the global best is feasible after an iteration; returns
+    // the previous scale when it changed
+    pub fn adapt(&mut self, adaptive: &AdaptivePenalty, feasible: bool) -> Option<f64> {
+        let previous = self.value;
+        if feasible {
+            self.feasible_for += 1;
+            self.infeasible_for = 0;
+            if self.feasible_for >= adaptive.window {
+                self.value /= adaptive.decrease;
+            }
+        } else {
+            self.infeasible_for += 1;
+            self.feasible_for = 0;
+            if self.infeasible_for >= adaptive.window {
+                self.value *= adaptive.increase;
+            }
+        }
+        // bounded, so a long feasible (or infeasible) stretch can be undone
+        self.value = self.value.clamp(1e-12, 1e12);
+        (self.value != previous).then_some(previous)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn violations_measure_the_distance_to_feasibility() {
+        let g = |x: &[f64]| x[0] - 1.0;
+        assert_eq!(g.violation(&[0.5]), 0.0);
+        assert_eq!(g.violation(&[3.0]), 2.0);
+        assert_eq!(g.violation(&[f64::NAN]), f64::INFINITY);
+
+        let h = Equality::new(|x: &[f64]| x[0] - 1.0, 0.1);
+        assert_eq!(h.violation(&[1.05]), 0.0);
+        assert!((h.violation(&[0.5]) - 0.4).abs() < 1e-12);
+    }
+
+    #[test]
+    fn the_penalty_scale_follows_the_best_feasibility() {
+        let adaptive = AdaptivePenalty {
+            window: 2,
+            increase: 2.0,
+            decrease: 4.0,
+        };
+        let mut scale = PenaltyScale::default();
+        assert_eq!(scale.adapt(&adaptive, false), None);
+        assert_eq!(scale.adapt(&adaptive, false), Some(1.0));
+        assert_eq!(scale.adapt(&adaptive, false), Some(2.0));
+        assert_eq!(scale.value, 4.0);
+        // a change of feasibility restarts the count
+        assert_eq!(scale.adapt(&adaptive, true), None);
+        assert_eq!(scale.adapt(&adaptive, true), Some(4.0));
+        assert_eq!(scale.value, 1.0);
+    }
+}
@@ -0,0 +1,139 @@
+// Desc: Convergence trace: one row per iteration, in the same schema for every
+// algorithm, so runs of different optimizers can be compared and plotted alike
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::events::Event;
+use crate::privacy::Privacy;
+
+pub const COLUMNS: [&str; 8] = [
+    "algorithm",
+    "run",
+    "iteration",
+    "evaluations",
+    "best",
+    "diversity",
+    "seconds",
+    "events",
+];
+
+// Serializes the appends of runs sharing one trace file
+static APPEND: Mutex<()> = Mutex::new(());
+
+// State of a run after one iteration, as every optimizer reports it; fitness
+// values are in the objective's own sign, whether it is minimized or maximized
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct TracePoint {
+    pub iteration: usize, // index of the iteration, as in the CSV log
+    pub evaluations: usize,
+    pub best: f64,         // best-so-far fitness
+    pub mean_fitness: f64, // over the iteration's population
+    pub diversity: f64,    // mean distance of the iteration's population to its centroid
+    pub seconds: f64,      // wall-clock time since the run started
+    pub at_bounds: f64,    // share of the iteration's population with a coordinate on a bound
+    // swarm state, for PSO only
+    pub mean_velocity: Option<f64>, // mean velocity norm
+    pub max_velocity: Option<f64>,
+    pub mean_pbest: Option<f64>, // mean personal best fitness
+    // violations at the best so far, for constrained runs only
+    #[serde(default)]
+    pub inequality_violation: Option<f64>,
+    #[serde(default)]
+    pub equality_violation: Option<f64>,
+}
+
+// The rows of one run; they are appended to the file in one piece when the
+// trace is dropped, so parallel runs can share a file. The events column
+// lists the events of the row's iteration, as kind:name separated by `;`.
+pub struct ConvergenceTrace {
+    path: String,
+    algorithm: &'static str,
+    run: usize,
+    privacy: Option<Privacy>,
+    points: Vec<TracePoint>,
+    events: Vec<(usize, String)>, // iteration and label
+}
+
+impl ConvergenceTrace {
+    // None for pipeline stages, whose trace the pipeline writes
+    pub fn create(path: Option<&str>, config: &Config, privacy: Option<Privacy>) -> Option<Self> {
+        if config.quiet {
+            return None;
+        }
+        Some(ConvergenceTrace {
+            path: path?.to_string(),
+            algorithm: config.algorithm.name(),
+            run: config.run,
+            privacy,
+            points: Vec::new(),
+            events: Vec::new(),
+        })
+    }
+
+    pub fn record(&mut self, point: &TracePoint) {
+        let best = self.privacy.map_or(point.best, |p| p.apply(point.best));
+        self.points.push(TracePoint { best, ..*point });
+    }
+
+    // Replaces the last row, for a change made after the last iteration
+    pub fn amend(&mut self, point: &TracePoint) {
+        self.points.pop();
+        self.record(point);
+    }
+
+    pub fn annotate(&mut self, event: &Event) {
+        self.events.push((event.iteration, event.label()));
+    }
+
+    fn rows(&self) -> Result<Vec<u8>, csv::Error> {
+        let mut rows = csv::Writer::from_writer(Vec::new());
+        for point in &self.points {
+            let events: Vec<&str> = self
+                .events
+                .iter()
+                .filter(|(iteration, _)| *iteration == point.iteration)
+                .map(|(_, label)| label.as_str())
+                .collect();
+            rows.write_record([
+                self.algorithm.to_string(),
+                self.run.to_string(),
+                point.iteration.to_string(),
+                point.evaluations.to_string(),
+                format!("{:?}", point.best),
+                format!("{:?}", point.diversity),
+                format!("{:.6}", point.seconds),
+                events.join(";"),
+            ])?;
+        }
+        rows.into_inner().map_err(|e| e.into_error().into())
+    }
+}
+
+impl Drop for ConvergenceTrace {
+    fn drop(&mut self) {
+        let _guard = APPEND.lock().unwrap_or_else(|e| e.into_inner());
+        let Ok(rows) = self.rows() else {
+            return;
+        };
+        let appended = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| {
+                if file.metadata()?.len() == 0 {
+                    writeln!(file, "{}", COLUMNS.join(","))?;
+                }
+                file.write_all(&rows)
+            });
+        if let Err(e) = appended {
+            eprintln!(
+                "Warning: cannot write convergence trace {}: {}",
+                self.path, e
+            );
+        }
+    }
+}
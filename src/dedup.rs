@@ -0,0 +1,43 @@
+// Desc: Approximate duplicate-particle detection and dispersal
+use std::collections::HashSet;
+
+use rand::Rng;
+
+use crate::config::Config;
+use crate::pso::Particle;
+use crate::sampling;
+
+// Positions are snapped to a grid of this cell size before hashing, so two
+// particles count as duplicates when every coordinate lands in the same cell.
+fn grid_key(x: &[f64], tolerance: f64) -> Vec<i64> {
+    x.iter().map(|xi| (xi / tolerance).round() as i64).collect()
+}
+
+// Re-disperses all but the first particle of every group sharing a grid cell.
+// Their personal bests are kept; only position and velocity are resampled.
+// Returns the number of particles moved.
+pub fn disperse_duplicates(
+    particles: &mut [Particle],
+    tolerance: f64,
+    config: &Config,
+    rng: &mut impl Rng,
+) -> usize {
+    let mut seen = HashSet::with_capacity(particles.len());
+    let mut dispersed = 0;
+
+    for p in particles.iter_mut() {
+        if seen.insert(grid_key(&p.x, tolerance)) {
+            continue;
+        }
+
+        for pos in 0..p.x.len() {
+            let (lower, upper) = config.bounds_of(pos);
+            p.x[pos] = sampling::coordinate(config, pos, rng);
+            p.vx[pos] = config.velocity_init.sample(p.x[pos], (lower, upper), rng);
+        }
+        p.fitness = None;
+        dispersed += 1;
+    }
+
+    dispersed
+}
@@ -0,0 +1,242 @@
+// Desc: Per-iteration diagnostics log
+use crate::config::Config;
+use crate::linalg;
+use crate::pso::Particle;
+use crate::sink::Sink;
+
+pub struct BoundaryStats {
+    pub hits_per_dimension: Vec<usize>, // bound hits on each dimension this iteration
+    pub pinned_particles: usize,        // particles that hit at least one bound
+}
+
+impl BoundaryStats {
+    pub fn new(dimensions: usize) -> Self {
+        BoundaryStats {
+            hits_per_dimension: vec![0; dimensions],
+            pinned_particles: 0,
+        }
+    }
+
+    pub fn total_hits(&self) -> usize {
+        self.hits_per_dimension.iter().sum()
+    }
+}
+
+// Up to this many dimensions the covariance is kept whole, for its
+// eigenvalues; above it only the variances, for its trace
+const FULL_COVARIANCE_DIMENSIONS: usize = 50;
+
+// Covariance of the positions of one iteration, accumulated a particle at a
+// time (Welford's update), without keeping the positions
+pub struct Covariance {
+    count: usize,
+    mean: Vec<f64>,
+    comoments: Vec<Vec<f64>>, // d x d, or 1 x d with the variances only
+}
+
+// What the log reports of a covariance matrix; a collapsing trace is the
+// earliest sign of premature convergence
+pub struct CovarianceSummary {
+    pub trace: f64,
+    pub largest: Option<f64>,   // eigenvalue, for the whole matrix only
+    pub condition: Option<f64>, // largest over smallest eigenvalue
+}
+
+impl Covariance {
+    pub fn new(dimensions: usize) -> Self {
+        let rows = if dimensions <= FULL_COVARIANCE_DIMENSIONS {
+            dimensions
+        } else {
+            1
+        };
+        Covariance {
+            count: 0,
+            mean: vec![0.0; dimensions],
+            comoments: vec![vec![0.0; dimensions]; rows],
+        }
+    }
+
+    fn full(&self) -> bool {
+        self.comoments.len() == self.mean.len()
+    }
+
+    pub fn push(&mut self, x: &[f64]) {
+        self.count += 1;
+        let before: Vec<f64> = x.iter().zip(&self.mean).map(|(xi, m)| xi - m).collect();
+        for (m, d) in self.mean.iter_mut().zip(&before) {
+            *m += d / self.count as f64;
+        }
+        let after: Vec<f64> = x.iter().zip(&self.mean).map(|(xi, m)| xi - m).collect();
+        if self.full() {
+            for (row, di) in self.comoments.iter_mut().zip(&before) {
+                for (c, dj) in row.iter_mut().zip(&after) {
+                    *c += di * dj;
+                }
+            }
+        } else {
+            for ((c, di), dj) in self.comoments[0].iter_mut().zip(&before).zip(&after) {
+                *c += di * dj;
+            }
+        }
+    }
+
+    // Of the unbiased estimate; needs two positions
+    pub fn summary(&self) -> CovarianceSummary {
+        let n = self.count.saturating_sub(1).max(1) as f64;
+        if !self.full() {
+            return CovarianceSummary {
+                trace: self.comoments[0].iter().sum::<f64>() / n,
+                largest: None,
+                condition: None,
+            };
+        }
+        let matrix: Vec<Vec<f64>> = self
+            .comoments
+            .iter()
+            .map(|row| row.iter().map(|c| c / n).collect())
+            .collect();
+        let eigenvalues = linalg::symmetric_eigenvalues(&matrix);
+        let (smallest, largest) = (eigenvalues[0], eigenvalues[eigenvalues.len() - 1]);
+        CovarianceSummary {
+            trace: (0..matrix.len()).map(|i| matrix[i][i]).sum(),
+            largest: Some(largest),
+            condition: Some(if smallest > 0.0 {
+                largest / smallest
+            } else {
+                f64::INFINITY
+            }),
+        }
+    }
+}
+
+pub struct DiagnosticsLog {
+    writer: csv::Writer<Sink>,
+    initial_trace: Option<f64>, // of the first iteration logged
+}
+
+impl DiagnosticsLog {
+    pub fn create(path: &str, dimensions: usize) -> Self {
+        let mut writer = csv::Writer::from_writer(Sink::create(path));
+
+        let mut header = vec![
+            "iteration".to_string(),
+            "pinned_particles".to_string(),
+            "bound_hits".to_string(),
+        ];
+        header.extend((1..=dimensions).map(|d| format!("hits_x{}", d)));
+        header.extend(
+            [
+                "cov_trace",
+                "cov_trace_ratio",
+                "cov_largest_eigenvalue",
+                "cov_condition",
+                "success_rate",
+            ]
+            .map(String::from),
+        );
+        writer
+            .write_record(&header)
+            .expect("Error writing diagnostics file");
+
+        DiagnosticsLog {
+            writer,
+            initial_trace: None,
+        }
+    }
+
+    // `success_rate`: the share of particles that improved their personal best
+    pub fn log(
+        &mut self,
+        iteration: usize,
+        bounds: &BoundaryStats,
+        covariance: &Covariance,
+        success_rate: f64,
+    ) {
+        let mut record = vec![
+            iteration.to_string(),
+            bounds.pinned_particles.to_string(),
+            bounds.total_hits().to_string(),
+        ];
+        record.extend(bounds.hits_per_dimension.iter().map(|h| h.to_string()));
+        let cov = covariance.summary();
+        let initial = *self.initial_trace.get_or_insert(cov.trace);
+        let optional = |v: Option<f64>| v.map_or(String::new(), |v| v.to_string());
+        record.extend([
+            cov.trace.to_string(),
+            (cov.trace / initial).to_string(),
+            optional(cov.largest),
+            optional(cov.condition),
+            success_rate.to_string(),
+        ]);
+        self.writer
+            .write_record(&record)
+            .expect("Error writing diagnostics file");
+    }
+}
+
+impl Drop for DiagnosticsLog {
+    fn drop(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
+
+// Mean Euclidean distance of the particles to the swarm centroid, over the
+// first `dimensions` coordinates (the ones the objective sees)
+pub fn diversity(particles: &[Particle], dimensions: usize) -> f64 {
+    let points: Vec<&[f64]> = particles.iter().map(|p| &p.x[..dimensions]).collect();
+    spread(&points)
+}
+
+// Mean and largest Euclidean norm of the particles' velocities, objective
+// dimensions only
+pub fn velocity_norms(particles: &[Particle], dimensions: usize) -> (f64, f64) {
+    let norms: Vec<f64> = particles
+        .iter()
+        .map(|p| p.vx[..dimensions].iter().map(|v| v * v).sum::<f64>().sqrt())
+        .collect();
+    let mean = norms.iter().sum::<f64>() / norms.len().max(1) as f64;
+    (mean, norms.iter().copied().fold(0.0, f64::max))
+}
+
+// Share of `points` with a coordinate on its bound
+pub fn at_bounds(points: &[&[f64]], config: &Config) -> f64 {
+    let on_bound = points
+        .iter()
+        .filter(|x| {
+            x.iter().enumerate().any(|(pos, &xi)| {
+                let (lower, upper) = config.bounds_of(pos);
+                xi <= lower || xi >= upper
+            })
+        })
+        .count();
+    on_bound as f64 / points.len().max(1) as f64
+}
+
+// Mean Euclidean distance of `points` to their centroid
+pub fn spread(points: &[&[f64]]) -> f64 {
+    let Some(first) = points.first() else {
+        return 0.0;
+    };
+
+    let mut centroid = vec![0.0; first.len()];
+    for x in points {
+        for (c, xi) in centroid.iter_mut().zip(*x) {
+            *c += xi;
+        }
+    }
+    for c in centroid.iter_mut() {
+        *c /= points.len() as f64;
+    }
+
+    let total: f64 = points
+        .iter()
+        .map(|x| {
+            x.iter()
+                .zip(&centroid)
+                .map(|(xi, c)| (xi - c).powi(2))
+                .sum::<f64>()
+                .sqrt()
+        })
+        .sum();
+    total / points.len() as f64
+}
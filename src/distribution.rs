@@ -0,0 +1,73 @@
+// Desc: Configurable probability distributions for sampled parameters
+use std::cmp::Ordering;
+
+use rand::Rng;
+use rand_distr::{Cauchy, Distribution as _, Normal};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Distribution {
+    /// Uniform on [min, max)
+    Uniform { min: f64, max: f64 },
+    /// Gaussian with the given mean and standard deviation
+    Normal { mean: f64, std: f64 },
+    /// Cauchy with the given location and scale; heavy tails give occasional long jumps
+    Cauchy { location: f64, scale: f64 },
+}
+
+impl Distribution {
+    pub fn sample(&self, rng: &mut impl Rng) -> f64 {
+        match *self {
+            Distribution::Uniform { min, max } => rng.gen_range(min..max),
+            Distribution::Normal { mean, std } => Normal::new(mean, std)
+                .expect("validated normal distribution")
+                .sample(rng),
+            Distribution::Cauchy { location, scale } => Cauchy::new(location, scale)
+                .expect("validated cauchy distribution")
+                .sample(rng),
+        }
+    }
+
+    // Centre of symmetry, around which antithetic draws are mirrored
+    pub fn center(&self) -> f64 {
+        match *self {
+            Distribution::Uniform { min, max } => (min + max) / 2.0,
+            Distribution::Normal { mean, .. } => mean,
+            Distribution::Cauchy { location, .. } => location,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            Distribution::Uniform { min, max } if min.partial_cmp(&max) != Some(Ordering::Less) => {
+                Err(format!(
+                    "uniform distribution needs min < max, got [{}, {}]",
+                    min, max
+                ))
+            }
+            Distribution::Normal { std, .. } if !std.is_finite() || std < 0.0 => Err(format!(
+                "normal distribution needs a finite std >= 0, got {}",
+                std
+            )),
+            Distribution::Cauchy { scale, .. } if !scale.is_finite() || scale <= 0.0 => {
+                Err(format!(
+                    "cauchy distribution needs a finite scale > 0, got {}",
+                    scale
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Distribution::Uniform { min, max } => format!("uniform [{}, {})", min, max),
+            Distribution::Normal { mean, std } => format!("normal({}, {})", mean, std),
+            Distribution::Cauchy { location, scale } => {
+                format!("cauchy({}, {})", location, scale)
+            }
+        }
+    }
+}
@@ -0,0 +1,85 @@
+// Desc: Runtime ECDF (`--ecdf`), the usual way benchmarking papers compare
+// optimizers: the share of (run, target) pairs solved by each evaluation
+// count, for targets from 1e2 down to 1e-8 above the optimum, five per decade
+use std::io::Write;
+
+use crate::config::Objective;
+use crate::experiment::RunRecord;
+
+const TARGETS: usize = 51;
+
+fn target(k: usize) -> f64 {
+    10f64.powf(2.0 - k as f64 / 5.0)
+}
+
+// (evaluations, share of the pairs solved by then), one point per
+// evaluation count at which a pair was first solved
+pub fn points(records: &[RunRecord], optimum: f64, objective: Objective) -> Vec<(usize, f64)> {
+    let pairs = (records.len() * TARGETS) as f64;
+    let mut hits: Vec<usize> = records
+        .iter()
+        .flat_map(|r| {
+            (0..TARGETS).filter_map(|k| {
+                r.trace
+                    .iter()
+                    .find(|&&(_, best)| objective.shortfall(best, optimum) <= target(k))
+                    .map(|&(evaluations, _)| evaluations)
+            })
+        })
+        .collect();
+    hits.sort_unstable();
+
+    let mut points: Vec<(usize, f64)> = Vec::new();
+    for (n, &evaluations) in hits.iter().enumerate() {
+        let share = (n + 1) as f64 / pairs;
+        match points.last_mut() {
+            Some(last) if last.0 == evaluations => last.1 = share,
+            _ => points.push((evaluations, share)),
+        }
+    }
+    points
+}
+
+pub fn save(
+    path: &str,
+    records: &[RunRecord],
+    optimum: f64,
+    objective: Objective,
+) -> Result<(), String> {
+    let mut file =
+        std::fs::File::create(path).map_err(|e| format!("cannot create {}: {}", path, e))?;
+    let mut written = writeln!(file, "evaluations,fraction");
+    for (evaluations, share) in points(records, optimum, objective) {
+        written = written.and_then(|_| writeln!(file, "{},{}", evaluations, share));
+    }
+    written.map_err(|e| format!("cannot write {}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(trace: Vec<(usize, f64)>) -> RunRecord {
+        RunRecord {
+            best: trace.last().map_or(f64::INFINITY, |&(_, best)| best),
+            evaluations: trace.last().map_or(0, |&(e, _)| e),
+            iterations: trace.len(),
+            converged_at: trace.len(),
+            stopped: None,
+            trace,
+            position: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn shares_count_the_targets_each_run_reached() {
+        // 1000 above the optimum reaches no target, 1 reaches the 11 down to
+        // 1e0 and 1e-9 all of them
+        let a = record(vec![(10, 1001.0), (20, 2.0), (30, 1.0 + 1e-9)]);
+        let b = record(vec![(10, 1001.0), (20, 1001.0)]);
+        let points = points(&[a, b], 1.0, Objective::Minimize);
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0], (20, 11.0 / 102.0));
+        assert_eq!(points[1], (30, 51.0 / 102.0));
+    }
+}
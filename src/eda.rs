@@ -0,0 +1,105 @@
+// Desc: Univariate Gaussian estimation-of-distribution algorithm (UMDA-c)
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand_distr::StandardNormal;
+
+use crate::comparator::Score;
+use crate::config::Config;
+use crate::convergence::TracePoint;
+use crate::optimizer::{self, Optimizer, Outcome, Tracker};
+use crate::options::RunOptions;
+use crate::sampling;
+use crate::selection;
+
+pub struct Eda<'a> {
+    tracker: Tracker<'a>,
+    rng: StdRng,
+    elite: usize,           // candidates the model is fitted to
+    model: Vec<(f64, f64)>, // (mean, standard deviation) per dimension; empty before the first fit
+}
+
+impl<'a> Eda<'a> {
+    pub fn new(
+        config: Config,
+        options: &'a RunOptions,
+        elite_fraction: f64,
+    ) -> Result<Self, String> {
+        let elite = ((config.particles as f64 * elite_fraction).ceil() as usize).max(1);
+        Ok(Eda {
+            rng: optimizer::seeded_rng(&config),
+            tracker: Tracker::new(config, options)?,
+            elite,
+            model: Vec::new(),
+        })
+    }
+
+    // A candidate drawn from the model, uniform over the bounds before the
+    // first fit; clamped to the bounds and snapped to integer variables
+    fn sample(&mut self) -> Vec<f64> {
+        let config = &self.tracker.config;
+        (0..config.dimensions)
+            .map(|pos| match self.model.get(pos) {
+                Some(&(mean, sd)) => {
+                    let x = mean + sd * self.rng.sample::<f64, _>(StandardNormal);
+                    sampling::confine(config, pos, x)
+                }
+                None => sampling::coordinate(config, pos, &mut self.rng),
+            })
+            .collect()
+    }
+
+    // Maximum-likelihood mean and standard deviation of every coordinate
+    fn fit(&mut self, elite: &[&[f64]]) {
+        let n = elite.len() as f64;
+        self.model = (0..self.tracker.config.dimensions)
+            .map(|pos| {
+                let mean = elite.iter().map(|x| x[pos]).sum::<f64>() / n;
+                let var = elite.iter().map(|x| (x[pos] - mean).powi(2)).sum::<f64>() / n;
+                (mean, var.sqrt())
+            })
+            .collect();
+    }
+}
+
+impl Optimizer for Eda<'_> {
+    fn step(&mut self) {
+        let population: Vec<Vec<f64>> = (0..self.tracker.config.particles)
+            .map(|_| self.sample())
+            .collect();
+        let scores: Vec<Score> = population
+            .iter()
+            .enumerate()
+            .map(|(id, x)| self.tracker.evaluate(id, x))
+            .collect();
+
+        let comparator = &self.tracker.config.comparison;
+        let elite: Vec<&[f64]> =
+            selection::ranked_indices(&scores, self.elite, comparator, &mut self.rng)
+                .into_iter()
+                .map(|k| population[k].as_slice())
+                .collect();
+        self.fit(&elite);
+
+        self.tracker.end_iteration();
+    }
+
+    fn is_finished(&self) -> bool {
+        self.tracker.is_finished()
+    }
+
+    fn stop(&mut self, reason: &'static str) {
+        self.tracker.stop(reason);
+    }
+
+    fn best(&self) -> f64 {
+        self.tracker.best()
+    }
+
+    fn progress(&self) -> TracePoint {
+        self.tracker.progress()
+    }
+
+    fn finish(self: Box<Self>) -> Outcome {
+        self.tracker.finish(&[])
+    }
+}
@@ -0,0 +1,95 @@
+// Desc: Events of a run (stops, restarts, escapes, topology regenerations,
+// local searches and phase switches), kept beside the per-iteration metrics: written to the
+// event log (`--events`) as they happen, annotated on the convergence trace
+// and marked on the convergence plot
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::sink::Sink;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Stop,
+    Restart,
+    Escape,   // gbest mutation, opposition or partial restart of a stagnating swarm
+    Topology, // new neighbourhood links
+    Phase,    // next pipeline stage or block-coordinate group
+    Refine,   // local search from gbest
+}
+
+impl EventKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            EventKind::Stop => "stop",
+            EventKind::Restart => "restart",
+            EventKind::Escape => "escape",
+            EventKind::Topology => "topology",
+            EventKind::Phase => "phase",
+            EventKind::Refine => "refine",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Event {
+    pub iteration: usize,
+    pub evaluations: usize,
+    pub kind: EventKind,
+    pub name: String, // the condition, escape or phase, as in the event log
+    pub detail: String,
+}
+
+impl Event {
+    // Kind and name, as in the convergence trace's events column
+    pub fn label(&self) -> String {
+        format!("{}:{}", self.kind.name(), self.name)
+    }
+}
+
+// How many events of `kind` there were of each name, e.g. `stagnation 2,
+// velocity_collapse 1`; None without any
+pub fn census(events: &[Event], kind: EventKind) -> Option<String> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for event in events.iter().filter(|e| e.kind == kind) {
+        *counts.entry(event.name.as_str()).or_default() += 1;
+    }
+    let counts: Vec<String> = counts
+        .into_iter()
+        .map(|(name, n)| format!("{} {}", name, n))
+        .collect();
+    (!counts.is_empty()).then(|| counts.join(", "))
+}
+
+pub struct EventLog {
+    writer: csv::Writer<Sink>,
+}
+
+impl EventLog {
+    pub fn create(path: &str) -> Self {
+        let mut writer = csv::Writer::from_writer(Sink::create(path));
+        writer
+            .write_record(["iteration", "evaluations", "event", "detail"])
+            .expect("Error writing events file");
+
+        EventLog { writer }
+    }
+
+    pub fn log(&mut self, event: &Event) {
+        self.writer
+            .serialize((
+                event.iteration,
+                event.evaluations,
+                &event.name,
+                &event.detail,
+            ))
+            .expect("Error writing events file");
+    }
+}
+
+impl Drop for EventLog {
+    fn drop(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
@@ -0,0 +1,210 @@
+// Desc: Multi-run experiments: independent runs of one configuration and the
+// statistics of their final fitness
+//
+//   let config = PsoBuilder::new().seed(1).target_fitness(0.0, 1e-6).build()?;
+//   let result = Experiment::new(config, 30).run(|x| x.iter().map(|v| v * v).sum());
+//   println!("{:?}", result.summary.success_rate);
+use std::io::Write;
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::api::PsoConfig;
+use crate::config::{Config, Objective};
+use crate::functions::ObjectiveFunction;
+use crate::handle::CancelToken;
+use crate::multirun;
+use crate::options::RunOptions;
+use crate::stats;
+
+// What one run of an experiment ended with
+#[derive(Clone, Debug)]
+pub struct RunRecord {
+    pub best: f64,
+    pub evaluations: usize,
+    pub iterations: usize,   // iterations completed
+    pub converged_at: usize, // iterations until the best-so-far last improved
+    pub stopped: Option<&'static str>,
+    pub trace: Vec<(usize, f64)>, // evaluations and best-so-far after every iteration
+    pub position: Vec<f64>,       // best solution found, objective coordinates
+}
+
+// Statistics over the runs of an experiment
+#[derive(Clone, Debug, Serialize)]
+pub struct ExperimentSummary {
+    pub runs: usize,
+    pub mean: f64,
+    pub median: f64,
+    pub std: f64,
+    pub best: f64,
+    pub worst: f64,
+    pub success_rate: Option<f64>, // share of runs that reached the target, when there is one
+    pub mean_iterations_to_convergence: f64,
+    pub mean_evaluations: f64,
+}
+
+impl ExperimentSummary {
+    // A run succeeds when its best is within the tolerance of the config's target
+    pub fn of(records: &[RunRecord], config: &Config) -> Self {
+        let values: Vec<f64> = records.iter().map(|r| r.best).collect();
+        let objective = config.objective;
+        let (lowest, highest) = (
+            values.iter().copied().fold(f64::INFINITY, f64::min),
+            values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        );
+        let (best, worst) = match objective {
+            Objective::Minimize => (lowest, highest),
+            Objective::Maximize => (highest, lowest),
+        };
+        let mean_of = |f: fn(&RunRecord) -> usize| {
+            stats::mean(&records.iter().map(|r| f(r) as f64).collect::<Vec<_>>())
+        };
+        ExperimentSummary {
+            runs: records.len(),
+            mean: stats::mean(&values),
+            median: stats::median(&values),
+            std: stats::std_dev(&values),
+            best,
+            worst,
+            success_rate: config.stop.target.as_ref().map(|t| {
+                let reached = values
+                    .iter()
+                    .filter(|&&v| objective.shortfall(v, t.fitness) <= t.tolerance)
+                    .count();
+                reached as f64 / records.len() as f64
+            }),
+            mean_iterations_to_convergence: mean_of(|r| r.converged_at),
+            mean_evaluations: mean_of(|r| r.evaluations),
+        }
+    }
+
+    // The run statistics the bootstrap summary does not cover
+    pub fn print(&self) {
+        if let Some(rate) = self.success_rate {
+            println!("  Success rate: {:.1}%", rate * 100.0);
+        }
+        println!(
+            "  Mean iterations to convergence: {:.1}",
+            self.mean_iterations_to_convergence
+        );
+        println!("  Mean evaluations: {:.1}", self.mean_evaluations);
+    }
+
+    // Writes the summary as JSON when `path` ends in .json, as a one-row CSV
+    // otherwise
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let mut file =
+            std::fs::File::create(path).map_err(|e| format!("cannot create {}: {}", path, e))?;
+        let written = if path.ends_with(".json") {
+            let json = serde_json::to_string_pretty(self).expect("Error serializing summary");
+            writeln!(file, "{}", json)
+        } else {
+            writeln!(
+                file,
+                "runs,mean,median,std,best,worst,success_rate,\
+                 mean_iterations_to_convergence,mean_evaluations"
+            )
+            .and_then(|_| {
+                writeln!(
+                    file,
+                    "{},{},{},{},{},{},{},{},{}",
+                    self.runs,
+                    self.mean,
+                    self.median,
+                    self.std,
+                    self.best,
+                    self.worst,
+                    self.success_rate.map_or(String::new(), |r| r.to_string()),
+                    self.mean_iterations_to_convergence,
+                    self.mean_evaluations
+                )
+            })
+        };
+        written.map_err(|e| format!("cannot write {}: {}", path, e))
+    }
+}
+
+// Outcome of `Experiment::run`: the runs in order and their statistics
+#[derive(Clone, Debug)]
+pub struct ExperimentResult {
+    pub runs: Vec<RunRecord>,
+    pub summary: ExperimentSummary,
+}
+
+// Independent runs of one `PsoConfig`. A seeded config gives run i the seed
+// + i - 1, so the experiment is reproducible; without a seed every run draws
+// its own.
+pub struct Experiment {
+    config: PsoConfig,
+    runs: usize,
+    jobs: Option<usize>,
+}
+
+impl Experiment {
+    pub fn new(config: PsoConfig, runs: usize) -> Self {
+        Experiment {
+            config,
+            runs,
+            jobs: None,
+        }
+    }
+
+    // Worker threads; by default as many as the cores hold, each evaluation
+    // taking objective_threads of them
+    pub fn jobs(mut self, jobs: usize) -> Self {
+        self.jobs = Some(jobs);
+        self
+    }
+
+    // Minimizes `objective` `runs` times, without console or file output
+    pub fn run(&self, objective: impl ObjectiveFunction + 'static) -> ExperimentResult {
+        let mut config = self.config.to_config();
+        config.prepare();
+        config.objective_fn = Some(Arc::new(objective));
+        config.quiet = true;
+
+        let jobs = self
+            .jobs
+            .unwrap_or_else(|| multirun::jobs(None, config.evaluation_threads()))
+            .max(1);
+        let runs = multirun::run(
+            &config,
+            &RunOptions::default(),
+            self.runs,
+            jobs,
+            &CancelToken::default(),
+        );
+        ExperimentResult {
+            summary: ExperimentSummary::of(&runs, &config),
+            runs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::PsoBuilder;
+
+    #[test]
+    fn seeded_experiments_are_reproducible_and_summarized() {
+        let config = PsoBuilder::new()
+            .dimensions(3)
+            .iterations(200)
+            .seed(5)
+            .target_fitness(0.0, 1e-4)
+            .build()
+            .unwrap();
+        let sphere = |x: &[f64]| x.iter().map(|v| v * v).sum::<f64>();
+        let a = Experiment::new(config.clone(), 6).jobs(3).run(sphere);
+        let b = Experiment::new(config, 6).jobs(1).run(sphere);
+
+        let bests = |r: &ExperimentResult| r.runs.iter().map(|r| r.best).collect::<Vec<_>>();
+        assert_eq!(bests(&a), bests(&b));
+        assert_eq!(a.summary.runs, 6);
+        assert_eq!(a.summary.success_rate, Some(1.0));
+        assert!(a.runs.iter().all(|r| r.stopped == Some("target")));
+        assert!(a.summary.best <= a.summary.median && a.summary.median <= a.summary.worst);
+        assert!(a.summary.mean_iterations_to_convergence < 200.0);
+    }
+}
@@ -0,0 +1,70 @@
+// Desc: Step-by-step explanation of the PSO update, for teaching with tiny swarms
+
+// Warn above this many particle-dimensions per iteration; the output gets long fast
+const VERBOSE_LIMIT: usize = 50;
+
+pub struct DimensionTerms {
+    pub inertia: f64,   // w * v
+    pub cognitive: f64, // c1 * r1 * (pbest - x)
+    pub social: f64,    // c2 * r2 * (gbest - x)
+    pub sphere: f64,    // random offset of SPSO-2011, or the whole step of bare-bones and QPSO
+    pub old_x: f64,
+    pub new_x: f64,
+    pub boundary: Option<String>, // what the boundary handling did, if the bound was crossed
+}
+
+pub fn warn_if_verbose(particles: usize, dimensions: usize) {
+    if particles * dimensions > VERBOSE_LIMIT {
+        eprintln!(
+            "Warning: --explain prints {} lines per iteration; it is meant for tiny swarms",
+            particles * (dimensions + 3)
+        );
+    }
+}
+
+pub fn print_header(iteration: usize, particle: usize, r1: f64, r2: f64) {
+    println!(
+        "Iteration {}, particle {} (r1 = {:.4}, r2 = {:.4})",
+        iteration, particle, r1, r2
+    );
+}
+
+pub fn print_dimension(dimension: usize, t: &DimensionTerms) {
+    let boundary = match &t.boundary {
+        Some(note) => format!(" [{}]", note),
+        None => String::new(),
+    };
+    let sphere = if t.sphere != 0.0 {
+        format!(" + sampled {:+.6}", t.sphere)
+    } else {
+        String::new()
+    };
+    println!(
+        "  x{}: inertia {:+.6} + cognitive {:+.6} + social {:+.6}{} = v {:+.6}; x {:.6} -> {:.6}{}",
+        dimension + 1,
+        t.inertia,
+        t.cognitive,
+        t.social,
+        sphere,
+        t.inertia + t.cognitive + t.social + t.sphere,
+        t.old_x,
+        t.new_x,
+        boundary
+    );
+}
+
+pub fn print_outcome(fitness: f64, penalized: bool, old_pbest: f64, old_gbest: f64) {
+    let penalty = if penalized { " (includes penalty)" } else { "" };
+    println!("  fitness {}{}", fitness, penalty);
+
+    if fitness < old_pbest {
+        println!("  pbest updated: {} -> {}", old_pbest, fitness);
+    } else {
+        println!("  pbest kept: {}", old_pbest);
+    }
+    if fitness < old_gbest {
+        println!("  gbest updated: {} -> {}", old_gbest, fitness);
+    } else {
+        println!("  gbest kept: {}", old_gbest);
+    }
+}
@@ -0,0 +1,326 @@
+// Desc: Arithmetic expressions over the problem variables, for constraints
+//
+// Grammar: sums and differences of products and quotients of powers (`^`,
+// right-associative) of unary minus, numbers, variable names, the constants
+// `pi` and `e`, the number of variables `n`, parentheses and the
+// one-argument functions below. `sum(...)` and `prod(...)` run their
+// argument over every variable, which it reads as `x_i`; `i` is its 1-based
+// index.
+
+#[derive(Clone, Debug)]
+pub enum Expr {
+    Number(f64),
+    Variable(usize),
+    Neg(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+    Call(fn(f64) -> f64, Box<Expr>),
+    Dimensions,
+    Indexed, // x_i
+    Index,   // i
+    Sum(Box<Expr>),
+    Product(Box<Expr>),
+}
+
+impl Expr {
+    pub fn eval(&self, x: &[f64]) -> f64 {
+        self.eval_at(x, 0)
+    }
+
+    // `i` is the 0-based index of the innermost enclosing sum or product
+    fn eval_at(&self, x: &[f64], i: usize) -> f64 {
+        match self {
+            Expr::Number(v) => *v,
+            Expr::Variable(k) => x[*k],
+            Expr::Neg(e) => -e.eval_at(x, i),
+            Expr::Binary(op, a, b) => {
+                let (a, b) = (a.eval_at(x, i), b.eval_at(x, i));
+                match op {
+                    '+' => a + b,
+                    '-' => a - b,
+                    '*' => a * b,
+                    '/' => a / b,
+                    _ => a.powf(b),
+                }
+            }
+            Expr::Call(f, e) => f(e.eval_at(x, i)),
+            Expr::Dimensions => x.len() as f64,
+            Expr::Indexed => x[i],
+            Expr::Index => (i + 1) as f64,
+            Expr::Sum(e) => (0..x.len()).map(|i| e.eval_at(x, i)).sum(),
+            Expr::Product(e) => (0..x.len()).map(|i| e.eval_at(x, i)).product(),
+        }
+    }
+
+    // Coefficients and constant of the expression over `n` variables when it
+    // is affine in them, c + a·x; None when it is not (or not provably so)
+    pub fn affine(&self, n: usize) -> Option<(Vec<f64>, f64)> {
+        self.affine_at(n, 0)
+    }
+
+    fn affine_at(&self, n: usize, i: usize) -> Option<(Vec<f64>, f64)> {
+        let constant = |c: f64| Some((vec![0.0; n], c));
+        let is_constant = |a: &[f64]| a.iter().all(|&ai| ai == 0.0);
+        let scaled =
+            |(a, c): (Vec<f64>, f64), k: f64| Some((a.iter().map(|ai| k * ai).collect(), k * c));
+        let combined = |(a, c): (Vec<f64>, f64), (b, d): (Vec<f64>, f64), sign: f64| {
+            let sum = a.iter().zip(&b).map(|(ai, bi)| ai + sign * bi).collect();
+            Some((sum, c + sign * d))
+        };
+        match self {
+            Expr::Number(v) => constant(*v),
+            Expr::Variable(k) => {
+                let mut a = vec![0.0; n];
+                a[*k] = 1.0;
+                Some((a, 0.0))
+            }
+            Expr::Indexed => {
+                let mut a = vec![0.0; n];
+                a[i] = 1.0;
+                Some((a, 0.0))
+            }
+            Expr::Neg(e) => scaled(e.affine_at(n, i)?, -1.0),
+            Expr::Binary(op, a, b) => {
+                let (a, b) = (a.affine_at(n, i)?, b.affine_at(n, i)?);
+                match op {
+                    '+' => combined(a, b, 1.0),
+                    '-' => combined(a, b, -1.0),
+                    '*' if is_constant(&a.0) => scaled(b, a.1),
+                    '*' if is_constant(&b.0) => scaled(a, b.1),
+                    '/' if is_constant(&b.0) => scaled(a, 1.0 / b.1),
+                    '^' if is_constant(&b.0) && b.1 == 1.0 => Some(a),
+                    _ if is_constant(&a.0) && is_constant(&b.0) => {
+                        constant(self.eval_at(&vec![0.0; n], i))
+                    }
+                    _ => None,
+                }
+            }
+            Expr::Call(_, e) => {
+                let (a, _) = e.affine_at(n, i)?;
+                is_constant(&a).then(|| (a, self.eval_at(&vec![0.0; n], i)))
+            }
+            Expr::Dimensions => constant(n as f64),
+            Expr::Index => constant((i + 1) as f64),
+            Expr::Sum(e) => (0..n).try_fold((vec![0.0; n], 0.0), |sum, i| {
+                combined(sum, e.affine_at(n, i)?, 1.0)
+            }),
+            Expr::Product(e) => (0..n).try_fold((vec![0.0; n], 1.0), |product, i| {
+                let factor = e.affine_at(n, i)?;
+                match (is_constant(&product.0), is_constant(&factor.0)) {
+                    (true, _) => scaled(factor, product.1),
+                    (_, true) => scaled(product, factor.1),
+                    _ => None,
+                }
+            }),
+        }
+    }
+}
+
+fn function(name: &str) -> Option<fn(f64) -> f64> {
+    Some(match name {
+        "sin" => f64::sin,
+        "cos" => f64::cos,
+        "tan" => f64::tan,
+        "exp" => f64::exp,
+        "ln" => f64::ln,
+        "sqrt" => f64::sqrt,
+        "abs" => f64::abs,
+        _ => return None,
+    })
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Op(char),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            // exponent of a literal such as 1e-3
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let mut j = i + 1;
+                if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                    j += 1;
+                }
+                if j < chars.len() && chars[j].is_ascii_digit() {
+                    i = j;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let literal: String = chars[start..i].iter().collect();
+            let value = literal
+                .parse()
+                .map_err(|_| format!("invalid number `{}`", literal))?;
+            tokens.push(Token::Number(value));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Name(chars[start..i].iter().collect()));
+        } else if "+-*/^()".contains(c) {
+            tokens.push(Token::Op(c));
+            i += 1;
+        } else {
+            return Err(format!("unexpected character `{}`", c));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    variables: &'a [String],
+    aggregates: usize, // depth of enclosing sum/prod, where `x_i` and `i` exist
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eat(&mut self, op: char) -> bool {
+        if self.peek() == Some(&Token::Op(op)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut left = self.product()?;
+        loop {
+            let op = if self.eat('+') {
+                '+'
+            } else if self.eat('-') {
+                '-'
+            } else {
+                return Ok(left);
+            };
+            left = Expr::Binary(op, Box::new(left), Box::new(self.product()?));
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+        loop {
+            let op = if self.eat('*') {
+                '*'
+            } else if self.eat('/') {
+                '/'
+            } else {
+                return Ok(left);
+            };
+            left = Expr::Binary(op, Box::new(left), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat('-') {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<Expr, String> {
+        let base = self.atom()?;
+        if self.eat('^') {
+            return Ok(Expr::Binary('^', Box::new(base), Box::new(self.unary()?)));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Expr, String> {
+        let token = self.peek().cloned().ok_or("unexpected end of expression")?;
+        self.pos += 1;
+        match token {
+            Token::Number(v) => Ok(Expr::Number(v)),
+            Token::Op('(') => {
+                let inner = self.sum()?;
+                if !self.eat(')') {
+                    return Err("missing `)`".to_string());
+                }
+                Ok(inner)
+            }
+            Token::Name(name) => {
+                if let Some(i) = self.variables.iter().position(|v| *v == name) {
+                    return Ok(Expr::Variable(i));
+                }
+                match name.as_str() {
+                    "pi" => return Ok(Expr::Number(std::f64::consts::PI)),
+                    "e" => return Ok(Expr::Number(std::f64::consts::E)),
+                    "n" => return Ok(Expr::Dimensions),
+                    "x_i" | "i" if self.aggregates == 0 => {
+                        return Err(format!("`{}` is only defined inside sum() or prod()", name))
+                    }
+                    "x_i" => return Ok(Expr::Indexed),
+                    "i" => return Ok(Expr::Index),
+                    _ => {}
+                }
+                let aggregate = name == "sum" || name == "prod";
+                let f = function(&name);
+                if f.is_none() && !aggregate {
+                    return Err(format!("unknown name `{}`", name));
+                }
+                if !self.eat('(') {
+                    return Err(format!("`{}` needs an argument in parentheses", name));
+                }
+                self.aggregates += aggregate as usize;
+                let argument = Box::new(self.sum()?);
+                self.aggregates -= aggregate as usize;
+                if !self.eat(')') {
+                    return Err("missing `)`".to_string());
+                }
+                Ok(match (f, name.as_str()) {
+                    (Some(f), _) => Expr::Call(f, argument),
+                    (None, "sum") => Expr::Sum(argument),
+                    _ => Expr::Product(argument),
+                })
+            }
+            Token::Op(op) => Err(format!("unexpected `{}`", op)),
+        }
+    }
+}
+
+// Parses `text`, resolving names against `variables` (by position)
+pub fn parse(text: &str, variables: &[String]) -> Result<Expr, String> {
+    let mut parser = Parser {
+        tokens: tokenize(text)?,
+        pos: 0,
+        variables,
+        aggregates: 0,
+    };
+    let expr = parser.sum()?;
+    if parser.pos < parser.tokens.len() {
+        return Err(format!("unexpected {:?}", parser.tokens[parser.pos]));
+    }
+    Ok(expr)
+}
+
+// A column or asset name of a data file as a name expressions can use
+pub fn identifier(name: &str) -> String {
+    let mut id: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    if id.starts_with(|c: char| c.is_ascii_digit()) {
+        id.insert(0, '_');
+    }
+    id
+}
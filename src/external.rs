@@ -0,0 +1,295 @@
+// Desc: Objective computed by an external program (`[command]` in the problem
+// file), so problems written outside Rust need no rebuild
+//
+// The program is started once and kept running. Each request is one JSON
+// line on its stdin holding a batch of candidates, e.g. [[0.5,1.2],[0.1,-3]],
+// and the program answers with one JSON line of their fitness values in the
+// same order, e.g. [2.1,9.3]. With a [noise] section the request is an object
+// that also gives the noise stream of every candidate, for programs that draw
+// their own noise: {"candidates":[[0.5,1.2],[0.1,-3]],"noise":[{"seed":7,
+// "mirrored":false},{"seed":7,"mirrored":true}]}. A value may come with the
+// cost of its evaluation, as {"value":2.1,"cost":0.4}, which then replaces
+// evaluation_cost for it. Evaluations that arrive while a batch is out
+// (with --threads, or runs in parallel) wait and leave together in the next
+// one, up to `batch` candidates. A batch whose reply is late, malformed or
+// cut short by the program exiting scores the worst value for the direction
+// of the run (+inf minimizing, -inf maximizing), and the program is restarted
+// for the next one; after a few failures in a row the run gives up. With
+// objective_threads the program finds that count in the usual thread-pool
+// variables (OMP_NUM_THREADS and the like), so its own parallelism stays
+// within the cores the run leaves it.
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::noise::NoiseStream;
+
+const MAX_FAILURES: usize = 3; // consecutive failed batches before the run stops
+
+// Variables the common threading runtimes size their pools from
+const THREAD_VARIABLES: [&str; 4] = [
+    "OMP_NUM_THREADS",
+    "MKL_NUM_THREADS",
+    "OPENBLAS_NUM_THREADS",
+    "RAYON_NUM_THREADS",
+];
+
+struct Process {
+    child: Child,
+    stdin: ChildStdin,
+    replies: Receiver<String>, // lines of its stdout, read on a thread of their own
+}
+
+impl Process {
+    fn spawn(program: &str, args: &[String], threads: Option<u64>) -> Result<Process, String> {
+        let mut command = Command::new(program);
+        if let Some(threads) = threads {
+            for variable in THREAD_VARIABLES {
+                command.env(variable, threads.to_string());
+            }
+        }
+        let mut child = command
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("cannot start external objective {}: {}", program, e))?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let (sender, replies) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Process {
+            child,
+            stdin,
+            replies,
+        })
+    }
+
+    // Fitness values of `batch` and the costs the program reported, or what went wrong
+    fn request(&mut self, batch: &[Candidate], timeout: Duration) -> Result<Vec<Reply>, String> {
+        let candidates: Vec<&[f64]> = batch.iter().map(|c| c.x.as_slice()).collect();
+        let streams: Option<Vec<NoiseStream>> = batch.iter().map(|c| c.stream).collect();
+        let line = match streams {
+            Some(noise) => serde_json::to_string(&Request {
+                candidates: &candidates,
+                noise: &noise,
+            }),
+            None => serde_json::to_string(&candidates),
+        }
+        .expect("candidates serialize");
+        writeln!(self.stdin, "{}", line)
+            .and_then(|_| self.stdin.flush())
+            .map_err(|e| format!("cannot send it candidates: {}", e))?;
+        let reply = match self.replies.recv_timeout(timeout) {
+            Ok(reply) => reply,
+            Err(RecvTimeoutError::Timeout) => {
+                return Err(format!("no reply within {} s", timeout.as_secs_f64()))
+            }
+            Err(RecvTimeoutError::Disconnected) => return Err("it exited".to_string()),
+        };
+        let values: Vec<Reply> = serde_json::from_str(&reply)
+            .map_err(|e| format!("invalid reply `{}`: {}", reply, e))?;
+        if values.len() != batch.len() {
+            return Err(format!(
+                "{} value(s) for {} candidate(s)",
+                values.len(),
+                batch.len()
+            ));
+        }
+        Ok(values)
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+// The answer for one candidate: its value, with or without its cost
+#[derive(Clone, Copy, Deserialize)]
+#[serde(untagged)]
+enum Reply {
+    Value(f64),
+    Costed { value: f64, cost: f64 },
+}
+
+impl Reply {
+    fn split(self) -> (f64, Option<f64>) {
+        match self {
+            Reply::Value(value) => (value, None),
+            Reply::Costed { value, cost } => (value, Some(cost)),
+        }
+    }
+}
+
+// A point to evaluate and its noise stream, with a [noise] section
+struct Candidate {
+    x: Vec<f64>,
+    stream: Option<NoiseStream>,
+}
+
+// A batch of candidates with their noise streams
+#[derive(Serialize)]
+struct Request<'a> {
+    candidates: &'a [&'a [f64]],
+    noise: &'a [NoiseStream],
+}
+
+#[derive(Default)]
+struct Queue {
+    pending: Vec<(u64, Candidate)>, // tickets and candidates not sent yet, oldest first
+    done: HashMap<u64, Option<(f64, Option<f64>)>>, // values and costs not collected yet, None if their batch failed
+    next: u64,
+    out: bool, // a batch is waiting for its reply
+}
+
+struct Link {
+    process: Option<Process>, // None until the first batch and after a failure
+    failures: usize,          // in a row
+    given_up: Option<String>, // why, once MAX_FAILURES batches in a row failed
+}
+
+pub struct ExternalObjective {
+    pub program: String,
+    args: Vec<String>,
+    threads: Option<u64>, // objective_threads, passed on to the program
+    batch: usize,
+    timeout: Duration,
+    queue: Mutex<Queue>,
+    replied: Condvar,
+    link: Mutex<Link>,
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl ExternalObjective {
+    // Starts the program at once, so a wrong command fails before the run
+    pub fn start(
+        program: &str,
+        args: Vec<String>,
+        batch: usize,
+        timeout: f64,
+        threads: Option<u64>,
+    ) -> Result<ExternalObjective, String> {
+        let process = Process::spawn(program, &args, threads)?;
+        Ok(ExternalObjective {
+            program: program.to_string(),
+            args,
+            threads,
+            batch,
+            timeout: Duration::from_secs_f64(timeout),
+            queue: Mutex::new(Queue::default()),
+            replied: Condvar::new(),
+            link: Mutex::new(Link {
+                process: Some(process),
+                failures: 0,
+                given_up: None,
+            }),
+        })
+    }
+
+    // The program and its arguments, as started
+    pub fn command_line(&self) -> String {
+        std::iter::once(&self.program)
+            .chain(&self.args)
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    // Queues `x`; whichever caller finds no batch out sends the oldest
+    // pending candidates, its own among them sooner or later. The value, with
+    // the cost if the program gave one; None when the batch it left in failed.
+    pub fn evaluate(&self, x: &[f64], stream: Option<NoiseStream>) -> Option<(f64, Option<f64>)> {
+        let mut queue = lock(&self.queue);
+        let ticket = queue.next;
+        queue.next += 1;
+        let x = x.to_vec();
+        queue.pending.push((ticket, Candidate { x, stream }));
+        loop {
+            if let Some(fitness) = queue.done.remove(&ticket) {
+                return fitness;
+            }
+            if queue.out {
+                queue = self.replied.wait(queue).unwrap_or_else(|e| e.into_inner());
+                continue;
+            }
+            let n = queue.pending.len().min(self.batch);
+            let (tickets, batch): (Vec<u64>, Vec<Candidate>) = queue.pending.drain(..n).unzip();
+            queue.out = true;
+            drop(queue);
+
+            let values = self.send(&batch);
+            queue = lock(&self.queue);
+            queue.out = false;
+            queue.done.extend(tickets.into_iter().zip(values));
+            self.replied.notify_all();
+        }
+    }
+
+    // Why the program was given up, if it was; every candidate after that
+    // scores the worst value without being sent
+    pub fn failure(&self) -> Option<String> {
+        lock(&self.link).given_up.clone()
+    }
+
+    fn send(&self, batch: &[Candidate]) -> Vec<Option<(f64, Option<f64>)>> {
+        let mut link = lock(&self.link);
+        if link.given_up.is_some() {
+            return vec![None; batch.len()];
+        }
+        let process = match link.process.take() {
+            Some(process) => Ok(process),
+            None => Process::spawn(&self.program, &self.args, self.threads),
+        };
+        let reply = process.and_then(|mut process| {
+            let values = process.request(batch, self.timeout)?;
+            Ok((process, values))
+        });
+        match reply {
+            Ok((process, values)) => {
+                link.process = Some(process);
+                link.failures = 0;
+                values
+                    .into_iter()
+                    .map(|reply| Some(reply.split()))
+                    .collect()
+            }
+            Err(e) => {
+                link.failures += 1;
+                if link.failures >= MAX_FAILURES {
+                    link.given_up = Some(format!(
+                        "external objective {} failed {} batches in a row: {}",
+                        self.program, link.failures, e
+                    ));
+                    return vec![None; batch.len()];
+                }
+                eprintln!(
+                    "Warning: external objective {}: {}; {} candidate(s) score the worst value and it is restarted",
+                    self.program,
+                    e,
+                    batch.len()
+                );
+                vec![None; batch.len()]
+            }
+        }
+    }
+}
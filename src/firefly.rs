@@ -0,0 +1,117 @@
+// Desc: Firefly algorithm (Yang): fireflies fly towards brighter ones, attraction fading with distance
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::comparator::Score;
+use crate::config::Config;
+use crate::convergence::TracePoint;
+use crate::optimizer::{self, Optimizer, Outcome, Tracker};
+use crate::options::RunOptions;
+use crate::sampling;
+
+pub struct Firefly<'a> {
+    tracker: Tracker<'a>,
+    rng: StdRng,
+    alpha: f64, // current randomization step, as a fraction of each range
+    beta0: f64,
+    gamma: f64,
+    alpha_decay: f64,
+    flies: Vec<(Vec<f64>, Score)>,
+}
+
+impl<'a> Firefly<'a> {
+    pub fn new(
+        config: Config,
+        options: &'a RunOptions,
+        alpha: f64,
+        beta0: f64,
+        gamma: f64,
+        alpha_decay: f64,
+    ) -> Result<Self, String> {
+        let mut firefly = Firefly {
+            rng: optimizer::seeded_rng(&config),
+            tracker: Tracker::new(config, options)?,
+            alpha,
+            beta0,
+            gamma,
+            alpha_decay,
+            flies: Vec::new(),
+        };
+        for id in 0..firefly.tracker.config.particles {
+            let config = &firefly.tracker.config;
+            let x: Vec<f64> = sampling::point(config, &mut firefly.rng);
+            let score = firefly.tracker.evaluate(id, &x);
+            firefly.flies.push((x, score));
+        }
+        Ok(firefly)
+    }
+}
+
+impl Optimizer for Firefly<'_> {
+    fn step(&mut self) {
+        let config = &self.tracker.config;
+        let ranges: Vec<f64> = (0..config.dimensions)
+            .map(|pos| {
+                let (lower, upper) = config.bounds_of(pos);
+                upper - lower
+            })
+            .collect();
+
+        // every firefly moves towards each one that was brighter at the start of
+        // the generation, with attraction beta0 * exp(-gamma r^2) over the
+        // range-normalized distance r, then takes a random step
+        let mut moved = Vec::with_capacity(self.flies.len());
+        for (x, score) in &self.flies {
+            let mut y = x.clone();
+            for (other, other_score) in &self.flies {
+                if !config.comparison.better(*other_score, *score) {
+                    continue;
+                }
+                let r2: f64 = y
+                    .iter()
+                    .zip(other)
+                    .zip(&ranges)
+                    .map(|((a, b), r)| ((a - b) / r).powi(2))
+                    .sum();
+                let beta = self.beta0 * (-self.gamma * r2).exp();
+                for (yi, oi) in y.iter_mut().zip(other) {
+                    *yi += beta * (oi - *yi);
+                }
+            }
+            for (pos, yi) in y.iter_mut().enumerate() {
+                let (lower, upper) = config.bounds_of(pos);
+                let step = self.alpha * (self.rng.gen::<f64>() - 0.5) * ranges[pos];
+                *yi = config.snap(pos, (*yi + step).clamp(lower, upper));
+            }
+            moved.push(y);
+        }
+
+        for (id, (fly, y)) in self.flies.iter_mut().zip(moved).enumerate() {
+            let score = self.tracker.evaluate(id, &y);
+            *fly = (y, score);
+        }
+        self.alpha *= self.alpha_decay;
+
+        self.tracker.end_iteration();
+    }
+
+    fn is_finished(&self) -> bool {
+        self.tracker.is_finished()
+    }
+
+    fn stop(&mut self, reason: &'static str) {
+        self.tracker.stop(reason);
+    }
+
+    fn best(&self) -> f64 {
+        self.tracker.best()
+    }
+
+    fn progress(&self) -> TracePoint {
+        self.tracker.progress()
+    }
+
+    fn finish(self: Box<Self>) -> Outcome {
+        self.tracker.finish(&[])
+    }
+}
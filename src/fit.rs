@@ -0,0 +1,140 @@
+// Desc: Least-squares fitting objective (the `[fit]` table of a problem
+// file): the parameters of a model expression, scored by the sum of squared
+// residuals against the observations of a data file,
+//
+//   Σ_rows (model(parameters, inputs) - observed)^2
+//
+// The data is a CSV file with a header; the `target` column (default the
+// last) holds the observations and the other columns are inputs the model
+// reads by name next to the parameters, the problem variables. `sum()`,
+// `prod()` and `n` in the model run over the parameters and then the inputs.
+use crate::expr::{self, identifier, Expr};
+use crate::trajectory::Trajectory;
+
+#[derive(Clone, Debug)]
+pub struct Fit {
+    pub path: String,
+    pub name: String, // the model and the data, for the reports
+    model: Expr,
+    inputs: Vec<Vec<f64>>, // per row, in column order
+    observed: Vec<f64>,
+}
+
+impl Fit {
+    pub fn load(
+        path: &str,
+        text: &str,
+        target: Option<&str>,
+        parameters: &[String],
+    ) -> Result<Fit, String> {
+        let data = Trajectory::open(path)?;
+        let columns: Vec<String> = data
+            .header()
+            .unwrap_or_default()
+            .split(',')
+            .map(|name| identifier(name.trim()))
+            .collect();
+        let at = match target {
+            Some(name) => columns
+                .iter()
+                .position(|c| c == name)
+                .ok_or_else(|| format!("{} has no target column {}", path, name))?,
+            None => columns.len() - 1,
+        };
+        if columns[at].is_empty() {
+            return Err(format!("{} has no header of column names", path));
+        }
+        let inputs: Vec<String> = columns
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| j != at)
+            .map(|(_, c)| c.clone())
+            .collect();
+        if let Some(clash) = inputs.iter().find(|c| parameters.contains(c)) {
+            return Err(format!(
+                "column {} of {} has the name of a variable",
+                clash, path
+            ));
+        }
+        let names: Vec<String> = parameters.iter().chain(&inputs).cloned().collect();
+        let model = expr::parse(text, &names).map_err(|e| format!("model `{}`: {}", text, e))?;
+
+        let mut fit = Fit {
+            path: path.to_string(),
+            name: format!("least squares of {} against {}", text, path),
+            model,
+            inputs: Vec::new(),
+            observed: Vec::new(),
+        };
+        let mut failure = None;
+        let mut line = 0;
+        data.rows(|fields| {
+            line += 1;
+            if line == 1 || failure.is_some() || fields.iter().all(|f| f.trim().is_empty()) {
+                return;
+            }
+            if fields.len() != columns.len() {
+                failure = Some(format!(
+                    "{} line {}: {} values for {} columns",
+                    path,
+                    line,
+                    fields.len(),
+                    columns.len()
+                ));
+                return;
+            }
+            match fields
+                .iter()
+                .map(|f| f.trim().parse::<f64>())
+                .collect::<Result<Vec<f64>, _>>()
+            {
+                Ok(mut row) => {
+                    fit.observed.push(row.remove(at));
+                    fit.inputs.push(row);
+                }
+                Err(e) => failure = Some(format!("{} line {}: {}", path, line, e)),
+            }
+        })?;
+        if let Some(failure) = failure {
+            return Err(failure);
+        }
+        if fit.observed.is_empty() {
+            return Err(format!("{} has no observations", path));
+        }
+        Ok(fit)
+    }
+
+    pub fn evaluate(&self, parameters: &[f64]) -> f64 {
+        let p = parameters.len();
+        let mut x = parameters.to_vec();
+        x.resize(p + self.inputs[0].len(), 0.0);
+        self.inputs
+            .iter()
+            .zip(&self.observed)
+            .map(|(row, y)| {
+                x[p..].copy_from_slice(row);
+                let residual = self.model.eval(&x) - y;
+                residual * residual
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_residuals_vanish_at_the_true_parameters() {
+        let path = std::env::temp_dir().join("pso_fit_line.csv");
+        std::fs::write(&path, "y,t\n1,0\n3,1\n5,2\n").unwrap();
+        let names = ["a".to_string(), "b".to_string()];
+        let fit = Fit::load(path.to_str().unwrap(), "a + b*t", Some("y"), &names).unwrap();
+        assert_eq!(fit.evaluate(&[1.0, 2.0]), 0.0);
+        // residuals 1, 1, 1
+        assert_eq!(fit.evaluate(&[2.0, 2.0]), 3.0);
+        // a variable named like an input column is ambiguous
+        let clash = ["t".to_string()];
+        assert!(Fit::load(path.to_str().unwrap(), "t", Some("y"), &clash).is_err());
+    }
+}
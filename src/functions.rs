@@ -0,0 +1,259 @@
+// Desc: Objective functions the swarm can minimize
+use clap::ValueEnum;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::benchmarks;
+use crate::noise::NoiseStream;
+
+// Anything that maps a position to a fitness to be minimized. Closures and
+// plain functions qualify, as do the built-in benchmarks.
+pub trait ObjectiveFunction: Send + Sync {
+    fn evaluate(&self, x: &[f64]) -> f64;
+
+    // The value at `x` for an objective that draws its own noise: from the
+    // noise stream of the evaluation, given with a [noise] section
+    fn evaluate_with(&self, x: &[f64], _stream: Option<NoiseStream>) -> f64 {
+        self.evaluate(x)
+    }
+
+    // The value and what the evaluation cost, for objectives whose cost
+    // varies (a simulation billed by its run time); None charges the
+    // configured evaluation_cost
+    fn evaluate_with_cost(&self, x: &[f64], stream: Option<NoiseStream>) -> (f64, Option<f64>) {
+        (self.evaluate_with(x, stream), None)
+    }
+
+    // A cheap approximation of the value at `x`, for multi-fidelity runs, at
+    // the fidelity `level` they are configured with; None for objectives that
+    // have none, which are then evaluated accurately throughout
+    fn evaluate_fidelity(&self, _x: &[f64], _level: usize) -> Option<f64> {
+        None
+    }
+}
+
+impl<F> ObjectiveFunction for F
+where
+    F: Fn(&[f64]) -> f64 + Send + Sync,
+{
+    fn evaluate(&self, x: &[f64]) -> f64 {
+        self(x)
+    }
+}
+
+impl ObjectiveFunction for Function {
+    fn evaluate(&self, x: &[f64]) -> f64 {
+        Function::evaluate(self, x)
+    }
+
+    // `level` terms of the sum, for the decomposable functions
+    fn evaluate_fidelity(&self, x: &[f64], level: usize) -> Option<f64> {
+        self.is_decomposable()
+            .then(|| self.evaluate_low_fidelity(x, level))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, JsonSchema, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Function {
+    /// Rastrigin function, global minimum 0 at the origin
+    Rastrigin,
+    /// Quadratic bowl (sphere) sum(x_i^2), minimum 0 at the origin; its
+    /// linear PSO dynamics can be worked out by hand
+    #[serde(alias = "sphere")]
+    #[value(alias = "sphere")]
+    Quadratic,
+    /// Rosenbrock's valley, minimum 0 at (1, ..., 1)
+    Rosenbrock,
+    /// Ackley function, minimum 0 at the origin
+    Ackley,
+    /// Griewank function, minimum 0 at the origin
+    Griewank,
+    /// Schwefel function, minimum (about) 0 at x_i = 420.9687
+    Schwefel,
+}
+
+// Canonical search domain and a sensible problem size for a function
+pub struct FunctionDefaults {
+    pub lower_bound: f64,
+    pub upper_bound: f64,
+    pub dimensions: usize,
+}
+
+impl Function {
+    pub fn evaluate(&self, x: &[f64]) -> f64 {
+        match self {
+            Function::Rastrigin => benchmarks::rastrigin(x),
+            Function::Quadratic => benchmarks::sphere(x),
+            Function::Rosenbrock => benchmarks::rosenbrock(x),
+            Function::Ackley => benchmarks::ackley(x),
+            Function::Griewank => benchmarks::griewank(x),
+            Function::Schwefel => benchmarks::schwefel(x),
+        }
+    }
+
+    // Whether the function is a sum of per-dimension terms, so that a change
+    // of a few coordinates can be evaluated from those coordinates alone
+    pub fn is_decomposable(&self) -> bool {
+        match self {
+            Function::Rastrigin | Function::Quadratic | Function::Schwefel => true,
+            Function::Rosenbrock | Function::Ackley | Function::Griewank => false,
+        }
+    }
+
+    // Contribution of one coordinate to the sum of a decomposable function
+    fn component(&self, xi: f64) -> f64 {
+        match self {
+            Function::Rastrigin => {
+                10.0 + xi.powi(2) - 10.0 * (2.0 * std::f64::consts::PI * xi).cos()
+            }
+            Function::Quadratic => xi * xi,
+            Function::Schwefel => benchmarks::schwefel_component(xi),
+            Function::Rosenbrock | Function::Ackley | Function::Griewank => {
+                unreachable!("{} is not decomposable", self.name())
+            }
+        }
+    }
+
+    // Fitness at `x` given the fitness `previous` before the coordinates in
+    // `changed` moved from their listed old values. Only valid for
+    // decomposable functions; rounding error accumulates over many updates.
+    pub fn evaluate_partial(&self, previous: f64, x: &[f64], changed: &[(usize, f64)]) -> f64 {
+        debug_assert!(self.is_decomposable());
+        changed.iter().fold(previous, |fitness, &(pos, old)| {
+            fitness - self.component(old) + self.component(x[pos])
+        })
+    }
+
+    // Cheap approximation of a decomposable function: only `components`
+    // evenly spaced terms of the sum are computed and scaled up to all of them
+    pub fn evaluate_low_fidelity(&self, x: &[f64], components: usize) -> f64 {
+        debug_assert!(self.is_decomposable());
+        let m = components.clamp(1, x.len());
+        let sum: f64 = (0..m).map(|k| self.component(x[k * x.len() / m])).sum();
+        sum * x.len() as f64 / m as f64
+    }
+
+    // Global minimum value, when it is known
+    pub fn optimum(&self) -> Option<f64> {
+        match self {
+            // Schwefel's minimum is 0 only up to the rounding of its offset
+            Function::Rastrigin
+            | Function::Quadratic
+            | Function::Rosenbrock
+            | Function::Ackley
+            | Function::Griewank
+            | Function::Schwefel => Some(0.0),
+        }
+    }
+
+    // Applied when the config selects the function without overriding these
+    pub fn defaults(&self) -> FunctionDefaults {
+        match self {
+            Function::Rastrigin | Function::Quadratic => FunctionDefaults {
+                lower_bound: -5.12,
+                upper_bound: 5.12,
+                dimensions: 10,
+            },
+            Function::Rosenbrock => FunctionDefaults {
+                lower_bound: -5.0,
+                upper_bound: 10.0,
+                dimensions: 10,
+            },
+            Function::Ackley => FunctionDefaults {
+                lower_bound: -32.768,
+                upper_bound: 32.768,
+                dimensions: 10,
+            },
+            Function::Griewank => FunctionDefaults {
+                lower_bound: -600.0,
+                upper_bound: 600.0,
+                dimensions: 10,
+            },
+            Function::Schwefel => FunctionDefaults {
+                lower_bound: -500.0,
+                upper_bound: 500.0,
+                dimensions: 10,
+            },
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Function::Rastrigin => "rastrigin",
+            Function::Quadratic => "quadratic",
+            Function::Rosenbrock => "rosenbrock",
+            Function::Ackley => "ackley",
+            Function::Griewank => "griewank",
+            Function::Schwefel => "schwefel",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    const FUNCTIONS: [Function; 3] = [Function::Rastrigin, Function::Quadratic, Function::Schwefel];
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() <= 1e-9 * b.abs().max(1.0), "{} != {}", a, b);
+    }
+
+    #[test]
+    fn components_sum_to_full_evaluation() {
+        let x = [0.3, -1.7, 4.2, 0.0, -5.12];
+        for f in FUNCTIONS {
+            let sum: f64 = x.iter().map(|&xi| f.component(xi)).sum();
+            assert_close(sum, f.evaluate(&x));
+        }
+    }
+
+    #[test]
+    fn partial_evaluation_matches_full_evaluation() {
+        let mut rng = rand::thread_rng();
+        for f in FUNCTIONS {
+            let mut x: Vec<f64> = (0..10).map(|_| rng.gen_range(-5.12..5.12)).collect();
+            let mut fitness = f.evaluate(&x);
+
+            for _ in 0..100 {
+                let mut changed = Vec::new();
+                for pos in rand::seq::index::sample(&mut rng, x.len(), 3) {
+                    changed.push((pos, x[pos]));
+                    x[pos] = rng.gen_range(-5.12..5.12);
+                }
+                fitness = f.evaluate_partial(fitness, &x, &changed);
+                assert_close(fitness, f.evaluate(&x));
+            }
+        }
+    }
+
+    #[test]
+    fn low_fidelity_with_all_components_is_exact() {
+        let x = [0.3, -1.7, 4.2, 0.0, -5.12];
+        for f in FUNCTIONS {
+            assert_close(f.evaluate_low_fidelity(&x, x.len()), f.evaluate(&x));
+        }
+    }
+
+    #[test]
+    fn benchmarks_reach_their_optimum() {
+        let at = |v: f64| [v; 4];
+        assert_close(Function::Rastrigin.evaluate(&at(0.0)), 0.0);
+        assert_close(Function::Quadratic.evaluate(&at(0.0)), 0.0);
+        assert_close(Function::Rosenbrock.evaluate(&at(1.0)), 0.0);
+        assert_close(Function::Ackley.evaluate(&at(0.0)), 0.0);
+        assert_close(Function::Griewank.evaluate(&at(0.0)), 0.0);
+        assert!(Function::Schwefel.evaluate(&at(420.968_746_2)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn partial_evaluation_without_changes_keeps_fitness() {
+        let x = [1.0, 2.0, 3.0];
+        for f in FUNCTIONS {
+            let fitness = f.evaluate(&x);
+            assert_eq!(f.evaluate_partial(fitness, &x, &[]), fitness);
+        }
+    }
+}
@@ -0,0 +1,99 @@
+// Desc: Differential grouping (Omidvar et al., 2014) to detect interacting variables
+use crate::config::Config;
+
+// Groups the dimensions of the objective by pairwise interaction, using the
+// change of the fitness difference along dimension i when dimension j moves
+// from the lower bound to the centre of the box. Variables that interact with
+// nobody end up together in one separable group. Stops testing once `budget`
+// evaluations are used; untested variables then count as separable.
+// Returns 0-based groups; every evaluation goes through `f`.
+pub fn differential_grouping(
+    config: &Config,
+    epsilon: f64,
+    budget: Option<usize>,
+    mut f: impl FnMut(&[f64]) -> f64,
+) -> Vec<Vec<usize>> {
+    let bounds: Vec<(f64, f64)> = (0..config.dimensions)
+        .map(|d| config.bounds_of(d))
+        .collect();
+    let lower: Vec<f64> = bounds.iter().map(|b| b.0).collect();
+
+    let mut remaining: Vec<usize> = (0..config.dimensions).collect();
+    let mut groups = Vec::new();
+    let mut separable = Vec::new();
+    let mut evaluations = 0;
+    let exhausted = |evaluations: usize| budget.is_some_and(|b| evaluations + 4 > b);
+
+    while let Some(i) = remaining.first().copied() {
+        remaining.remove(0);
+        let mut group = vec![i];
+
+        let mut k = 0;
+        while k < remaining.len() && !exhausted(evaluations) {
+            let j = remaining[k];
+
+            let mut p1 = lower.clone();
+            let mut p2 = p1.clone();
+            p2[i] = bounds[i].1;
+            let delta1 = f(&p1) - f(&p2);
+
+            let centre = (bounds[j].0 + bounds[j].1) / 2.0;
+            p1[j] = centre;
+            p2[j] = centre;
+            let delta2 = f(&p1) - f(&p2);
+            evaluations += 4;
+
+            if (delta1 - delta2).abs() > epsilon {
+                group.push(remaining.remove(k));
+            } else {
+                k += 1;
+            }
+        }
+
+        if group.len() == 1 {
+            separable.push(i);
+        } else {
+            group.sort_unstable();
+            groups.push(group);
+        }
+    }
+
+    if !separable.is_empty() {
+        groups.push(separable);
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rosenbrock(a: f64, b: f64) -> f64 {
+        100.0 * (b - a * a).powi(2) + (1.0 - a).powi(2)
+    }
+
+    #[test]
+    fn independent_blocks_are_split_apart() {
+        let mut config = Config::default();
+        config.dimensions = 6;
+        // on a box symmetric about 0, x0 -> -x0 leaves x0^2 alone and hides the coupling
+        config.lower_bound = -2.0;
+        config.upper_bound = 3.0;
+        // two 2-D Rosenbrock blocks, then two separable variables
+        let f =
+            |x: &[f64]| rosenbrock(x[0], x[1]) + rosenbrock(x[2], x[3]) + x[4] * x[4] + x[5] * x[5];
+        let pairs = 6 * 5 / 2;
+        let mut evaluations = 0;
+        let groups = differential_grouping(&config, 1e-3, Some(4 * pairs), |x| {
+            evaluations += 1;
+            f(x)
+        });
+        assert_eq!(groups, [vec![0, 1], vec![2, 3], vec![4, 5]]);
+        // grouped variables are not tested again
+        assert!(evaluations < 4 * pairs, "{} evaluations", evaluations);
+
+        // out of budget after the first pair: the rest counts as separable
+        let groups = differential_grouping(&config, 1e-3, Some(4), f);
+        assert_eq!(groups, [vec![0, 1], vec![2, 3, 4, 5]]);
+    }
+}
@@ -0,0 +1,109 @@
+// Desc: Grey Wolf Optimizer (Mirjalili et al.): the pack hunts around its three best wolves
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::comparator::Score;
+use crate::config::Config;
+use crate::convergence::TracePoint;
+use crate::optimizer::{self, Optimizer, Outcome, Tracker};
+use crate::options::RunOptions;
+use crate::sampling;
+
+pub struct Gwo<'a> {
+    tracker: Tracker<'a>,
+    rng: StdRng,
+    wolves: Vec<Vec<f64>>,
+    leaders: Vec<(Vec<f64>, Score)>, // alpha, beta and delta: the best positions so far, best first
+}
+
+impl<'a> Gwo<'a> {
+    pub fn new(config: Config, options: &'a RunOptions) -> Result<Self, String> {
+        let mut gwo = Gwo {
+            rng: optimizer::seeded_rng(&config),
+            tracker: Tracker::new(config, options)?,
+            wolves: Vec::new(),
+            leaders: Vec::with_capacity(4),
+        };
+        for id in 0..gwo.tracker.config.particles {
+            let config = &gwo.tracker.config;
+            let x: Vec<f64> = sampling::point(config, &mut gwo.rng);
+            let score = gwo.tracker.evaluate(id, &x);
+            gwo.rank(&x, score);
+            gwo.wolves.push(x);
+        }
+        Ok(gwo)
+    }
+
+    // Lets the wolf at `x` into the leading three if it beats one of them
+    fn rank(&mut self, x: &[f64], score: Score) {
+        let comparator = &self.tracker.config.comparison;
+        if self.leaders.iter().any(|(l, _)| l.as_slice() == x) {
+            return;
+        }
+        let at = self
+            .leaders
+            .iter()
+            .position(|(_, s)| comparator.better(score, *s))
+            .unwrap_or(self.leaders.len());
+        self.leaders.insert(at, (x.to_vec(), score));
+        self.leaders.truncate(3);
+    }
+}
+
+impl Optimizer for Gwo<'_> {
+    fn step(&mut self) {
+        let config = &self.tracker.config;
+        // `a` falls linearly from 2 to 0: exploration first, then encircling
+        let a = 2.0 * (1.0 - self.tracker.iteration as f64 / config.iterations.max(1) as f64);
+
+        let mut moved = Vec::with_capacity(self.wolves.len());
+        for wolf in &self.wolves {
+            let x: Vec<f64> = (0..config.dimensions)
+                .map(|pos| {
+                    let pull: f64 = self
+                        .leaders
+                        .iter()
+                        .map(|(leader, _)| {
+                            let big_a = a * (2.0 * self.rng.gen::<f64>() - 1.0);
+                            let big_c = 2.0 * self.rng.gen::<f64>();
+                            let distance = (big_c * leader[pos] - wolf[pos]).abs();
+                            leader[pos] - big_a * distance
+                        })
+                        .sum();
+                    let (lower, upper) = config.bounds_of(pos);
+                    let x = pull / self.leaders.len() as f64;
+                    config.snap(pos, x.clamp(lower, upper))
+                })
+                .collect();
+            moved.push(x);
+        }
+
+        for (id, x) in moved.iter().enumerate() {
+            let score = self.tracker.evaluate(id, x);
+            self.rank(x, score);
+        }
+        self.wolves = moved;
+
+        self.tracker.end_iteration();
+    }
+
+    fn is_finished(&self) -> bool {
+        self.tracker.is_finished()
+    }
+
+    fn stop(&mut self, reason: &'static str) {
+        self.tracker.stop(reason);
+    }
+
+    fn best(&self) -> f64 {
+        self.tracker.best()
+    }
+
+    fn progress(&self) -> TracePoint {
+        self.tracker.progress()
+    }
+
+    fn finish(self: Box<Self>) -> Outcome {
+        self.tracker.finish(&[])
+    }
+}
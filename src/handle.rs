@@ -0,0 +1,94 @@
+// Desc: Run an optimization on a background thread, with a cancellation token
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+
+use crate::config::Config;
+use crate::optimizer::{self, Optimizer, Outcome};
+use crate::options::RunOptions;
+use crate::platform;
+
+// Shared flag asking a background run to stop after the current iteration
+#[derive(Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+fn print_status(run: &dyn Optimizer, iterations: usize) {
+    let point = run.progress();
+    eprintln!(
+        "Status: iteration {} of {}, {} evaluations, best {}, {:.1} s",
+        point.iteration + 1,
+        iterations,
+        point.evaluations,
+        point.best,
+        point.seconds
+    );
+}
+
+pub struct RunHandle {
+    token: CancelToken,
+    thread: JoinHandle<Option<Outcome>>, // None when the run could not start
+}
+
+impl RunHandle {
+    // Starts the run on its own thread, once it has started; it prints its
+    // report when it ends, and a status line between iterations when one is
+    // requested
+    pub fn start(config: Config, options: RunOptions) -> Result<Self, String> {
+        if let Err(e) = platform::listen_for_status() {
+            eprintln!("Warning: cannot listen for status requests: {}", e);
+        }
+        let token = CancelToken::default();
+        let cancel = token.clone();
+        let (started, start) = mpsc::channel();
+        let thread = std::thread::spawn(move || {
+            let iterations = config.iterations;
+            let created = optimizer::create(config, &options);
+            let failure = created.as_ref().err().cloned();
+            let _ = started.send(failure);
+            let mut run = created.ok()?;
+            while !run.is_finished() {
+                if cancel.is_cancelled() {
+                    run.stop("cancelled");
+                    break;
+                }
+                run.step();
+                if platform::status_requested() {
+                    print_status(run.as_ref(), iterations);
+                }
+            }
+            Some(run.finish())
+        });
+
+        match start.recv().expect("optimization thread panicked") {
+            Some(e) => {
+                let _ = thread.join();
+                Err(e)
+            }
+            None => Ok(RunHandle { token, thread }),
+        }
+    }
+
+    // A token other threads (signal handlers, GUIs) can use to cancel the run
+    pub fn token(&self) -> CancelToken {
+        self.token.clone()
+    }
+
+    // Waits for the run to end and returns its result, the best so far when
+    // it was cancelled
+    pub fn join(self) -> Outcome {
+        self.thread
+            .join()
+            .expect("optimization thread panicked")
+            .expect("the run started")
+    }
+}
@@ -0,0 +1,108 @@
+// Desc: Harmony search (Geem et al.): improvise from a memory of good solutions
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::comparator::Score;
+use crate::config::Config;
+use crate::convergence::TracePoint;
+use crate::optimizer::{self, Optimizer, Outcome, Tracker};
+use crate::options::RunOptions;
+use crate::sampling;
+
+pub struct Harmony<'a> {
+    tracker: Tracker<'a>,
+    rng: StdRng,
+    hmcr: f64,      // probability of taking a coordinate from memory
+    par: f64,       // probability of adjusting a remembered coordinate
+    bandwidth: f64, // largest pitch adjustment, as a fraction of each range
+    memory: Vec<(Vec<f64>, Score)>,
+}
+
+impl<'a> Harmony<'a> {
+    // A harmony memory of `particles` random solutions
+    pub fn new(
+        config: Config,
+        options: &'a RunOptions,
+        hmcr: f64,
+        par: f64,
+        bandwidth: f64,
+    ) -> Result<Self, String> {
+        let mut harmony = Harmony {
+            rng: optimizer::seeded_rng(&config),
+            tracker: Tracker::new(config, options)?,
+            hmcr,
+            par,
+            bandwidth,
+            memory: Vec::new(),
+        };
+        for id in 0..harmony.tracker.config.particles {
+            let config = &harmony.tracker.config;
+            let x: Vec<f64> = sampling::point(config, &mut harmony.rng);
+            let score = harmony.tracker.evaluate(id, &x);
+            harmony.memory.push((x, score));
+        }
+        Ok(harmony)
+    }
+}
+
+impl Optimizer for Harmony<'_> {
+    // Improvises `particles` new harmonies, each replacing the worst one in
+    // memory when it is better
+    fn step(&mut self) {
+        let n = self.memory.len();
+        for id in 0..n {
+            let config = &self.tracker.config;
+            let x: Vec<f64> = (0..config.dimensions)
+                .map(|pos| {
+                    let (lower, upper) = config.bounds_of(pos);
+                    if !self.rng.gen_bool(self.hmcr) {
+                        return sampling::coordinate(config, pos, &mut self.rng);
+                    }
+                    let remembered = self.memory[self.rng.gen_range(0..n)].0[pos];
+                    let xi = if self.rng.gen_bool(self.par) {
+                        let shift = self.rng.gen_range(-1.0..=1.0);
+                        remembered + shift * self.bandwidth * (upper - lower)
+                    } else {
+                        remembered
+                    };
+                    sampling::confine(config, pos, xi)
+                })
+                .collect();
+
+            let score = self.tracker.evaluate(id, &x);
+            let comparator = &self.tracker.config.comparison;
+            let worst = (1..n).fold(0, |w, i| {
+                if comparator.better(self.memory[w].1, self.memory[i].1) {
+                    i
+                } else {
+                    w
+                }
+            });
+            if comparator.better(score, self.memory[worst].1) {
+                self.memory[worst] = (x, score);
+            }
+        }
+
+        self.tracker.end_iteration();
+    }
+
+    fn is_finished(&self) -> bool {
+        self.tracker.is_finished()
+    }
+
+    fn stop(&mut self, reason: &'static str) {
+        self.tracker.stop(reason);
+    }
+
+    fn best(&self) -> f64 {
+        self.tracker.best()
+    }
+
+    fn progress(&self) -> TracePoint {
+        self.tracker.progress()
+    }
+
+    fn finish(self: Box<Self>) -> Outcome {
+        self.tracker.finish(&[])
+    }
+}
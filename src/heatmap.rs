@@ -0,0 +1,65 @@
+// Desc: Visitation histogram over a 2D projection of the search space
+use crate::sink::Sink;
+
+pub struct Heatmap {
+    dims: (usize, usize), // projected dimensions (0-based)
+    bins: usize,
+    lower: f64,
+    upper: f64,
+    counts: Vec<u64>, // row-major, bins * bins
+}
+
+impl Heatmap {
+    pub fn new(dims: (usize, usize), bins: usize, (lower, upper): (f64, f64)) -> Self {
+        Heatmap {
+            dims,
+            bins,
+            lower,
+            upper,
+            counts: vec![0; bins * bins],
+        }
+    }
+
+    fn bin(&self, value: f64) -> usize {
+        let t = (value - self.lower) / (self.upper - self.lower);
+        ((t * self.bins as f64) as usize).min(self.bins - 1)
+    }
+
+    fn center(&self, bin: usize) -> f64 {
+        let width = (self.upper - self.lower) / self.bins as f64;
+        self.lower + (bin as f64 + 0.5) * width
+    }
+
+    pub fn record(&mut self, x: &[f64]) {
+        let bx = self.bin(x[self.dims.0]);
+        let by = self.bin(x[self.dims.1]);
+        self.counts[by * self.bins + bx] += 1;
+    }
+
+    // One row per cell: bin indices, cell centers and visit count
+    pub fn save(&self, path: &str) {
+        let mut writer = csv::Writer::from_writer(Sink::create(path));
+
+        let x_name = format!("x{}", self.dims.0 + 1);
+        let y_name = format!("x{}", self.dims.1 + 1);
+        writer
+            .write_record(["bin_x", "bin_y", x_name.as_str(), y_name.as_str(), "visits"])
+            .expect("Error writing heatmap file");
+
+        for by in 0..self.bins {
+            for bx in 0..self.bins {
+                writer
+                    .serialize((
+                        bx,
+                        by,
+                        self.center(bx),
+                        self.center(by),
+                        self.counts[by * self.bins + bx],
+                    ))
+                    .expect("Error writing heatmap file");
+            }
+        }
+
+        writer.flush().expect("Error writing heatmap file");
+    }
+}
@@ -0,0 +1,50 @@
+// Desc: Event log of global best improvements
+use crate::privacy::Privacy;
+use crate::sink::Sink;
+
+pub struct ImprovementLog {
+    writer: csv::Writer<Sink>,
+    privacy: Option<Privacy>,
+}
+
+impl ImprovementLog {
+    pub fn create(path: &str, privacy: Option<Privacy>) -> Self {
+        let mut writer = csv::Writer::from_writer(Sink::create(path));
+        writer
+            .write_record([
+                "iteration",
+                "evaluations",
+                "gbest",
+                "delta",
+                "particle",
+                "cost",
+            ])
+            .expect("Error writing improvements file");
+
+        ImprovementLog { writer, privacy }
+    }
+
+    pub fn log(
+        &mut self,
+        iteration: usize,
+        evaluations: usize,
+        gbest: f64,
+        delta: f64,
+        particle: usize,
+        cost: f64,
+    ) {
+        let (gbest, delta) = match &self.privacy {
+            Some(privacy) => (privacy.apply(gbest), privacy.apply(delta)),
+            None => (gbest, delta),
+        };
+        self.writer
+            .serialize((iteration, evaluations, gbest, delta, particle, cost))
+            .expect("Error writing improvements file");
+    }
+}
+
+impl Drop for ImprovementLog {
+    fn drop(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
@@ -0,0 +1,87 @@
+// Desc: Inertia weight strategies that need more than the iteration count
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Inertia weight strategy; replaces `w` when set
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InertiaSchedule {
+    /// The same weight throughout
+    Constant { w: f64 },
+    /// Linear decay from `w_max` at the start to `w_min` at the end
+    Linear { w_max: f64, w_min: f64 },
+    /// Chaotic decreasing inertia (Feng et al.): the linear decay, with
+    /// `w_min` scaled by a logistic map z <- 4z(1 - z) every iteration
+    Chaotic { w_max: f64, w_min: f64 },
+    /// Follows the swarm's diversity: `w_max` while the particles are as
+    /// spread out as at the start, down to `w_min` as they converge
+    Adaptive { w_max: f64, w_min: f64 },
+    /// Follows the swarm's success rate (Nickabadi et al.): `w_max` when
+    /// every particle improved its personal best in the last iteration,
+    /// down to `w_min` when none did
+    SuccessRate { w_max: f64, w_min: f64 },
+}
+
+impl InertiaSchedule {
+    // Weight at `progress` (the fraction of the run completed), with `z` the
+    // current logistic map value, `spread` the swarm's diversity relative to
+    // the initial one and `success` the last iteration's success rate
+    pub fn value(&self, progress: f64, z: f64, spread: f64, success: f64) -> f64 {
+        match *self {
+            InertiaSchedule::Constant { w } => w,
+            InertiaSchedule::Linear { w_max, w_min } => w_max - (w_max - w_min) * progress,
+            InertiaSchedule::Chaotic { w_max, w_min } => {
+                (w_max - w_min) * (1.0 - progress) + w_min * z
+            }
+            InertiaSchedule::Adaptive { w_max, w_min } => {
+                w_min + (w_max - w_min) * spread.clamp(0.0, 1.0)
+            }
+            InertiaSchedule::SuccessRate { w_max, w_min } => w_min + (w_max - w_min) * success,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            InertiaSchedule::Constant { .. } => Ok(()),
+            InertiaSchedule::Linear { w_max, w_min }
+            | InertiaSchedule::Chaotic { w_max, w_min }
+            | InertiaSchedule::Adaptive { w_max, w_min }
+            | InertiaSchedule::SuccessRate { w_max, w_min } => {
+                if w_min.partial_cmp(&w_max) == Some(std::cmp::Ordering::Greater)
+                    || !w_min.is_finite()
+                    || !w_max.is_finite()
+                {
+                    Err(format!(
+                        "inertia_schedule needs finite w_min <= w_max, got {} and {}",
+                        w_min, w_max
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            InertiaSchedule::Constant { w } => format!("constant {}", w),
+            InertiaSchedule::Linear { w_max, w_min } => {
+                format!("linear decay {} -> {}", w_max, w_min)
+            }
+            InertiaSchedule::Chaotic { w_max, w_min } => {
+                format!("chaotic decay {} -> {}", w_max, w_min)
+            }
+            InertiaSchedule::Adaptive { w_max, w_min } => {
+                format!("diversity-adaptive in [{}, {}]", w_min, w_max)
+            }
+            InertiaSchedule::SuccessRate { w_max, w_min } => {
+                format!("success-rate adaptive in [{}, {}]", w_min, w_max)
+            }
+        }
+    }
+}
+
+// The next value of the logistic map at r = 4, which is chaotic on (0, 1)
+pub fn logistic(z: f64) -> f64 {
+    4.0 * z * (1.0 - z)
+}
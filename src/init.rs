@@ -0,0 +1,54 @@
+// Desc: Initialization of particle state
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// How a particle's initial velocity is drawn, per coordinate
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum VelocityInit {
+    /// Uniform over the coordinate's whole range [lower, upper); large first steps
+    #[default]
+    FullRange,
+    /// Start at rest
+    Zero,
+    /// Uniform within +-fraction of the range width
+    Fraction { fraction: f64 },
+    /// Half the difference between a second random point and the position
+    HalfDifference,
+}
+
+impl VelocityInit {
+    pub fn sample(&self, x: f64, (lower, upper): (f64, f64), rng: &mut impl Rng) -> f64 {
+        match *self {
+            VelocityInit::FullRange => rng.gen_range(lower..upper),
+            VelocityInit::Zero => 0.0,
+            VelocityInit::Fraction { fraction } => {
+                let half = fraction * (upper - lower);
+                rng.gen_range(-half..=half)
+            }
+            VelocityInit::HalfDifference => (rng.gen_range(lower..upper) - x) / 2.0,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            VelocityInit::Fraction { fraction } if !(fraction > 0.0 && fraction <= 1.0) => {
+                Err(format!(
+                    "velocity_init fraction must be within (0, 1], got {}",
+                    fraction
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            VelocityInit::FullRange => "full range".to_string(),
+            VelocityInit::Zero => "zero".to_string(),
+            VelocityInit::Fraction { fraction } => format!("uniform +-{} of the range", fraction),
+            VelocityInit::HalfDifference => "half difference to a random point".to_string(),
+        }
+    }
+}
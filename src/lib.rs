@@ -0,0 +1,95 @@
+// Desc: Particle swarm optimization library; the pso_rastrigin binary is one consumer of it
+mod abc;
+mod advice;
+mod api;
+mod archive;
+mod audit;
+mod axis_bias;
+mod bands;
+mod batch;
+mod bench;
+pub mod benchmarks;
+mod boundary;
+mod checkpoint;
+pub mod cli;
+mod cluster;
+mod cma_es;
+mod comparator;
+mod config;
+mod constraint;
+mod convergence;
+mod dedup;
+mod diagnostics;
+mod distribution;
+mod ecdf;
+mod eda;
+mod events;
+mod experiment;
+mod explain;
+mod expr;
+mod external;
+mod firefly;
+mod fit;
+mod functions;
+mod grouping;
+mod gwo;
+mod handle;
+mod harmony;
+mod heatmap;
+mod improvements;
+mod inertia;
+mod init;
+mod linalg;
+mod local_search;
+mod multirun;
+mod nelder_mead;
+mod noise;
+mod onnx;
+mod optimizer;
+mod options;
+mod output;
+mod pattern;
+mod pattern_search;
+mod pipeline;
+mod platform;
+mod plot;
+mod plugin;
+mod portfolio;
+mod power;
+mod preflight;
+mod privacy;
+mod problem;
+mod projection;
+mod pso;
+mod repair;
+mod repl;
+mod resources;
+mod rotation;
+mod sampling;
+mod schedule;
+mod selection;
+mod sensitivity;
+mod simplex;
+mod sink;
+mod stats;
+mod stopping;
+mod sweep;
+mod throttle;
+mod topology;
+mod trace;
+mod trajectory;
+mod view;
+mod web;
+
+pub use api::{
+    ConstraintHandling, IterationObserver, Pso, PsoBuilder, PsoConfig, PsoHandle, PsoResult,
+};
+pub use constraint::{AdaptivePenalty, Constraint, ConstraintKind, Equality, Linear};
+pub use convergence::TracePoint;
+pub use experiment::{Experiment, ExperimentResult, ExperimentSummary, RunRecord};
+pub use functions::{Function, ObjectiveFunction};
+pub use inertia::InertiaSchedule;
+pub use noise::NoiseStream;
+pub use pso::{Particle, Swarm};
+pub use sweep::{Sweep, SweepRow};
+pub use view::{Snapshot, SwarmView};
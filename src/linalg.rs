@@ -0,0 +1,273 @@
+// Desc: Small dense linear algebra: sample mean and covariance, Cholesky
+// factorization, eigenvalues and leading eigenvectors, and multivariate
+// Gaussian sampling
+use rand::Rng;
+use rand_distr::StandardNormal;
+
+pub fn mean(samples: &[&[f64]]) -> Vec<f64> {
+    let mut mean = vec![0.0; samples[0].len()];
+    for x in samples {
+        for (m, xi) in mean.iter_mut().zip(x.iter()) {
+            *m += xi;
+        }
+    }
+    let n = samples.len() as f64;
+    mean.iter_mut().for_each(|m| *m /= n);
+    mean
+}
+
+// Unbiased sample covariance (n - 1 in the denominator); needs two samples
+pub fn covariance(samples: &[&[f64]], mean: &[f64]) -> Vec<Vec<f64>> {
+    let d = mean.len();
+    let n = (samples.len() - 1) as f64;
+    let mut cov = vec![vec![0.0; d]; d];
+    for x in samples {
+        let centered: Vec<f64> = x.iter().zip(mean).map(|(xi, m)| xi - m).collect();
+        for (row, ci) in cov.iter_mut().zip(&centered) {
+            for (c, cj) in row.iter_mut().zip(&centered) {
+                *c += ci * cj / n;
+            }
+        }
+    }
+    cov
+}
+
+// Lower-triangular L with L L^T = matrix, or None when the matrix is not
+// (numerically) positive definite
+pub fn cholesky(matrix: &[Vec<f64>]) -> Option<Vec<Vec<f64>>> {
+    let d = matrix.len();
+    let mut l = vec![vec![0.0; d]; d];
+    for i in 0..d {
+        for j in 0..=i {
+            let dot: f64 = (0..j).map(|k| l[i][k] * l[j][k]).sum();
+            if i == j {
+                let pivot = matrix[i][i] - dot;
+                if pivot.partial_cmp(&0.0) != Some(std::cmp::Ordering::Greater) {
+                    return None;
+                }
+                l[i][i] = pivot.sqrt();
+            } else {
+                l[i][j] = (matrix[i][j] - dot) / l[j][j];
+            }
+        }
+    }
+    Some(l)
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+// Makes `v` orthogonal to the unit vectors in `basis` and of unit length;
+// false when nothing of it is left
+fn orthonormalize(v: &mut [f64], basis: &[Vec<f64>]) -> bool {
+    for b in basis {
+        let along = dot(v, b);
+        v.iter_mut().zip(b).for_each(|(vi, bi)| *vi -= along * bi);
+    }
+    let norm = dot(v, v).sqrt();
+    if norm < 1e-12 {
+        return false;
+    }
+    v.iter_mut().for_each(|vi| *vi /= norm);
+    true
+}
+
+// The `k` eigenvectors of the largest eigenvalues of a symmetric positive
+// semi-definite matrix, by power iteration kept orthogonal to the ones found
+// before. Directions without variance are filled in with coordinate axes.
+pub fn principal_axes(matrix: &[Vec<f64>], k: usize) -> Vec<Vec<f64>> {
+    let d = matrix.len();
+    let mut axes: Vec<Vec<f64>> = Vec::with_capacity(k);
+    for _ in 0..k {
+        let mut v: Vec<f64> = (0..d).map(|i| 1.0 + i as f64 / d as f64).collect();
+        let mut found = orthonormalize(&mut v, &axes);
+        for _ in 0..1000 {
+            if !found {
+                break;
+            }
+            let mut next: Vec<f64> = matrix.iter().map(|row| dot(row, &v)).collect();
+            found = orthonormalize(&mut next, &axes);
+            let change = next
+                .iter()
+                .zip(&v)
+                .map(|(a, b)| (a - b).abs())
+                .fold(0.0, f64::max);
+            v = next;
+            if change < 1e-12 {
+                break;
+            }
+        }
+        if !found {
+            v = (0..d)
+                .map(|axis| {
+                    let mut e = vec![0.0; d];
+                    e[axis] = 1.0;
+                    e
+                })
+                .find_map(|mut e| orthonormalize(&mut e, &axes).then_some(e))
+                .expect("k is at most the dimension");
+        }
+        axes.push(v);
+    }
+    axes
+}
+
+// Eigenvalues of a symmetric matrix in ascending order, by cyclic Jacobi
+// rotations
+pub fn symmetric_eigenvalues(matrix: &[Vec<f64>]) -> Vec<f64> {
+    let d = matrix.len();
+    let mut a = matrix.to_vec();
+    let scale: f64 = a.iter().flatten().map(|x| x * x).sum();
+    for _ in 0..100 {
+        let off: f64 = (0..d)
+            .flat_map(|p| ((p + 1)..d).map(move |q| (p, q)))
+            .map(|(p, q)| a[p][q] * a[p][q])
+            .sum();
+        if off <= 1e-30 * scale {
+            break;
+        }
+        for p in 0..d {
+            for q in (p + 1)..d {
+                if a[p][q] == 0.0 {
+                    continue;
+                }
+                // the rotation in the (p, q) plane that zeroes a[p][q]
+                let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                for row in a.iter_mut() {
+                    let (kp, kq) = (row[p], row[q]);
+                    row[p] = c * kp - s * kq;
+                    row[q] = s * kp + c * kq;
+                }
+                let (head, tail) = a.split_at_mut(q);
+                for (pk, qk) in head[p].iter_mut().zip(tail[0].iter_mut()) {
+                    (*pk, *qk) = (c * *pk - s * *qk, s * *pk + c * *qk);
+                }
+            }
+        }
+    }
+    let mut eigenvalues: Vec<f64> = (0..d).map(|i| a[i][i]).collect();
+    eigenvalues.sort_by(f64::total_cmp);
+    eigenvalues
+}
+
+// `k` random orthonormal directions in `d` dimensions, uniformly oriented
+pub fn random_axes(d: usize, k: usize, rng: &mut impl Rng) -> Vec<Vec<f64>> {
+    let mut axes: Vec<Vec<f64>> = Vec::with_capacity(k);
+    while axes.len() < k {
+        let mut v: Vec<f64> = (0..d).map(|_| rng.sample(StandardNormal)).collect();
+        if orthonormalize(&mut v, &axes) {
+            axes.push(v);
+        }
+    }
+    axes
+}
+
+// Multivariate normal distribution kept as its mean and Cholesky factor
+pub struct Gaussian {
+    mean: Vec<f64>,
+    factor: Vec<Vec<f64>>,
+}
+
+impl Gaussian {
+    // Maximum-likelihood fit to at least two samples. A small ridge on the
+    // diagonal keeps the fit usable when the samples span fewer directions
+    // than there are dimensions.
+    pub fn fit(samples: &[&[f64]]) -> Option<Gaussian> {
+        if samples.len() < 2 {
+            return None;
+        }
+        let mean = mean(samples);
+        let mut cov = covariance(samples, &mean);
+        let d = mean.len();
+        let scale = (0..d).map(|i| cov[i][i]).sum::<f64>() / d as f64;
+        let ridge = 1e-10 * scale.max(1e-10);
+        for (i, row) in cov.iter_mut().enumerate() {
+            row[i] += ridge;
+        }
+        let factor = cholesky(&cov)?;
+        Some(Gaussian { mean, factor })
+    }
+
+    // Independent coordinates with standard deviations `sigma`
+    pub fn diagonal(mean: Vec<f64>, sigma: &[f64]) -> Gaussian {
+        let factor = (0..sigma.len())
+            .map(|i| {
+                let mut row = vec![0.0; sigma.len()];
+                row[i] = sigma[i];
+                row
+            })
+            .collect();
+        Gaussian { mean, factor }
+    }
+
+    pub fn sample(&self, rng: &mut impl Rng) -> Vec<f64> {
+        let z: Vec<f64> = (0..self.mean.len())
+            .map(|_| rng.sample(StandardNormal))
+            .collect();
+        self.mean
+            .iter()
+            .zip(&self.factor)
+            .map(|(m, row)| m + row.iter().zip(&z).map(|(l, zi)| l * zi).sum::<f64>())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cholesky_reconstructs_matrix() {
+        let a = vec![
+            vec![4.0, 2.0, 0.6],
+            vec![2.0, 5.0, 1.5],
+            vec![0.6, 1.5, 3.0],
+        ];
+        let l = cholesky(&a).unwrap();
+        for i in 0..3 {
+            for j in 0..3 {
+                let product: f64 = (0..3).map(|k| l[i][k] * l[j][k]).sum();
+                assert!((product - a[i][j]).abs() < 1e-12);
+            }
+        }
+        assert!(cholesky(&[vec![1.0, 2.0], vec![2.0, 1.0]]).is_none());
+    }
+
+    #[test]
+    fn principal_axes_of_a_diagonal_matrix() {
+        let a = vec![
+            vec![1.0, 0.0, 0.0],
+            vec![0.0, 9.0, 0.0],
+            vec![0.0, 0.0, 4.0],
+        ];
+        let axes = principal_axes(&a, 2);
+        assert!((axes[0][1].abs() - 1.0).abs() < 1e-9);
+        assert!((axes[1][2].abs() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn eigenvalues_of_a_symmetric_matrix() {
+        let a = vec![
+            vec![2.0, 1.0, 0.0],
+            vec![1.0, 2.0, 0.0],
+            vec![0.0, 0.0, 5.0],
+        ];
+        let eigenvalues = symmetric_eigenvalues(&a);
+        for (got, want) in eigenvalues.iter().zip([1.0, 3.0, 5.0]) {
+            assert!((got - want).abs() < 1e-12, "{:?}", eigenvalues);
+        }
+    }
+
+    #[test]
+    fn covariance_of_known_samples() {
+        let samples: [&[f64]; 3] = [&[1.0, 2.0], &[2.0, 4.0], &[3.0, 6.0]];
+        let m = mean(&samples);
+        assert_eq!(m, vec![2.0, 4.0]);
+        let cov = covariance(&samples, &m);
+        assert_eq!(cov, vec![vec![1.0, 2.0], vec![2.0, 4.0]]);
+    }
+}
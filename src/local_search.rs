@@ -0,0 +1,101 @@
+// Desc: Refinement of PSO's gbest (`local_search`): a Nelder-Mead simplex or
+// Hooke-Jeeves pattern search started at gbest, on a budget of objective
+// evaluations that count towards the run's totals
+use serde::{Deserialize, Serialize};
+
+use crate::comparator::{Comparator, Score};
+use crate::config::{Config, LocalMethod, LocalSearch};
+use crate::noise::EvalContext;
+use crate::pattern::PatternSearch;
+use crate::pso::{self, EvalHooks};
+use crate::simplex::{Probe, Simplex};
+
+// relative simplex diameter or step size at which a refinement has converged
+const TOLERANCE: f64 = 1e-10;
+const SHRINK: f64 = 0.5;
+
+// Refinements of a run, for the final report
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Refinements {
+    pub runs: usize,
+    pub improved: usize, // runs that found a better gbest
+    pub evaluations: usize,
+}
+
+// The run's objective as a local search sees it: objective coordinates only,
+// evaluated outside the swarm until the budget is spent, after which points
+// score as infinitely bad without being evaluated
+pub struct Budget<'r> {
+    pub config: &'r Config,
+    pub evaluations: &'r mut usize,
+    pub hooks: &'r mut EvalHooks,
+    pub iteration: usize,
+    pub penalty_factor: f64, // per unit of constraint violation
+    pub left: usize,
+    pub best: (Vec<f64>, Score),
+}
+
+impl Probe for Budget<'_> {
+    fn comparator(&self) -> &Comparator {
+        &self.config.comparison
+    }
+
+    fn project(&self, mut x: Vec<f64>) -> Vec<f64> {
+        for (pos, xi) in x.iter_mut().enumerate() {
+            let (lower, upper) = self.config.bounds_of(pos);
+            *xi = self.config.snap(pos, xi.clamp(lower, upper));
+        }
+        x
+    }
+
+    fn evaluate(&mut self, _id: usize, x: &[f64]) -> Score {
+        if self.left == 0 {
+            return Score {
+                fitness: f64::INFINITY,
+                violation: f64::INFINITY,
+            };
+        }
+        self.left -= 1;
+        let config = self.config;
+        let at = EvalContext {
+            iteration: self.iteration,
+            particle: None,
+        };
+        let fitness = pso::evaluate(config, self.evaluations, self.hooks, at, x);
+        let score =
+            config
+                .comparison
+                .score(fitness, config.constraint_violation(x), self.penalty_factor);
+        if config.comparison.better(score, self.best.1) {
+            self.best = (x.to_vec(), score);
+        }
+        score
+    }
+}
+
+// Searches around `budget.best` until the budget is spent or the search has
+// converged; the best point seen is left in `budget.best`
+pub fn refine(settings: &LocalSearch, budget: &mut Budget) {
+    let ranges: Vec<f64> = (0..budget.config.dimensions)
+        .map(|pos| {
+            let (lower, upper) = budget.config.bounds_of(pos);
+            upper - lower
+        })
+        .collect();
+    let steps: Vec<f64> = ranges.iter().map(|r| settings.step * r).collect();
+    let (start, score) = budget.best.clone();
+    match settings.method {
+        LocalMethod::NelderMead => {
+            let mut simplex = Simplex::around(&start, &steps, budget);
+            while budget.left > 0 && simplex.diameter(&ranges) > TOLERANCE {
+                simplex.step(budget);
+            }
+        }
+        LocalMethod::PatternSearch => {
+            let mut search = PatternSearch::new(start, score, steps, SHRINK);
+            while budget.left > 0 && search.step_size(&ranges) > TOLERANCE {
+                search.step(budget);
+            }
+        }
+    }
+}
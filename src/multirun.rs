@@ -0,0 +1,232 @@
+// Desc: `--runs`: independent runs of one configuration spread over a thread pool
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::config::Config;
+use crate::experiment::RunRecord;
+use crate::handle::CancelToken;
+use crate::optimizer;
+use crate::options::RunOptions;
+
+// Completed runs so far, shared by the workers for the progress lines
+struct Progress {
+    done: usize,
+    best: Option<f64>, // in the objective's own sign
+}
+
+pub fn cores() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+// Worker threads to use: the requested number, or as many as the CPU cores
+// hold when each keeps `per_job` of them busy
+pub fn jobs(requested: Option<u64>, per_job: usize) -> usize {
+    match requested {
+        Some(j) => j as usize,
+        None => (cores() / per_job.max(1)).max(1),
+    }
+}
+
+// Executes `runs` headless runs on `jobs` threads, each run with its own
+// generator, and returns the records of the completed ones in run order.
+// Once `token` is cancelled, running runs stop after their current iteration
+// and runs not yet started are skipped; both are left out of the result.
+pub fn run(
+    config: &Config,
+    options: &RunOptions,
+    runs: usize,
+    jobs: usize,
+    token: &CancelToken,
+) -> Vec<RunRecord> {
+    // a seeded config gives run i the seed + i - 1, as batch replicates do
+    let tasks: Vec<(String, Config)> = (1..=runs)
+        .map(|i| {
+            let mut config = config.clone();
+            config.seed = config.seed.map(|seed| seed.wrapping_add(i as u64 - 1));
+            config.run = i;
+            (format!("Run {}", i), config)
+        })
+        .collect();
+    run_all(&tasks, options, jobs, token, |_, _| {})
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+// Executes labelled runs of different configs on `jobs` threads; `done` is
+// called with the task index and record as each run completes. Returns the
+// record per task, None for tasks that could not start and tasks cut short
+// or skipped by cancellation. Quiet configs print no progress lines.
+pub fn run_all(
+    tasks: &[(String, Config)],
+    options: &RunOptions,
+    jobs: usize,
+    token: &CancelToken,
+    done: impl Fn(usize, &RunRecord) + Sync,
+) -> Vec<Option<RunRecord>> {
+    let mut batch = options.clone();
+    batch.batch = true;
+
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(vec![None; tasks.len()]);
+    let progress = Mutex::new(Progress {
+        done: 0,
+        best: None,
+    });
+    let start = Instant::now();
+
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, tasks.len().max(1)) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                if index >= tasks.len() || token.is_cancelled() {
+                    break;
+                }
+                let (label, config) = &tasks[index];
+
+                let mut run = match optimizer::create(config.clone(), &batch) {
+                    Ok(run) => run,
+                    Err(e) => {
+                        eprintln!("Warning: {} cannot start: {}", label, e);
+                        continue;
+                    }
+                };
+                let mut cancelled = false;
+                let (mut iterations, mut converged_at) = (0, 0);
+                let mut best = run.best();
+                while !run.is_finished() {
+                    if token.is_cancelled() {
+                        run.stop("cancelled");
+                        cancelled = true;
+                        break;
+                    }
+                    run.step();
+                    iterations += 1;
+                    if run.best() < best {
+                        best = run.best();
+                        converged_at = iterations;
+                    }
+                }
+                let outcome = run.finish();
+                let gbest = config.reported(outcome.best);
+                let record = RunRecord {
+                    best: gbest,
+                    evaluations: outcome.evaluations,
+                    iterations,
+                    converged_at,
+                    stopped: outcome.stopped,
+                    trace: outcome
+                        .history
+                        .iter()
+                        .map(|p| (p.evaluations, p.best))
+                        .collect(),
+                    position: outcome.best_x[..config.dimensions.min(outcome.best_x.len())]
+                        .to_vec(),
+                };
+                // a run cut short would skew the statistics of the complete ones
+                if cancelled {
+                    continue;
+                }
+                done(index, &record);
+                results.lock().expect("results lock poisoned")[index] = Some(record);
+
+                let mut p = progress.lock().expect("progress lock poisoned");
+                p.done += 1;
+                let so_far = p.best.map_or(gbest, |b| config.objective.best(b, gbest));
+                p.best = Some(so_far);
+                if config.quiet {
+                    continue;
+                }
+                println!(
+                    "{} finished ({}/{} done, {:.1} s): gbest {}, best so far {}",
+                    label,
+                    p.done,
+                    tasks.len(),
+                    start.elapsed().as_secs_f64(),
+                    gbest,
+                    so_far
+                );
+            });
+        }
+    });
+
+    results.into_inner().expect("results lock poisoned")
+}
+
+// One final gbest per line, readable by `summarize` and `power`
+pub fn save_results(path: &str, values: &[f64]) -> Result<(), String> {
+    let mut file =
+        std::fs::File::create(path).map_err(|e| format!("cannot create {}: {}", path, e))?;
+    for v in values {
+        writeln!(file, "{}", v).map_err(|e| format!("cannot write {}: {}", path, e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multithreaded_objectives_get_fewer_workers() {
+        assert_eq!(jobs(None, 1), cores());
+        assert_eq!(jobs(None, cores()), 1);
+        // more threads per evaluation than cores still runs one worker
+        assert_eq!(jobs(None, cores() + 1), 1);
+        // a requested count is kept as it is
+        assert_eq!(jobs(Some(3), 8), 3);
+    }
+
+    #[test]
+    fn runs_cut_short_by_cancellation_are_left_out() {
+        let token = CancelToken::default();
+        let cancel = token.clone();
+        let evaluations = AtomicUsize::new(0);
+        let mut config = Config::default();
+        config.iterations = 50;
+        config.seed = Some(1);
+        config.quiet = true;
+        // the first run cancels itself halfway
+        config.objective_fn = Some(std::sync::Arc::new(move |x: &[f64]| {
+            if evaluations.fetch_add(1, Ordering::Relaxed) == 500 {
+                cancel.cancel();
+            }
+            x.iter().map(|v| v * v).sum()
+        }));
+        let records = run(&config, &RunOptions::default(), 3, 1, &token);
+        assert!(token.is_cancelled());
+        assert!(records.is_empty());
+    }
+
+    #[test]
+    fn parallel_runs_share_one_evaluation_rate() {
+        let evaluations = std::sync::Arc::new(AtomicUsize::new(0));
+        let counted = evaluations.clone();
+        let mut config = Config::default();
+        config.particles = 4;
+        config.iterations = 5;
+        config.seed = Some(1);
+        config.quiet = true;
+        config.max_evaluations_per_second = Some(400.0);
+        config.objective_fn = Some(std::sync::Arc::new(move |x: &[f64]| {
+            counted.fetch_add(1, Ordering::Relaxed);
+            x.iter().map(|v| v * v).sum()
+        }));
+        let start = Instant::now();
+        let records = run(
+            &config,
+            &RunOptions::default(),
+            3,
+            3,
+            &CancelToken::default(),
+        );
+        let elapsed = start.elapsed().as_secs_f64();
+        assert_eq!(records.len(), 3);
+        // three runs at 400/s each would finish in a third of this
+        let evaluations = evaluations.load(Ordering::Relaxed);
+        assert!(evaluations >= 60);
+        assert!(elapsed >= (evaluations - 1) as f64 / 400.0, "{} s", elapsed);
+    }
+}
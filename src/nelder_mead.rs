@@ -0,0 +1,113 @@
+// Desc: Standalone Nelder-Mead optimizer with random restarts once the simplex collapses
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::config::Config;
+use crate::convergence::TracePoint;
+use crate::events::EventKind;
+use crate::optimizer::{self, Optimizer, Outcome, Tracker};
+use crate::options::RunOptions;
+use crate::sampling;
+use crate::simplex::Simplex;
+
+pub struct NelderMead<'a> {
+    tracker: Tracker<'a>,
+    rng: StdRng,
+    initial_step: f64, // edge length of a new simplex, as a fraction of each range
+    tolerance: f64,    // relative diameter below which the simplex counts as collapsed
+    simplex: Simplex,
+    restarts: usize,
+}
+
+impl<'a> NelderMead<'a> {
+    // The first simplex is built around `start`, or a random point without one
+    pub fn new(
+        config: Config,
+        options: &'a RunOptions,
+        initial_step: f64,
+        tolerance: f64,
+        start: Option<&[f64]>,
+    ) -> Result<Self, String> {
+        let mut rng = optimizer::seeded_rng(&config);
+        let mut tracker = Tracker::new(config, options)?;
+        let simplex = match start {
+            Some(start) => {
+                let steps = ranges(&tracker.config)
+                    .iter()
+                    .map(|r| initial_step * r)
+                    .collect::<Vec<_>>();
+                Simplex::around(start, &steps, &mut tracker)
+            }
+            None => random_simplex(&mut tracker, &mut rng, initial_step),
+        };
+        Ok(NelderMead {
+            tracker,
+            rng,
+            initial_step,
+            tolerance,
+            simplex,
+            restarts: 0,
+        })
+    }
+}
+
+fn ranges(config: &Config) -> Vec<f64> {
+    (0..config.dimensions)
+        .map(|pos| {
+            let (lower, upper) = config.bounds_of(pos);
+            upper - lower
+        })
+        .collect()
+}
+
+// A fresh simplex around a uniformly random point
+fn random_simplex(tracker: &mut Tracker, rng: &mut impl Rng, initial_step: f64) -> Simplex {
+    let config = &tracker.config;
+    let (start, steps): (Vec<f64>, Vec<f64>) = (0..config.dimensions)
+        .map(|pos| {
+            let (lower, upper) = config.bounds_of(pos);
+            let start = sampling::coordinate(config, pos, rng);
+            (start, initial_step * (upper - lower))
+        })
+        .unzip();
+    Simplex::around(&start, &steps, tracker)
+}
+
+impl Optimizer for NelderMead<'_> {
+    fn step(&mut self) {
+        self.simplex.step(&mut self.tracker);
+
+        if self.simplex.diameter(&ranges(&self.tracker.config)) < self.tolerance {
+            self.simplex = random_simplex(&mut self.tracker, &mut self.rng, self.initial_step);
+            self.restarts += 1;
+            self.tracker
+                .annotate(EventKind::Restart, "collapse", "the simplex collapsed");
+        }
+
+        self.tracker.end_iteration();
+    }
+
+    fn is_finished(&self) -> bool {
+        self.tracker.is_finished()
+    }
+
+    fn stop(&mut self, reason: &'static str) {
+        self.tracker.stop(reason);
+    }
+
+    fn best(&self) -> f64 {
+        self.tracker.best()
+    }
+
+    fn progress(&self) -> TracePoint {
+        self.tracker.progress()
+    }
+
+    fn finish(self: Box<Self>) -> Outcome {
+        let notes = [format!(
+            "Simplex restarts after collapse: {}",
+            self.restarts
+        )];
+        self.tracker.finish(&notes)
+    }
+}
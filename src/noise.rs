@@ -0,0 +1,168 @@
+// Desc: Seeded observation noise with common random numbers and antithetic pairs
+//
+// Every noise value is drawn from a stream seeded by the evaluation context,
+// not from the run's generator, so sharing or mirroring draws between the
+// candidates of an iteration is a matter of sharing the stream seed.
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::Serialize;
+
+use crate::config::{Noise, NoiseSampling};
+use crate::distribution::Distribution;
+
+// Where an objective evaluation happens
+#[derive(Clone, Copy)]
+pub struct EvalContext {
+    pub iteration: usize,
+    pub particle: Option<usize>, // None outside the swarm (analysis passes)
+}
+
+impl EvalContext {
+    pub fn particle(iteration: usize, particle: usize) -> EvalContext {
+        EvalContext {
+            iteration,
+            particle: Some(particle),
+        }
+    }
+
+    pub fn analysis() -> EvalContext {
+        EvalContext {
+            iteration: 0,
+            particle: None,
+        }
+    }
+}
+
+// SplitMix64 finalizer, to derive independent stream seeds from small integers
+fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+pub fn stream(seed: u64, parts: &[u64]) -> u64 {
+    parts.iter().fold(mix(seed), |h, &part| mix(h ^ part))
+}
+
+// The noise stream of one evaluation. Objectives that draw their own noise
+// seed their generator with `seed`; the second particle of an antithetic pair
+// gets the seed of the first with `mirrored` set, to mirror its draws.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct NoiseStream {
+    pub seed: u64,
+    pub mirrored: bool,
+}
+
+// Stream of the `evaluation`-th evaluation (1-based), made at `at`
+pub fn stream_of(noise: &Noise, at: EvalContext, evaluation: usize) -> NoiseStream {
+    let seed = noise.seed.unwrap_or(0);
+    let it = at.iteration as u64;
+    let (seed, mirrored) = match (noise.sampling, at.particle) {
+        (NoiseSampling::Common, _) => (stream(seed, &[1, it]), false),
+        (NoiseSampling::Antithetic, Some(p)) => (stream(seed, &[2, it, p as u64 / 2]), p % 2 == 1),
+        // evaluations outside the swarm have no partner to be mirrored with
+        (NoiseSampling::Independent | NoiseSampling::Antithetic, _) => {
+            (stream(seed, &[0, evaluation as u64]), false)
+        }
+    };
+    NoiseStream { seed, mirrored }
+}
+
+// Noise added to the evaluation on `stream`
+pub fn sample(distribution: &Distribution, stream: NoiseStream) -> f64 {
+    let draw = distribution.sample(&mut StdRng::seed_from_u64(stream.seed));
+    if stream.mirrored {
+        2.0 * distribution.center() - draw
+    } else {
+        draw
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::functions::ObjectiveFunction;
+    use std::sync::{Arc, Mutex};
+
+    // Records the noise stream of every evaluation
+    #[derive(Default)]
+    struct Streams(Mutex<Vec<Option<NoiseStream>>>);
+
+    impl ObjectiveFunction for Streams {
+        fn evaluate(&self, _: &[f64]) -> f64 {
+            unreachable!("the runs evaluate with a noise stream")
+        }
+
+        fn evaluate_with(&self, _: &[f64], stream: Option<NoiseStream>) -> f64 {
+            self.0.lock().unwrap().push(stream);
+            0.0
+        }
+    }
+
+    // The streams and added noise of particles 0 to 3 in iterations 1 and 2
+    fn draws(sampling: NoiseSampling) -> (Vec<NoiseStream>, Vec<f64>) {
+        let streams = Arc::new(Streams::default());
+        let mut config = Config::default();
+        config.objective_fn = Some(streams.clone());
+        config.noise = Some(Noise {
+            distribution: Some(Distribution::Normal {
+                mean: 1.0,
+                std: 0.5,
+            }),
+            sampling,
+            seed: Some(9),
+        });
+        let mut values = Vec::new();
+        for (evaluation, (iteration, particle)) in [1, 2]
+            .into_iter()
+            .flat_map(|it| (0..4).map(move |p| (it, p)))
+            .enumerate()
+        {
+            let at = EvalContext::particle(iteration, particle);
+            values.push(config.evaluate(&[0.0, 0.0], at, evaluation + 1).0);
+        }
+        let streams = streams
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|s| s.unwrap())
+            .collect();
+        (streams, values)
+    }
+
+    #[test]
+    fn common_random_numbers_share_the_draw_of_an_iteration() {
+        let (streams, values) = draws(NoiseSampling::Common);
+        for it in streams.chunks(4) {
+            assert!(it.iter().all(|s| *s == it[0] && !s.mirrored));
+        }
+        assert_ne!(streams[0], streams[4]);
+        for it in values.chunks(4) {
+            assert!(it.iter().all(|v| v.to_bits() == it[0].to_bits()));
+        }
+        assert_ne!(values[0], values[4]);
+
+        let (streams, _) = draws(NoiseSampling::Independent);
+        assert_ne!(streams[0], streams[1]);
+    }
+
+    #[test]
+    fn antithetic_pairs_get_mirrored_draws() {
+        let (streams, values) = draws(NoiseSampling::Antithetic);
+        for (pair, draws) in streams.chunks(2).zip(values.chunks(2)) {
+            assert_eq!(pair[0].seed, pair[1].seed);
+            assert!(!pair[0].mirrored && pair[1].mirrored);
+            // mirrored about the mean of the noise
+            assert!(
+                (draws[0] - 1.0 + draws[1] - 1.0).abs() < 1e-12,
+                "{:?}",
+                draws
+            );
+        }
+        assert_ne!(streams[0].seed, streams[2].seed);
+        assert_ne!(streams[0].seed, streams[4].seed);
+    }
+}
@@ -0,0 +1,84 @@
+// Desc: Objective backed by a trained ONNX model (cargo feature `onnx`)
+//
+// The model takes the objective coordinates as one f32 input of shape
+// [1, dimensions]; the fitness is one element of its flattened first output.
+
+#[cfg(feature = "onnx")]
+mod enabled {
+    use std::sync::Arc;
+
+    use tract_onnx::prelude::*;
+
+    #[derive(Clone)]
+    pub struct Model {
+        plan: Arc<TypedRunnableModel>,
+        output: usize,
+    }
+
+    impl Model {
+        pub fn load(path: &str, dimensions: usize, output: usize) -> Result<Model, String> {
+            let plan = tract_onnx::onnx()
+                .model_for_path(path)
+                .and_then(|m| m.with_input_fact(0, f32::fact([1, dimensions]).into()))
+                .and_then(|m| m.into_optimized())
+                .and_then(|m| m.into_runnable())
+                .map_err(|e| format!("cannot load ONNX model {}: {}", path, e))?;
+            let model = Model { plan, output };
+            // fail early on a model that does not accept the input or is too small
+            let probe = model.outputs(&vec![0.0; dimensions])?.len();
+            if output >= probe {
+                return Err(format!(
+                    "ONNX model {} has {} output value(s), output {} requested",
+                    path, probe, output
+                ));
+            }
+            Ok(model)
+        }
+
+        // The flattened first output at `x`
+        fn outputs(&self, x: &[f64]) -> Result<Vec<f32>, String> {
+            let input: Vec<f32> = x.iter().map(|&v| v as f32).collect();
+            let tensor = tract_ndarray::Array2::from_shape_vec((1, x.len()), input)
+                .map_err(|e| e.to_string())?;
+            let result = self
+                .plan
+                .run(tvec!(Tensor::from(tensor).into()))
+                .map_err(|e| format!("ONNX model evaluation failed: {}", e))?;
+            let view = result[0]
+                .to_plain_array_view::<f32>()
+                .map_err(|e| format!("ONNX model output is not f32: {}", e))?;
+            Ok(view.iter().copied().collect())
+        }
+
+        // A failed inference counts as an infeasible point
+        pub fn evaluate(&self, x: &[f64]) -> f64 {
+            match self.outputs(x) {
+                Ok(values) => values[self.output] as f64,
+                Err(_) => f64::NAN,
+            }
+        }
+    }
+}
+
+#[cfg(feature = "onnx")]
+pub use enabled::Model;
+
+// Without the feature no model can be loaded, so the type has no values
+#[cfg(not(feature = "onnx"))]
+#[derive(Clone)]
+pub enum Model {}
+
+#[cfg(not(feature = "onnx"))]
+impl Model {
+    pub fn load(path: &str, _dimensions: usize, _output: usize) -> Result<Model, String> {
+        Err(format!(
+            "cannot load ONNX model {}: built without the `onnx` feature \
+             (rebuild with `cargo build --features onnx`)",
+            path
+        ))
+    }
+
+    pub fn evaluate(&self, _x: &[f64]) -> f64 {
+        match *self {}
+    }
+}
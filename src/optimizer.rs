@@ -0,0 +1,951 @@
+// Desc: Interface shared by the optimization algorithms, and the choice of algorithm
+use rand::SeedableRng;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::abc::Abc;
+use crate::cma_es::CmaEs;
+use crate::comparator::{Comparator, Score};
+use crate::config::Config;
+use crate::convergence::{ConvergenceTrace, TracePoint};
+use crate::diagnostics;
+use crate::eda::Eda;
+use crate::events::{Event, EventKind};
+use crate::firefly::Firefly;
+use crate::gwo::Gwo;
+use crate::harmony::Harmony;
+use crate::nelder_mead::NelderMead;
+use crate::noise::EvalContext;
+use crate::options::RunOptions;
+use crate::output;
+use crate::pattern_search::HookeJeeves;
+use crate::pipeline::{self, Pipeline, Stage};
+use crate::plugin::{self, External, PluginOptimizer};
+use crate::privacy::{self, Privacy};
+use crate::pso::{self, EvalHooks, Run};
+use crate::schedule::Schedule;
+use crate::simplex::Probe;
+use crate::stopping::StopMonitor;
+
+/// Optimization algorithm driving the run
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Algorithm {
+    /// Particle swarm optimization, tuned by the rest of the config
+    #[default]
+    Pso,
+    /// Univariate Gaussian estimation of distribution (UMDA-c): every
+    /// generation fits one normal distribution per dimension to the elite and
+    /// samples `particles` new candidates from them
+    Eda {
+        /// Fraction of each generation the distributions are fitted to
+        #[serde(default = "default_elite_fraction")]
+        elite_fraction: f64,
+    },
+    /// Artificial Bee Colony: `particles` food sources worked by as many
+    /// employed and onlooker bees, with scouts replacing exhausted sources
+    Abc {
+        /// Cycles without improvement before a source is abandoned; defaults
+        /// to particles * dimensions
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+    /// Grey Wolf Optimizer: `particles` wolves move towards the three best
+    /// positions found so far, with a step factor that falls from 2 to 0
+    Gwo,
+    /// Nelder-Mead simplex search for low-dimensional problems: when the
+    /// simplex collapses it restarts around a new random point
+    NelderMead {
+        /// Edge length of each new simplex, as a fraction of the search range
+        #[serde(default = "default_initial_step")]
+        initial_step: f64,
+        /// Simplex size, relative to the search range, below which it counts
+        /// as collapsed
+        #[serde(default = "default_tolerance")]
+        tolerance: f64,
+    },
+    /// Hooke-Jeeves pattern search: coordinate moves around a base point plus
+    /// jumps along the improving direction, with steps that shrink when no
+    /// move helps; the run stops once they are below the tolerance
+    PatternSearch {
+        /// Starting step along every axis, as a fraction of its range
+        #[serde(default = "default_initial_step")]
+        initial_step: f64,
+        /// Factor applied to the steps after an unsuccessful iteration
+        #[serde(default = "default_shrink")]
+        shrink: f64,
+        /// Step size, relative to the search range, at which the search stops
+        #[serde(default = "default_tolerance")]
+        tolerance: f64,
+    },
+    /// Firefly algorithm: each of the `particles` fireflies moves towards every
+    /// brighter one, attracted less the further away it is, plus a random step
+    Firefly {
+        /// Random step size, as a fraction of each range
+        #[serde(default = "default_alpha")]
+        alpha: f64,
+        /// Attraction at distance 0
+        #[serde(default = "default_beta0")]
+        beta0: f64,
+        /// Light absorption: how fast attraction fades with the distance,
+        /// measured in units of the search range
+        #[serde(default = "default_gamma")]
+        gamma: f64,
+        /// Factor applied to `alpha` after every generation
+        #[serde(default = "default_alpha_decay")]
+        alpha_decay: f64,
+    },
+    /// Harmony search: a memory of `particles` solutions; every iteration
+    /// improvises `particles` new ones, coordinate by coordinate, from memory
+    /// (sometimes pitch-adjusted) or at random, each replacing the worst
+    /// remembered solution when it is better
+    Harmony {
+        /// Harmony memory considering rate: probability of taking a coordinate from memory
+        #[serde(default = "default_hmcr")]
+        hmcr: f64,
+        /// Pitch adjusting rate: probability of shifting a coordinate taken from memory
+        #[serde(default = "default_par")]
+        par: f64,
+        /// Largest pitch adjustment, as a fraction of each range
+        #[serde(default = "default_bandwidth")]
+        bandwidth: f64,
+    },
+    /// CMA-ES: `particles` samples per generation from a Gaussian whose
+    /// mean, step size and covariance adapt to the best half of them
+    CmaEs {
+        /// Initial step size, as a fraction of each range
+        #[serde(default = "default_sigma")]
+        sigma: f64,
+    },
+    /// Hybrid run: the stages run one after another, splitting the
+    /// iterations, each optionally starting from what the previous one found
+    Pipeline { stages: Vec<Stage> },
+    /// Optimizer registered by a plugin library (`plugins` feature): asked
+    /// for `particles` candidates every iteration and told their fitness
+    Plugin {
+        /// Path of the library, or its name within the plugin directories
+        library: String,
+        /// Name the library registered the optimizer under
+        name: String,
+    },
+}
+
+fn default_elite_fraction() -> f64 {
+    0.3
+}
+
+fn default_initial_step() -> f64 {
+    0.1
+}
+
+fn default_tolerance() -> f64 {
+    1e-8
+}
+
+fn default_shrink() -> f64 {
+    0.5
+}
+
+fn default_alpha() -> f64 {
+    0.2
+}
+
+fn default_beta0() -> f64 {
+    1.0
+}
+
+fn default_gamma() -> f64 {
+    1.0
+}
+
+fn default_alpha_decay() -> f64 {
+    0.97
+}
+
+fn default_hmcr() -> f64 {
+    0.9
+}
+
+fn default_par() -> f64 {
+    0.3
+}
+
+fn default_bandwidth() -> f64 {
+    0.01
+}
+
+fn default_sigma() -> f64 {
+    0.3
+}
+
+// What an algorithm can work with, so that combinations it would get wrong
+// are rejected up front
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Capabilities {
+    pub constraints: bool, // problem constraints, through the constrained comparison
+    pub discrete: bool,    // integer variables, snapped after every move
+    pub batch_evaluation: bool, // a whole population evaluated in parallel (--threads)
+    pub checkpointing: bool, // saving a run and resuming it later
+}
+
+impl Capabilities {
+    const ALL: Capabilities = Capabilities {
+        constraints: true,
+        discrete: true,
+        batch_evaluation: true,
+        checkpointing: true,
+    };
+
+    // What both support
+    fn and(self, other: Capabilities) -> Capabilities {
+        Capabilities {
+            constraints: self.constraints && other.constraints,
+            discrete: self.discrete && other.discrete,
+            batch_evaluation: self.batch_evaluation && other.batch_evaluation,
+            checkpointing: self.checkpointing && other.checkpointing,
+        }
+    }
+
+    // Names of the supported features, for listings
+    pub fn names(self) -> Vec<&'static str> {
+        [
+            (self.constraints, "constraints"),
+            (self.discrete, "discrete"),
+            (self.batch_evaluation, "batch_evaluation"),
+            (self.checkpointing, "checkpointing"),
+        ]
+        .into_iter()
+        .filter_map(|(supported, name)| supported.then_some(name))
+        .collect()
+    }
+}
+
+impl Algorithm {
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            Algorithm::CmaEs { sigma } if !(sigma > 0.0 && sigma <= 1.0) => {
+                Err(format!("cma_es sigma must be within (0, 1], got {}", sigma))
+            }
+            Algorithm::Pipeline { ref stages } => pipeline::validate(stages),
+            Algorithm::Plugin {
+                ref library,
+                ref name,
+            } => plugin::resolve(library)
+                .and_then(|path| PluginOptimizer::load(&path, name))
+                .map(|_| ()),
+            Algorithm::Eda { elite_fraction }
+                if !(elite_fraction > 0.0 && elite_fraction <= 1.0) =>
+            {
+                Err(format!(
+                    "eda elite_fraction must be within (0, 1], got {}",
+                    elite_fraction
+                ))
+            }
+            Algorithm::NelderMead { initial_step, .. }
+                if !(initial_step > 0.0 && initial_step <= 1.0) =>
+            {
+                Err(format!(
+                    "nelder_mead initial_step must be within (0, 1], got {}",
+                    initial_step
+                ))
+            }
+            Algorithm::NelderMead { tolerance, .. } if tolerance.is_nan() || tolerance <= 0.0 => {
+                Err(format!(
+                    "nelder_mead tolerance must be positive, got {}",
+                    tolerance
+                ))
+            }
+            Algorithm::PatternSearch { initial_step, .. }
+                if !(initial_step > 0.0 && initial_step <= 1.0) =>
+            {
+                Err(format!(
+                    "pattern_search initial_step must be within (0, 1], got {}",
+                    initial_step
+                ))
+            }
+            Algorithm::PatternSearch { shrink, .. } if !(shrink > 0.0 && shrink < 1.0) => {
+                Err(format!(
+                    "pattern_search shrink must be within (0, 1), got {}",
+                    shrink
+                ))
+            }
+            Algorithm::PatternSearch { tolerance, .. }
+                if tolerance.is_nan() || tolerance <= 0.0 =>
+            {
+                Err(format!(
+                    "pattern_search tolerance must be positive, got {}",
+                    tolerance
+                ))
+            }
+            Algorithm::Firefly {
+                alpha,
+                beta0,
+                gamma,
+                alpha_decay,
+            } => {
+                if !(alpha >= 0.0 && beta0 >= 0.0 && gamma >= 0.0) {
+                    return Err("firefly alpha, beta0 and gamma must not be negative".to_string());
+                }
+                if !(alpha_decay > 0.0 && alpha_decay <= 1.0) {
+                    return Err(format!(
+                        "firefly alpha_decay must be within (0, 1], got {}",
+                        alpha_decay
+                    ));
+                }
+                Ok(())
+            }
+            Algorithm::Harmony {
+                hmcr,
+                par,
+                bandwidth,
+            } => {
+                if !((0.0..=1.0).contains(&hmcr) && (0.0..=1.0).contains(&par)) {
+                    return Err(format!(
+                        "harmony hmcr and par must be within [0, 1], got {} and {}",
+                        hmcr, par
+                    ));
+                }
+                if !(bandwidth > 0.0 && bandwidth <= 1.0) {
+                    return Err(format!(
+                        "harmony bandwidth must be within (0, 1], got {}",
+                        bandwidth
+                    ));
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn capabilities(&self) -> Capabilities {
+        let population = Capabilities {
+            constraints: true,
+            discrete: true,
+            batch_evaluation: false,
+            checkpointing: false,
+        };
+        match self {
+            Algorithm::Pso => Capabilities {
+                batch_evaluation: true,
+                checkpointing: true,
+                ..population
+            },
+            // rounding to integers breaks the geometry their steps rely on
+            Algorithm::NelderMead { .. } | Algorithm::CmaEs { .. } => Capabilities {
+                discrete: false,
+                ..population
+            },
+            // the plugin sees fitness values only, not constraint violations
+            Algorithm::Plugin { .. } => Capabilities {
+                constraints: false,
+                ..population
+            },
+            Algorithm::Pipeline { stages } => stages
+                .iter()
+                .map(|s| s.algorithm.capabilities())
+                .fold(Capabilities::ALL, Capabilities::and),
+            _ => population,
+        }
+    }
+
+    // Every algorithm with its default parameters, pipelines aside
+    pub fn catalogue() -> Vec<Algorithm> {
+        vec![
+            Algorithm::Pso,
+            Algorithm::Eda {
+                elite_fraction: default_elite_fraction(),
+            },
+            Algorithm::Abc { limit: None },
+            Algorithm::Gwo,
+            Algorithm::NelderMead {
+                initial_step: default_initial_step(),
+                tolerance: default_tolerance(),
+            },
+            Algorithm::PatternSearch {
+                initial_step: default_initial_step(),
+                shrink: default_shrink(),
+                tolerance: default_tolerance(),
+            },
+            Algorithm::Firefly {
+                alpha: default_alpha(),
+                beta0: default_beta0(),
+                gamma: default_gamma(),
+                alpha_decay: default_alpha_decay(),
+            },
+            Algorithm::Harmony {
+                hmcr: default_hmcr(),
+                par: default_par(),
+                bandwidth: default_bandwidth(),
+            },
+            Algorithm::CmaEs {
+                sigma: default_sigma(),
+            },
+        ]
+    }
+
+    // Whether this algorithm, or one of its pipeline stages, matches `test`
+    pub fn runs(&self, test: impl Fn(&Algorithm) -> bool) -> bool {
+        match self {
+            Algorithm::Pipeline { stages } => stages.iter().any(|s| test(&s.algorithm)),
+            algorithm => test(algorithm),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Pso => "pso",
+            Algorithm::Eda { .. } => "eda",
+            Algorithm::Abc { .. } => "abc",
+            Algorithm::Gwo => "gwo",
+            Algorithm::NelderMead { .. } => "nelder_mead",
+            Algorithm::PatternSearch { .. } => "pattern_search",
+            Algorithm::Firefly { .. } => "firefly",
+            Algorithm::Harmony { .. } => "harmony",
+            Algorithm::CmaEs { .. } => "cma_es",
+            Algorithm::Pipeline { .. } => "pipeline",
+            Algorithm::Plugin { .. } => "plugin",
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Algorithm::Pso => "particle swarm".to_string(),
+            Algorithm::Eda { elite_fraction } => {
+                format!("univariate Gaussian EDA, elite fraction {}", elite_fraction)
+            }
+            Algorithm::Abc { limit: None } => "artificial bee colony".to_string(),
+            Algorithm::Abc { limit: Some(limit) } => {
+                format!("artificial bee colony, limit {}", limit)
+            }
+            Algorithm::Gwo => "grey wolf optimizer".to_string(),
+            Algorithm::NelderMead {
+                initial_step,
+                tolerance,
+            } => format!(
+                "Nelder-Mead, initial step {}, collapse tolerance {}",
+                initial_step, tolerance
+            ),
+            Algorithm::PatternSearch {
+                initial_step,
+                shrink,
+                tolerance,
+            } => format!(
+                "Hooke-Jeeves pattern search, initial step {}, shrink {}, tolerance {}",
+                initial_step, shrink, tolerance
+            ),
+            Algorithm::Firefly {
+                alpha,
+                beta0,
+                gamma,
+                alpha_decay,
+            } => format!(
+                "firefly algorithm, alpha {} (decay {}), beta0 {}, gamma {}",
+                alpha, alpha_decay, beta0, gamma
+            ),
+            Algorithm::Harmony {
+                hmcr,
+                par,
+                bandwidth,
+            } => format!(
+                "harmony search, HMCR {}, PAR {}, bandwidth {}",
+                hmcr, par, bandwidth
+            ),
+            Algorithm::CmaEs { sigma } => format!("CMA-ES, initial step size {}", sigma),
+            Algorithm::Pipeline { stages } => pipeline::describe(stages),
+            Algorithm::Plugin { library, name } => {
+                format!("plugin optimizer {} from {}", name, library)
+            }
+        }
+    }
+}
+
+// Result of a finished run, whatever the algorithm
+pub struct Outcome {
+    pub best_x: Vec<f64>,
+    pub best: f64, // minimized: see `Config::reported`
+    pub evaluations: usize,
+    pub elite: Vec<Vec<f64>>, // best positions found, best first, for a pipeline handoff
+    pub stopped: Option<&'static str>, // condition that ended the run early
+    pub history: Vec<TracePoint>, // one point per iteration
+    pub events: Vec<Event>,
+}
+
+// A run advanced one iteration (generation) at a time by its driver
+pub trait Optimizer {
+    fn step(&mut self);
+    fn is_finished(&self) -> bool;
+    // Ends the run after the current iteration
+    fn stop(&mut self, reason: &'static str);
+    // Best fitness found so far
+    fn best(&self) -> f64;
+    // State after the last iteration, in the convergence trace's schema
+    fn progress(&self) -> TracePoint;
+    // Flushes the end-of-run outputs, prints the final report unless in batch
+    // mode and returns the result
+    fn finish(self: Box<Self>) -> Outcome;
+}
+
+// The run selected by `config.algorithm`
+pub fn create<'a>(
+    config: Config,
+    options: &'a RunOptions,
+) -> Result<Box<dyn Optimizer + 'a>, String> {
+    create_from(config, options, None)
+}
+
+// The run selected by `config.algorithm`, starting from the `start` points
+// where the algorithm can (cma_es, nelder_mead and pattern_search)
+pub fn create_from<'a>(
+    config: Config,
+    options: &'a RunOptions,
+    start: Option<&[Vec<f64>]>,
+) -> Result<Box<dyn Optimizer + 'a>, String> {
+    let first = start.and_then(|points| points.first()).map(Vec::as_slice);
+    Ok(match config.algorithm.clone() {
+        Algorithm::Pso => Box::new(Run::starting_at(config, options, start)?),
+        Algorithm::Eda { elite_fraction } => Box::new(Eda::new(config, options, elite_fraction)?),
+        Algorithm::Abc { limit } => Box::new(Abc::new(config, options, limit)?),
+        Algorithm::Gwo => Box::new(Gwo::new(config, options)?),
+        Algorithm::NelderMead {
+            initial_step,
+            tolerance,
+        } => Box::new(NelderMead::new(
+            config,
+            options,
+            initial_step,
+            tolerance,
+            first,
+        )?),
+        Algorithm::PatternSearch {
+            initial_step,
+            shrink,
+            tolerance,
+        } => Box::new(HookeJeeves::new(
+            config,
+            options,
+            initial_step,
+            shrink,
+            tolerance,
+            first,
+        )?),
+        Algorithm::Firefly {
+            alpha,
+            beta0,
+            gamma,
+            alpha_decay,
+        } => Box::new(Firefly::new(
+            config,
+            options,
+            alpha,
+            beta0,
+            gamma,
+            alpha_decay,
+        )?),
+        Algorithm::Harmony {
+            hmcr,
+            par,
+            bandwidth,
+        } => Box::new(Harmony::new(config, options, hmcr, par, bandwidth)?),
+        Algorithm::CmaEs { sigma } => Box::new(CmaEs::new(config, options, sigma, start)?),
+        Algorithm::Pipeline { stages } => Box::new(Pipeline::new(config, options, stages)?),
+        Algorithm::Plugin { library, name } => {
+            Box::new(External::new(config, options, &library, &name)?)
+        }
+    })
+}
+
+// Generator seeded from `config.seed`, or from entropy when it is not set
+pub fn seeded_rng<R: SeedableRng>(config: &Config) -> R {
+    match config.seed {
+        Some(seed) => R::seed_from_u64(seed),
+        None => R::from_entropy(),
+    }
+}
+
+// Bookkeeping of the algorithms other than PSO: evaluation counting and
+// hooks, the best-so-far, per-iteration output and the final report
+pub struct Tracker<'a> {
+    pub config: Config,
+    pub iteration: usize,
+    options: &'a RunOptions,
+    hooks: EvalHooks,
+    privacy: Option<Privacy>,
+    stopped: Option<&'static str>,
+    stop_monitor: StopMonitor,
+    convergence: Option<ConvergenceTrace>,
+    points: Vec<Vec<f64>>, // evaluated in the current iteration, for its diversity
+    fitness_sum: f64,      // of the points evaluated in the current iteration
+    history: Vec<TracePoint>,
+    events: Vec<Event>,
+    best_x: Vec<f64>,
+    best: Option<Score>,
+    evaluations: usize,
+}
+
+impl<'a> Tracker<'a> {
+    pub fn new(mut config: Config, options: &'a RunOptions) -> Result<Self, String> {
+        let privacy = privacy::from_options(options);
+        config.advance(0);
+        Ok(Tracker {
+            hooks: EvalHooks::new(options, &config, privacy)?,
+            convergence: ConvergenceTrace::create(options.convergence.as_deref(), &config, privacy),
+            config,
+            iteration: 0,
+            options,
+            privacy,
+            stopped: None,
+            stop_monitor: StopMonitor::default(),
+            points: Vec::new(),
+            fitness_sum: 0.0,
+            history: Vec::new(),
+            events: Vec::new(),
+            best_x: Vec::new(),
+            best: None,
+            evaluations: 0,
+        })
+    }
+
+    // Score of candidate `id` of the current iteration at `x`, which the
+    // best-so-far follows
+    pub fn evaluate(&mut self, id: usize, x: &[f64]) -> Score {
+        let config = &self.config;
+        let fitness = pso::evaluate(
+            config,
+            &mut self.evaluations,
+            &mut self.hooks,
+            EvalContext::particle(self.iteration, id),
+            x,
+        );
+        self.points.push(x.to_vec());
+        self.fitness_sum += fitness;
+        let score = config.comparison.score(
+            fitness,
+            config.constraint_violation(x),
+            config
+                .penalty_factor
+                .value(self.iteration, config.iterations),
+        );
+        if self
+            .best
+            .is_none_or(|best| config.comparison.better(score, best))
+        {
+            self.best = Some(score);
+            self.best_x = x.to_vec();
+        }
+        score
+    }
+
+    pub fn best(&self) -> f64 {
+        self.best.map_or(f64::INFINITY, |s| s.fitness)
+    }
+
+    // Records an event of the current iteration, annotated on the convergence trace
+    pub fn annotate(&mut self, kind: EventKind, name: &str, detail: &str) {
+        let event = Event {
+            iteration: self.iteration,
+            evaluations: self.evaluations,
+            kind,
+            name: name.to_string(),
+            detail: detail.to_string(),
+        };
+        if let Some(trace) = self.convergence.as_mut() {
+            trace.annotate(&event);
+        }
+        self.events.push(event);
+    }
+
+    // Logs and traces the iteration, checks the stop conditions and moves on
+    // to the next iteration
+    pub fn end_iteration(&mut self) {
+        let points: Vec<&[f64]> = self.points.iter().map(Vec::as_slice).collect();
+        let (inequality_violation, equality_violation) =
+            self.config.logged_violations(&self.best_x);
+        let point = TracePoint {
+            iteration: self.iteration,
+            evaluations: self.evaluations,
+            best: self.config.reported(self.best()),
+            mean_fitness: self
+                .config
+                .reported(self.fitness_sum / points.len().max(1) as f64),
+            diversity: diagnostics::spread(&points),
+            seconds: self.stop_monitor.elapsed(),
+            at_bounds: diagnostics::at_bounds(&points, &self.config),
+            inequality_violation,
+            equality_violation,
+            ..TracePoint::default()
+        };
+        self.points.clear();
+        self.fitness_sum = 0.0;
+        output::log_iteration(&point, &self.config, self.options, self.privacy);
+        if let Some(trace) = self.convergence.as_mut() {
+            trace.record(&point);
+        }
+        self.history.push(point);
+        if let Some(score) = self.best {
+            let progress = self
+                .stop_monitor
+                .progress(&self.config, score, self.evaluations);
+            if let Some(trigger) = progress {
+                self.stopped = Some(trigger.condition);
+                self.annotate(EventKind::Stop, trigger.condition, &trigger.detail);
+            }
+        }
+        self.iteration += 1;
+        if self.config.advance(self.iteration) && self.config.equality_tolerance.is_some() {
+            // the best so far is measured against the new equality band
+            let config = &self.config;
+            if let Some(best) = self.best.as_mut() {
+                let now = config.constraint_violation(&self.best_x);
+                if config.comparison.penalizes() && best.violation.is_finite() && now.is_finite() {
+                    let penalty = config
+                        .penalty_factor
+                        .value(self.iteration, config.iterations);
+                    best.fitness += penalty * (now - best.violation);
+                }
+                best.violation = now;
+            }
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.stopped.is_some() || self.iteration >= self.config.iterations
+    }
+
+    pub fn stop(&mut self, reason: &'static str) {
+        self.stopped = Some(reason);
+        self.annotate(EventKind::Stop, reason, "stop requested");
+    }
+
+    pub fn progress(&self) -> TracePoint {
+        self.history.last().copied().unwrap_or_default()
+    }
+
+    // Reports the run, with the algorithm's own `notes` first, and returns its result
+    pub fn finish(self, notes: &[String]) -> Outcome {
+        let best = self.best();
+        if !self.options.batch && !self.config.quiet {
+            for note in notes {
+                println!("{}", note);
+            }
+            if let Some(reason) = self.stopped {
+                println!(
+                    "Stopped early by {} after {} of {} iterations",
+                    reason, self.iteration, self.config.iterations
+                );
+            }
+            println!("Evaluations: {}", self.evaluations);
+            if let (Some(g), Some(h)) = self.config.logged_violations(&self.best_x) {
+                println!(
+                    "Constraint violation of the best: {} inequality, {} equality",
+                    g, h
+                );
+            }
+            println!(
+                "Best solution found at: fitness = {}",
+                output::format_value(self.config.reported(best), self.options.console_precision())
+            );
+            for (i, xi) in self.best_x.iter().enumerate() {
+                println!("x{}: {}", i + 1, xi);
+            }
+        }
+        Outcome {
+            elite: vec![self.best_x.clone()],
+            best_x: self.best_x,
+            best,
+            evaluations: self.evaluations,
+            stopped: self.stopped,
+            history: self.history,
+            events: self.events,
+        }
+    }
+}
+
+impl Probe for Tracker<'_> {
+    fn comparator(&self) -> &Comparator {
+        &self.config.comparison
+    }
+
+    fn project(&self, mut x: Vec<f64>) -> Vec<f64> {
+        for (pos, xi) in x.iter_mut().enumerate() {
+            let (lower, upper) = self.config.bounds_of(pos);
+            *xi = self.config.snap(pos, xi.clamp(lower, upper));
+        }
+        x
+    }
+
+    fn evaluate(&mut self, id: usize, x: &[f64]) -> Score {
+        Tracker::evaluate(self, id, x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // gbest after every iteration of a headless run
+    fn trajectory(config: &Config, options: &RunOptions) -> Vec<f64> {
+        let mut run = create(config.clone(), options).unwrap();
+        let mut history = Vec::new();
+        while !run.is_finished() {
+            run.step();
+            history.push(run.best());
+        }
+        history
+    }
+
+    fn seeded(algorithm: Algorithm, seed: u64) -> Config {
+        let mut config = Config::default();
+        config.algorithm = algorithm;
+        config.iterations = 30;
+        config.seed = Some(seed);
+        config
+    }
+
+    const ALGORITHMS: [Algorithm; 9] = [
+        Algorithm::Pso,
+        Algorithm::Eda {
+            elite_fraction: 0.3,
+        },
+        Algorithm::Abc { limit: None },
+        Algorithm::Gwo,
+        Algorithm::NelderMead {
+            initial_step: 0.1,
+            tolerance: 1e-8,
+        },
+        Algorithm::PatternSearch {
+            initial_step: 0.1,
+            shrink: 0.5,
+            tolerance: 1e-8,
+        },
+        Algorithm::Firefly {
+            alpha: 0.2,
+            beta0: 1.0,
+            gamma: 1.0,
+            alpha_decay: 0.97,
+        },
+        Algorithm::Harmony {
+            hmcr: 0.9,
+            par: 0.3,
+            bandwidth: 0.01,
+        },
+        Algorithm::CmaEs { sigma: 0.3 },
+    ];
+
+    #[test]
+    fn same_seed_gives_identical_trajectories() {
+        let options = RunOptions::default();
+        for algorithm in ALGORITHMS {
+            let config = seeded(algorithm, 42);
+            assert_eq!(trajectory(&config, &options), trajectory(&config, &options));
+        }
+    }
+
+    #[test]
+    fn different_seeds_give_different_trajectories() {
+        let options = RunOptions::default();
+        for algorithm in ALGORITHMS {
+            let (a, b) = (seeded(algorithm.clone(), 1), seeded(algorithm, 2));
+            assert_ne!(trajectory(&a, &options), trajectory(&b, &options));
+        }
+    }
+
+    #[test]
+    fn pipeline_spends_the_whole_budget_across_its_stages() {
+        let options = RunOptions::default();
+        let stage = |algorithm, fraction, handoff| pipeline::Stage {
+            algorithm,
+            fraction,
+            handoff,
+            stagnation: None,
+        };
+        let config = seeded(
+            Algorithm::Pipeline {
+                stages: vec![
+                    stage(Algorithm::Pso, 0.5, pipeline::Handoff::Fresh),
+                    stage(
+                        Algorithm::CmaEs { sigma: 0.3 },
+                        0.5,
+                        pipeline::Handoff::Elite,
+                    ),
+                ],
+            },
+            3,
+        );
+        config.validate().unwrap();
+        let history = trajectory(&config, &options);
+        assert_eq!(history.len(), config.iterations);
+        assert!(history.windows(2).all(|w| w[1] <= w[0]));
+        assert_eq!(history, trajectory(&config, &options));
+    }
+
+    #[test]
+    fn a_stalled_stage_hands_its_iterations_to_the_next() {
+        let options = RunOptions::default();
+        // no improvement is ever large enough, so pso stalls after 4 iterations
+        let stages = vec![
+            pipeline::Stage {
+                algorithm: Algorithm::Pso,
+                fraction: 0.5,
+                handoff: pipeline::Handoff::Fresh,
+                stagnation: Some(crate::config::Stagnation {
+                    iterations: 4,
+                    min_improvement: f64::INFINITY,
+                }),
+            },
+            pipeline::Stage {
+                algorithm: Algorithm::Pso,
+                fraction: 0.5,
+                handoff: pipeline::Handoff::Elite,
+                stagnation: None,
+            },
+        ];
+        let config = seeded(Algorithm::Pipeline { stages }, 5);
+        config.validate().unwrap();
+        let mut run = create(config.clone(), &options).unwrap();
+        while !run.is_finished() {
+            run.step();
+        }
+        let outcome = run.finish();
+        assert_eq!(outcome.history.len(), config.iterations);
+        let switch = outcome
+            .events
+            .iter()
+            .find(|e| e.name == "stage")
+            .expect("the second stage took over");
+        assert!(switch.iteration < config.iterations / 2, "{:?}", switch);
+    }
+
+    #[test]
+    fn a_pipeline_supports_what_all_its_stages_support() {
+        let stage = |algorithm| pipeline::Stage {
+            algorithm,
+            fraction: 0.5,
+            handoff: pipeline::Handoff::Fresh,
+            stagnation: None,
+        };
+        let pipeline = Algorithm::Pipeline {
+            stages: vec![
+                stage(Algorithm::Pso),
+                stage(Algorithm::CmaEs { sigma: 0.3 }),
+            ],
+        };
+        let capabilities = pipeline.capabilities();
+        assert!(capabilities.constraints);
+        assert!(!capabilities.discrete && !capabilities.batch_evaluation);
+        assert!(Algorithm::Pso.capabilities().batch_evaluation);
+    }
+
+    #[test]
+    fn parallel_pso_does_not_depend_on_the_thread_count() {
+        let one = RunOptions {
+            threads: Some(1),
+            ..RunOptions::default()
+        };
+        let four = RunOptions {
+            threads: Some(4),
+            ..RunOptions::default()
+        };
+        let config = seeded(Algorithm::Pso, 7);
+        assert_eq!(trajectory(&config, &one), trajectory(&config, &four));
+    }
+}
@@ -0,0 +1,109 @@
+// Desc: Run options for the logs, views and side outputs of a run, independent of the command line
+use crate::output;
+
+// What a run writes and shows besides its result. The command line fills
+// these from its flags; runs driven from code start from the defaults.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunOptions {
+    pub batch: bool, // headless: no console output, no CSV log
+    pub output: String,
+    pub format: output::Format,
+    pub append: bool,
+    pub precision: Option<output::Precision>,
+    pub console_precision: Option<output::Precision>,
+    pub tags: Vec<(String, String)>,
+    pub log_error: bool,
+    pub explain: bool,
+    pub convergence: Option<String>,
+    pub diagnostics: Option<String>,
+    pub improvements: Option<String>,
+    pub events: Option<String>,
+    pub trace_evaluations: Option<String>,
+    pub trace_hashed: bool,
+    pub audit: Option<String>,
+    pub checkpoint: Option<String>,
+    pub checkpoint_every: u64,
+    pub resume: Option<String>,
+    pub warm_start: Option<String>,
+    pub heatmap: Option<String>,
+    pub heatmap_dims: Vec<usize>,
+    pub heatmap_bins: usize,
+    pub projection: Option<String>,
+    pub projection_method: String,
+    pub animate: Option<String>,
+    pub web: bool,
+    pub web_port: u16,
+    pub web_delay: u64,
+    pub threads: Option<u64>,
+    pub saturation_warning: f64,
+    pub disperse_duplicates: bool,
+    pub duplicate_tolerance: f64,
+    pub dp_epsilon: Option<f64>,
+    pub dp_sensitivity: f64,
+}
+
+// The command line's defaults, with console and CSV output off
+impl Default for RunOptions {
+    fn default() -> Self {
+        RunOptions {
+            batch: true,
+            output: "test_9.csv".to_string(),
+            format: output::Format::Csv,
+            append: false,
+            precision: None,
+            console_precision: None,
+            tags: Vec::new(),
+            log_error: false,
+            explain: false,
+            convergence: None,
+            diagnostics: None,
+            improvements: None,
+            events: None,
+            trace_evaluations: None,
+            trace_hashed: false,
+            audit: None,
+            checkpoint: None,
+            checkpoint_every: 100,
+            resume: None,
+            warm_start: None,
+            heatmap: None,
+            heatmap_dims: vec![1, 2],
+            heatmap_bins: 50,
+            projection: None,
+            projection_method: "pca".to_string(),
+            animate: None,
+            web: false,
+            web_port: 8080,
+            web_delay: 0,
+            threads: None,
+            saturation_warning: 0.5,
+            disperse_duplicates: false,
+            duplicate_tolerance: 1e-6,
+            dp_epsilon: None,
+            dp_sensitivity: 1.0,
+        }
+    }
+}
+
+impl RunOptions {
+    // Precision of the progress lines and the final report: console_precision,
+    // or precision without it
+    pub fn console_precision(&self) -> Option<output::Precision> {
+        self.console_precision.or(self.precision)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::cli::Cli;
+
+    #[test]
+    fn the_defaults_are_the_command_lines_without_console_output() {
+        let mut cli = Cli::parse_from(["pso_rastrigin"]);
+        cli.batch = true;
+        assert_eq!(cli.options(), RunOptions::default());
+    }
+}
@@ -0,0 +1,320 @@
+// Desc: Run output files: the per-iteration CSV log with its sidecar, or a
+// JSON document of the whole run
+use std::io::Write;
+use std::sync::Mutex;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::convergence::TracePoint;
+use crate::optimizer::Outcome;
+use crate::options::RunOptions;
+use crate::privacy::Privacy;
+use crate::sink::Sink;
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Format {
+    /// Per-iteration CSV log with a header, plus a .meta sidecar
+    Csv,
+    /// One JSON document with the config, the per-iteration history and the
+    /// final solution, written when the run ends
+    Json,
+}
+
+const AUTO_DIGITS: i32 = 4; // significant digits shown by `--precision auto`
+const AUTO_SMALLEST: f64 = 1e-12; // below it, `auto` switches to scientific notation
+
+// How fitness values are written, `--precision`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Precision {
+    Scientific(usize), // this many digits after the point
+    Auto,              // more decimals the closer the value gets to zero
+}
+
+pub fn parse_precision(s: &str) -> Result<Precision, String> {
+    if s == "auto" {
+        return Ok(Precision::Auto);
+    }
+    s.parse()
+        .map(Precision::Scientific)
+        .map_err(|_| format!("expected a number of digits or `auto`, got `{}`", s))
+}
+
+// Shortest round-trip representation by default. `auto` keeps four
+// significant digits in fixed notation, so 0.000123 does not show as 0.0001,
+// and falls back to scientific notation for tiny values.
+pub fn format_value(value: f64, precision: Option<Precision>) -> String {
+    match precision {
+        Some(Precision::Scientific(p)) => format!("{:.*e}", p, value),
+        Some(Precision::Auto) if value == 0.0 || !value.is_finite() => value.to_string(),
+        Some(Precision::Auto) if value.abs() < AUTO_SMALLEST => {
+            format!("{:.*e}", AUTO_DIGITS as usize - 1, value)
+        }
+        Some(Precision::Auto) => {
+            let magnitude = value.abs().log10().floor() as i32;
+            let decimals = (AUTO_DIGITS - 1 - magnitude).max(1);
+            format!("{:.*}", decimals as usize, value)
+        }
+        None => value.to_string(),
+    }
+}
+
+// Columns of the CSV log; tag values follow the fixed columns. The velocity
+// and personal best columns stay empty for algorithms without a swarm, the
+// violation columns for runs without constraints.
+fn columns(log_error: bool, tags: &[(String, String)]) -> Vec<&str> {
+    let mut columns = vec![
+        "iteration",
+        "gbest",
+        "mean_fitness",
+        "diversity",
+        "seconds",
+        "at_bounds",
+        "mean_velocity",
+        "max_velocity",
+        "mean_pbest",
+        "inequality_violation",
+        "equality_violation",
+    ];
+    if log_error {
+        columns.push("log10_error");
+    }
+    columns.extend(tags.iter().map(|(key, _)| key.as_str()));
+    columns
+}
+
+// The CSV log, open from `start` (or its first row) until `close`
+static LOG: Mutex<Option<Sink>> = Mutex::new(None);
+
+// Prepares `--output` before a run: the CSV log is replaced, or continued
+// with --append or --resume, and starts with a header unless it already has rows; its
+// sidecar is rewritten. A JSON document is only written at the end.
+pub fn start(config: &Config, options: &RunOptions) {
+    if options.format == Format::Json {
+        return;
+    }
+    let mut log = if options.append || options.resume.is_some() {
+        Sink::append(&options.output)
+    } else {
+        Sink::create(&options.output)
+    };
+    if log.is_empty() {
+        csv::Writer::from_writer(&mut log)
+            .write_record(columns(options.log_error, &options.tags))
+            .expect("Error writing to CSV file");
+    }
+    *LOG.lock().unwrap_or_else(|e| e.into_inner()) = Some(log);
+    save_sidecar(&options.output, config, &options.tags, options.log_error);
+}
+
+// Closes the CSV log after the run, writing out what a failing disk left buffered
+pub fn close() {
+    LOG.lock().unwrap_or_else(|e| e.into_inner()).take();
+}
+
+// Progress line every 100 iterations and the CSV record of one iteration,
+// both skipped in batch mode
+pub fn log_iteration(
+    point: &TracePoint,
+    config: &Config,
+    options: &RunOptions,
+    privacy: Option<Privacy>,
+) {
+    if options.batch || config.quiet {
+        return;
+    }
+    let (iteration, gbest) = (point.iteration, point.best);
+    if iteration.is_multiple_of(100) {
+        println!(
+            "Iteration: {}, gbest: {}",
+            iteration,
+            format_value(gbest, options.console_precision())
+        );
+    }
+
+    if options.format == Format::Json {
+        return;
+    }
+    let logged_gbest = privacy.map_or(gbest, |p| p.apply(gbest));
+    let noisy = |value: f64| privacy.map_or(value, |p| p.apply(value));
+    let optional =
+        |value: Option<f64>| value.map_or(String::new(), |v| format_value(v, options.precision));
+    let mut values = vec![
+        format_value(logged_gbest, options.precision),
+        format_value(noisy(point.mean_fitness), options.precision),
+        format_value(point.diversity, options.precision),
+        format!("{:.6}", point.seconds),
+        point.at_bounds.to_string(),
+        optional(point.mean_velocity),
+        optional(point.max_velocity),
+        optional(point.mean_pbest.map(noisy)),
+        optional(point.inequality_violation),
+        optional(point.equality_violation),
+    ];
+    if options.log_error {
+        // validated in main: the optimum is known; an exact hit gives -inf
+        let optimum = config.optimum().unwrap_or(0.0);
+        let error = config
+            .objective
+            .shortfall(logged_gbest, optimum)
+            .max(0.0)
+            .log10();
+        values.push(format_value(error, options.precision));
+    }
+    save_fitness_to_csv(&options.output, iteration, &values, &options.tags);
+}
+
+// `values` are the already formatted columns after the iteration, up to the
+// log10 error when enabled
+pub fn save_fitness_to_csv(
+    path: &str,
+    iteration: usize,
+    values: &[String],
+    tags: &[(String, String)],
+) {
+    let mut log = LOG.lock().unwrap_or_else(|e| e.into_inner());
+    if log.as_ref().is_none_or(|log| log.path() != path) {
+        *log = Some(Sink::append(path));
+    }
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_writer(log.as_mut().expect("the CSV log is open"));
+
+    // tag values follow the fixed columns, in the order listed in the sidecar
+    let mut record = vec![iteration.to_string()];
+    record.extend_from_slice(values);
+    record.extend(tags.iter().map(|(_, value)| value.clone()));
+
+    writer
+        .write_record(&record)
+        .expect("Error writing to CSV file");
+}
+
+// Writes the run parameters and tags next to the CSV log at `path` as key,value rows
+pub fn save_sidecar(path: &str, config: &Config, tags: &[(String, String)], log_error: bool) {
+    let filename = format!("{}.meta", path);
+
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(Sink::create(&filename));
+
+    writer
+        .write_record(["columns", columns(log_error, tags).join(" ").as_str()])
+        .expect("Error writing sidecar file");
+
+    for (key, value) in config.entries() {
+        writer
+            .write_record([key, value.as_str()])
+            .expect("Error writing sidecar file");
+    }
+    for (key, value) in tags {
+        writer
+            .write_record([format!("tag.{}", key), value.clone()])
+            .expect("Error writing sidecar file");
+    }
+}
+
+// The whole run for downstream analysis; fitness values and positions carry
+// the log noise of --dp-epsilon like the CSV log
+#[derive(Serialize)]
+struct RunDocument<'a> {
+    config: &'a Config,
+    tags: serde_json::Map<String, serde_json::Value>,
+    history: Vec<TracePoint>,
+    result: RunResult,
+}
+
+#[derive(Serialize)]
+struct RunResult {
+    fitness: f64,
+    position: Vec<f64>,
+    evaluations: usize,
+    stopped: Option<&'static str>,
+}
+
+// Writes the `--format json` document of a finished run to `--output`
+pub fn save_json(
+    config: &Config,
+    options: &RunOptions,
+    outcome: &Outcome,
+    privacy: Option<Privacy>,
+) {
+    let noisy = |value: f64| privacy.map_or(value, |p| p.apply(value));
+    let document = RunDocument {
+        config,
+        tags: options
+            .tags
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone().into()))
+            .collect(),
+        history: outcome
+            .history
+            .iter()
+            .map(|point| TracePoint {
+                best: noisy(point.best),
+                mean_fitness: noisy(point.mean_fitness),
+                mean_pbest: point.mean_pbest.map(noisy),
+                ..*point
+            })
+            .collect(),
+        result: RunResult {
+            fitness: noisy(config.reported(outcome.best)),
+            position: privacy.map_or(outcome.best_x.clone(), |p| p.apply_all(&outcome.best_x)),
+            evaluations: outcome.evaluations,
+            stopped: outcome.stopped,
+        },
+    };
+    let json = serde_json::to_string_pretty(&document).expect("Error serializing run");
+    let mut file = Sink::create(&options.output);
+    writeln!(file, "{}", json).expect("Error writing JSON output");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auto_precision_keeps_small_values_readable() {
+        let auto = Some(Precision::Auto);
+        assert_eq!(format_value(123.456, auto), "123.5");
+        assert_eq!(format_value(0.000123456, auto), "0.0001235");
+        assert_eq!(format_value(3.0e-15, auto), "3.000e-15");
+        assert_eq!(format_value(0.0, auto), "0");
+        assert_eq!(format_value(0.5, Some(Precision::Scientific(2))), "5.00e-1");
+        assert_eq!(parse_precision("auto"), Ok(Precision::Auto));
+        assert!(parse_precision("many").is_err());
+    }
+
+    #[test]
+    fn a_log_on_an_unwritable_path_lets_the_run_go_on() {
+        let blocked = std::env::temp_dir().join("pso_output_blocked");
+        let path = blocked.join("pso_output_log.csv");
+        std::fs::create_dir_all(&path).unwrap();
+        let fallback = std::env::temp_dir().join("pso_output_log.csv");
+        let _ = std::fs::remove_file(&fallback);
+
+        let config = Config::default();
+        let options = RunOptions {
+            batch: false,
+            output: path.to_str().unwrap().to_string(),
+            ..RunOptions::default()
+        };
+        start(&config, &options);
+        for iteration in 1..=3 {
+            let point = TracePoint {
+                iteration,
+                best: 1.0 / iteration as f64,
+                ..TracePoint::default()
+            };
+            log_iteration(&point, &config, &options, None);
+        }
+        close();
+
+        let rows = std::fs::read_to_string(&fallback).unwrap();
+        assert_eq!(rows.lines().count(), 4);
+        assert!(rows.starts_with("iteration,"));
+        assert!(rows.lines().last().unwrap().starts_with("3,"));
+    }
+}
@@ -0,0 +1,97 @@
+// Desc: Hooke-Jeeves pattern search, reusable by any optimizer that refines a point
+use crate::comparator::Score;
+use crate::simplex::Probe;
+
+// A base point and one step size per axis, shrunk whenever no move around
+// the base improves it
+pub struct PatternSearch {
+    base: Vec<f64>,
+    score: Score,
+    steps: Vec<f64>,
+    shrink: f64,
+}
+
+impl PatternSearch {
+    // `base` must already have been scored as `score`
+    pub fn new(base: Vec<f64>, score: Score, steps: Vec<f64>, shrink: f64) -> Self {
+        PatternSearch {
+            base,
+            score,
+            steps,
+            shrink,
+        }
+    }
+
+    // Largest step relative to `ranges`; the search has converged when this gets tiny
+    pub fn step_size(&self, ranges: &[f64]) -> f64 {
+        self.steps
+            .iter()
+            .zip(ranges)
+            .map(|(s, r)| s / r)
+            .fold(0.0, f64::max)
+    }
+
+    // Tries +step then -step along every axis in turn, keeping each move that
+    // improves; moves a bound swallows are not evaluated. Returns the point,
+    // its score and the evaluations used.
+    fn explore(
+        &self,
+        mut x: Vec<f64>,
+        mut score: Score,
+        probe: &mut impl Probe,
+        id: &mut usize,
+    ) -> (Vec<f64>, Score, usize) {
+        let mut evaluations = 0;
+        for (i, step) in self.steps.iter().enumerate() {
+            for sign in [1.0, -1.0] {
+                let mut y = x.clone();
+                y[i] += sign * step;
+                let y = probe.project(y);
+                if y == x {
+                    continue;
+                }
+                let y_score = probe.evaluate(*id, &y);
+                *id += 1;
+                evaluations += 1;
+                if probe.comparator().better(y_score, score) {
+                    x = y;
+                    score = y_score;
+                    break;
+                }
+            }
+        }
+        (x, score, evaluations)
+    }
+
+    // One Hooke-Jeeves iteration: explore around the base; on success jump
+    // along the improving direction and explore there too, otherwise shrink
+    // the steps. Returns the number of evaluations it took.
+    pub fn step(&mut self, probe: &mut impl Probe) -> usize {
+        let mut id = 0;
+        let (x, score, mut evaluations) =
+            self.explore(self.base.clone(), self.score, probe, &mut id);
+        if !probe.comparator().better(score, self.score) {
+            for step in &mut self.steps {
+                *step *= self.shrink;
+            }
+            return evaluations;
+        }
+
+        // pattern move: x + (x - base)
+        let jump = probe.project(x.iter().zip(&self.base).map(|(a, b)| 2.0 * a - b).collect());
+        self.base = x;
+        self.score = score;
+        if jump != self.base {
+            let jump_score = probe.evaluate(id, &jump);
+            id += 1;
+            evaluations += 1;
+            let (y, y_score, n) = self.explore(jump, jump_score, probe, &mut id);
+            evaluations += n;
+            if probe.comparator().better(y_score, self.score) {
+                self.base = y;
+                self.score = y_score;
+            }
+        }
+        evaluations
+    }
+}
@@ -0,0 +1,83 @@
+// Desc: Standalone Hooke-Jeeves pattern search, stopped once its steps have shrunk away
+use rand::rngs::StdRng;
+
+use crate::config::Config;
+use crate::convergence::TracePoint;
+use crate::optimizer::{self, Optimizer, Outcome, Tracker};
+use crate::options::RunOptions;
+use crate::pattern::PatternSearch;
+use crate::sampling;
+
+pub struct HookeJeeves<'a> {
+    tracker: Tracker<'a>,
+    search: PatternSearch,
+    ranges: Vec<f64>,
+    tolerance: f64, // relative step size below which the search has converged
+}
+
+impl<'a> HookeJeeves<'a> {
+    // Starts from `start`, or a uniformly random point without one, with steps
+    // of `initial_step` of each range
+    pub fn new(
+        config: Config,
+        options: &'a RunOptions,
+        initial_step: f64,
+        shrink: f64,
+        tolerance: f64,
+        start: Option<&[f64]>,
+    ) -> Result<Self, String> {
+        let mut rng: StdRng = optimizer::seeded_rng(&config);
+        let mut tracker = Tracker::new(config, options)?;
+        let config = &tracker.config;
+        let ranges: Vec<f64> = (0..config.dimensions)
+            .map(|pos| {
+                let (lower, upper) = config.bounds_of(pos);
+                upper - lower
+            })
+            .collect();
+        let start: Vec<f64> = (0..config.dimensions)
+            .map(|pos| match start {
+                Some(start) => sampling::confine(config, pos, start[pos]),
+                None => sampling::coordinate(config, pos, &mut rng),
+            })
+            .collect();
+        let steps = ranges.iter().map(|r| initial_step * r).collect();
+        let score = tracker.evaluate(0, &start);
+        Ok(HookeJeeves {
+            tracker,
+            search: PatternSearch::new(start, score, steps, shrink),
+            ranges,
+            tolerance,
+        })
+    }
+}
+
+impl Optimizer for HookeJeeves<'_> {
+    fn step(&mut self) {
+        self.search.step(&mut self.tracker);
+        if self.search.step_size(&self.ranges) < self.tolerance {
+            self.tracker.stop("step size convergence");
+        }
+        self.tracker.end_iteration();
+    }
+
+    fn is_finished(&self) -> bool {
+        self.tracker.is_finished()
+    }
+
+    fn stop(&mut self, reason: &'static str) {
+        self.tracker.stop(reason);
+    }
+
+    fn best(&self) -> f64 {
+        self.tracker.best()
+    }
+
+    fn progress(&self) -> TracePoint {
+        self.tracker.progress()
+    }
+
+    fn finish(self: Box<Self>) -> Outcome {
+        self.tracker.finish(&[])
+    }
+}
@@ -0,0 +1,415 @@
+// Desc: Hybrid runs: a sequence of optimizers sharing the iteration budget,
+// each stage optionally starting from what the previous one found. A stage
+// with a stagnation trigger hands over as soon as it stalls, and the next
+// stage takes over the iterations it leaves.
+use std::time::Instant;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Config, Stagnation};
+use crate::convergence::{ConvergenceTrace, TracePoint};
+use crate::events::{Event, EventKind};
+use crate::optimizer::{self, Algorithm, Optimizer, Outcome};
+use crate::options::RunOptions;
+use crate::output;
+use crate::privacy::{self, Privacy};
+
+/// One optimizer of a pipeline
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Stage {
+    pub algorithm: Algorithm,
+    /// Share of the run's iterations; the shares of all stages add up to 1
+    pub fraction: f64,
+    /// What the stage starts from
+    #[serde(default)]
+    pub handoff: Handoff,
+    /// Hand over to the next stage once the best improved by less than
+    /// `min_improvement` for this many iterations, in place of stop.stagnation
+    #[serde(default)]
+    pub stagnation: Option<Stagnation>,
+}
+
+/// State a stage takes over from the previous one
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Handoff {
+    /// Start from scratch
+    #[default]
+    Fresh,
+    /// Start at the previous stage's best position
+    Best,
+    /// Start from the previous stage's elite: PSO's elite archive (or its
+    /// personal bests without one), the best position for the other
+    /// algorithms. A PSO stage places its first particles there.
+    Elite,
+}
+
+impl Handoff {
+    fn name(self) -> &'static str {
+        match self {
+            Handoff::Fresh => "fresh",
+            Handoff::Best => "best",
+            Handoff::Elite => "elite",
+        }
+    }
+}
+
+pub fn validate(stages: &[Stage]) -> Result<(), String> {
+    if stages.is_empty() {
+        return Err("pipeline needs at least one stage".to_string());
+    }
+    for (k, stage) in stages.iter().enumerate() {
+        let algorithm = &stage.algorithm;
+        if matches!(algorithm, Algorithm::Pipeline { .. }) {
+            return Err("pipeline stages cannot be pipelines themselves".to_string());
+        }
+        algorithm.validate()?;
+        if !(stage.fraction > 0.0 && stage.fraction <= 1.0) {
+            return Err(format!(
+                "pipeline stage {} fraction must be within (0, 1], got {}",
+                k + 1,
+                stage.fraction
+            ));
+        }
+        if let Some(stagnation) = &stage.stagnation {
+            if stagnation.iterations == 0
+                || stagnation.min_improvement.is_nan()
+                || stagnation.min_improvement < 0.0
+            {
+                return Err(format!(
+                    "pipeline stage {} stagnation needs positive iterations and a \
+                     non-negative min_improvement",
+                    k + 1
+                ));
+            }
+        }
+        let takes_start = matches!(
+            algorithm,
+            Algorithm::Pso
+                | Algorithm::CmaEs { .. }
+                | Algorithm::NelderMead { .. }
+                | Algorithm::PatternSearch { .. }
+        );
+        if stage.handoff != Handoff::Fresh && !(takes_start && k > 0) {
+            return Err(format!(
+                "pipeline stage {} ({}) cannot start from a {} handoff; only later \
+                 pso, cma_es, nelder_mead and pattern_search stages can",
+                k + 1,
+                algorithm.name(),
+                stage.handoff.name()
+            ));
+        }
+    }
+    let total: f64 = stages.iter().map(|s| s.fraction).sum();
+    if (total - 1.0).abs() > 1e-9 {
+        return Err(format!(
+            "pipeline stage fractions must add up to 1, got {}",
+            total
+        ));
+    }
+    Ok(())
+}
+
+pub fn describe(stages: &[Stage]) -> String {
+    let parts: Vec<String> = stages
+        .iter()
+        .map(|s| {
+            let from = match s.handoff {
+                Handoff::Fresh => String::new(),
+                handoff => format!(" from {}", handoff.name()),
+            };
+            let until = match &s.stagnation {
+                Some(stagnation) => format!(" until stalled for {}", stagnation.iterations),
+                None => String::new(),
+            };
+            format!(
+                "{} ({}%){}{}",
+                s.algorithm.name(),
+                s.fraction * 100.0,
+                from,
+                until
+            )
+        })
+        .collect();
+    format!("pipeline: {}", parts.join(", then "))
+}
+
+// The stages run one after another on quiet copies of the config; the
+// pipeline logs the iterations and prints the report for the whole run. The
+// evaluation and time limits are shared by the stages, a stage stopped by
+// anything but stagnation ends the pipeline.
+pub struct Pipeline<'a> {
+    config: Config,
+    options: &'a RunOptions,
+    privacy: Option<Privacy>,
+    stages: Vec<Stage>,
+    current: Option<Box<dyn Optimizer + 'a>>, // None once a stage ended the pipeline
+    index: usize,                             // stage running now
+    stage_start: usize,                       // iteration at which the current stage took over
+    stage_end: usize,                         // iteration at which the current stage hands over
+    iteration: usize,                         // iterations completed over all stages
+    done: Vec<Outcome>,                       // outcomes of the finished stages
+    stopped: Option<&'static str>,
+    started: Instant,
+    convergence: Option<ConvergenceTrace>,
+    history: Vec<TracePoint>,
+    events: Vec<Event>, // of the stages, on the pipeline's iteration and evaluation counts
+}
+
+impl<'a> Pipeline<'a> {
+    pub fn new(
+        config: Config,
+        options: &'a RunOptions,
+        stages: Vec<Stage>,
+    ) -> Result<Self, String> {
+        let started = Instant::now();
+        let (current, stage_end) = start_stage(&config, options, &stages, 0, 0, &[], started)?;
+        let privacy = privacy::from_options(options);
+        Ok(Pipeline {
+            convergence: ConvergenceTrace::create(options.convergence.as_deref(), &config, privacy),
+            history: Vec::new(),
+            events: Vec::new(),
+            privacy,
+            config,
+            options,
+            stages,
+            current: Some(current),
+            index: 0,
+            stage_start: 0,
+            stage_end,
+            iteration: 0,
+            done: Vec::new(),
+            stopped: None,
+            started,
+        })
+    }
+
+    fn running(&self) -> &(dyn Optimizer + 'a) {
+        self.current.as_deref().expect("a stage is running")
+    }
+
+    fn running_mut(&mut self) -> &mut (dyn Optimizer + 'a) {
+        self.current.as_deref_mut().expect("a stage is running")
+    }
+
+    // Adds an event on the pipeline's counts to the run's and the trace's
+    fn annotate(&mut self, event: Event) {
+        if let Some(trace) = self.convergence.as_mut() {
+            trace.annotate(&event);
+        }
+        self.events.push(event);
+    }
+
+    // The events of a finished stage, moved onto the pipeline's counts
+    fn absorb(&mut self, outcome: &Outcome) {
+        let evaluations: usize = self.done.iter().map(|o| o.evaluations).sum();
+        for event in &outcome.events {
+            self.annotate(Event {
+                iteration: event.iteration + self.stage_start,
+                evaluations: event.evaluations + evaluations,
+                ..event.clone()
+            });
+        }
+    }
+}
+
+// Stage `k` starting at iteration `from`, with the previous stage's outcome
+// for its handoff and what is left of the limits of a pipeline started at
+// `started`; returns it with the iteration at which it ends, or why it could
+// not start
+fn start_stage<'a>(
+    config: &Config,
+    options: &'a RunOptions,
+    stages: &[Stage],
+    k: usize,
+    from: usize,
+    done: &[Outcome],
+    started: Instant,
+) -> Result<(Box<dyn Optimizer + 'a>, usize), String> {
+    let stage = &stages[k];
+    let end = if k + 1 == stages.len() {
+        config.iterations
+    } else {
+        let share: f64 = stages[..=k].iter().map(|s| s.fraction).sum();
+        ((share * config.iterations as f64).round() as usize).clamp(from, config.iterations)
+    };
+
+    let mut stage_config = config.clone();
+    stage_config.algorithm = stage.algorithm.clone();
+    stage_config.iterations = end - from;
+    stage_config.seed = config.seed.map(|seed| seed.wrapping_add(k as u64));
+    stage_config.quiet = true;
+    if stage.stagnation.is_some() {
+        stage_config.stop.stagnation = stage.stagnation.clone();
+    }
+    let spent: usize = done.iter().map(|o| o.evaluations).sum();
+    stage_config.stop.max_evaluations = config
+        .stop
+        .max_evaluations
+        .map(|limit| limit.saturating_sub(spent).max(1));
+    stage_config.stop.max_seconds = config
+        .stop
+        .max_seconds
+        .map(|limit| (limit - started.elapsed().as_secs_f64()).max(f64::MIN_POSITIVE));
+
+    let previous = done.last();
+
+    let start = previous.and_then(|outcome| match stage.handoff {
+        Handoff::Fresh => None,
+        Handoff::Best => Some(vec![outcome.best_x.clone()]),
+        Handoff::Elite => Some(outcome.elite.clone()),
+    });
+    Ok((
+        optimizer::create_from(stage_config, options, start.as_deref())?,
+        end,
+    ))
+}
+
+impl Optimizer for Pipeline<'_> {
+    fn step(&mut self) {
+        self.running_mut().step();
+        self.iteration += 1;
+        // the running stage's state, on the pipeline's iteration and evaluation counts
+        let stage = self.running().progress();
+        let point = TracePoint {
+            iteration: self.iteration - 1,
+            evaluations: self.done.iter().map(|o| o.evaluations).sum::<usize>() + stage.evaluations,
+            best: self.config.reported(self.best()),
+            seconds: self.started.elapsed().as_secs_f64(),
+            ..stage
+        };
+        output::log_iteration(&point, &self.config, self.options, self.privacy);
+        if let Some(trace) = self.convergence.as_mut() {
+            trace.record(&point);
+        }
+        self.history.push(point);
+
+        let stage_done = self.running().is_finished() || self.iteration >= self.stage_end;
+        if stage_done && self.index + 1 < self.stages.len() && self.stopped.is_none() {
+            let finished = self.current.take().expect("a stage is running").finish();
+            let ends_pipeline = finished.stopped.filter(|&c| c != "stagnation");
+            self.absorb(&finished);
+            self.done.push(finished);
+            if ends_pipeline.is_some() {
+                self.stopped = ends_pipeline;
+                return;
+            }
+            self.index += 1;
+            let started = start_stage(
+                &self.config,
+                self.options,
+                &self.stages,
+                self.index,
+                self.iteration,
+                &self.done,
+                self.started,
+            );
+            // a stage that cannot start ends the pipeline with the stages so far
+            let (next, end) = match started {
+                Ok(started) => started,
+                Err(e) => {
+                    eprintln!("Warning: stage {} cannot start: {}", self.index + 1, e);
+                    self.index -= 1;
+                    self.stopped = Some("stage_failed");
+                    return;
+                }
+            };
+            self.current = Some(next);
+            self.stage_start = self.iteration;
+            self.stage_end = end;
+            let stage = &self.stages[self.index];
+            self.annotate(Event {
+                iteration: self.iteration,
+                evaluations: self.done.iter().map(|o| o.evaluations).sum(),
+                kind: EventKind::Phase,
+                name: "stage".to_string(),
+                detail: format!("stage {}: {}", self.index + 1, stage.algorithm.name()),
+            });
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.stopped.is_some()
+            || self.iteration >= self.config.iterations
+            || (self.index + 1 == self.stages.len() && self.running().is_finished())
+    }
+
+    fn stop(&mut self, reason: &'static str) {
+        self.stopped = Some(reason);
+        if let Some(current) = self.current.as_deref_mut() {
+            current.stop(reason);
+        }
+    }
+
+    fn progress(&self) -> TracePoint {
+        self.history.last().copied().unwrap_or_default()
+    }
+
+    fn best(&self) -> f64 {
+        let running = self.current.as_deref().map_or(f64::INFINITY, |c| c.best());
+        self.done.iter().map(|o| o.best).fold(running, f64::min)
+    }
+
+    fn finish(mut self: Box<Self>) -> Outcome {
+        if let Some(last) = self.current.take().map(|c| c.finish()) {
+            self.absorb(&last);
+            self.done.push(last);
+        }
+        let Pipeline {
+            config,
+            options,
+            stages,
+            done,
+            iteration,
+            stopped,
+            history,
+            events,
+            ..
+        } = *self;
+        let stopped = stopped.or_else(|| done.last().and_then(|o| o.stopped));
+
+        let evaluations = done.iter().map(|o| o.evaluations).sum();
+        let best = done
+            .iter()
+            .min_by(|a, b| a.best.total_cmp(&b.best))
+            .expect("a pipeline has a stage");
+        let outcome = Outcome {
+            best_x: best.best_x.clone(),
+            best: best.best,
+            evaluations,
+            elite: best.elite.clone(),
+            stopped,
+            history,
+            events,
+        };
+
+        if !options.batch && !config.quiet {
+            for (k, (stage, o)) in stages.iter().zip(&done).enumerate() {
+                println!(
+                    "Stage {} ({}): best {} after {} evaluations",
+                    k + 1,
+                    stage.algorithm.name(),
+                    output::format_value(config.reported(o.best), options.console_precision()),
+                    o.evaluations
+                );
+            }
+            if let Some(reason) = stopped {
+                println!(
+                    "Stopped early by {} after {} of {} iterations",
+                    reason, iteration, config.iterations
+                );
+            }
+            println!("Evaluations: {}", outcome.evaluations);
+            println!(
+                "Best solution found at: fitness = {}",
+                output::format_value(config.reported(outcome.best), options.console_precision())
+            );
+            for (i, xi) in outcome.best_x.iter().enumerate() {
+                println!("x{}: {}", i + 1, xi);
+            }
+        }
+        outcome
+    }
+}
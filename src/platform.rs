@@ -0,0 +1,43 @@
+// Desc: What differs between Unix (Linux, macOS) and Windows: console
+// signals and exit codes. File paths are built with std::path everywhere, so
+// they take the platform's separator.
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Exit code of a process ended by Ctrl-C: 128 + SIGINT on Unix,
+// STATUS_CONTROL_C_EXIT on Windows
+#[cfg(unix)]
+pub const INTERRUPTED_EXIT: i32 = 130;
+#[cfg(not(unix))]
+pub const INTERRUPTED_EXIT: i32 = 0xC000_013A_u32 as i32;
+
+// Ctrl-C: SIGINT on Unix, the console control handler on Windows
+pub fn on_interrupt(handler: impl FnMut() + Send + 'static) -> Result<(), String> {
+    ctrlc::set_handler(handler).map_err(|e| e.to_string())
+}
+
+static STATUS_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+// Status requests come from SIGUSR1 (`kill -USR1 <pid>`); Windows consoles
+// have no spare signal, so there the runs cannot be asked
+#[cfg(unix)]
+pub fn listen_for_status() -> Result<(), String> {
+    extern "C" fn request(_signal: libc::c_int) {
+        STATUS_REQUESTED.store(true, Ordering::Relaxed);
+    }
+    let handler = request as extern "C" fn(libc::c_int);
+    let previous = unsafe { libc::signal(libc::SIGUSR1, handler as libc::sighandler_t) };
+    if previous == libc::SIG_ERR {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn listen_for_status() -> Result<(), String> {
+    Ok(())
+}
+
+// Whether a status report was requested since the last call
+pub fn status_requested() -> bool {
+    STATUS_REQUESTED.swap(false, Ordering::Relaxed)
+}
@@ -0,0 +1,571 @@
+// Desc: PNG plots (cargo feature `plot`): the convergence curve of a run
+// (`--plot`) and, for 2-dimensional problems, one frame per iteration of the
+// swarm over the objective's contours (`--animate`)
+//
+// The images are palette PNGs drawn and encoded here, so the feature adds no
+// dependencies.
+
+#[cfg(feature = "plot")]
+mod enabled {
+    use std::path::Path;
+
+    use crate::config::Config;
+    use crate::convergence::TracePoint;
+    use crate::events::{Event, EventKind};
+
+    // Palette indices; 16 and up are the colour ramp of the contours
+    const WHITE: u8 = 0;
+    const BLACK: u8 = 1;
+    const GREY: u8 = 2;
+    const RED: u8 = 3;
+    const BLUE: u8 = 4;
+    const ORANGE: u8 = 5;
+    const LIGHT: u8 = 6;
+    const RAMP: u8 = 16;
+    const RAMP_LEVELS: usize = 24;
+    const CONTOURS: f64 = 12.0; // contour lines over the value range
+
+    fn palette() -> Vec<[u8; 3]> {
+        let mut colours = vec![[0, 0, 0]; RAMP as usize];
+        colours[WHITE as usize] = [255, 255, 255];
+        colours[GREY as usize] = [96, 96, 96];
+        colours[RED as usize] = [220, 30, 30];
+        colours[BLUE as usize] = [31, 119, 180];
+        colours[ORANGE as usize] = [255, 127, 14];
+        colours[LIGHT as usize] = [225, 225, 225];
+        // dark purple through teal to yellow, low values first
+        let stops = [
+            [68.0, 1.0, 84.0],
+            [33.0, 145.0, 140.0],
+            [253.0, 231.0, 37.0],
+        ];
+        for level in 0..RAMP_LEVELS {
+            let t = level as f64 / (RAMP_LEVELS - 1) as f64 * 2.0;
+            let (from, to) = if t < 1.0 {
+                (stops[0], stops[1])
+            } else {
+                (stops[1], stops[2])
+            };
+            let f = if t < 1.0 { t } else { t - 1.0 };
+            colours.push(std::array::from_fn(|c| {
+                (from[c] + (to[c] - from[c]) * f).round() as u8
+            }));
+        }
+        colours
+    }
+
+    // 3x5 glyphs of the axis labels, one row of three bits per line
+    fn glyph(c: char) -> Option<[u8; 5]> {
+        Some(match c {
+            '0' => [7, 5, 5, 5, 7],
+            '1' => [2, 6, 2, 2, 7],
+            '2' => [7, 1, 7, 4, 7],
+            '3' => [7, 1, 7, 1, 7],
+            '4' => [5, 5, 7, 1, 1],
+            '5' => [7, 4, 7, 1, 7],
+            '6' => [7, 4, 7, 5, 7],
+            '7' => [7, 1, 1, 1, 1],
+            '8' => [7, 5, 7, 5, 7],
+            '9' => [7, 5, 7, 1, 7],
+            '.' => [0, 0, 0, 0, 2],
+            '-' => [0, 0, 7, 0, 0],
+            'e' => [2, 5, 7, 4, 3],
+            _ => return None,
+        })
+    }
+
+    struct Canvas {
+        width: usize,
+        height: usize,
+        pixels: Vec<u8>, // palette indices, row by row from the top
+    }
+
+    impl Canvas {
+        fn new(width: usize, height: usize, background: u8) -> Self {
+            Canvas {
+                width,
+                height,
+                pixels: vec![background; width * height],
+            }
+        }
+
+        fn set(&mut self, x: i64, y: i64, colour: u8) {
+            if x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height {
+                self.pixels[y as usize * self.width + x as usize] = colour;
+            }
+        }
+
+        fn line(&mut self, from: (f64, f64), to: (f64, f64), colour: u8) {
+            let steps = (to.0 - from.0)
+                .abs()
+                .max((to.1 - from.1).abs())
+                .ceil()
+                .max(1.0);
+            for s in 0..=steps as usize {
+                let t = s as f64 / steps;
+                let x = from.0 + (to.0 - from.0) * t;
+                let y = from.1 + (to.1 - from.1) * t;
+                self.set(x.round() as i64, y.round() as i64, colour);
+            }
+        }
+
+        fn dot(&mut self, (x, y): (f64, f64), radius: i64, colour: u8) {
+            let (cx, cy) = (x.round() as i64, y.round() as i64);
+            for dy in -radius..=radius {
+                for dx in -radius..=radius {
+                    if dx * dx + dy * dy <= radius * radius {
+                        self.set(cx + dx, cy + dy, colour);
+                    }
+                }
+            }
+        }
+
+        // Text at twice the glyph size, its top left corner at (x, y)
+        fn text(&mut self, x: i64, y: i64, text: &str, colour: u8) {
+            for (k, rows) in text.chars().filter_map(glyph).enumerate() {
+                for (row, bits) in rows.iter().enumerate() {
+                    for col in 0..3 {
+                        if bits & (4 >> col) != 0 {
+                            let px = x + k as i64 * 8 + col * 2;
+                            let py = y + row as i64 * 2;
+                            for (ox, oy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                                self.set(px + ox, py + oy, colour);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        fn save(&self, path: &Path) -> Result<(), String> {
+            std::fs::write(
+                path,
+                encode_png(self.width, self.height, &self.pixels, &palette()),
+            )
+            .map_err(|e| format!("cannot write plot {}: {}", path.display(), e))
+        }
+    }
+
+    const PLOT_WIDTH: usize = 640;
+    const PLOT_HEIGHT: usize = 400;
+    const LEFT: f64 = 70.0; // room for the value labels
+    const BOTTOM: f64 = 30.0;
+    const MARGIN: f64 = 12.0;
+
+    // gbest (blue) and mean fitness (orange) against the iteration, on a log
+    // scale when every value is positive. Restarts and escapes are marked by
+    // red vertical lines, stops and phase switches by grey ones; topology
+    // redraws and local searches, which can follow every iteration, are not
+    // marked.
+    pub fn convergence(path: &str, history: &[TracePoint], events: &[Event]) -> Result<(), String> {
+        if history.is_empty() {
+            return Err(format!("cannot plot {}: the run has no iterations", path));
+        }
+        let series: [(Vec<f64>, u8); 2] = [
+            (history.iter().map(|p| p.best).collect(), BLUE),
+            (history.iter().map(|p| p.mean_fitness).collect(), ORANGE),
+        ];
+        let finite: Vec<f64> = series
+            .iter()
+            .flat_map(|(values, _)| values.iter().copied())
+            .filter(|v| v.is_finite())
+            .collect();
+        let log = finite.iter().all(|&v| v > 0.0);
+        let scale = |v: f64| if log { v.log10() } else { v };
+        let (mut low, mut high) = finite
+            .iter()
+            .map(|&v| scale(v))
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+                (lo.min(v), hi.max(v))
+            });
+        if !low.is_finite() {
+            (low, high) = (0.0, 1.0);
+        }
+        if high - low < 1e-12 {
+            (low, high) = (low - 0.5, high + 0.5);
+        }
+
+        let mut canvas = Canvas::new(PLOT_WIDTH, PLOT_HEIGHT, WHITE);
+        let (x0, x1) = (LEFT, PLOT_WIDTH as f64 - MARGIN);
+        let (y0, y1) = (PLOT_HEIGHT as f64 - BOTTOM, MARGIN);
+        let last = history
+            .last()
+            .expect("history is not empty")
+            .iteration
+            .max(1) as f64;
+        let px = |iteration: usize| x0 + (x1 - x0) * iteration as f64 / last;
+        let py = |v: f64| y0 + (y1 - y0) * (v - low) / (high - low);
+
+        // value ticks: the decades on a log scale, five even steps otherwise
+        let ticks: Vec<f64> = if log {
+            let step = ((high.floor() - low.ceil()) / 6.0).ceil().max(1.0);
+            let mut ticks = Vec::new();
+            let mut tick = low.ceil();
+            while tick <= high {
+                ticks.push(tick);
+                tick += step;
+            }
+            ticks
+        } else {
+            (0..=4)
+                .map(|k| low + (high - low) * k as f64 / 4.0)
+                .collect()
+        };
+        for &tick in &ticks {
+            let y = py(tick);
+            canvas.line((x0, y), (x1, y), LIGHT);
+            let label = if log {
+                format!("1e{}", tick)
+            } else {
+                format!("{:.2e}", tick)
+            };
+            canvas.text(4, y.round() as i64 - 5, &label, BLACK);
+        }
+        for k in 0..=4 {
+            let iteration = (last * k as f64 / 4.0).round() as usize;
+            let x = px(iteration);
+            canvas.line((x, y0), (x, y1), LIGHT);
+            let label = iteration.to_string();
+            let width = label.len() as i64 * 8;
+            canvas.text(x.round() as i64 - width / 2, y0 as i64 + 8, &label, BLACK);
+        }
+        for event in events {
+            let colour = match event.kind {
+                EventKind::Restart | EventKind::Escape => RED,
+                EventKind::Stop | EventKind::Phase => GREY,
+                EventKind::Topology | EventKind::Refine => continue,
+            };
+            let x = px(event.iteration);
+            canvas.line((x, y0), (x, y1), colour);
+        }
+        canvas.line((x0, y0), (x1, y0), BLACK);
+        canvas.line((x0, y0), (x0, y1), BLACK);
+
+        for (values, colour) in &series {
+            let mut previous: Option<(f64, f64)> = None;
+            for (point, &v) in history.iter().zip(values) {
+                if !v.is_finite() {
+                    previous = None;
+                    continue;
+                }
+                let at = (px(point.iteration), py(scale(v)));
+                canvas.line(previous.unwrap_or(at), at, *colour);
+                previous = Some(at);
+            }
+        }
+        canvas.save(Path::new(path))
+    }
+
+    const FRAME_SIZE: usize = 400;
+
+    // Frames of the swarm over the objective on the first two dimensions; the
+    // contours are computed once
+    pub struct Animation {
+        dir: String,
+        backdrop: Canvas,
+        bounds: [(f64, f64); 2],
+    }
+
+    impl Animation {
+        pub fn new(dir: &str, config: &Config) -> Result<Self, String> {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("cannot create animation directory {}: {}", dir, e))?;
+            let bounds = [config.bounds_of(0), config.bounds_of(1)];
+            let at = |pixel: usize, (lower, upper): (f64, f64)| {
+                lower + (upper - lower) * (pixel as f64 + 0.5) / FRAME_SIZE as f64
+            };
+            let mut values = Vec::with_capacity(FRAME_SIZE * FRAME_SIZE);
+            for row in 0..FRAME_SIZE {
+                // the top row is the upper bound of the second dimension
+                let y = at(FRAME_SIZE - 1 - row, bounds[1]);
+                for col in 0..FRAME_SIZE {
+                    values.push(config.objective(&[at(col, bounds[0]), y]));
+                }
+            }
+
+            // log-scaled above the lowest value, so the basins near the
+            // minimum get most of the colours
+            let finite = values.iter().copied().filter(|v| v.is_finite());
+            let lowest = finite.clone().fold(f64::INFINITY, f64::min);
+            let highest = finite.fold(f64::NEG_INFINITY, f64::max);
+            let span = (highest - lowest).ln_1p().max(f64::MIN_POSITIVE);
+            let levels: Vec<f64> = values
+                .iter()
+                .map(|&v| {
+                    if v.is_finite() {
+                        (v - lowest).ln_1p() / span
+                    } else {
+                        1.0
+                    }
+                })
+                .collect();
+
+            let mut backdrop = Canvas::new(FRAME_SIZE, FRAME_SIZE, WHITE);
+            let band = |t: f64| (t * CONTOURS).floor();
+            for row in 0..FRAME_SIZE {
+                for col in 0..FRAME_SIZE {
+                    let t = levels[row * FRAME_SIZE + col];
+                    let right = levels[row * FRAME_SIZE + (col + 1).min(FRAME_SIZE - 1)];
+                    let below = levels[(row + 1).min(FRAME_SIZE - 1) * FRAME_SIZE + col];
+                    let colour = if band(t) != band(right) || band(t) != band(below) {
+                        GREY
+                    } else {
+                        RAMP + (t * (RAMP_LEVELS - 1) as f64).round() as u8
+                    };
+                    backdrop.pixels[row * FRAME_SIZE + col] = colour;
+                }
+            }
+            Ok(Animation {
+                dir: dir.to_string(),
+                backdrop,
+                bounds,
+            })
+        }
+
+        fn pixel(&self, x: &[f64]) -> (f64, f64) {
+            let coordinate = |v: f64, (lower, upper): (f64, f64)| {
+                (v - lower) / (upper - lower) * (FRAME_SIZE - 1) as f64
+            };
+            (
+                coordinate(x[0], self.bounds[0]),
+                (FRAME_SIZE - 1) as f64 - coordinate(x[1], self.bounds[1]),
+            )
+        }
+
+        // `frame_00042.png` for iteration 42: the particles in white, the
+        // global best in red
+        pub fn frame(
+            &self,
+            iteration: usize,
+            positions: &[&[f64]],
+            gbest: &[f64],
+        ) -> Result<(), String> {
+            let mut canvas = Canvas {
+                pixels: self.backdrop.pixels.clone(),
+                ..self.backdrop
+            };
+            for x in positions {
+                canvas.dot(self.pixel(x), 3, BLACK);
+                canvas.dot(self.pixel(x), 2, WHITE);
+            }
+            canvas.dot(self.pixel(gbest), 4, RED);
+            let name = format!("frame_{:05}.png", iteration);
+            canvas.save(&Path::new(&self.dir).join(name))
+        }
+    }
+
+    // PNG with an 8-bit palette; the image data is deflated with the fixed
+    // Huffman codes, repeating only runs and the row above, which is what
+    // plots are made of
+    fn encode_png(width: usize, height: usize, pixels: &[u8], palette: &[[u8; 3]]) -> Vec<u8> {
+        let mut raw = Vec::with_capacity((width + 1) * height);
+        for row in pixels.chunks(width) {
+            raw.push(0); // no filter
+            raw.extend_from_slice(row);
+        }
+
+        let mut header = Vec::new();
+        header.extend_from_slice(&(width as u32).to_be_bytes());
+        header.extend_from_slice(&(height as u32).to_be_bytes());
+        header.extend_from_slice(&[8, 3, 0, 0, 0]); // 8 bits, palette, no interlace
+
+        let mut zlib = vec![0x78, 0x01];
+        zlib.extend(deflate(&raw, width + 1));
+        zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+        let mut png = vec![0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+        chunk(&mut png, b"IHDR", &header);
+        chunk(&mut png, b"PLTE", &palette.concat());
+        chunk(&mut png, b"IDAT", &zlib);
+        chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+        png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        let start = png.len();
+        png.extend_from_slice(kind);
+        png.extend_from_slice(data);
+        let crc = crc32(&png[start..]);
+        png.extend_from_slice(&crc.to_be_bytes());
+    }
+
+    fn crc32(bytes: &[u8]) -> u32 {
+        let mut crc = !0u32;
+        for &byte in bytes {
+            crc ^= byte as u32;
+            for _ in 0..8 {
+                crc = if crc & 1 != 0 {
+                    (crc >> 1) ^ 0xedb8_8320
+                } else {
+                    crc >> 1
+                };
+            }
+        }
+        !crc
+    }
+
+    fn adler32(bytes: &[u8]) -> u32 {
+        let (mut a, mut b) = (1u32, 0u32);
+        for chunk in bytes.chunks(5552) {
+            for &byte in chunk {
+                a += byte as u32;
+                b += a;
+            }
+            a %= 65521;
+            b %= 65521;
+        }
+        (b << 16) | a
+    }
+
+    const LENGTH_BASE: [usize; 29] = [
+        3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115,
+        131, 163, 195, 227, 258,
+    ];
+    const LENGTH_EXTRA: [u32; 29] = [
+        0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+    ];
+    const DISTANCE_BASE: [usize; 30] = [
+        1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+        2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+    ];
+    const DISTANCE_EXTRA: [u32; 30] = [
+        0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12,
+        13, 13,
+    ];
+    const MAX_MATCH: usize = 258;
+
+    // Bits are packed from the least significant end; Huffman codes go in
+    // most significant bit first
+    struct Bits {
+        out: Vec<u8>,
+        acc: u64,
+        count: u32,
+    }
+
+    impl Bits {
+        fn put(&mut self, value: u32, count: u32) {
+            self.acc |= (value as u64) << self.count;
+            self.count += count;
+            while self.count >= 8 {
+                self.out.push(self.acc as u8);
+                self.acc >>= 8;
+                self.count -= 8;
+            }
+        }
+
+        fn code(&mut self, code: u32, length: u32) {
+            self.put(code.reverse_bits() >> (32 - length), length);
+        }
+
+        fn symbol(&mut self, symbol: usize) {
+            let s = symbol as u32;
+            match symbol {
+                0..=143 => self.code(0x30 + s, 8),
+                144..=255 => self.code(0x190 + s - 144, 9),
+                256..=279 => self.code(s - 256, 7),
+                _ => self.code(0xc0 + s - 280, 8),
+            }
+        }
+    }
+
+    fn deflate(data: &[u8], stride: usize) -> Vec<u8> {
+        let mut bits = Bits {
+            out: Vec::new(),
+            acc: 0,
+            count: 0,
+        };
+        bits.put(0b011, 3); // the final block, fixed codes
+        let mut i = 0;
+        while i < data.len() {
+            let longest = MAX_MATCH.min(data.len() - i);
+            let (length, distance) = [1, stride]
+                .into_iter()
+                .filter(|&d| d <= i)
+                .map(|d| {
+                    let n = (0..longest)
+                        .take_while(|&k| data[i + k] == data[i + k - d])
+                        .count();
+                    (n, d)
+                })
+                .max()
+                .unwrap_or((0, 0));
+            if length < 3 {
+                bits.symbol(data[i] as usize);
+                i += 1;
+                continue;
+            }
+            let code = LENGTH_BASE
+                .iter()
+                .rposition(|&b| b <= length)
+                .expect("length >= 3");
+            bits.symbol(257 + code);
+            bits.put((length - LENGTH_BASE[code]) as u32, LENGTH_EXTRA[code]);
+            let code = DISTANCE_BASE
+                .iter()
+                .rposition(|&b| b <= distance)
+                .expect("distance >= 1");
+            bits.code(code as u32, 5);
+            bits.put(
+                (distance - DISTANCE_BASE[code]) as u32,
+                DISTANCE_EXTRA[code],
+            );
+            i += length;
+        }
+        bits.symbol(256); // end of block
+        bits.put(0, 7); // pad to a whole byte
+        bits.out
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn checksums_match_the_reference_values() {
+            assert_eq!(crc32(b"IEND"), 0xae42_6082);
+            assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+        }
+    }
+}
+
+#[cfg(feature = "plot")]
+pub use enabled::{convergence, Animation};
+
+// Without the feature nothing can be plotted; the flags are refused up front
+#[cfg(not(feature = "plot"))]
+pub enum Animation {}
+
+#[cfg(not(feature = "plot"))]
+fn unavailable(path: &str) -> String {
+    format!(
+        "cannot plot {}: built without the `plot` feature \
+         (rebuild with `cargo build --features plot`)",
+        path
+    )
+}
+
+#[cfg(not(feature = "plot"))]
+pub fn convergence(
+    path: &str,
+    _history: &[crate::convergence::TracePoint],
+    _events: &[crate::events::Event],
+) -> Result<(), String> {
+    Err(unavailable(path))
+}
+
+#[cfg(not(feature = "plot"))]
+impl Animation {
+    pub fn new(dir: &str, _config: &crate::config::Config) -> Result<Self, String> {
+        Err(unavailable(dir))
+    }
+
+    pub fn frame(
+        &self,
+        _iteration: usize,
+        _positions: &[&[f64]],
+        _gbest: &[f64],
+    ) -> Result<(), String> {
+        match *self {}
+    }
+}
@@ -0,0 +1,480 @@
+// Desc: Plugins (cargo feature `plugins`, Unix): objectives and optimizers
+// from shared libraries loaded at runtime, so they can ship without a fork
+// of the crate. A library exports one C function,
+//
+//   uint32_t pso_plugin_register(const pso_registrar *registrar);
+//
+// which calls `registrar->objective` and `registrar->optimizer` once per
+// thing it provides and returns the ABI version it was built for (1):
+//
+//   typedef double (*pso_objective)(void *state, const double *x, size_t n);
+//   typedef struct {
+//       void *(*create)(void *state, size_t dimensions, const double *lower,
+//                       const double *upper, size_t population, uint64_t seed);
+//       void (*ask)(void *run, double *points);        /* population x dimensions */
+//       void (*tell)(void *run, const double *fitness); /* population, minimized */
+//       void (*destroy)(void *run);
+//   } pso_optimizer;
+//   typedef struct {
+//       void *context;
+//       void (*objective)(void *context, const char *name, pso_objective f, void *state);
+//       void (*optimizer)(void *context, const char *name, const pso_optimizer *o, void *state);
+//   } pso_registrar;
+//
+// Objectives must be callable from several threads at once (--threads and
+// --runs evaluate in parallel); each optimizer run is used by one thread.
+// A library is named by its path, or by its file name (with or without the
+// `lib` prefix and extension) within the plugin directories: those listed
+// in PSO_RASTRIGIN_PLUGINS (separated like PATH) and `./plugins`. Loaded
+// libraries stay loaded until the process exits.
+use std::path::{Path, PathBuf};
+
+use rand::Rng;
+
+use crate::config::Config;
+use crate::convergence::TracePoint;
+use crate::optimizer::{self, Optimizer, Outcome, Tracker};
+use crate::options::RunOptions;
+use crate::sampling;
+
+pub const DIRECTORIES: &str = "PSO_RASTRIGIN_PLUGINS";
+
+#[cfg(all(feature = "plugins", unix))]
+mod enabled {
+    use std::ffi::{c_char, c_void, CStr, CString};
+    use std::sync::{Arc, Mutex};
+
+    const ABI_VERSION: u32 = 1;
+
+    type ObjectiveFn = extern "C" fn(*mut c_void, *const f64, usize) -> f64;
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct OptimizerTable {
+        create:
+            extern "C" fn(*mut c_void, usize, *const f64, *const f64, usize, u64) -> *mut c_void,
+        ask: extern "C" fn(*mut c_void, *mut f64),
+        tell: extern "C" fn(*mut c_void, *const f64),
+        destroy: extern "C" fn(*mut c_void),
+    }
+
+    #[repr(C)]
+    struct Registrar {
+        context: *mut c_void,
+        objective: extern "C" fn(*mut c_void, *const c_char, ObjectiveFn, *mut c_void),
+        optimizer: extern "C" fn(*mut c_void, *const c_char, *const OptimizerTable, *mut c_void),
+    }
+
+    // What one library registered
+    #[derive(Default)]
+    struct Library {
+        path: String,
+        objectives: Vec<(String, ObjectiveFn, State)>,
+        optimizers: Vec<(String, OptimizerTable, State)>,
+    }
+
+    // A plugin's own pointer, handed back on every call
+    #[derive(Clone, Copy)]
+    struct State(*mut c_void);
+
+    // The plugin contract makes the registered functions callable from any
+    // thread, with their state
+    unsafe impl Send for State {}
+    unsafe impl Sync for State {}
+
+    static LOADED: Mutex<Vec<Arc<Library>>> = Mutex::new(Vec::new());
+
+    fn name_of(name: *const c_char) -> String {
+        if name.is_null() {
+            return String::new();
+        }
+        // SAFETY: the plugin passes a NUL-terminated string
+        unsafe { CStr::from_ptr(name) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    extern "C" fn add_objective(
+        context: *mut c_void,
+        name: *const c_char,
+        f: ObjectiveFn,
+        state: *mut c_void,
+    ) {
+        // SAFETY: `context` is the library being registered, see `load`
+        let library = unsafe { &mut *(context as *mut Library) };
+        library.objectives.push((name_of(name), f, State(state)));
+    }
+
+    extern "C" fn add_optimizer(
+        context: *mut c_void,
+        name: *const c_char,
+        table: *const OptimizerTable,
+        state: *mut c_void,
+    ) {
+        // SAFETY: as above; the table is read before the call returns
+        let library = unsafe { &mut *(context as *mut Library) };
+        if let Some(table) = unsafe { table.as_ref() } {
+            library
+                .optimizers
+                .push((name_of(name), *table, State(state)));
+        }
+    }
+
+    fn load(path: &str) -> Result<Arc<Library>, String> {
+        let mut loaded = LOADED.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(library) = loaded.iter().find(|l| l.path == path) {
+            return Ok(library.clone());
+        }
+        let failure = |what: &str| {
+            // SAFETY: dlerror returns NULL or a NUL-terminated message
+            let error = unsafe { libc::dlerror() };
+            let detail = if error.is_null() {
+                String::new()
+            } else {
+                format!(": {}", name_of(error))
+            };
+            format!("cannot load plugin {}: {}{}", path, what, detail)
+        };
+        let c_path = CString::new(path).map_err(|_| format!("invalid plugin path {}", path))?;
+        // SAFETY: loading runs the library's initializers, which the user trusts
+        let handle = unsafe { libc::dlopen(c_path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+        if handle.is_null() {
+            return Err(failure("dlopen failed"));
+        }
+        let symbol = unsafe { libc::dlsym(handle, c"pso_plugin_register".as_ptr()) };
+        if symbol.is_null() {
+            return Err(failure("no pso_plugin_register function"));
+        }
+        // SAFETY: the exported symbol has the documented signature
+        let register: extern "C" fn(*const Registrar) -> u32 =
+            unsafe { std::mem::transmute(symbol) };
+
+        let mut library = Library {
+            path: path.to_string(),
+            ..Library::default()
+        };
+        let registrar = Registrar {
+            context: &mut library as *mut Library as *mut c_void,
+            objective: add_objective,
+            optimizer: add_optimizer,
+        };
+        let version = register(&registrar);
+        if version != ABI_VERSION {
+            return Err(format!(
+                "plugin {} is built for plugin ABI {}, this build speaks {}",
+                path, version, ABI_VERSION
+            ));
+        }
+        let library = Arc::new(library);
+        loaded.push(library.clone());
+        Ok(library)
+    }
+
+    // The objectives and optimizers a library registers
+    pub fn registrations(path: &str) -> Result<(Vec<String>, Vec<String>), String> {
+        let library = load(path)?;
+        Ok((
+            library.objectives.iter().map(|(n, ..)| n.clone()).collect(),
+            library.optimizers.iter().map(|(n, ..)| n.clone()).collect(),
+        ))
+    }
+
+    #[derive(Clone)]
+    pub struct PluginObjective {
+        f: ObjectiveFn,
+        state: State,
+    }
+
+    impl PluginObjective {
+        pub fn load(path: &str, name: &str) -> Result<PluginObjective, String> {
+            let library = load(path)?;
+            let (_, f, state) = library
+                .objectives
+                .iter()
+                .find(|(n, ..)| n == name)
+                .ok_or_else(|| format!("plugin {} has no objective {}", path, name))?;
+            Ok(PluginObjective {
+                f: *f,
+                state: *state,
+            })
+        }
+
+        pub fn evaluate(&self, x: &[f64]) -> f64 {
+            (self.f)(self.state.0, x.as_ptr(), x.len())
+        }
+    }
+
+    #[derive(Clone)]
+    pub struct PluginOptimizer {
+        table: OptimizerTable,
+        state: State,
+    }
+
+    impl PluginOptimizer {
+        pub fn load(path: &str, name: &str) -> Result<PluginOptimizer, String> {
+            let library = load(path)?;
+            let (_, table, state) = library
+                .optimizers
+                .iter()
+                .find(|(n, ..)| n == name)
+                .ok_or_else(|| format!("plugin {} has no optimizer {}", path, name))?;
+            Ok(PluginOptimizer {
+                table: *table,
+                state: *state,
+            })
+        }
+
+        pub fn start(
+            &self,
+            lower: &[f64],
+            upper: &[f64],
+            population: usize,
+            seed: u64,
+        ) -> Result<PluginRun, String> {
+            let run = (self.table.create)(
+                self.state.0,
+                lower.len(),
+                lower.as_ptr(),
+                upper.as_ptr(),
+                population,
+                seed,
+            );
+            if run.is_null() {
+                return Err("the plugin optimizer could not start a run".to_string());
+            }
+            Ok(PluginRun {
+                table: self.table,
+                run,
+                size: lower.len() * population,
+                population,
+            })
+        }
+    }
+
+    // One run of a plugin optimizer, destroyed with it
+    pub struct PluginRun {
+        table: OptimizerTable,
+        run: *mut c_void,
+        size: usize, // population * dimensions
+        population: usize,
+    }
+
+    impl PluginRun {
+        // The population to evaluate next, flattened point by point
+        pub fn ask(&mut self) -> Vec<f64> {
+            let mut points = vec![0.0; self.size];
+            (self.table.ask)(self.run, points.as_mut_ptr());
+            points
+        }
+
+        // The minimized fitness of each point of the last `ask`
+        pub fn tell(&mut self, fitness: &[f64]) {
+            debug_assert_eq!(fitness.len(), self.population);
+            (self.table.tell)(self.run, fitness.as_ptr());
+        }
+    }
+
+    impl Drop for PluginRun {
+        fn drop(&mut self) {
+            (self.table.destroy)(self.run);
+        }
+    }
+}
+
+#[cfg(all(feature = "plugins", unix))]
+pub use enabled::{registrations, PluginObjective, PluginOptimizer, PluginRun};
+
+// Without the feature nothing can be loaded, so the types have no values
+#[cfg(not(all(feature = "plugins", unix)))]
+mod disabled {
+    fn unavailable(path: &str) -> String {
+        format!(
+            "cannot load plugin {}: built without the `plugins` feature \
+             (rebuild with `cargo build --features plugins`, on Unix)",
+            path
+        )
+    }
+
+    pub fn registrations(path: &str) -> Result<(Vec<String>, Vec<String>), String> {
+        Err(unavailable(path))
+    }
+
+    #[derive(Clone)]
+    pub enum PluginObjective {}
+
+    impl PluginObjective {
+        pub fn load(path: &str, _name: &str) -> Result<PluginObjective, String> {
+            Err(unavailable(path))
+        }
+
+        pub fn evaluate(&self, _x: &[f64]) -> f64 {
+            match *self {}
+        }
+    }
+
+    #[derive(Clone)]
+    pub enum PluginOptimizer {}
+
+    impl PluginOptimizer {
+        pub fn load(path: &str, _name: &str) -> Result<PluginOptimizer, String> {
+            Err(unavailable(path))
+        }
+
+        pub fn start(
+            &self,
+            _lower: &[f64],
+            _upper: &[f64],
+            _population: usize,
+            _seed: u64,
+        ) -> Result<PluginRun, String> {
+            match *self {}
+        }
+    }
+
+    pub enum PluginRun {}
+
+    impl PluginRun {
+        pub fn ask(&mut self) -> Vec<f64> {
+            match *self {}
+        }
+
+        pub fn tell(&mut self, _fitness: &[f64]) {
+            match *self {}
+        }
+    }
+}
+
+#[cfg(not(all(feature = "plugins", unix)))]
+pub use disabled::{registrations, PluginObjective, PluginOptimizer, PluginRun};
+
+// The plugin directories, in search order
+fn directories() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = std::env::var_os(DIRECTORIES)
+        .map(|paths| std::env::split_paths(&paths).collect())
+        .unwrap_or_default();
+    dirs.push(PathBuf::from("plugins"));
+    dirs
+}
+
+fn is_library(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .is_some_and(|e| e == "so" || e == "dylib" || e == "dll")
+}
+
+// Every shared library in the plugin directories
+pub fn discover() -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    for dir in directories() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut libraries: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| is_library(p))
+            .collect();
+        libraries.sort();
+        found.extend(libraries);
+    }
+    found
+}
+
+// The file of `library`: a path as given, or else the first library in the
+// plugin directories with that name
+pub fn resolve(library: &str) -> Result<String, String> {
+    if library.contains(std::path::MAIN_SEPARATOR) || Path::new(library).is_file() {
+        return Ok(library.to_string());
+    }
+    discover()
+        .into_iter()
+        .find(|path| {
+            let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+            stem == library || stem.strip_prefix("lib") == Some(library)
+        })
+        .map(|path| path.to_string_lossy().into_owned())
+        .ok_or_else(|| {
+            format!(
+                "plugin {} not found in the plugin directories ({} and ./plugins)",
+                library, DIRECTORIES
+            )
+        })
+}
+
+// The `plugin` algorithm: a plugin optimizer asked for a population every
+// iteration and told its fitness. Candidates are clamped into the bounds and
+// snapped before they are evaluated.
+pub struct External<'a> {
+    tracker: Tracker<'a>,
+    run: PluginRun,
+    population: usize,
+}
+
+impl<'a> External<'a> {
+    // without the feature no optimizer loads, so nothing after `start` runs
+    #[cfg_attr(
+        not(all(feature = "plugins", unix)),
+        allow(unused_variables, unreachable_code)
+    )]
+    pub fn new(
+        config: Config,
+        options: &'a RunOptions,
+        library: &str,
+        name: &str,
+    ) -> Result<Self, String> {
+        // validated with the config, so a failure here is the plugin's
+        let started = resolve(library)
+            .and_then(|path| PluginOptimizer::load(&path, name))
+            .and_then(|optimizer| {
+                let (lower, upper): (Vec<f64>, Vec<f64>) = (0..config.dimensions)
+                    .map(|pos| config.bounds_of(pos))
+                    .unzip();
+                let seed = optimizer::seeded_rng::<rand::rngs::StdRng>(&config).gen();
+                optimizer.start(&lower, &upper, config.particles, seed)
+            });
+        Ok(External {
+            population: config.particles,
+            tracker: Tracker::new(config, options)?,
+            run: started?,
+        })
+    }
+}
+
+impl Optimizer for External<'_> {
+    fn step(&mut self) {
+        let points = self.run.ask();
+        let n = self.tracker.config.dimensions;
+        let mut fitness = Vec::with_capacity(self.population);
+        for (id, point) in points.chunks(n).take(self.population).enumerate() {
+            let config = &self.tracker.config;
+            let x: Vec<f64> = point
+                .iter()
+                .enumerate()
+                .map(|(pos, &xi)| sampling::confine(config, pos, xi))
+                .collect();
+            fitness.push(self.tracker.evaluate(id, &x).fitness);
+        }
+        self.run.tell(&fitness);
+        self.tracker.end_iteration();
+    }
+
+    fn is_finished(&self) -> bool {
+        self.tracker.is_finished()
+    }
+
+    fn stop(&mut self, reason: &'static str) {
+        self.tracker.stop(reason);
+    }
+
+    fn best(&self) -> f64 {
+        self.tracker.best()
+    }
+
+    fn progress(&self) -> TracePoint {
+        self.tracker.progress()
+    }
+
+    fn finish(self: Box<Self>) -> Outcome {
+        self.tracker.finish(&[])
+    }
+}
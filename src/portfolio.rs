@@ -0,0 +1,136 @@
+// Desc: Mean-variance portfolio objective (the `[portfolio]` table of a
+// problem file): the weights of the assets of a return history, scored by
+//
+//   risk_aversion * w'Σw - μ'w
+//
+// with μ the mean and Σ the sample covariance of the assets' returns per
+// period. The history is a CSV file with one column per asset (its name in
+// the header) and one row per period; the problem file adds the budget
+// constraint and, unless it declares them, one variable in [0, 1] per asset.
+use crate::expr::identifier;
+use crate::trajectory::Trajectory;
+
+#[derive(Clone, Debug)]
+pub struct Portfolio {
+    pub path: String,
+    pub name: String, // `portfolio (path)`, for the reports
+    pub assets: Vec<String>,
+    pub risk_aversion: f64,
+    mean: Vec<f64>,
+    covariance: Vec<Vec<f64>>,
+}
+
+impl Portfolio {
+    pub fn load(path: &str, risk_aversion: f64) -> Result<Portfolio, String> {
+        if !(risk_aversion >= 0.0 && risk_aversion.is_finite()) {
+            return Err(format!(
+                "portfolio risk_aversion must not be negative, got {}",
+                risk_aversion
+            ));
+        }
+        let history = Trajectory::open(path)?;
+        let assets: Vec<String> = history
+            .header()
+            .unwrap_or_default()
+            .split(',')
+            .map(|name| identifier(name.trim()))
+            .collect();
+        if assets.iter().all(String::is_empty) {
+            return Err(format!("{} has no header of asset names", path));
+        }
+        let mut returns: Vec<Vec<f64>> = Vec::new();
+        let mut failure = None;
+        let mut line = 0;
+        history.rows(|fields| {
+            line += 1;
+            if line == 1 || failure.is_some() || fields.iter().all(|f| f.trim().is_empty()) {
+                return;
+            }
+            if fields.len() != assets.len() {
+                failure = Some(format!(
+                    "{} line {}: {} values for {} assets",
+                    path,
+                    line,
+                    fields.len(),
+                    assets.len()
+                ));
+                return;
+            }
+            match fields.iter().map(|f| f.trim().parse::<f64>()).collect() {
+                Ok(row) => returns.push(row),
+                Err(e) => failure = Some(format!("{} line {}: {}", path, line, e)),
+            }
+        })?;
+        if let Some(failure) = failure {
+            return Err(failure);
+        }
+        if returns.len() < 2 {
+            return Err(format!(
+                "{} needs at least 2 periods of returns, has {}",
+                path,
+                returns.len()
+            ));
+        }
+
+        let n = assets.len();
+        let periods = returns.len() as f64;
+        let mean: Vec<f64> = (0..n)
+            .map(|j| returns.iter().map(|r| r[j]).sum::<f64>() / periods)
+            .collect();
+        let covariance = (0..n)
+            .map(|j| {
+                (0..n)
+                    .map(|k| {
+                        returns
+                            .iter()
+                            .map(|r| (r[j] - mean[j]) * (r[k] - mean[k]))
+                            .sum::<f64>()
+                            / (periods - 1.0)
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok(Portfolio {
+            path: path.to_string(),
+            name: format!("portfolio ({})", path),
+            assets,
+            risk_aversion,
+            mean,
+            covariance,
+        })
+    }
+
+    pub fn evaluate(&self, w: &[f64]) -> f64 {
+        self.risk_aversion * self.variance(w) - self.expected_return(w)
+    }
+
+    pub fn expected_return(&self, w: &[f64]) -> f64 {
+        w.iter().zip(&self.mean).map(|(wi, mi)| wi * mi).sum()
+    }
+
+    pub fn variance(&self, w: &[f64]) -> f64 {
+        self.covariance
+            .iter()
+            .zip(w)
+            .map(|(row, wj)| wj * row.iter().zip(w).map(|(c, wk)| c * wk).sum::<f64>())
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mean_and_covariance_come_from_the_returns() {
+        let path = std::env::temp_dir().join("pso_portfolio_returns.csv");
+        std::fs::write(&path, "A,B-2\n0.1,0.0\n0.3,0.2\n").unwrap();
+        let portfolio = Portfolio::load(path.to_str().unwrap(), 2.0).unwrap();
+        assert_eq!(portfolio.assets, ["A", "B_2"]);
+        // μ = (0.2, 0.1), Σ = 0.02 in every entry
+        let w = [0.5, 0.5];
+        assert!((portfolio.expected_return(&w) - 0.15).abs() < 1e-12);
+        assert!((portfolio.variance(&w) - 0.02).abs() < 1e-12);
+        assert!((portfolio.evaluate(&w) - (2.0 * 0.02 - 0.15)).abs() < 1e-12);
+    }
+}
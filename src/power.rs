@@ -0,0 +1,67 @@
+// Desc: `power` subcommand: how many runs are needed to tell two configurations apart
+use crate::stats;
+use crate::trajectory::Trajectory;
+
+// Reads one result per line; with CSV lines the first field is used.
+// Blank lines and lines that do not start with a number (headers) are skipped.
+pub fn read_results(path: &str) -> Result<Vec<f64>, String> {
+    let mut values: Vec<f64> = Vec::new();
+    Trajectory::open(path)?.rows(|fields| {
+        if let Some(value) = fields.first().and_then(|f| f.trim().parse().ok()) {
+            values.push(value);
+        }
+    })?;
+    if values.len() < 2 {
+        return Err(format!("{} needs at least two pilot results", path));
+    }
+    Ok(values)
+}
+
+pub fn run(
+    baseline: &str,
+    candidate: &str,
+    alpha: f64,
+    power: f64,
+    effect_size: Option<f64>,
+) -> Result<(), String> {
+    let in_unit = |v: f64| v > 0.0 && v < 1.0;
+    if !in_unit(alpha) || !in_unit(power) {
+        return Err("alpha and power must be within (0, 1)".to_string());
+    }
+
+    let a = read_results(baseline)?;
+    let b = read_results(candidate)?;
+    let observed = stats::cohens_d(&a, &b);
+    let d = effect_size.unwrap_or(observed);
+    if !(d > 0.0 && d.is_finite()) {
+        return Err(format!(
+            "effect size must be positive and finite (observed d = {})",
+            observed
+        ));
+    }
+
+    println!("Pilot runs:");
+    println!(
+        "  {}: n = {}, mean = {}, std = {}",
+        baseline,
+        a.len(),
+        stats::mean(&a),
+        stats::std_dev(&a)
+    );
+    println!(
+        "  {}: n = {}, mean = {}, std = {}",
+        candidate,
+        b.len(),
+        stats::mean(&b),
+        stats::std_dev(&b)
+    );
+    println!("  Observed effect size (Cohen's d): {:.4}", observed);
+
+    let runs = stats::required_runs(d, alpha, power);
+    println!(
+        "\nTo detect d = {:.4} at alpha = {} with power {}: {} runs per configuration",
+        d, alpha, power, runs
+    );
+    println!("{{\"runs\": {}}}", runs);
+    Ok(())
+}
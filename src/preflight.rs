@@ -0,0 +1,61 @@
+// Desc: `--dry-run` pre-flight check of the configuration and the evaluation path
+use std::time::Instant;
+
+use crate::config::Config;
+use crate::sampling;
+use crate::throttle::Throttle;
+
+const SAMPLES: usize = 10; // random points evaluated by the check
+
+// Evaluates a few random points of the search space and prints the effective
+// configuration. Returns false when the objective produced NaN or infinity.
+pub fn run(config: &Config) -> bool {
+    let mut rng = rand::thread_rng();
+    let mut fitness = Vec::with_capacity(SAMPLES);
+    let throttle = config.max_evaluations_per_second.map(Throttle::shared);
+    let mut feasible = 0;
+
+    let start = Instant::now();
+    for _ in 0..SAMPLES {
+        let x: Vec<f64> = sampling::point(config, &mut rng);
+        if let Some(t) = &throttle {
+            t.wait();
+        }
+        fitness.push(config.objective(&x));
+        if config.constraint_violation(&x) == 0.0 {
+            feasible += 1;
+        }
+    }
+    let per_evaluation = start.elapsed().as_secs_f64() / SAMPLES as f64;
+
+    let non_finite = fitness.iter().filter(|f| !f.is_finite()).count();
+    let finite = fitness.iter().copied().filter(|f| f.is_finite());
+    let (min, max) = finite.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), f| {
+        (lo.min(f), hi.max(f))
+    });
+
+    println!("Dry run:");
+    println!("  Sample evaluations: {}", SAMPLES);
+    println!("  Time per evaluation: {:.3e} s", per_evaluation);
+    println!(
+        "  Estimated objective time for the run: {:.3} s",
+        per_evaluation * (config.particles * (config.iterations + 1)) as f64
+    );
+    if non_finite < SAMPLES {
+        println!("  Sample fitness range: [{}, {}]", min, max);
+    }
+    if config.problem.is_some() {
+        println!("  Feasible samples: {}/{}", feasible, SAMPLES);
+    }
+    if non_finite > 0 {
+        println!("  Non-finite results (NaN or infinity): {}", non_finite);
+    }
+
+    println!("\nEffective configuration:");
+    println!(
+        "{}",
+        serde_json::to_string_pretty(config).expect("Error serializing config")
+    );
+
+    non_finite == 0
+}
@@ -0,0 +1,35 @@
+// Desc: Laplace noise on logged values, so logs can be shared without exact objective details
+use rand::Rng;
+
+// Laplace mechanism with scale sensitivity / epsilon, applied independently
+// to every released value. Only written outputs pass through it; the
+// optimizer itself always works on the true values.
+#[derive(Clone, Copy)]
+pub struct Privacy {
+    scale: f64,
+}
+
+impl Privacy {
+    pub fn new(epsilon: f64, sensitivity: f64) -> Self {
+        Privacy {
+            scale: sensitivity / epsilon,
+        }
+    }
+
+    pub fn apply(&self, value: f64) -> f64 {
+        // inverse CDF of the Laplace distribution
+        let u: f64 = rand::thread_rng().gen_range(-0.5..0.5);
+        value - self.scale * u.signum() * (1.0 - 2.0 * u.abs()).ln()
+    }
+
+    pub fn apply_all(&self, x: &[f64]) -> Vec<f64> {
+        x.iter().map(|&xi| self.apply(xi)).collect()
+    }
+}
+
+// Noise for the run's logs, when the options ask for it
+pub fn from_options(options: &crate::options::RunOptions) -> Option<Privacy> {
+    options
+        .dp_epsilon
+        .map(|epsilon| Privacy::new(epsilon, options.dp_sensitivity))
+}
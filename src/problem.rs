@@ -0,0 +1,567 @@
+// Desc: Declarative problem definition (`--problem`): variables, bounds, types and constraints
+//
+// A problem file, TOML or JSON by extension, describes *what* is optimized
+// and leaves *how* to the config file:
+//
+//   objective = "rastrigin"
+//   lower_bound = -5.12            # shared default for the variables
+//   upper_bound = 5.12
+//   constraints = ["x + y <= 1", "n * x >= -2"]
+//
+//   [[variables]]
+//   name = "x"
+//
+//   [[variables]]
+//   name = "y"
+//   upper_bound = 2.0
+//
+//   [[variables]]
+//   name = "n"
+//   type = "integer"
+//   lower_bound = 0
+//   upper_bound = 10
+//
+//   [[variables]]
+//   name = "z"
+//   fixed = 0.5                    # frozen at this value, within its bounds
+//
+// Without `variables`, `dimensions` continuous variables x1..xn are created.
+// `objective` is a built-in function name or an expression over the
+// variables (see `expr`), e.g. "10*n + sum(x_i^2 - 10*cos(2*pi*x_i))";
+// `optimum` gives an expression's known minimum for `--log-error`.
+//
+// Instead of `objective`, a `[model]` table evaluates a trained ONNX model
+// (needs the `onnx` feature): `path`, the `output` element used as fitness
+// (default 0) and an optional `transform` expression of that value `y`,
+// e.g. "-y" to maximize the model's prediction.
+//
+// Or a `[command]` table runs an external program that computes the
+// objective (see `external`): `program`, its `args`, the most candidates sent
+// in one `batch` (default 16) and the `timeout` in seconds of a reply
+// (default 60).
+//
+// Or a `[plugin]` table takes an objective a shared library registers (see
+// `plugin`, needs the `plugins` feature): its `library` and `objective` name.
+//
+// Or a `[portfolio]` table weighs the assets of a return history (see
+// `portfolio`): the CSV file of `returns`, the `risk_aversion` (default 1)
+// and the `budget` the weights sum to (default 1), added as an equality
+// constraint. Without `variables` there is one per asset, named after it,
+// within `lower_bound` and `upper_bound` (default 0 and 1: long only).
+//
+// Or a `[fit]` table fits the variables as the parameters of a `model`
+// expression to the CSV file of `data` by least squares (see `fit`); the
+// model reads the other columns by name, `target` names the observed one
+// (default the last), e.g. model = "a*exp(-b*t) + c*exp(-d*t)".
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::config::Config;
+use crate::constraint::{self, ConstraintKind};
+use crate::expr::{self, Expr};
+use crate::external::ExternalObjective;
+use crate::fit::Fit;
+use crate::functions::Function;
+use crate::onnx::Model;
+use crate::plugin::{self, PluginObjective};
+use crate::portfolio::Portfolio;
+
+const EQUALITY_TOLERANCE: f64 = 1e-4; // |lhs - rhs| accepted as satisfying `==`
+const COMMAND_BATCH: usize = 16;
+const COMMAND_TIMEOUT: f64 = 60.0; // seconds
+const RISK_AVERSION: f64 = 1.0;
+const BUDGET: f64 = 1.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VariableType {
+    #[default]
+    Continuous,
+    Integer,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProblemFile {
+    objective: Option<String>,
+    optimum: Option<f64>,
+    dimensions: Option<usize>,
+    lower_bound: Option<f64>,
+    upper_bound: Option<f64>,
+    #[serde(default)]
+    variables: Vec<VariableFile>,
+    #[serde(default)]
+    constraints: Vec<String>,
+    equality_tolerance: Option<f64>,
+    model: Option<ModelFile>,
+    command: Option<CommandFile>,
+    plugin: Option<PluginFile>,
+    portfolio: Option<PortfolioFile>,
+    fit: Option<FitFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FitFile {
+    data: String,
+    model: String,
+    target: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PortfolioFile {
+    returns: String,
+    risk_aversion: Option<f64>,
+    budget: Option<f64>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PluginFile {
+    library: String,
+    objective: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CommandFile {
+    program: String,
+    #[serde(default)]
+    args: Vec<String>,
+    batch: Option<usize>,
+    timeout: Option<f64>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ModelFile {
+    path: String,
+    #[serde(default)]
+    output: usize,
+    transform: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VariableFile {
+    name: Option<String>,
+    lower_bound: Option<f64>,
+    upper_bound: Option<f64>,
+    #[serde(default, rename = "type")]
+    kind: VariableType,
+    fixed: Option<f64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Variable {
+    pub name: String,
+    pub lower_bound: f64,
+    pub upper_bound: f64,
+    pub kind: VariableType,
+    pub fixed: Option<f64>, // frozen at this value
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Relation {
+    AtMost,
+    AtLeast,
+    Equal,
+}
+
+#[derive(Clone, Debug)]
+pub struct Constraint {
+    pub text: String,
+    lhs: Expr,
+    relation: Relation,
+    rhs: Expr,
+    tolerance: f64, // of an equality
+}
+
+impl Constraint {
+    fn parse(text: &str, names: &[String], tolerance: f64) -> Result<Constraint, String> {
+        let (at, relation) = ["<=", ">=", "=="]
+            .iter()
+            .zip([Relation::AtMost, Relation::AtLeast, Relation::Equal])
+            .find_map(|(op, relation)| text.find(op).map(|at| (at, relation)))
+            .ok_or(format!("constraint `{}` needs one of <=, >=, ==", text))?;
+        let invalid = |e: String| format!("constraint `{}`: {}", text, e);
+        Ok(Constraint {
+            text: text.to_string(),
+            lhs: expr::parse(&text[..at], names).map_err(invalid)?,
+            relation,
+            rhs: expr::parse(&text[at + 2..], names).map_err(invalid)?,
+            tolerance,
+        })
+    }
+}
+
+// `lhs <= rhs` is the inequality lhs - rhs <= 0, `lhs == rhs` the equality
+// lhs - rhs = 0
+impl constraint::Constraint for Constraint {
+    fn value(&self, x: &[f64]) -> f64 {
+        let (lhs, rhs) = (self.lhs.eval(x), self.rhs.eval(x));
+        match self.relation {
+            Relation::AtMost | Relation::Equal => lhs - rhs,
+            Relation::AtLeast => rhs - lhs,
+        }
+    }
+
+    fn kind(&self) -> ConstraintKind {
+        match self.relation {
+            Relation::Equal => ConstraintKind::Equality {
+                tolerance: self.tolerance,
+            },
+            _ => ConstraintKind::Inequality,
+        }
+    }
+
+    fn linear(&self, dimensions: usize) -> Option<constraint::Linear> {
+        let (lhs, c) = self.lhs.affine(dimensions)?;
+        let (rhs, d) = self.rhs.affine(dimensions)?;
+        let sign = match self.relation {
+            Relation::AtMost | Relation::Equal => 1.0,
+            Relation::AtLeast => -1.0,
+        };
+        Some(constraint::Linear {
+            a: lhs.iter().zip(&rhs).map(|(l, r)| sign * (l - r)).collect(),
+            b: sign * (d - c),
+        })
+    }
+}
+
+// Objective written as an expression in the problem file
+#[derive(Clone, Debug)]
+pub struct Expression {
+    pub text: String,
+    expr: Expr,
+}
+
+impl Expression {
+    pub fn evaluate(&self, x: &[f64]) -> f64 {
+        self.expr.eval(x)
+    }
+}
+
+// Objective computed by an ONNX model, with optional post-processing
+#[derive(Clone)]
+pub struct ModelObjective {
+    pub path: String,
+    pub transform: Option<Expression>,
+    model: Model,
+}
+
+impl ModelObjective {
+    pub fn evaluate(&self, x: &[f64]) -> f64 {
+        let y = self.model.evaluate(x);
+        match &self.transform {
+            Some(transform) => transform.evaluate(&[y]),
+            None => y,
+        }
+    }
+}
+
+// Objective registered by a plugin library
+#[derive(Clone)]
+pub struct PluginObjectiveRef {
+    pub name: String,    // `objective (library)`, for the reports
+    pub library: String, // the resolved path of the library, for the audit log
+    objective: PluginObjective,
+}
+
+impl PluginObjectiveRef {
+    pub fn evaluate(&self, x: &[f64]) -> f64 {
+        self.objective.evaluate(x)
+    }
+}
+
+#[derive(Clone)]
+pub struct Problem {
+    pub path: String,
+    pub objective: Option<Function>,
+    pub expression: Option<Expression>,
+    pub model: Option<ModelObjective>,
+    pub command: Option<Arc<ExternalObjective>>, // shared by the clones a run makes
+    pub plugin: Option<PluginObjectiveRef>,
+    pub portfolio: Option<Portfolio>,
+    pub fit: Option<Fit>,
+    pub optimum: Option<f64>,
+    pub variables: Vec<Variable>,
+    pub constraints: Vec<Constraint>,
+    pub equality_tolerance: f64,
+}
+
+impl Problem {
+    // Reads a problem file; bounds left out fall back to `config`'s, or to the
+    // problem objective's defaults when it selects a function
+    pub fn load(path: &str, config: &Config) -> Result<Problem, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read problem file {}: {}", path, e))?;
+        let invalid = |e: String| format!("invalid problem file {}: {}", path, e);
+        let file: ProblemFile = if path.ends_with(".toml") {
+            toml::from_str(&text).map_err(|e| invalid(e.to_string()))?
+        } else {
+            serde_json::from_str(&text).map_err(|e| invalid(e.to_string()))?
+        };
+
+        let objective = file.objective.as_deref().and_then(|name| {
+            serde_json::from_value::<Function>(serde_json::Value::from(name)).ok()
+        });
+        let portfolio = match &file.portfolio {
+            Some(_)
+                if file.objective.is_some()
+                    || file.model.is_some()
+                    || file.command.is_some()
+                    || file.plugin.is_some() =>
+            {
+                return Err(invalid(
+                    "portfolio is exclusive with objective, model, command and plugin".to_string(),
+                ))
+            }
+            Some(p) => Some(Portfolio::load(
+                &p.returns,
+                p.risk_aversion.unwrap_or(RISK_AVERSION),
+            )?),
+            None => None,
+        };
+        let (default_lower, default_upper) = match (objective, &portfolio) {
+            (Some(f), _) => (f.defaults().lower_bound, f.defaults().upper_bound),
+            (None, Some(_)) => (0.0, 1.0),
+            (None, None) => (config.lower_bound, config.upper_bound),
+        };
+        let lower = file.lower_bound.unwrap_or(default_lower);
+        let upper = file.upper_bound.unwrap_or(default_upper);
+
+        let declared = if file.variables.is_empty() {
+            let n = match &portfolio {
+                Some(p) => p.assets.len(),
+                None => file.dimensions.unwrap_or(config.dimensions),
+            };
+            (0..n)
+                .map(|_| VariableFile {
+                    name: None,
+                    lower_bound: None,
+                    upper_bound: None,
+                    kind: VariableType::Continuous,
+                    fixed: None,
+                })
+                .collect()
+        } else {
+            if file.dimensions.is_some_and(|n| n != file.variables.len()) {
+                return Err(invalid(format!(
+                    "dimensions ({}) does not match the {} variables",
+                    file.dimensions.unwrap_or(0),
+                    file.variables.len()
+                )));
+            }
+            file.variables
+        };
+
+        let variables: Vec<Variable> = declared
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| Variable {
+                name: v.name.unwrap_or_else(|| match &portfolio {
+                    Some(p) => p.assets.get(i).cloned().unwrap_or_default(),
+                    None => format!("x{}", i + 1),
+                }),
+                lower_bound: v.lower_bound.unwrap_or(lower),
+                upper_bound: v.upper_bound.unwrap_or(upper),
+                kind: v.kind,
+                fixed: v.fixed,
+            })
+            .collect();
+
+        let names: Vec<String> = variables.iter().map(|v| v.name.clone()).collect();
+        let equality_tolerance = file.equality_tolerance.unwrap_or(EQUALITY_TOLERANCE);
+        if let Some(p) = &portfolio {
+            if variables.len() != p.assets.len() {
+                return Err(invalid(format!(
+                    "{} variables for the {} assets of {}",
+                    variables.len(),
+                    p.assets.len(),
+                    p.path
+                )));
+            }
+        }
+        let budget = file
+            .portfolio
+            .as_ref()
+            .map(|p| format!("sum(x_i) == {}", p.budget.unwrap_or(BUDGET)));
+        let constraints = budget
+            .iter()
+            .chain(&file.constraints)
+            .map(|c| Constraint::parse(c, &names, equality_tolerance))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(invalid)?;
+        let expression = match (&file.objective, objective) {
+            (Some(text), None) => Some(Expression {
+                text: text.clone(),
+                expr: expr::parse(text, &names)
+                    .map_err(|e| invalid(format!("objective `{}`: {}", text, e)))?,
+            }),
+            _ => None,
+        };
+
+        let model = match file.model {
+            Some(_) if file.objective.is_some() => {
+                return Err(invalid("objective and model are exclusive".to_string()))
+            }
+            Some(m) => {
+                let transform = match &m.transform {
+                    Some(text) => Some(Expression {
+                        text: text.clone(),
+                        expr: expr::parse(text, &["y".to_string()])
+                            .map_err(|e| invalid(format!("model transform `{}`: {}", text, e)))?,
+                    }),
+                    None => None,
+                };
+                Some(ModelObjective {
+                    model: Model::load(&m.path, variables.len(), m.output)?,
+                    path: m.path,
+                    transform,
+                })
+            }
+            None => None,
+        };
+
+        let command = match file.command {
+            Some(_) if file.objective.is_some() || model.is_some() => {
+                return Err(invalid(
+                    "command is exclusive with objective and model".to_string(),
+                ))
+            }
+            Some(c) => {
+                let batch = c.batch.unwrap_or(COMMAND_BATCH);
+                let timeout = c.timeout.unwrap_or(COMMAND_TIMEOUT);
+                if batch == 0 {
+                    return Err(invalid("command batch must be at least 1".to_string()));
+                }
+                if !(timeout > 0.0 && timeout.is_finite()) {
+                    return Err(invalid(format!(
+                        "command timeout must be a positive number of seconds, got {}",
+                        timeout
+                    )));
+                }
+                let external = ExternalObjective::start(
+                    &c.program,
+                    c.args,
+                    batch,
+                    timeout,
+                    config.objective_threads,
+                )?;
+                Some(Arc::new(external))
+            }
+            None => None,
+        };
+
+        let plugin = match file.plugin {
+            Some(_) if file.objective.is_some() || model.is_some() || command.is_some() => {
+                return Err(invalid(
+                    "plugin is exclusive with objective, model and command".to_string(),
+                ))
+            }
+            Some(p) => {
+                let library = plugin::resolve(&p.library)?;
+                Some(PluginObjectiveRef {
+                    objective: PluginObjective::load(&library, &p.objective)?,
+                    name: format!("{} ({})", p.objective, p.library),
+                    library,
+                })
+            }
+            None => None,
+        };
+
+        let fit = match file.fit {
+            Some(_)
+                if file.objective.is_some()
+                    || model.is_some()
+                    || command.is_some()
+                    || plugin.is_some()
+                    || portfolio.is_some() =>
+            {
+                return Err(invalid(
+                    "fit is exclusive with objective, model, command, plugin and portfolio"
+                        .to_string(),
+                ))
+            }
+            Some(f) => Some(Fit::load(&f.data, &f.model, f.target.as_deref(), &names)?),
+            None => None,
+        };
+
+        let problem = Problem {
+            path: path.to_string(),
+            objective,
+            expression,
+            model,
+            command,
+            plugin,
+            portfolio,
+            fit,
+            optimum: file.optimum,
+            variables,
+            constraints,
+            equality_tolerance,
+        };
+        problem.validate().map_err(invalid)?;
+        Ok(problem)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.variables.is_empty() {
+            return Err("at least one variable is needed".to_string());
+        }
+        for (i, v) in self.variables.iter().enumerate() {
+            if self.variables[..i].iter().any(|w| w.name == v.name) {
+                return Err(format!("variable `{}` is declared twice", v.name));
+            }
+            if v.lower_bound.partial_cmp(&v.upper_bound) != Some(std::cmp::Ordering::Less) {
+                return Err(format!(
+                    "variable `{}`: lower_bound ({}) must be below upper_bound ({})",
+                    v.name, v.lower_bound, v.upper_bound
+                ));
+            }
+            if v.kind == VariableType::Integer && v.lower_bound.ceil() > v.upper_bound.floor() {
+                return Err(format!(
+                    "integer variable `{}` has no integer within [{}, {}]",
+                    v.name, v.lower_bound, v.upper_bound
+                ));
+            }
+            if let Some(fixed) = v.fixed {
+                if !(v.lower_bound..=v.upper_bound).contains(&fixed) {
+                    return Err(format!(
+                        "variable `{}` is fixed at {}, outside [{}, {}]",
+                        v.name, fixed, v.lower_bound, v.upper_bound
+                    ));
+                }
+                if v.kind == VariableType::Integer && fixed.fract() != 0.0 {
+                    return Err(format!(
+                        "integer variable `{}` is fixed at {}, not an integer",
+                        v.name, fixed
+                    ));
+                }
+            }
+        }
+        if self.equality_tolerance.partial_cmp(&0.0) == Some(std::cmp::Ordering::Less) {
+            return Err("equality_tolerance must not be negative".to_string());
+        }
+        Ok(())
+    }
+
+    // The variable value nearest to `x` that its type allows
+    pub fn snap(&self, pos: usize, x: f64) -> f64 {
+        let v = &self.variables[pos];
+        match v.kind {
+            VariableType::Continuous => x,
+            VariableType::Integer => x.round().clamp(v.lower_bound.ceil(), v.upper_bound.floor()),
+        }
+    }
+
+    pub fn integer_variables(&self) -> usize {
+        self.variables
+            .iter()
+            .filter(|v| v.kind == VariableType::Integer)
+            .count()
+    }
+}
@@ -0,0 +1,72 @@
+// Desc: 2D projection of the particle trajectories (`--projection`), to
+// follow the swarm when it has many dimensions: every position of the run,
+// centred on their mean, onto the two principal axes of all of them (pca)
+// or onto two random orthonormal directions (random)
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+
+use crate::linalg;
+use crate::sink::Sink;
+
+pub struct Projection {
+    random: bool,
+    seed: u64,                                // of the random directions
+    positions: Vec<(usize, usize, Vec<f64>)>, // iteration, particle id, objective coordinates
+}
+
+impl Projection {
+    pub fn new(method: &str, seed: u64) -> Self {
+        Projection {
+            random: method == "random",
+            seed,
+            positions: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, iteration: usize, id: usize, x: &[f64]) {
+        self.positions.push((iteration, id, x.to_vec()));
+    }
+
+    fn axes(&self, samples: &[&[f64]], mean: &[f64]) -> Vec<Vec<f64>> {
+        let d = mean.len();
+        if self.random {
+            let mut rng = ChaCha12Rng::seed_from_u64(self.seed);
+            return linalg::random_axes(d, 2.min(d), &mut rng);
+        }
+        let cov = if samples.len() < 2 {
+            vec![vec![0.0; d]; d]
+        } else {
+            linalg::covariance(samples, mean)
+        };
+        linalg::principal_axes(&cov, 2.min(d))
+    }
+
+    // One row per recorded position: iteration, particle and its two
+    // coordinates in the projection (the second is 0 for one dimension)
+    pub fn save(&self, path: &str) {
+        let mut writer = csv::Writer::from_writer(Sink::create(path));
+        writer
+            .write_record(["iteration", "particle", "u", "v"])
+            .expect("Error writing projection file");
+        if !self.positions.is_empty() {
+            let samples: Vec<&[f64]> = self.positions.iter().map(|(_, _, x)| &x[..]).collect();
+            let mean = linalg::mean(&samples);
+            let axes = self.axes(&samples, &mean);
+            let along = |x: &[f64], k: usize| {
+                axes.get(k).map_or(0.0, |axis| {
+                    x.iter()
+                        .zip(&mean)
+                        .zip(axis)
+                        .map(|((xi, m), a)| (xi - m) * a)
+                        .sum::<f64>()
+                })
+            };
+            for (iteration, id, x) in &self.positions {
+                writer
+                    .serialize((iteration, id, along(x, 0), along(x, 1)))
+                    .expect("Error writing projection file");
+            }
+        }
+        writer.flush().expect("Error writing projection file");
+    }
+}
@@ -0,0 +1,2662 @@
+// Desc: Particle swarm state and the stepwise optimization loop
+use std::sync::Arc;
+
+use rand::seq::index;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+use rand_distr::StandardNormal;
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::advice;
+use crate::archive::EliteArchive;
+use crate::audit::AuditLog;
+use crate::checkpoint::{self, Checkpoint, RngState};
+use crate::comparator::{Comparator, Score};
+use crate::config::{Config, Heterogeneous, StopAction, VelocityUpdate};
+use crate::constraint::PenaltyScale;
+use crate::convergence::{ConvergenceTrace, TracePoint};
+use crate::dedup;
+use crate::diagnostics::{self, BoundaryStats, Covariance, DiagnosticsLog};
+use crate::events::{self, Event, EventKind, EventLog};
+use crate::explain::{self, DimensionTerms};
+use crate::grouping;
+use crate::heatmap::Heatmap;
+use crate::improvements::ImprovementLog;
+use crate::inertia::{self, InertiaSchedule};
+use crate::linalg::Gaussian;
+use crate::local_search::{self, Budget, Refinements};
+use crate::noise::{self, EvalContext};
+use crate::optimizer::{self, Optimizer, Outcome};
+use crate::options::RunOptions;
+use crate::output;
+use crate::plot::Animation;
+use crate::privacy::{self, Privacy};
+use crate::projection::Projection;
+use crate::repair::Polytope;
+use crate::sampling;
+use crate::schedule::{self, Schedule};
+use crate::selection;
+use crate::stopping::StopMonitor;
+use crate::throttle::Throttle;
+use crate::topology::{Neighbourhoods, Topology};
+use crate::trace::EvalTrace;
+use crate::view::SwarmView;
+use crate::web;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Particle {
+    pub id: usize,
+    pub x: Vec<f64>,
+    pub vx: Vec<f64>,
+    pub pbest_x: Vec<f64>,
+    pub pbest: f64,
+    pub pbest_violation: f64,       // constraint violation at pbest_x
+    pub fitness: Option<f64>,       // objective value at x without penalty, when known
+    pub pbest_low: Option<f64>,     // low-fidelity value at pbest_x in multi-fidelity mode
+    pub coef: Option<Coefficients>, // own coefficients in a heterogeneous swarm
+    pub stagnation: usize,          // iterations since pbest last improved
+    pub gbest_improvements: usize,  // times this particle improved the global best
+    pub gbest_gain: f64,            // total fitness decrease contributed to the global best
+}
+
+impl Particle {
+    pub fn pbest_score(&self) -> Score {
+        Score {
+            fitness: self.pbest,
+            violation: self.pbest_violation,
+        }
+    }
+
+    // The particle's own coefficients: sampled ones in a heterogeneous swarm,
+    // its strategy coordinates in a self-adaptive one, None otherwise
+    pub fn own_coefficients(&self, config: &Config) -> Option<Coefficients> {
+        if config.self_adaptive.is_some() {
+            let d = config.dimensions;
+            return Some(Coefficients {
+                w: self.x[d],
+                c1: self.x[d + 1],
+                c2: self.x[d + 2],
+            });
+        }
+        self.coef
+    }
+}
+
+pub struct Swarm {
+    pub particles: Vec<Particle>,
+    pub gbest_x: Vec<f64>,
+    pub gbest: f64,
+    pub gbest_violation: f64,
+    pub evaluations: usize,     // objective function evaluations so far
+    pub low_evaluations: usize, // low-fidelity screening evaluations so far
+}
+
+// Low-fidelity screening value of `x` in multi-fidelity mode, counted apart
+// from the accurate evaluations
+fn evaluate_low(config: &Config, low_evaluations: &mut usize, x: &[f64]) -> Option<f64> {
+    let mf = config.multi_fidelity.as_ref()?;
+    let low = config.low_fidelity(&x[..config.dimensions], mf.components)?;
+    *low_evaluations += 1;
+    Some(config.internal(low))
+}
+
+// Side effects of every objective evaluation
+pub struct EvalHooks {
+    trace: Option<EvalTrace>,
+    audit: Option<AuditLog>,
+    throttle: Option<Arc<Throttle>>,
+    cost: f64, // of the evaluations so far
+}
+
+impl EvalHooks {
+    // The evaluation trace and audit log the options ask for, and the rate limit
+    pub fn new(
+        options: &RunOptions,
+        config: &Config,
+        privacy: Option<Privacy>,
+    ) -> Result<Self, String> {
+        let audit = options
+            .audit
+            .as_deref()
+            .map(|path| AuditLog::open(path, &config.evaluator()))
+            .transpose()?;
+        Ok(EvalHooks {
+            trace: options.trace_evaluations.as_deref().map(|path| {
+                EvalTrace::create(path, options.trace_hashed, config.dimensions, privacy)
+            }),
+            audit,
+            throttle: config.max_evaluations_per_second.map(Throttle::shared),
+            cost: 0.0,
+        })
+    }
+
+    fn before(&self) {
+        if let Some(throttle) = &self.throttle {
+            throttle.wait();
+        }
+    }
+
+    // `particle` is None for evaluations made outside the swarm (analysis passes)
+    fn after(
+        &mut self,
+        evaluation: usize,
+        particle: Option<usize>,
+        x: &[f64],
+        (fitness, cost): (f64, f64),
+    ) {
+        self.cost += cost;
+        if let Some(trace) = self.trace.as_mut() {
+            trace.record(evaluation, particle, x, fitness);
+        }
+        if let Some(audit) = self.audit.as_mut() {
+            audit.record(particle, x, fitness);
+        }
+    }
+}
+
+// Evaluates the objective on the first `dimensions` coordinates of `x`,
+// counting the evaluation and passing it to the hooks
+pub fn evaluate(
+    config: &Config,
+    evaluations: &mut usize,
+    hooks: &mut EvalHooks,
+    at: EvalContext,
+    x: &[f64],
+) -> f64 {
+    hooks.before();
+    let objective_x = &x[..config.dimensions];
+    *evaluations += 1;
+    let (fitness, cost) = config.evaluate(objective_x, at, *evaluations);
+    hooks.after(
+        *evaluations,
+        at.particle,
+        objective_x,
+        (config.reported(fitness), cost),
+    );
+    fitness
+}
+
+// Settings of one iteration shared by every particle's move
+struct MoveContext<'c> {
+    config: &'c Config,
+    coef: Coefficients,
+    chi: f64,
+    vmax: Option<f64>,
+    explain: bool,
+    iteration: usize,
+    mbest: Option<Vec<f64>>,        // mean personal best, for QPSO
+    polytope: Option<&'c Polytope>, // of the linear repair
+}
+
+// What a move did to the particle, for the bookkeeping after its evaluation
+struct Moved {
+    crossed: Vec<usize>,        // objective coordinates that crossed a bound
+    changed: Vec<(usize, f64)>, // objective coordinates moved, with old values
+    repaired: bool,             // projected onto the linear constraints
+}
+
+// The best of every particle's neighbourhood, None under the global topology
+fn neighbourhood_bests(
+    neighbourhoods: Option<&Neighbourhoods>,
+    swarm: &Swarm,
+    config: &Config,
+) -> Option<Vec<Vec<f64>>> {
+    neighbourhoods.map(|nb| {
+        (0..config.particles)
+            .map(|i| nb.best(i, &swarm.particles, &config.comparison).to_vec())
+            .collect()
+    })
+}
+
+// The stream a particle draws its moves from at `iteration` with
+// rng_streams, keyed by the seed alone so any (iteration, particle) pair can
+// be replayed without the run; the tag keeps it apart from other streams
+pub fn particle_stream(seed: u64, iteration: usize, particle: usize) -> ChaCha12Rng {
+    ChaCha12Rng::seed_from_u64(noise::stream(seed, &[3, iteration as u64, particle as u64]))
+}
+
+// r1 and r2 of the cognitive and social terms
+fn draw_r(config: &Config, rng: &mut impl Rng) -> (f64, f64) {
+    match (config.deterministic, &config.random_coefficients) {
+        (Some(r), _) => (r, r),
+        (None, Some(d)) => (d.sample(rng), d.sample(rng)),
+        (None, None) => (rng.gen(), rng.gen()),
+    }
+}
+
+// Draws the particle's random coefficients and social guide (`social_x`, the
+// best of its neighbourhood, or now and then a random elite from the
+// archive), then updates its velocity and position over `dims` and applies
+// the boundary handling
+fn move_particle(
+    ctx: &MoveContext,
+    p: &mut Particle,
+    dims: &[usize],
+    social_x: &[f64],
+    archive: Option<&EliteArchive>,
+    rng: &mut impl Rng,
+) -> Moved {
+    let config = ctx.config;
+    let (r1, r2) = draw_r(config, rng);
+    let mut coef = p.own_coefficients(config).unwrap_or(ctx.coef);
+    let mut chi = ctx.chi;
+    if config.velocity_update == VelocityUpdate::Constriction {
+        // Clerc's update keeps the whole previous velocity inside the constriction
+        chi *= clerc_constriction(coef.c1 + coef.c2);
+        coef.w = 1.0;
+    }
+
+    let injected = match (archive, &config.archive) {
+        (Some(archive), Some(settings)) if rng.gen_bool(settings.injection_probability) => {
+            archive.random(rng).map(<[f64]>::to_vec)
+        }
+        _ => None,
+    };
+    let guide = injected.as_deref().unwrap_or(social_x);
+
+    if ctx.explain {
+        explain::print_header(ctx.iteration, p.id, r1, r2);
+    }
+
+    let spso = (config.velocity_update == VelocityUpdate::Spso2011)
+        .then(|| hypersphere_terms(&coef, &p.x, &p.pbest_x, guide, dims, rng));
+
+    let mut moved = Moved {
+        crossed: Vec::new(),
+        changed: Vec::new(),
+        repaired: false,
+    };
+    let unmoved = ctx.polytope.map(|_| p.x[..config.dimensions].to_vec());
+    // update velocity and position
+    for (k, &pos) in dims.iter().enumerate() {
+        let old_x = p.x[pos];
+        // the sampling variants jump straight to a new point
+        let jump = match config.velocity_update {
+            VelocityUpdate::BareBones => {
+                let (pbest, guide) = (p.pbest_x[pos], guide[pos]);
+                let z: f64 = rng.sample(StandardNormal);
+                Some((pbest + guide) / 2.0 + z * (pbest - guide).abs() - old_x)
+            }
+            VelocityUpdate::Qpso => {
+                let phi: f64 = rng.gen();
+                let attractor = phi * p.pbest_x[pos] + (1.0 - phi) * guide[pos];
+                let mbest = ctx.mbest.as_ref().map_or(attractor, |m| m[pos]);
+                let u: f64 = 1.0 - rng.gen::<f64>(); // in (0, 1]
+                let spread = coef.w * (mbest - old_x).abs() * (1.0 / u).ln();
+                let sign = if rng.gen_bool(0.5) { 1.0 } else { -1.0 };
+                Some(attractor + sign * spread - old_x)
+            }
+            _ => None,
+        };
+        let (inertia, cognitive, social, sphere) = match (&spso, jump) {
+            (_, Some(jump)) => (0.0, 0.0, 0.0, jump),
+            (Some(terms), None) => {
+                let (cognitive, social, sphere) = terms[k];
+                (coef.w * p.vx[pos], cognitive, social, sphere)
+            }
+            (None, None) => {
+                // the first dimension uses the pair drawn for the header
+                let (r1, r2) = if config.random_per_dimension && k > 0 {
+                    draw_r(config, rng)
+                } else {
+                    (r1, r2)
+                };
+                let (inertia, cognitive, social) = velocity_terms(
+                    &coef,
+                    p.vx[pos],
+                    p.x[pos],
+                    p.pbest_x[pos],
+                    guide[pos],
+                    r1,
+                    r2,
+                );
+                (inertia, cognitive, social, 0.0)
+            }
+        };
+        let (inertia, cognitive, social, sphere) =
+            (chi * inertia, chi * cognitive, chi * social, chi * sphere);
+        p.vx[pos] = inertia + cognitive + social + sphere;
+        let (lower, upper) = config.bounds_of(pos);
+        let vmax = ctx
+            .vmax
+            .or(config.vmax_fraction.map(|f| f * (upper - lower)));
+        if let Some(vmax) = vmax {
+            p.vx[pos] = p.vx[pos].clamp(-vmax, vmax);
+        }
+        p.x[pos] = config.snap(pos, p.x[pos] + p.vx[pos]);
+        if pos < config.dimensions {
+            moved.changed.push((pos, old_x));
+        }
+        let mut boundary = None;
+        if pos >= config.dimensions {
+            // strategy coordinates stay in range without penalty or bookkeeping
+            if p.x[pos] < lower || p.x[pos] > upper {
+                p.x[pos] = p.x[pos].clamp(lower, upper);
+                p.vx[pos] = 0.0;
+            }
+        } else if let Some(crossed) =
+            config
+                .boundary
+                .apply(&mut p.x[pos], &mut p.vx[pos], (lower, upper), rng)
+        {
+            p.x[pos] = config.snap(pos, p.x[pos]);
+            moved.crossed.push(pos);
+            boundary = Some(config.boundary.note(crossed));
+        }
+
+        if ctx.explain {
+            let terms = DimensionTerms {
+                inertia,
+                cognitive,
+                social,
+                sphere,
+                old_x,
+                new_x: p.x[pos],
+                boundary,
+            };
+            explain::print_dimension(pos, &terms);
+        }
+    }
+
+    // the repair may move coordinates outside `dims` too; velocities are kept
+    if let (Some(polytope), Some(unmoved)) = (ctx.polytope, unmoved) {
+        moved.repaired = polytope.repair(&mut p.x[..config.dimensions]);
+        if moved.repaired {
+            for (pos, &old_x) in unmoved.iter().enumerate() {
+                p.x[pos] = config.snap(pos, p.x[pos]);
+                if p.x[pos] != old_x && !moved.changed.iter().any(|&(q, _)| q == pos) {
+                    moved.changed.push((pos, old_x));
+                }
+            }
+        }
+    }
+
+    moved
+}
+
+// Multi-fidelity screening of the particle's new position: the low-fidelity
+// value, and whether it predicts a personal best improvement (always true
+// without multi-fidelity). The low-fidelity evaluation is not counted here.
+fn screen(config: &Config, p: &Particle) -> (Option<f64>, bool) {
+    let low = config.multi_fidelity.as_ref().and_then(|mf| {
+        config
+            .low_fidelity(&p.x[..config.dimensions], mf.components)
+            .map(|low| config.internal(low))
+    });
+    let promoted = match (low, p.pbest_low) {
+        (Some(low), Some(pbest_low)) => low < pbest_low,
+        _ => true,
+    };
+    (low, promoted)
+}
+
+// Fitness at the particle's new position, from its known fitness and the
+// moved coordinates when `partial` allows it, and the cost of getting it;
+// `evaluation` is the number this evaluation gets when counted. Neither
+// counts it nor runs the hooks.
+fn fitness_at(
+    config: &Config,
+    p: &Particle,
+    changed: &[(usize, f64)],
+    partial: bool,
+    iteration: usize,
+    evaluation: usize,
+) -> (f64, f64) {
+    let objective_x = &p.x[..config.dimensions];
+    match p.fitness.filter(|_| partial) {
+        Some(previous) => (
+            config.internal(config.function.evaluate_partial(
+                config.reported(previous),
+                objective_x,
+                changed,
+            )),
+            config.evaluation_cost.unwrap_or(0.0),
+        ),
+        None => config.evaluate(
+            objective_x,
+            EvalContext::particle(iteration, p.id),
+            evaluation,
+        ),
+    }
+}
+
+// Coefficients of the velocity update in effect for one iteration
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Coefficients {
+    pub w: f64,
+    pub c1: f64,
+    pub c2: f64,
+}
+
+impl Coefficients {
+    pub fn at(config: &Config, iteration: usize) -> Self {
+        Coefficients {
+            w: config.w.value(iteration, config.iterations),
+            c1: config.c1.value(iteration, config.iterations),
+            c2: config.c2.value(iteration, config.iterations),
+        }
+    }
+
+    pub fn sample(h: &Heterogeneous, rng: &mut impl Rng) -> Self {
+        Coefficients {
+            w: h.w.sample(rng),
+            c1: h.c1.sample(rng),
+            c2: h.c2.sample(rng),
+        }
+    }
+}
+
+// Clerc and Kennedy's constriction coefficient for phi = c1 + c2 > 4
+fn clerc_constriction(phi: f64) -> f64 {
+    2.0 / (2.0 - phi - (phi * phi - 4.0 * phi).sqrt()).abs()
+}
+
+// Mean of the personal bests, the QPSO mbest
+fn mean_pbest_x(particles: &[Particle]) -> Vec<f64> {
+    let mut mean = vec![0.0; particles.first().map_or(0, |p| p.pbest_x.len())];
+    for p in particles {
+        for (m, x) in mean.iter_mut().zip(&p.pbest_x) {
+            *m += x / particles.len() as f64;
+        }
+    }
+    mean
+}
+
+// The three terms of the velocity update for one dimension:
+// (inertia w*v, cognitive c1*r1*(pbest - x), social c2*r2*(gbest - x))
+pub fn velocity_terms(
+    coef: &Coefficients,
+    v: f64,
+    x: f64,
+    pbest: f64,
+    gbest: f64,
+    r1: f64,
+    r2: f64,
+) -> (f64, f64, f64) {
+    (
+        coef.w * v,
+        coef.c1 * r1 * (pbest - x),
+        coef.c2 * r2 * (gbest - x),
+    )
+}
+
+// SPSO-2011 for the coordinates in `dims`: the attraction terms toward the
+// centre G of x, x + c1*(pbest - x) and x + c2*(guide - x) (when pbest is the
+// guide, G lies between x and x + c1*(pbest - x)), plus a point drawn
+// uniformly in the hypersphere of radius |G - x| around G. Returns
+// (cognitive, social, sphere) per coordinate.
+pub fn hypersphere_terms(
+    coef: &Coefficients,
+    x: &[f64],
+    pbest: &[f64],
+    guide: &[f64],
+    dims: &[usize],
+    rng: &mut impl Rng,
+) -> Vec<(f64, f64, f64)> {
+    let own_best = pbest == guide;
+    let parts = if own_best { 2.0 } else { 3.0 };
+    let attraction: Vec<(f64, f64)> = dims
+        .iter()
+        .map(|&d| {
+            let cognitive = coef.c1 * (pbest[d] - x[d]) / parts;
+            let social = if own_best {
+                0.0
+            } else {
+                coef.c2 * (guide[d] - x[d]) / parts
+            };
+            (cognitive, social)
+        })
+        .collect();
+
+    let radius = attraction
+        .iter()
+        .map(|(c, s)| (c + s).powi(2))
+        .sum::<f64>()
+        .sqrt();
+    let direction: Vec<f64> = dims
+        .iter()
+        .map(|_| rng.sample::<f64, _>(StandardNormal))
+        .collect();
+    let norm = direction.iter().map(|u| u * u).sum::<f64>().sqrt();
+    let length = radius * rng.gen::<f64>().powf(1.0 / dims.len() as f64);
+    let scale = if norm > 0.0 { length / norm } else { 0.0 };
+
+    attraction
+        .into_iter()
+        .zip(direction)
+        .map(|((cognitive, social), u)| (cognitive, social, u * scale))
+        .collect()
+}
+
+// A PSO run that advances one iteration per `step`, so callers can inspect
+// or adjust the swarm and the configuration between iterations.
+pub struct Run<'a> {
+    pub swarm: Swarm,
+    pub config: Config,
+    pub iteration: usize, // iterations completed so far
+    options: &'a RunOptions,
+    rng: ChaCha12Rng, // StdRng's generator, whose position can be saved
+    hooks: EvalHooks,
+    diagnostics_log: Option<DiagnosticsLog>,
+    improvement_log: Option<ImprovementLog>,
+    heatmap: Option<Heatmap>,
+    view: Option<SwarmView>, // published after every iteration when someone watches
+    animation: Option<Animation>,
+    projection: Option<Projection>,
+    active_dims: Vec<Vec<usize>>, // coordinates each particle moves this iteration
+    dispersed: usize,
+    polytope: Option<Polytope>, // linear constraints the moves are repaired onto
+    repairs: usize,             // particles repaired
+    saturated_iterations: usize,
+    event_log: Option<EventLog>,
+    events: Vec<Event>, // of the whole run, for the trace, the plot and the report
+    convergence: Option<ConvergenceTrace>,
+    history: Vec<TracePoint>, // state after every iteration
+    stop_monitor: StopMonitor,
+    stopped: Option<&'static str>, // condition that ended the run early
+    restarts: usize,
+    stagnant_iterations: usize, // without a gbest improvement, for the escapes
+    escape_reference: (f64, f64), // gbest and its violation at the last improvement
+    refinements: Refinements,
+    penalty_scale: PenaltyScale, // of the penalty on constraint violations
+    privacy: Option<Privacy>,    // noise for the written logs
+    archive: Option<EliteArchive>,
+    neighbourhoods: Option<Neighbourhoods>, // None for the global topology
+    chaos: f64,                             // logistic map state of a chaotic inertia schedule
+    success_rate: f64, // share of particles that improved their pbest in the last iteration
+    initial_diversity: f64, // for a diversity-adaptive inertia schedule
+    pool: Option<ThreadPool>, // with --threads: particles move and are evaluated in parallel
+}
+
+impl<'a> Run<'a> {
+    pub fn new(config: Config, options: &'a RunOptions) -> Result<Self, String> {
+        Self::starting_at(config, options, None)
+    }
+
+    // A run whose first particles sit at `start` (a pipeline's handoff), or
+    // at the --warm-start positions without it
+    pub fn starting_at(
+        mut config: Config,
+        options: &'a RunOptions,
+        start: Option<&[Vec<f64>]>,
+    ) -> Result<Self, String> {
+        config.advance(0);
+        // a caller's objective may have no low fidelity, found out at its centre
+        if let Some(mf) = &config.multi_fidelity {
+            let centre: Vec<f64> = (0..config.dimensions)
+                .map(|d| {
+                    let (lower, upper) = config.bounds_of(d);
+                    (lower + upper) / 2.0
+                })
+                .collect();
+            if config.low_fidelity(&centre, mf.components).is_none() {
+                eprintln!(
+                    "Warning: the objective {} has no low fidelity; multi_fidelity is off and every particle is evaluated accurately",
+                    config.objective_name()
+                );
+                config.multi_fidelity = None;
+            }
+        }
+        let privacy = privacy::from_options(options);
+        let mut hooks = EvalHooks::new(options, &config, privacy)?;
+        let resumed = options
+            .resume
+            .as_deref()
+            .map(Checkpoint::load)
+            .transpose()?;
+        // a resumed run keeps the groups found before it was saved
+        if let (Some(checkpoint), Some(block)) = (&resumed, config.block_coordinate.as_mut()) {
+            if block.groups.is_empty() {
+                block.groups = checkpoint.groups.clone();
+            }
+        }
+        let grouping_evaluations = discover_groups(&mut config, &mut hooks, !options.batch);
+        let diagnostics_log = options
+            .diagnostics
+            .as_deref()
+            .map(|path| DiagnosticsLog::create(path, config.dimensions));
+        let improvement_log = options
+            .improvements
+            .as_deref()
+            .map(|path| ImprovementLog::create(path, privacy));
+        let heatmap = options.heatmap.as_ref().map(|_| {
+            Heatmap::new(
+                (options.heatmap_dims[0] - 1, options.heatmap_dims[1] - 1),
+                options.heatmap_bins,
+                (config.lower_bound, config.upper_bound),
+            )
+        });
+
+        let animation = options
+            .animate
+            .as_deref()
+            .map(|dir| Animation::new(dir, &config))
+            .transpose()?;
+
+        let projection = options.projection.as_ref().map(|_| {
+            Projection::new(
+                &options.projection_method,
+                config.seed.unwrap_or_else(rand::random),
+            )
+        });
+
+        let view = match config.view.clone() {
+            Some(view) => Some(view),
+            None if options.web => {
+                let view = SwarmView::new();
+                web::serve(
+                    options.web_port,
+                    (config.lower_bound, config.upper_bound),
+                    view.clone(),
+                )
+                .map_err(|e| {
+                    format!(
+                        "cannot start web server on port {}: {}",
+                        options.web_port, e
+                    )
+                })?;
+                Some(view)
+            }
+            None => None,
+        };
+
+        if options.explain {
+            explain::warn_if_verbose(config.particles, config.dimensions);
+        }
+
+        let convergence =
+            ConvergenceTrace::create(options.convergence.as_deref(), &config, privacy);
+        let rng = optimizer::seeded_rng(&config);
+        let polytope = config
+            .linear_repair
+            .then(|| Polytope::of(&config))
+            .flatten();
+        let pool = options
+            .threads
+            .map(|threads| {
+                ThreadPoolBuilder::new()
+                    .num_threads(threads as usize)
+                    .build()
+                    .map_err(|e| format!("cannot start {} worker threads: {}", threads, e))
+            })
+            .transpose()?;
+        let mut run = Run {
+            swarm: Swarm {
+                particles: Vec::new(),
+                gbest_x: vec![],
+                gbest: 0.0,
+                gbest_violation: 0.0,
+                evaluations: grouping_evaluations,
+                low_evaluations: 0,
+            },
+            config,
+            iteration: 0,
+            options,
+            rng,
+            hooks,
+            diagnostics_log,
+            improvement_log,
+            heatmap,
+            view,
+            animation,
+            projection,
+            active_dims: Vec::new(),
+            dispersed: 0,
+            polytope,
+            repairs: 0,
+            saturated_iterations: 0,
+            event_log: options.events.as_deref().map(EventLog::create),
+            events: Vec::new(),
+            convergence,
+            history: Vec::new(),
+            stop_monitor: StopMonitor::default(),
+            stopped: None,
+            restarts: 0,
+            stagnant_iterations: 0,
+            escape_reference: (f64::INFINITY, f64::INFINITY),
+            refinements: Refinements::default(),
+            penalty_scale: PenaltyScale::default(),
+            privacy,
+            archive: None,
+            neighbourhoods: None,
+            chaos: 0.0,
+            success_rate: 1.0,
+            initial_diversity: 0.0,
+            pool,
+        };
+        if let Some(checkpoint) = resumed {
+            checkpoint.check_config(&run.config)?;
+            run.restore(checkpoint);
+            return Ok(run);
+        }
+        let start = match (start, &options.warm_start) {
+            (Some(start), _) => start.to_vec(),
+            (None, Some(path)) => {
+                Checkpoint::load(path).and_then(|c| c.positions_for(&run.config))?
+            }
+            (None, None) => Vec::new(),
+        };
+        run.init_swarm(start);
+        run.initial_diversity = diagnostics::diversity(&run.swarm.particles, run.config.dimensions);
+        if let Some(InertiaSchedule::Chaotic { .. }) = run.config.inertia_schedule {
+            run.chaos = run.rng.gen_range(0.0..1.0);
+        }
+        run.neighbourhoods =
+            Neighbourhoods::build(&run.config.topology, run.config.particles, &mut run.rng);
+        Ok(run)
+    }
+
+    // Particles at the `start` positions while they last, then random ones
+    fn init_swarm(&mut self, mut start: Vec<Vec<f64>>) {
+        start.truncate(self.config.particles);
+        let mut start = start.into_iter();
+        for id in 0..self.config.particles {
+            let p = self.spawn_particle(id, start.next());
+            self.swarm.particles.push(p);
+        }
+
+        // Set the global best to the first particle
+        let s = &mut self.swarm;
+        s.gbest_x = s.particles[0].x.clone();
+        s.gbest = s.particles[0].pbest;
+        s.gbest_violation = s.particles[0].pbest_violation;
+
+        if let Some(settings) = &self.config.archive {
+            let mut archive = EliteArchive::new(settings.size, self.config.comparison.clone());
+            for p in &s.particles {
+                archive.offer(&p.pbest_x, p.pbest_score());
+            }
+            self.archive = Some(archive);
+        }
+    }
+
+    // A particle at a uniformly random position, or at `drawn` clamped to the
+    // bounds, evaluated once
+    fn spawn_particle(&mut self, id: usize, drawn: Option<Vec<f64>>) -> Particle {
+        let config = &self.config;
+        let s = &mut self.swarm;
+
+        let mut params = Vec::new();
+        let mut velocity = vec![];
+        for pos in 0..config.dimensions {
+            let (lower, upper) = config.bounds_of(pos);
+            let x = match &drawn {
+                Some(drawn) => sampling::confine(config, pos, drawn[pos]),
+                None => sampling::coordinate(config, pos, &mut self.rng),
+            };
+            let vx = match config.fixed_value(pos) {
+                Some(_) => 0.0,
+                None => config
+                    .velocity_init
+                    .sample(x, (lower, upper), &mut self.rng),
+            };
+            params.push(x);
+            velocity.push(vx);
+        }
+        // self-adaptive strategy coordinates start at rest inside their ranges,
+        // drawn anew when `drawn` lacks them
+        for pos in config.dimensions..config.search_dimensions() {
+            params.push(match drawn.as_ref().and_then(|d| d.get(pos)) {
+                Some(&drawn) => sampling::confine(config, pos, drawn),
+                None => sampling::coordinate(config, pos, &mut self.rng),
+            });
+            velocity.push(0.0);
+        }
+        if let Some(polytope) = &self.polytope {
+            if polytope.repair(&mut params[..config.dimensions]) {
+                for (pos, x) in params[..config.dimensions].iter_mut().enumerate() {
+                    *x = config.snap(pos, *x);
+                }
+                self.repairs += 1;
+            }
+        }
+
+        let fitness = evaluate(
+            config,
+            &mut s.evaluations,
+            &mut self.hooks,
+            EvalContext::particle(self.iteration, id),
+            &params,
+        );
+        let pbest_low = evaluate_low(config, &mut s.low_evaluations, &params);
+        let pbest = config.comparison.score(
+            fitness,
+            config.constraint_violation(&params),
+            config.penalty_factor.value(0, config.iterations) * self.penalty_scale.value,
+        );
+
+        let coef = config
+            .heterogeneous
+            .as_ref()
+            .map(|h| Coefficients::sample(h, &mut self.rng));
+
+        Particle {
+            id,
+            x: params.clone(),
+            vx: velocity,
+            pbest_x: params.clone(),
+            pbest: pbest.fitness,
+            pbest_violation: pbest.violation,
+            fitness: Some(fitness),
+            pbest_low,
+            coef,
+            stagnation: 0,
+            gbest_improvements: 0,
+            gbest_gain: 0.0,
+        }
+    }
+
+    // Re-initializes every particle, uniformly or, with a warm restart, from a
+    // Gaussian fitted to the elite archive. The global best and each
+    // particle's contribution counters survive the restart.
+    fn restart(&mut self) {
+        let sampler = match (&self.archive, &self.config.archive) {
+            (Some(archive), Some(settings)) if settings.warm_restart => {
+                Gaussian::fit(&archive.positions())
+            }
+            _ => None,
+        };
+        for id in 0..self.config.particles {
+            let drawn = sampler.as_ref().map(|g| g.sample(&mut self.rng));
+            let p = self.spawn_particle(id, drawn);
+            self.replace_particle(p);
+        }
+        self.restarts += 1;
+        self.active_dims.clear();
+    }
+
+    // Puts `p` in the place of the particle with its id, which keeps its
+    // contribution counters
+    fn replace_particle(&mut self, mut p: Particle) {
+        let id = p.id;
+        let old = &self.swarm.particles[id];
+        p.gbest_improvements = old.gbest_improvements;
+        p.gbest_gain = old.gbest_gain;
+        self.swarm.particles[id] = p;
+    }
+
+    // Stagnation escapes (`escape`), after every iteration
+    fn escape(&mut self) {
+        let (reference, reference_violation) = self.escape_reference;
+        let swarm = &self.swarm;
+        if swarm.gbest_violation < reference_violation
+            || reference - swarm.gbest > self.config.escape.min_improvement
+        {
+            self.escape_reference = (swarm.gbest, swarm.gbest_violation);
+            self.stagnant_iterations = 0;
+        } else {
+            self.stagnant_iterations += 1;
+        }
+        let n = self.stagnant_iterations;
+        let fires = |after: usize| n > 0 && n.is_multiple_of(after);
+        let escape = self.config.escape.clone();
+        let scores: Vec<Score> = self
+            .swarm
+            .particles
+            .iter()
+            .map(|p| p.pbest_score())
+            .collect();
+        let ranked = selection::ranked_indices(
+            &scores,
+            scores.len(),
+            &self.config.comparison,
+            &mut self.rng,
+        );
+
+        if let Some(m) = escape.gbest_mutation.filter(|m| fires(m.after)) {
+            let sigma: Vec<f64> = (0..self.config.search_dimensions())
+                .map(|pos| {
+                    let (lower, upper) = self.config.bounds_of(pos);
+                    m.sigma * (upper - lower)
+                })
+                .collect();
+            let mutation = Gaussian::diagonal(self.swarm.gbest_x.clone(), &sigma);
+            for &id in ranked.iter().rev().take(m.particles) {
+                let drawn = mutation.sample(&mut self.rng);
+                let p = self.spawn_particle(id, Some(drawn));
+                self.replace_particle(p);
+            }
+            let detail = format!("{} particle(s) moved near gbest", m.particles);
+            self.log_event(EventKind::Escape, "gbest_mutation", &detail);
+        }
+
+        if escape.opposition.is_some_and(|o| fires(o.after)) {
+            let mut moved = 0;
+            for id in 0..self.config.particles {
+                let opposite: Vec<f64> = self.swarm.particles[id]
+                    .x
+                    .iter()
+                    .enumerate()
+                    .map(|(pos, &x)| {
+                        let (lower, upper) = self.config.bounds_of(pos);
+                        lower + upper - x
+                    })
+                    .collect();
+                let p = self.spawn_particle(id, Some(opposite));
+                let old = self.swarm.particles[id].pbest_score();
+                if self.config.comparison.better(p.pbest_score(), old) {
+                    self.replace_particle(p);
+                    moved += 1;
+                }
+            }
+            let detail = format!("{} of {} particles moved", moved, self.config.particles);
+            self.log_event(EventKind::Escape, "opposition", &detail);
+        }
+
+        if let Some(r) = escape.partial_restart.filter(|r| fires(r.after)) {
+            for &id in &ranked[r.keep..] {
+                let p = self.spawn_particle(id, None);
+                self.replace_particle(p);
+            }
+            let detail = format!("kept the {} best particle(s)", r.keep);
+            self.log_event(EventKind::Escape, "partial_restart", &detail);
+        }
+
+        // a re-seeded particle may have landed on a new global best
+        let swarm = &mut self.swarm;
+        for p in &swarm.particles {
+            let gbest = Score {
+                fitness: swarm.gbest,
+                violation: swarm.gbest_violation,
+            };
+            if self.config.comparison.better(p.pbest_score(), gbest) {
+                swarm.gbest_x = p.pbest_x.clone();
+                swarm.gbest = p.pbest;
+                swarm.gbest_violation = p.pbest_violation;
+            }
+        }
+    }
+
+    // Local search from gbest (`local_search`), logged as an event of
+    // `iteration`. A better point it finds becomes gbest and the personal best
+    // of the best particle, so every topology follows it.
+    fn polish(&mut self, iteration: usize) {
+        let Some(settings) = self.config.local_search.clone() else {
+            return;
+        };
+        let config = &self.config;
+        let swarm = &mut self.swarm;
+        let d = config.dimensions;
+        let before = Score {
+            fitness: swarm.gbest,
+            violation: swarm.gbest_violation,
+        };
+        let spent = swarm.evaluations;
+        let mut budget = Budget {
+            config,
+            evaluations: &mut swarm.evaluations,
+            hooks: &mut self.hooks,
+            iteration,
+            penalty_factor: config.penalty_factor.value(iteration, config.iterations)
+                * self.penalty_scale.value,
+            left: settings.evaluations,
+            best: (swarm.gbest_x[..d].to_vec(), before),
+        };
+        local_search::refine(&settings, &mut budget);
+        let (x, score) = budget.best;
+        let spent = swarm.evaluations - spent;
+
+        let improved = config.comparison.better(score, before);
+        if improved {
+            swarm.gbest_x[..d].copy_from_slice(&x);
+            swarm.gbest = score.fitness;
+            swarm.gbest_violation = score.violation;
+            let scores: Vec<Score> = swarm.particles.iter().map(|p| p.pbest_score()).collect();
+            let best = selection::best_indices(&scores, 1, &config.comparison)[0];
+            let p = &mut swarm.particles[best];
+            if config.comparison.better(score, p.pbest_score()) {
+                p.pbest_x[..d].copy_from_slice(&x);
+                p.pbest = score.fitness;
+                p.pbest_violation = score.violation;
+                if let Some(archive) = self.archive.as_mut() {
+                    archive.offer(&p.pbest_x, score);
+                }
+            }
+        }
+        self.refinements.runs += 1;
+        self.refinements.improved += improved as usize;
+        self.refinements.evaluations += spent;
+        let detail = format!(
+            "gbest {:e} -> {:e} in {} evaluations",
+            config.reported(before.fitness),
+            config.reported(self.swarm.gbest),
+            spent
+        );
+        self.log_event_at(
+            iteration,
+            EventKind::Refine,
+            settings.method.name(),
+            &detail,
+        );
+    }
+
+    // Records an event of the current iteration in the event log, the
+    // convergence trace and the run's events
+    fn log_event(&mut self, kind: EventKind, name: &str, detail: &str) {
+        self.log_event_at(self.iteration, kind, name, detail);
+    }
+
+    fn log_event_at(&mut self, iteration: usize, kind: EventKind, name: &str, detail: &str) {
+        let event = Event {
+            iteration,
+            evaluations: self.swarm.evaluations,
+            kind,
+            name: name.to_string(),
+            detail: detail.to_string(),
+        };
+        if let Some(log) = &mut self.event_log {
+            log.log(&event);
+        }
+        if let Some(trace) = self.convergence.as_mut() {
+            trace.annotate(&event);
+        }
+        self.events.push(event);
+    }
+
+    // Picks the coordinates every particle moves. Without a dimension subset
+    // that is all of them; strategy coordinates are always included.
+    fn draw_active_dims(&mut self) {
+        let config = &self.config;
+        let strategy = config.dimensions..config.search_dimensions();
+        // fixed coordinates never move
+        let free: Vec<usize> = (0..config.dimensions)
+            .filter(|&pos| config.fixed_value(pos).is_none())
+            .collect();
+
+        if let Some(block) = &config.block_coordinate {
+            if self.active_dims.is_empty()
+                || self.iteration.is_multiple_of(block.iterations_per_group)
+            {
+                let groups = block.groups.len();
+                let group = (self.iteration / block.iterations_per_group) % groups;
+                let mut dims: Vec<usize> = block.groups[group]
+                    .iter()
+                    .map(|d| d - 1)
+                    .filter(|pos| free.contains(pos))
+                    .collect();
+                dims.extend(strategy);
+                self.active_dims = vec![dims; config.particles];
+                if block.share_context {
+                    self.share_block_context();
+                }
+                if self.iteration > 0 && groups > 1 {
+                    let detail = format!("group {} of {}", group + 1, groups);
+                    self.log_event(EventKind::Phase, "block_group", &detail);
+                }
+            }
+            return;
+        }
+
+        let Some(sub) = &config.dimension_subset else {
+            if self.active_dims.is_empty() {
+                let all: Vec<usize> = free.iter().copied().chain(strategy).collect();
+                self.active_dims = vec![all; config.particles];
+            }
+            return;
+        };
+        if !self.active_dims.is_empty() && !self.iteration.is_multiple_of(sub.resample_every) {
+            return;
+        }
+
+        let draw = |rng: &mut ChaCha12Rng| {
+            let mut dims: Vec<usize> = index::sample(rng, free.len(), sub.size)
+                .into_iter()
+                .map(|k| free[k])
+                .collect();
+            dims.sort_unstable();
+            dims.extend(strategy.clone());
+            dims
+        };
+        self.active_dims = if sub.shared {
+            vec![draw(&mut self.rng); config.particles]
+        } else {
+            (0..config.particles).map(|_| draw(&mut self.rng)).collect()
+        };
+    }
+
+    // Freezes every variable outside the active group at the global best's
+    // value, for positions and personal bests alike, and re-evaluates the
+    // personal bests in that context
+    fn share_block_context(&mut self) {
+        let config = &self.config;
+        let active = &self.active_dims[0];
+        let swarm = &mut self.swarm;
+
+        for p in swarm.particles.iter_mut() {
+            for pos in (0..config.dimensions).filter(|pos| !active.contains(pos)) {
+                p.x[pos] = swarm.gbest_x[pos];
+                p.pbest_x[pos] = swarm.gbest_x[pos];
+                p.vx[pos] = 0.0;
+            }
+            p.fitness = None;
+            let fitness = evaluate(
+                config,
+                &mut swarm.evaluations,
+                &mut self.hooks,
+                EvalContext::particle(self.iteration, p.id),
+                &p.pbest_x,
+            );
+            let pbest = config.comparison.score(
+                fitness,
+                config.constraint_violation(&p.pbest_x),
+                config
+                    .penalty_factor
+                    .value(self.iteration, config.iterations)
+                    * self.penalty_scale.value,
+            );
+            p.pbest = pbest.fitness;
+            p.pbest_violation = pbest.violation;
+            p.pbest_low = evaluate_low(config, &mut swarm.low_evaluations, &p.pbest_x);
+        }
+    }
+
+    // Coefficients of the current iteration, with the inertia schedule applied
+    pub fn coefficients(&self) -> Coefficients {
+        let config = &self.config;
+        let mut coef = Coefficients::at(config, self.iteration);
+        if let Some(inertia) = &config.inertia_schedule {
+            let spread = match inertia {
+                InertiaSchedule::Adaptive { .. } if self.initial_diversity > 0.0 => {
+                    diagnostics::diversity(&self.swarm.particles, config.dimensions)
+                        / self.initial_diversity
+                }
+                _ => 0.0,
+            };
+            let progress = schedule::progress(self.iteration, config.iterations);
+            coef.w = inertia.value(progress, self.chaos, spread, self.success_rate);
+        }
+        coef
+    }
+
+    // Switches the social term to `topology` from the next iteration on
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.neighbourhoods =
+            Neighbourhoods::build(&topology, self.config.particles, &mut self.rng);
+        self.log_event(EventKind::Topology, "switch", &topology.describe());
+        self.config.topology = topology;
+    }
+
+    // Ends the run before its planned length, e.g. on a cancellation request
+    pub fn stop(&mut self, reason: &'static str) {
+        self.stopped = Some(reason);
+        self.log_event(EventKind::Stop, reason, "stop requested");
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.stopped.is_some() || self.iteration >= self.config.iterations
+    }
+
+    pub fn step(&mut self) {
+        // the epsilon level and equality band of this iteration, for the bests
+        // and the archive alike
+        if self.config.advance(self.iteration) {
+            let config = &self.config;
+            let penalty = config
+                .penalty_factor
+                .value(self.iteration, config.iterations)
+                * self.penalty_scale.value;
+            if config.equality_tolerance.is_some() {
+                restate_violations(config, &mut self.swarm, penalty);
+            }
+            if let Some(archive) = self.archive.as_mut() {
+                archive.rerank(&config.comparison);
+                if config.equality_tolerance.is_some() {
+                    archive.restate(|x| config.constraint_violation(x), penalty);
+                }
+            }
+        }
+        self.draw_active_dims();
+        let coef = self.coefficients();
+        self.chaos = inertia::logistic(self.chaos);
+
+        let it = self.iteration;
+        let config = &self.config;
+        let options = self.options;
+        let swarm = &mut self.swarm;
+        let mut bounds = BoundaryStats::new(config.dimensions);
+        let mut successes = 0;
+        let mut covariance = self
+            .diagnostics_log
+            .is_some()
+            .then(|| Covariance::new(config.dimensions));
+        let penalty_factor = config.penalty_factor.value(it, config.iterations);
+        // per unit of constraint violation; the bound penalty is not adapted
+        let violation_penalty = penalty_factor * self.penalty_scale.value;
+        let vmax = config.vmax.as_ref().map(|v| v.value(it, config.iterations));
+        let chi = config
+            .constriction
+            .as_ref()
+            .map_or(1.0, |s| s.value(it, config.iterations));
+        let partial = config
+            .dimension_subset
+            .as_ref()
+            .is_some_and(|sub| sub.partial_evaluation);
+
+        // (mu+lambda): positions before the move that compete with the new ones
+        let previous: Vec<(Vec<f64>, Vec<f64>, f64)> = if config.generational {
+            swarm
+                .particles
+                .iter()
+                .filter_map(|p| p.fitness.map(|f| (p.x.clone(), p.vx.clone(), f)))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let gbest_before = (swarm.gbest, swarm.gbest_violation);
+
+        // with --threads every particle moves at once: each draws from its
+        // own stream (as with rng_streams in serial runs), follows the bests of the previous iteration and is
+        // evaluated in parallel; the bests are then updated in particle order
+        let ctx = MoveContext {
+            config,
+            coef,
+            chi,
+            vmax,
+            explain: options.explain,
+            iteration: it,
+            mbest: (config.velocity_update == VelocityUpdate::Qpso)
+                .then(|| mean_pbest_x(&swarm.particles)),
+            polytope: self.polytope.as_ref(),
+        };
+        // stochastic ranking under the global topology: the swarm follows the
+        // personal best ranked first, which may be infeasible, instead of gbest
+        let leader = match (&config.comparison, &self.neighbourhoods) {
+            (Comparator::StochasticRanking { .. }, None) => {
+                let scores: Vec<Score> = swarm.particles.iter().map(|p| p.pbest_score()).collect();
+                let first =
+                    selection::ranked_indices(&scores, 1, &config.comparison, &mut self.rng)[0];
+                Some(swarm.particles[first].pbest_x.clone())
+            }
+            _ => None,
+        };
+        let mut streams: Option<Vec<ChaCha12Rng>> = match (config.rng_streams, config.seed) {
+            (true, Some(seed)) => Some(
+                (0..config.particles)
+                    .map(|i| particle_stream(seed, it, i))
+                    .collect(),
+            ),
+            _ => self.pool.as_ref().map(|_| {
+                let base: u64 = self.rng.gen();
+                (0..config.particles)
+                    .map(|id| ChaCha12Rng::seed_from_u64(noise::stream(base, &[id as u64])))
+                    .collect()
+            }),
+        };
+        // synchronous updates: the serial moves follow the bests the iteration began with
+        let frozen = (config.synchronous && self.pool.is_none()).then(|| {
+            let lbests = neighbourhood_bests(self.neighbourhoods.as_ref(), swarm, config);
+            let gbest_x = leader.clone().unwrap_or_else(|| swarm.gbest_x.clone());
+            (gbest_x, lbests)
+        });
+        let mut assessed = match (&self.pool, streams.as_mut()) {
+            (Some(pool), Some(streams)) => {
+                let before = swarm.evaluations;
+                let gbest_x = leader.as_ref().unwrap_or(&swarm.gbest_x);
+                let archive = self.archive.as_ref();
+                let hooks = &self.hooks;
+                let lbests = neighbourhood_bests(self.neighbourhoods.as_ref(), swarm, config);
+                pool.install(|| {
+                    swarm
+                        .particles
+                        .par_iter_mut()
+                        .zip(streams.par_iter_mut())
+                        .zip(self.active_dims.par_iter())
+                        .enumerate()
+                        .map(|(i, ((p, rng), dims))| {
+                            let social_x = lbests.as_ref().map_or(gbest_x, |l| &l[i]);
+                            let moved = move_particle(&ctx, p, dims, social_x, archive, rng);
+                            let (low, promoted) = screen(config, p);
+                            // noise excludes multi-fidelity, so when it keys on
+                            // the evaluation number every particle is evaluated
+                            let fitness = promoted.then(|| {
+                                hooks.before();
+                                fitness_at(config, p, &moved.changed, partial, it, before + i + 1)
+                            });
+                            (moved, low, fitness)
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .into_iter()
+            }
+            _ => Vec::new().into_iter(),
+        };
+
+        for i in 0..config.particles {
+            let (moved, low, fitness) = match assessed.next() {
+                Some((moved, low, fitness)) => {
+                    let p = &swarm.particles[i];
+                    if low.is_some() {
+                        swarm.low_evaluations += 1;
+                    }
+                    if let Some((fitness, cost)) = fitness {
+                        swarm.evaluations += 1;
+                        let objective_x = &p.x[..config.dimensions];
+                        self.hooks.after(
+                            swarm.evaluations,
+                            Some(p.id),
+                            objective_x,
+                            (config.reported(fitness), cost),
+                        );
+                    }
+                    (moved, low, fitness.map(|(fitness, _)| fitness))
+                }
+                None => {
+                    let lbest = match &frozen {
+                        Some((_, lbests)) => lbests.as_ref().map(|l| l[i].clone()),
+                        None => self
+                            .neighbourhoods
+                            .as_ref()
+                            .map(|nb| nb.best(i, &swarm.particles, &config.comparison).to_vec()),
+                    };
+                    let gbest_x = match (&frozen, &leader) {
+                        (Some((g, _)), _) | (None, Some(g)) => g,
+                        (None, None) => &swarm.gbest_x,
+                    };
+                    let p = &mut swarm.particles[i];
+                    let rng = match streams.as_mut() {
+                        Some(streams) => &mut streams[i],
+                        None => &mut self.rng,
+                    };
+                    let moved = move_particle(
+                        &ctx,
+                        p,
+                        &self.active_dims[i],
+                        lbest.as_deref().unwrap_or(gbest_x),
+                        self.archive.as_ref(),
+                        rng,
+                    );
+                    // multi-fidelity: only promote to the accurate evaluation when
+                    // the cheap model predicts a personal best improvement
+                    let (low, promoted) = screen(config, p);
+                    if low.is_some() {
+                        swarm.low_evaluations += 1;
+                    }
+                    let fitness = promoted.then(|| {
+                        self.hooks.before();
+                        let (fitness, cost) = fitness_at(
+                            config,
+                            p,
+                            &moved.changed,
+                            partial,
+                            it,
+                            swarm.evaluations + 1,
+                        );
+                        swarm.evaluations += 1;
+                        let objective_x = &p.x[..config.dimensions];
+                        self.hooks.after(
+                            swarm.evaluations,
+                            Some(p.id),
+                            objective_x,
+                            (config.reported(fitness), cost),
+                        );
+                        fitness
+                    });
+                    (moved, low, fitness)
+                }
+            };
+            let rng = match streams.as_mut() {
+                Some(streams) => &mut streams[i],
+                None => &mut self.rng,
+            };
+
+            let p = &mut swarm.particles[i];
+            self.repairs += moved.repaired as usize;
+            for &pos in &moved.crossed {
+                bounds.hits_per_dimension[pos] += 1;
+            }
+            if !moved.crossed.is_empty() {
+                bounds.pinned_particles += 1;
+            }
+            let penalty = config.boundary.penalty(
+                &p.x[..config.dimensions],
+                |pos| config.bounds_of(pos),
+                moved.crossed.len(),
+                penalty_factor,
+            );
+
+            if let Some(covariance) = covariance.as_mut() {
+                covariance.push(&p.x[..config.dimensions]);
+            }
+            if let Some(heatmap) = self.heatmap.as_mut() {
+                match &self.privacy {
+                    Some(privacy) => heatmap.record(&privacy.apply_all(&p.x)),
+                    None => heatmap.record(&p.x),
+                }
+            }
+
+            // check if new position is better than previous position
+            p.fitness = fitness;
+            let promoted = fitness.is_some();
+            let mut fitness = fitness.unwrap_or(f64::INFINITY);
+
+            fitness += penalty;
+            // constraints from the problem file, weighed by the comparator; an
+            // unpromoted candidate never wins
+            let violation = if promoted {
+                config.constraint_violation(&p.x)
+            } else {
+                f64::INFINITY
+            };
+            let score = config
+                .comparison
+                .score(fitness, violation, violation_penalty);
+            let fitness = score.fitness;
+
+            if options.explain {
+                explain::print_outcome(fitness, penalty > 0.0, p.pbest, swarm.gbest);
+            }
+
+            if config.comparison.better(score, p.pbest_score()) {
+                p.pbest_x = p.x.clone();
+                p.pbest = fitness;
+                p.pbest_violation = score.violation;
+                p.pbest_low = low;
+                p.stagnation = 0;
+                successes += 1;
+                if let Some(archive) = self.archive.as_mut() {
+                    archive.offer(&p.pbest_x, score);
+                }
+            } else {
+                p.stagnation += 1;
+            }
+
+            // heterogeneous swarm: give a stagnating particle new coefficients
+            if let Some(h) = &config.heterogeneous {
+                if h.resample_after.is_some_and(|n| p.stagnation >= n) {
+                    p.coef = Some(Coefficients::sample(h, rng));
+                    p.stagnation = 0;
+                }
+            }
+            // check if new position is better than global best
+            let gbest = Score {
+                fitness: swarm.gbest,
+                violation: swarm.gbest_violation,
+            };
+            if config.comparison.better(score, gbest) {
+                if let Some(log) = self.improvement_log.as_mut() {
+                    let cost = config.cost_of(self.hooks.cost, swarm.low_evaluations);
+                    log.log(
+                        it,
+                        swarm.evaluations,
+                        config.reported(fitness),
+                        swarm.gbest - fitness,
+                        p.id,
+                        cost,
+                    );
+                }
+                p.gbest_improvements += 1;
+                p.gbest_gain += swarm.gbest - fitness;
+                swarm.gbest_x = p.x.clone();
+                swarm.gbest = fitness;
+                swarm.gbest_violation = score.violation;
+            }
+        }
+
+        // adaptive random topology: new links after an iteration without progress
+        let redrawn = matches!(config.topology, Topology::Random { .. })
+            && (swarm.gbest, swarm.gbest_violation) == gbest_before;
+        if redrawn {
+            self.neighbourhoods =
+                Neighbourhoods::build(&config.topology, config.particles, &mut self.rng);
+        }
+
+        if config.generational {
+            replace_generation(
+                config,
+                violation_penalty,
+                &mut swarm.particles,
+                previous,
+                &mut self.rng,
+            );
+        }
+
+        if let Some(adaptive) = &config.adaptive_penalty {
+            let feasible = swarm.gbest_violation == 0.0;
+            if let Some(previous) = self.penalty_scale.adapt(adaptive, feasible) {
+                let change = penalty_factor * (self.penalty_scale.value - previous);
+                reweigh_violations(swarm, change);
+            }
+        }
+
+        let pinned_fraction = bounds.pinned_particles as f64 / config.particles as f64;
+        if pinned_fraction > options.saturation_warning {
+            if self.saturated_iterations == 0 && !options.batch {
+                eprintln!(
+                    "Warning: {:.0}% of the swarm is pinned to a bound at iteration {} \
+                     (check the bounds or velocity limits)",
+                    pinned_fraction * 100.0,
+                    it
+                );
+            }
+            self.saturated_iterations += 1;
+        }
+
+        self.success_rate = successes as f64 / config.particles as f64;
+        if let (Some(log), Some(covariance)) = (self.diagnostics_log.as_mut(), &covariance) {
+            log.log(it, &bounds, covariance, self.success_rate);
+        }
+
+        if options.disperse_duplicates {
+            self.dispersed += dedup::disperse_duplicates(
+                &mut swarm.particles,
+                options.duplicate_tolerance,
+                config,
+                &mut self.rng,
+            );
+        }
+
+        let positions: Vec<&[f64]> = swarm
+            .particles
+            .iter()
+            .map(|p| &p.x[..config.dimensions])
+            .collect();
+        let (mean_velocity, max_velocity) =
+            diagnostics::velocity_norms(&swarm.particles, config.dimensions);
+        let (inequality_violation, equality_violation) = config.logged_violations(&swarm.gbest_x);
+        let point = TracePoint {
+            iteration: it,
+            evaluations: swarm.evaluations,
+            best: config.reported(swarm.gbest),
+            mean_fitness: config.reported(mean_fitness(&swarm.particles)),
+            diversity: diagnostics::spread(&positions),
+            seconds: self.stop_monitor.elapsed(),
+            at_bounds: diagnostics::at_bounds(&positions, config),
+            mean_velocity: Some(mean_velocity),
+            max_velocity: Some(max_velocity),
+            mean_pbest: Some(config.reported(mean_pbest(&swarm.particles))),
+            inequality_violation,
+            equality_violation,
+        };
+        output::log_iteration(&point, config, options, self.privacy);
+        if let Some(trace) = self.convergence.as_mut() {
+            trace.record(&point);
+        }
+        self.history.push(point);
+
+        if let Some(animation) = &self.animation {
+            if let Err(e) = animation.frame(it, &positions, &swarm.gbest_x) {
+                eprintln!("Warning: {}; the animation stops here", e);
+                self.animation = None;
+            }
+        }
+
+        if let Some(projection) = self.projection.as_mut() {
+            for p in &swarm.particles {
+                let x = &p.x[..config.dimensions];
+                match &self.privacy {
+                    Some(privacy) => projection.record(it, p.id, &privacy.apply_all(x)),
+                    None => projection.record(it, p.id, x),
+                }
+            }
+        }
+
+        if let Some(view) = &self.view {
+            view.publish(it + 1, point.best, swarm);
+            if options.web_delay > 0 {
+                std::thread::sleep(std::time::Duration::from_millis(options.web_delay));
+            }
+        }
+
+        if let Some(trigger) = self.stop_monitor.check(config, swarm, self.hooks.cost) {
+            match trigger.action {
+                StopAction::Stop => {
+                    self.stopped = Some(trigger.condition);
+                    let detail = format!("stop: {}", trigger.detail);
+                    self.log_event(EventKind::Stop, trigger.condition, &detail);
+                }
+                StopAction::Restart => {
+                    let detail = format!("restart: {}", trigger.detail);
+                    self.log_event(EventKind::Restart, trigger.condition, &detail);
+                    self.restart();
+                }
+            }
+        }
+
+        if redrawn {
+            self.log_event(EventKind::Topology, "redraw", "no gbest improvement");
+        }
+        if self.config.escape.any() {
+            self.escape();
+        }
+        let every = self.config.local_search.as_ref().and_then(|l| l.every);
+        if every.is_some_and(|k| (it + 1).is_multiple_of(k)) {
+            self.polish(it);
+        }
+
+        self.iteration += 1;
+        if self
+            .iteration
+            .is_multiple_of(options.checkpoint_every as usize)
+        {
+            self.save_checkpoint();
+        }
+    }
+
+    fn save_checkpoint(&self) {
+        let Some(path) = &self.options.checkpoint else {
+            return;
+        };
+        if let Err(e) = self.checkpoint().save(path) {
+            eprintln!("Warning: {}", e);
+        }
+    }
+
+    // Everything the rest of the run depends on
+    pub fn checkpoint(&self) -> Checkpoint {
+        let swarm = &self.swarm;
+        Checkpoint {
+            version: checkpoint::VERSION,
+            iteration: self.iteration,
+            evaluations: swarm.evaluations,
+            low_evaluations: swarm.low_evaluations,
+            cost: Some(self.hooks.cost),
+            dimensions: self.config.dimensions,
+            gbest: swarm.gbest,
+            gbest_violation: swarm.gbest_violation,
+            gbest_x: swarm.gbest_x.clone(),
+            config: Checkpoint::parameters(&self.config),
+            rng: RngState::of(&self.rng),
+            stop: self.stop_monitor.state(),
+            penalty_scale: self.penalty_scale.clone(),
+            active_dims: self.active_dims.clone(),
+            groups: self
+                .config
+                .block_coordinate
+                .as_ref()
+                .map_or_else(Vec::new, |block| block.groups.clone()),
+            chaos: self.chaos,
+            success_rate: self.success_rate,
+            initial_diversity: self.initial_diversity,
+            dispersed: self.dispersed,
+            repairs: self.repairs,
+            saturated_iterations: self.saturated_iterations,
+            restarts: self.restarts,
+            stagnant_iterations: self.stagnant_iterations,
+            escape_reference: self.escape_reference,
+            refinements: self.refinements,
+            archive: self.archive.clone(),
+            neighbourhoods: self.neighbourhoods.clone(),
+            particles: swarm.particles.clone(),
+            history: self.history.clone(),
+            events: self.events.clone(),
+            upgraded_from: None,
+        }
+    }
+
+    // Continues from `checkpoint` instead of a new swarm
+    fn restore(&mut self, checkpoint: Checkpoint) {
+        self.swarm = Swarm {
+            particles: checkpoint.particles,
+            gbest_x: checkpoint.gbest_x,
+            gbest: checkpoint.gbest,
+            gbest_violation: checkpoint.gbest_violation,
+            evaluations: checkpoint.evaluations,
+            low_evaluations: checkpoint.low_evaluations,
+        };
+        // older checkpoints charged every evaluation the same
+        self.hooks.cost = checkpoint
+            .cost
+            .unwrap_or(checkpoint.evaluations as f64 * self.config.evaluation_cost.unwrap_or(0.0));
+        self.iteration = checkpoint.iteration;
+        self.rng = checkpoint.rng.restore();
+        self.stop_monitor = StopMonitor::resume(&checkpoint.stop);
+        self.penalty_scale = checkpoint.penalty_scale;
+        self.active_dims = checkpoint.active_dims;
+        self.chaos = checkpoint.chaos;
+        self.success_rate = checkpoint.success_rate;
+        self.initial_diversity = checkpoint.initial_diversity;
+        self.dispersed = checkpoint.dispersed;
+        self.repairs = checkpoint.repairs;
+        self.saturated_iterations = checkpoint.saturated_iterations;
+        self.restarts = checkpoint.restarts;
+        self.stagnant_iterations = checkpoint.stagnant_iterations;
+        self.escape_reference = checkpoint.escape_reference;
+        self.refinements = checkpoint.refinements;
+        self.archive = checkpoint.archive;
+        self.neighbourhoods = checkpoint.neighbourhoods;
+        self.history = checkpoint.history;
+        self.events = checkpoint.events;
+    }
+
+    // Flushes the end-of-run outputs and prints the final report
+    pub fn finish(self) -> Swarm {
+        let options = self.options;
+
+        if !self
+            .iteration
+            .is_multiple_of(options.checkpoint_every as usize)
+        {
+            self.save_checkpoint();
+        }
+
+        if let (Some(heatmap), Some(path)) = (&self.heatmap, &options.heatmap) {
+            heatmap.save(path);
+        }
+        if let (Some(projection), Some(path)) = (&self.projection, &options.projection) {
+            projection.save(path);
+        }
+
+        if let Some(view) = &self.view {
+            view.finish();
+        }
+
+        if !options.batch && !self.config.quiet {
+            self.report();
+        }
+        self.swarm
+    }
+
+    // Best positions first: the elite archive's members, or the personal
+    // bests without an archive
+    pub fn elite(&self) -> Vec<Vec<f64>> {
+        if let Some(archive) = &self.archive {
+            return archive
+                .positions()
+                .into_iter()
+                .map(<[f64]>::to_vec)
+                .collect();
+        }
+        let mut particles: Vec<&Particle> = self.swarm.particles.iter().collect();
+        particles.sort_by(|a, b| {
+            self.config
+                .comparison
+                .ordering(a.pbest_score(), b.pbest_score())
+        });
+        particles.iter().map(|p| p.pbest_x.clone()).collect()
+    }
+
+    // Final report: counters of the optional mechanisms and the best solution
+    fn report(&self) {
+        let options = self.options;
+
+        if options.disperse_duplicates {
+            println!("Duplicate particles dispersed: {}", self.dispersed);
+        }
+        if self.polytope.is_some() {
+            println!(
+                "Linear repair: {} particle position(s) projected onto the constraints",
+                self.repairs
+            );
+        }
+        if let (Some(g), Some(h)) = self.config.logged_violations(&self.swarm.gbest_x) {
+            println!(
+                "Constraint violation of gbest: {} inequality, {} equality",
+                g, h
+            );
+        }
+        if self.saturated_iterations > 0 {
+            println!(
+                "Boundary saturation above {:.0}% in {} of {} iterations",
+                options.saturation_warning * 100.0,
+                self.saturated_iterations,
+                self.iteration
+            );
+        }
+
+        if let Some(condition) = self.stopped {
+            println!(
+                "Stopped early by {} after {} of {} iterations",
+                condition, self.iteration, self.config.iterations
+            );
+            if self.config.multi_fidelity.is_none() {
+                println!("Evaluations: {}", self.swarm.evaluations);
+            }
+        }
+        if self.restarts > 0 {
+            let causes = events::census(&self.events, EventKind::Restart);
+            println!(
+                "Swarm restarts: {} ({})",
+                self.restarts,
+                causes.unwrap_or_default()
+            );
+        }
+        if let Some(escapes) = events::census(&self.events, EventKind::Escape) {
+            println!("Stagnation escapes: {}", escapes);
+        }
+        if let Some(l) = &self.config.local_search {
+            let r = self.refinements;
+            println!(
+                "Local search ({}): {} refinement(s), {} improved gbest, {} evaluations",
+                l.method.name(),
+                r.runs,
+                r.improved,
+                r.evaluations
+            );
+        }
+        if let Some(archive) = &self.archive {
+            println!(
+                "Elite archive: {} member(s), worst fitness {}",
+                archive.len(),
+                archive.worst().unwrap_or(f64::NAN)
+            );
+        }
+        if self.config.adaptive_penalty.is_some() {
+            println!(
+                "Final constraint penalty: {} per unit of violation",
+                self.config
+                    .penalty_factor
+                    .value(self.iteration, self.config.iterations)
+                    * self.penalty_scale.value
+            );
+        }
+        if self.config.multi_fidelity.is_some() {
+            println!(
+                "Evaluations: {} high fidelity, {} low fidelity",
+                self.swarm.evaluations, self.swarm.low_evaluations
+            );
+        }
+        if self.config.evaluation_cost.is_some() || self.hooks.cost > 0.0 {
+            println!(
+                "Evaluation cost: {} over {} evaluations",
+                self.config
+                    .cost_of(self.hooks.cost, self.swarm.low_evaluations),
+                self.swarm.evaluations + self.swarm.low_evaluations
+            );
+        }
+
+        let swarm = &self.swarm;
+        let config = &self.config;
+        println!(
+            "Best solution found at: fitness = {}",
+            output::format_value(config.reported(swarm.gbest), options.console_precision())
+        );
+        for (i, xi) in swarm.gbest_x[..config.dimensions].iter().enumerate() {
+            println!("x{}: {}", i + 1, xi);
+        }
+        if config.self_adaptive.is_some() {
+            let d = config.dimensions;
+            println!(
+                "Strategy of the best particle: w = {}, c1 = {}, c2 = {}",
+                swarm.gbest_x[d],
+                swarm.gbest_x[d + 1],
+                swarm.gbest_x[d + 2]
+            );
+        }
+
+        print_contributions(swarm, config);
+
+        let advice = advice::advise(config, &self.history, self.stopped.is_some());
+        if !advice.is_empty() {
+            println!("\nSuggestions:");
+            for line in advice {
+                println!("  {}", line);
+            }
+        }
+    }
+}
+
+impl Optimizer for Run<'_> {
+    fn step(&mut self) {
+        Run::step(self);
+    }
+
+    fn is_finished(&self) -> bool {
+        Run::is_finished(self)
+    }
+
+    fn stop(&mut self, reason: &'static str) {
+        Run::stop(self, reason);
+    }
+
+    fn best(&self) -> f64 {
+        self.swarm.gbest
+    }
+
+    fn progress(&self) -> TracePoint {
+        self.history.last().copied().unwrap_or_default()
+    }
+
+    fn finish(mut self: Box<Self>) -> Outcome {
+        // without `every`, gbest is refined once the run is over, which the
+        // last iteration's trace point then includes
+        let at_end = self
+            .config
+            .local_search
+            .as_ref()
+            .is_some_and(|l| l.every.is_none());
+        if let (true, Some(last)) = (at_end, self.history.last().copied()) {
+            self.polish(last.iteration);
+            let point = TracePoint {
+                evaluations: self.swarm.evaluations,
+                best: self.config.reported(self.swarm.gbest),
+                ..last
+            };
+            if let Some(trace) = self.convergence.as_mut() {
+                trace.amend(&point);
+            }
+            *self.history.last_mut().expect("the run has iterations") = point;
+        }
+        let elite = self.elite();
+        let stopped = self.stopped;
+        // the final report reads them too
+        let history = self.history.clone();
+        let events = self.events.clone();
+        let swarm = Run::finish(*self);
+        Outcome {
+            history,
+            events,
+            best_x: swarm.gbest_x,
+            best: swarm.gbest,
+            evaluations: swarm.evaluations,
+            elite,
+            stopped,
+        }
+    }
+}
+
+// Mean current fitness of the particles evaluated so far
+fn mean_fitness(particles: &[Particle]) -> f64 {
+    let known: Vec<f64> = particles.iter().filter_map(|p| p.fitness).collect();
+    known.iter().sum::<f64>() / known.len().max(1) as f64
+}
+
+// Mean personal best over the particles that have a finite one
+fn mean_pbest(particles: &[Particle]) -> f64 {
+    let known: Vec<f64> = particles
+        .iter()
+        .map(|p| p.pbest)
+        .filter(|f| f.is_finite())
+        .collect();
+    known.iter().sum::<f64>() / known.len().max(1) as f64
+}
+
+// Adds `change` per unit of violation to the recorded penalized bests, after
+// the penalty on constraint violations changed
+fn reweigh_violations(swarm: &mut Swarm, change: f64) {
+    let bests = swarm
+        .particles
+        .iter_mut()
+        .map(|p| (&mut p.pbest, p.pbest_violation))
+        .chain([(&mut swarm.gbest, swarm.gbest_violation)]);
+    for (fitness, violation) in bests {
+        // an unevaluated best stays infinitely bad
+        if violation > 0.0 && violation.is_finite() {
+            *fitness += change * violation;
+        }
+    }
+}
+
+// Re-measures the violations of the bests after the equality band has moved,
+// without evaluating the objective again; a penalizing comparator moves
+// their fitness along by `penalty` per unit
+fn restate_violations(config: &Config, swarm: &mut Swarm, penalty: f64) {
+    let bests = swarm
+        .particles
+        .iter_mut()
+        .map(|p| (&p.pbest_x, &mut p.pbest, &mut p.pbest_violation))
+        .chain([(&swarm.gbest_x, &mut swarm.gbest, &mut swarm.gbest_violation)]);
+    for (x, fitness, violation) in bests {
+        // an unevaluated best stays infinitely bad
+        if x.is_empty() || !violation.is_finite() {
+            continue;
+        }
+        let now = config.constraint_violation(x);
+        if config.comparison.penalizes() && now.is_finite() {
+            *fitness += penalty * (now - *violation);
+        }
+        *violation = now;
+    }
+}
+
+// Keeps the best positions of the old and the new generation, best first in
+// particle order. Personal bests stay with their particles; only position,
+// velocity and current fitness move.
+fn replace_generation(
+    config: &Config,
+    violation_penalty: f64,
+    particles: &mut [Particle],
+    previous: Vec<(Vec<f64>, Vec<f64>, f64)>,
+    rng: &mut impl Rng,
+) {
+    let mut pool = previous;
+    pool.extend(
+        particles
+            .iter()
+            .filter_map(|p| p.fitness.map(|f| (p.x.clone(), p.vx.clone(), f))),
+    );
+    let scores: Vec<Score> = pool
+        .iter()
+        .map(|(x, _, f)| {
+            let violation = config.constraint_violation(x);
+            config.comparison.score(*f, violation, violation_penalty)
+        })
+        .collect();
+    let survivors = selection::ranked_indices(&scores, particles.len(), &config.comparison, rng);
+
+    let mut pool: Vec<Option<_>> = pool.into_iter().map(Some).collect();
+    for (p, k) in particles.iter_mut().zip(survivors) {
+        let (x, vx, f) = pool[k].take().expect("survivor selected twice");
+        p.x = x;
+        p.vx = vx;
+        p.fitness = Some(f);
+    }
+}
+
+// Fills in empty block-coordinate groups with differential grouping.
+// Returns the evaluations spent.
+fn discover_groups(config: &mut Config, hooks: &mut EvalHooks, verbose: bool) -> usize {
+    let Some(block) = &config.block_coordinate else {
+        return 0;
+    };
+    let Some(dg) = block.differential_grouping.clone() else {
+        return 0;
+    };
+    if !block.groups.is_empty() {
+        return 0;
+    }
+
+    let mut evaluations = 0;
+    let groups = grouping::differential_grouping(config, dg.epsilon, dg.max_evaluations, |x| {
+        evaluate(config, &mut evaluations, hooks, EvalContext::analysis(), x)
+    });
+    let groups: Vec<Vec<usize>> = groups
+        .into_iter()
+        .map(|g| g.into_iter().map(|d| d + 1).collect())
+        .collect();
+    if verbose {
+        println!(
+            "Differential grouping found {} group(s) in {} evaluations: {:?}",
+            groups.len(),
+            evaluations,
+            groups
+        );
+    }
+
+    if let Some(block) = config.block_coordinate.as_mut() {
+        block.groups = groups;
+    }
+    evaluations
+}
+
+fn print_contributions(swarm: &Swarm, config: &Config) {
+    let total: usize = swarm.particles.iter().map(|p| p.gbest_improvements).sum();
+
+    println!("\nContributions to gbest ({} improvements):", total);
+    for p in &swarm.particles {
+        if p.gbest_improvements == 0 {
+            continue;
+        }
+        let coef = match p.own_coefficients(config) {
+            Some(c) => format!(" [w = {:.3}, c1 = {:.3}, c2 = {:.3}]", c.w, c.c1, c.c2),
+            None => String::new(),
+        };
+        println!(
+            "  Particle {}: {} improvements ({:.1}%), total gain {}{}",
+            p.id,
+            p.gbest_improvements,
+            100.0 * p.gbest_improvements as f64 / total as f64,
+            p.gbest_gain,
+            coef
+        );
+    }
+
+    let idle = swarm
+        .particles
+        .iter()
+        .filter(|p| p.gbest_improvements == 0)
+        .count();
+    println!("  Particles without contributions: {}", idle);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::boundary::BoundaryHandling;
+    use crate::config::{
+        Escape, GbestMutation, LocalMethod, LocalSearch, Opposition, PartialRestart,
+    };
+    use crate::functions::Function;
+    use crate::schedule::Param;
+    use rand::rngs::StdRng;
+
+    // One particle whose pbest and gbest sit at the optimum of the quadratic
+    // never improves them, so with r1 = r2 = r fixed its position follows
+    // x[t+1] = (1 + w - phi) x[t] - w x[t-1], phi = (c1 + c2) r.
+    fn simulate(coef: &Coefficients, r: f64, x0: f64, v0: f64, steps: usize) -> Vec<f64> {
+        let (mut x, mut v) = (x0, v0);
+        let mut xs = vec![x];
+        for _ in 0..steps {
+            let (inertia, cognitive, social) = velocity_terms(coef, v, x, 0.0, 0.0, r, r);
+            v = inertia + cognitive + social;
+            x += v;
+            xs.push(x);
+        }
+        xs
+    }
+
+    // Closed form of the recurrence via the roots of l^2 - (1 + w - phi) l + w = 0
+    fn analytic(coef: &Coefficients, r: f64, x0: f64, v0: f64, t: usize) -> f64 {
+        let phi = (coef.c1 + coef.c2) * r;
+        let a = 1.0 + coef.w - phi;
+        let disc = a * a - 4.0 * coef.w;
+        let x1 = (1.0 - phi) * x0 + coef.w * v0;
+        let t = t as i32;
+
+        if disc > 0.0 {
+            let (l1, l2) = ((a + disc.sqrt()) / 2.0, (a - disc.sqrt()) / 2.0);
+            let k2 = (x1 - l1 * x0) / (l2 - l1);
+            let k1 = x0 - k2;
+            k1 * l1.powi(t) + k2 * l2.powi(t)
+        } else {
+            // complex pair rho * e^(+-i theta)
+            let rho = coef.w.sqrt();
+            let theta = (a / (2.0 * rho)).acos();
+            let b = (x1 - x0 * rho * theta.cos()) / (rho * theta.sin());
+            rho.powi(t) * (x0 * (theta * t as f64).cos() + b * (theta * t as f64).sin())
+        }
+    }
+
+    fn coefficients(w: f64, c: f64) -> Coefficients {
+        Coefficients { w, c1: c, c2: c }
+    }
+
+    #[test]
+    fn oscillating_trajectory_matches_closed_form() {
+        let coef = coefficients(0.7, 1.5);
+        let xs = simulate(&coef, 0.5, 0.8, 0.1, 40);
+        for (t, x) in xs.iter().enumerate() {
+            assert!(
+                (x - analytic(&coef, 0.5, 0.8, 0.1, t)).abs() < 1e-9,
+                "t = {}",
+                t
+            );
+        }
+    }
+
+    #[test]
+    fn overdamped_trajectory_matches_closed_form() {
+        let coef = coefficients(0.2, 0.5);
+        let xs = simulate(&coef, 0.3, -0.6, 0.0, 40);
+        for (t, x) in xs.iter().enumerate() {
+            assert!(
+                (x - analytic(&coef, 0.3, -0.6, 0.0, t)).abs() < 1e-9,
+                "t = {}",
+                t
+            );
+        }
+    }
+
+    // stable coefficients in the oscillating regime, r fixed for every draw
+    #[test]
+    fn stable_parameters_converge_to_the_optimum() {
+        let options = RunOptions::default();
+        let mut config = Config::default();
+        config.function = Function::Quadratic;
+        config.dimensions = 2;
+        config.particles = 5;
+        config.iterations = 300;
+        config.seed = Some(3);
+        config.deterministic = Some(0.5);
+        config.w = Param::Value(0.7);
+        config.c1 = Param::Value(1.5);
+        config.c2 = Param::Value(1.5);
+        let mut run = Run::new(config, &options).unwrap();
+        while !run.is_finished() {
+            run.step();
+        }
+        assert!(run.swarm.gbest < 1e-12, "gbest {}", run.swarm.gbest);
+        for p in &run.swarm.particles {
+            assert!(p.x.iter().all(|x| x.abs() < 1e-5), "{:?}", p.x);
+        }
+    }
+
+    #[test]
+    fn threaded_runs_keep_the_evaluation_rate() {
+        let options = RunOptions {
+            threads: Some(4),
+            ..RunOptions::default()
+        };
+        let mut config = Config::default();
+        config.particles = 8;
+        config.iterations = 5;
+        config.seed = Some(2);
+        config.max_evaluations_per_second = Some(500.0);
+        let start = std::time::Instant::now();
+        let mut run = Run::new(config, &options).unwrap();
+        while !run.is_finished() {
+            run.step();
+        }
+        let evaluations = run.swarm.evaluations;
+        assert!(evaluations >= 48);
+        let elapsed = start.elapsed().as_secs_f64();
+        assert!(elapsed >= (evaluations - 1) as f64 / 500.0, "{} s", elapsed);
+    }
+
+    // a lone particle whose best is already the optimum follows the closed form
+    #[test]
+    fn deterministic_runs_follow_the_closed_form() {
+        let options = RunOptions::default();
+        let mut config = Config::default();
+        config.function = Function::Quadratic;
+        config.dimensions = 1;
+        config.particles = 1;
+        config.iterations = 40;
+        config.seed = Some(1);
+        config.deterministic = Some(0.5);
+        config.w = Param::Value(0.7);
+        config.c1 = Param::Value(1.5);
+        config.c2 = Param::Value(1.5);
+        config.boundary = BoundaryHandling::PenaltyOnly { factor: 0.0 };
+        let coef = coefficients(0.7, 1.5);
+        let mut run = Run::new(config, &options).unwrap();
+        let p = &mut run.swarm.particles[0];
+        (p.x[0], p.vx[0], p.pbest_x[0], p.pbest) = (0.8, 0.1, 0.0, 0.0);
+        (run.swarm.gbest_x[0], run.swarm.gbest) = (0.0, 0.0);
+
+        for t in 1..=40 {
+            run.step();
+            let p = &run.swarm.particles[0];
+            let x = analytic(&coef, 0.5, 0.8, 0.1, t);
+            let v = x - analytic(&coef, 0.5, 0.8, 0.1, t - 1);
+            assert!((p.x[0] - x).abs() < 1e-9, "x at t = {}", t);
+            assert!((p.vx[0] - v).abs() < 1e-9, "v at t = {}", t);
+        }
+    }
+
+    // w = 1.2 makes the swarm diverge, so particles cross the bounds all the time
+    #[test]
+    fn boundary_handling_keeps_particles_feasible() {
+        let options = RunOptions::default();
+        for boundary in [
+            BoundaryHandling::Clamp,
+            BoundaryHandling::Reflect,
+            BoundaryHandling::Wrap,
+            BoundaryHandling::RandomReinit,
+        ] {
+            let mut config = Config::default();
+            config.boundary = boundary.clone();
+            config.w = Param::Value(1.2);
+            config.iterations = 50;
+            config.seed = Some(5);
+            let mut run = Run::new(config, &options).unwrap();
+            while !run.is_finished() {
+                run.step();
+                for p in &run.swarm.particles {
+                    assert!(
+                        p.x.iter().all(|x| (-5.12..=5.12).contains(x)),
+                        "{:?} left the box at iteration {}",
+                        boundary,
+                        run.iteration
+                    );
+                }
+            }
+        }
+    }
+
+    // the weight follows the share of particles whose pbest just improved
+    #[test]
+    fn success_rate_inertia_follows_the_improving_particles() {
+        let options = RunOptions::default();
+        let mut config = Config::default();
+        config.seed = Some(4);
+        config.iterations = 20;
+        config.inertia_schedule = Some(InertiaSchedule::SuccessRate {
+            w_max: 0.9,
+            w_min: 0.4,
+        });
+        let mut run = Run::new(config, &options).unwrap();
+        assert_eq!(run.coefficients().w, 0.9);
+        while !run.is_finished() {
+            run.step();
+            let improved = run.swarm.particles.iter().filter(|p| p.stagnation == 0);
+            let share = improved.count() as f64 / run.swarm.particles.len() as f64;
+            assert!((run.coefficients().w - (0.4 + 0.5 * share)).abs() < 1e-12);
+        }
+    }
+
+    // a move recomputed from the replayed stream alone matches the run's
+    #[test]
+    fn particle_streams_replay_a_move() {
+        let options = RunOptions::default();
+        let mut config = Config::default();
+        config.rng_streams = true;
+        config.synchronous = true;
+        config.seed = Some(9);
+        config.function = Function::Quadratic;
+        config.dimensions = 2;
+        // no bound handling touches the velocities
+        config.boundary = BoundaryHandling::PenaltyOnly { factor: 0.0 };
+        let mut run = Run::new(config, &options).unwrap();
+        run.step();
+        let (it, i) = (run.iteration, 2);
+        let p = run.swarm.particles[i].clone();
+        let gbest_x = run.swarm.gbest_x.clone();
+        run.step();
+
+        let mut rng = particle_stream(9, it, i);
+        let (r1, r2): (f64, f64) = (rng.gen(), rng.gen());
+        let coef = Coefficients {
+            w: 0.9,
+            c1: 1.3,
+            c2: 1.1,
+        };
+        for (pos, &g) in gbest_x.iter().enumerate() {
+            let (inertia, cognitive, social) =
+                velocity_terms(&coef, p.vx[pos], p.x[pos], p.pbest_x[pos], g, r1, r2);
+            let v = inertia + cognitive + social;
+            assert!((run.swarm.particles[i].vx[pos] - v).abs() <= 1e-9 * v.abs());
+        }
+    }
+
+    // every mechanism fires after each iteration without progress
+    #[test]
+    fn escapes_keep_the_global_best_and_the_best_particles() {
+        let options = RunOptions::default();
+        let mut config = Config::default();
+        config.dimensions = 10;
+        config.iterations = 100;
+        config.seed = Some(9);
+        config.escape = Escape {
+            min_improvement: 0.0,
+            gbest_mutation: Some(GbestMutation {
+                after: 1,
+                sigma: 0.05,
+                particles: 3,
+            }),
+            opposition: Some(Opposition { after: 1 }),
+            partial_restart: Some(PartialRestart { after: 1, keep: 5 }),
+        };
+        let mut run = Run::new(config, &options).unwrap();
+        let mut gbest = f64::INFINITY;
+        while !run.is_finished() {
+            run.step();
+            assert!(run.swarm.gbest <= gbest);
+            gbest = run.swarm.gbest;
+            let best_pbest = run
+                .swarm
+                .particles
+                .iter()
+                .map(|p| p.pbest)
+                .fold(f64::INFINITY, f64::min);
+            assert!(best_pbest >= gbest);
+        }
+        assert!(run.stagnant_iterations < 100);
+        let escapes = events::census(&run.events, EventKind::Escape).unwrap();
+        for name in ["gbest_mutation", "opposition", "partial_restart"] {
+            assert!(escapes.contains(name), "{}", escapes);
+        }
+    }
+
+    #[test]
+    fn a_final_local_search_spends_its_budget_on_a_better_gbest() {
+        let options = RunOptions::default();
+        let mut config = Config::default();
+        config.function = Function::Quadratic;
+        config.dimensions = 4;
+        config.iterations = 20;
+        config.seed = Some(4);
+        for method in [LocalMethod::NelderMead, LocalMethod::PatternSearch] {
+            let mut config = config.clone();
+            config.local_search = Some(LocalSearch {
+                method,
+                every: None,
+                evaluations: 150,
+                step: 0.01,
+            });
+            config.validate().unwrap();
+            let mut run: Box<dyn Optimizer> = Box::new(Run::new(config, &options).unwrap());
+            while !run.is_finished() {
+                run.step();
+            }
+            let unpolished = run.best();
+            let outcome = run.finish();
+            assert!(outcome.best < unpolished, "{:?}", method);
+            assert_eq!(outcome.history.last().unwrap().best, outcome.best);
+            // the initial swarm, 20 iterations and the local search
+            assert!(outcome.evaluations <= 30 * 21 + 150);
+        }
+    }
+
+    #[test]
+    fn fixed_variables_keep_their_value_while_the_rest_converge() {
+        let options = RunOptions::default();
+        let mut config = Config::default();
+        config.function = Function::Quadratic;
+        config.dimensions = 3;
+        config.iterations = 60;
+        config.seed = Some(2);
+        config.fixed.insert("x2".to_string(), 1.5);
+        config.validate().unwrap();
+        config.prepare();
+        let mut run = Run::new(config, &options).unwrap();
+        while !run.is_finished() {
+            run.step();
+        }
+        assert!(run
+            .swarm
+            .particles
+            .iter()
+            .all(|p| p.x[1] == 1.5 && p.vx[1] == 0.0 && p.pbest_x[1] == 1.5));
+        assert_eq!(run.swarm.gbest_x[1], 1.5);
+        // the others find the minimum given x2 = 1.5
+        assert!((run.swarm.gbest - 2.25).abs() < 1e-3);
+    }
+
+    #[test]
+    fn penalty_only_lets_particles_out_but_penalizes_them() {
+        let boundary = BoundaryHandling::PenaltyOnly { factor: 100.0 };
+        let mut v = 1.0;
+        let mut x = 6.0;
+        let mut rng = StdRng::seed_from_u64(0);
+        assert!(boundary
+            .apply(&mut x, &mut v, (-5.0, 5.0), &mut rng)
+            .is_some());
+        assert_eq!((x, v), (6.0, 1.0));
+        let penalty = boundary.penalty(&[6.0, -7.0, 0.0], |_| (-5.0, 5.0), 2, 0.0);
+        assert!((penalty - 300.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn every_variant_converges_on_the_quadratic_bowl() {
+        let options = RunOptions::default();
+        for variant in [
+            VelocityUpdate::Standard,
+            VelocityUpdate::Constriction,
+            VelocityUpdate::BareBones,
+            VelocityUpdate::Qpso,
+        ] {
+            let mut config = Config::default();
+            config.function = Function::Quadratic;
+            config.velocity_update = variant;
+            config.c1 = Param::Value(2.05);
+            config.c2 = Param::Value(2.05);
+            config.w = Param::Value(0.7);
+            config.iterations = 300;
+            config.seed = Some(2);
+            config.validate().unwrap();
+            let mut run = Run::new(config, &options).unwrap();
+            while !run.is_finished() {
+                run.step();
+            }
+            assert!(run.swarm.gbest < 1e-3, "{:?}: {}", variant, run.swarm.gbest);
+        }
+    }
+
+    #[test]
+    fn a_resumed_run_matches_the_uninterrupted_one() {
+        let options = RunOptions::default();
+        let mut config = Config::default();
+        config.topology = Topology::Random { k: 3 };
+        config.iterations = 60;
+        config.seed = Some(5);
+        config.validate().unwrap();
+
+        let mut uninterrupted = Run::new(config.clone(), &options).unwrap();
+        while !uninterrupted.is_finished() {
+            uninterrupted.step();
+        }
+
+        let mut first = Run::new(config.clone(), &options).unwrap();
+        for _ in 0..25 {
+            first.step();
+        }
+        let saved = toml::to_string(&first.checkpoint()).unwrap();
+        let mut resumed = Run::new(config, &options).unwrap();
+        resumed.restore(toml::from_str(&saved).unwrap());
+        while !resumed.is_finished() {
+            resumed.step();
+        }
+
+        assert_eq!(
+            resumed.swarm.gbest.to_bits(),
+            uninterrupted.swarm.gbest.to_bits()
+        );
+        assert_eq!(resumed.swarm.gbest_x, uninterrupted.swarm.gbest_x);
+        assert_eq!(resumed.swarm.evaluations, uninterrupted.swarm.evaluations);
+        for (a, b) in resumed
+            .swarm
+            .particles
+            .iter()
+            .zip(&uninterrupted.swarm.particles)
+        {
+            assert_eq!((&a.x, &a.vx, &a.pbest_x), (&b.x, &b.vx, &b.pbest_x));
+        }
+        let bests = |run: &Run| run.history.iter().map(|p| p.best).collect::<Vec<_>>();
+        assert_eq!(bests(&resumed), bests(&uninterrupted));
+    }
+
+    #[test]
+    fn a_resumed_run_keeps_its_discovered_groups() {
+        use crate::config::{BlockCoordinate, DifferentialGrouping};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let evaluations = Arc::new(AtomicUsize::new(0));
+        let counted = evaluations.clone();
+        let mut config = Config::default();
+        config.dimensions = 4;
+        config.lower_bound = -2.0;
+        config.upper_bound = 3.0;
+        config.iterations = 20;
+        config.seed = Some(4);
+        config.block_coordinate = Some(BlockCoordinate {
+            groups: Vec::new(),
+            differential_grouping: Some(DifferentialGrouping::default()),
+            iterations_per_group: 5,
+            share_context: true,
+        });
+        // x0 with x1 and x2 with x3 interact
+        config.objective_fn = Some(Arc::new(move |x: &[f64]| {
+            counted.fetch_add(1, Ordering::Relaxed);
+            (x[0] * x[1]).powi(2) + (x[2] * x[3]).powi(2)
+        }));
+        let path = std::env::temp_dir().join("pso_resumed_groups.toml");
+        let path = path.to_str().unwrap().to_string();
+
+        let headless = RunOptions::default();
+        let first = Run::new(config.clone(), &headless).unwrap();
+        let groups = first
+            .config
+            .block_coordinate
+            .as_ref()
+            .unwrap()
+            .groups
+            .clone();
+        assert_eq!(groups, [vec![1, 2], vec![3, 4]]);
+        first.checkpoint().save(&path).unwrap();
+
+        let options = RunOptions {
+            resume: Some(path),
+            ..RunOptions::default()
+        };
+        let before = evaluations.load(Ordering::Relaxed);
+        let resumed = Run::new(config, &options).unwrap();
+        assert_eq!(evaluations.load(Ordering::Relaxed), before);
+        let block = resumed.config.block_coordinate.as_ref().unwrap();
+        assert_eq!(block.groups, groups);
+    }
+
+    #[test]
+    fn the_cost_budget_counts_the_costs_the_objective_reports() {
+        use crate::functions::ObjectiveFunction;
+        use crate::noise::NoiseStream;
+        use std::sync::Mutex;
+
+        // billed more the farther the first coordinate is from 0
+        #[derive(Default)]
+        struct Billed(Mutex<Vec<f64>>);
+
+        impl ObjectiveFunction for Billed {
+            fn evaluate(&self, x: &[f64]) -> f64 {
+                x.iter().map(|v| v * v).sum()
+            }
+
+            fn evaluate_with_cost(&self, x: &[f64], _: Option<NoiseStream>) -> (f64, Option<f64>) {
+                let cost = 0.5 + x[0].abs();
+                self.0.lock().unwrap().push(cost);
+                (self.evaluate(x), Some(cost))
+            }
+        }
+
+        let billed = Arc::new(Billed::default());
+        let options = RunOptions::default();
+        let mut config = Config::default();
+        config.particles = 10;
+        config.iterations = 1000;
+        config.seed = Some(6);
+        config.stop.cost_budget = Some(200.0);
+        config.objective_fn = Some(billed.clone());
+        config.validate().unwrap();
+        let mut run = Run::new(config, &options).unwrap();
+        while !run.is_finished() {
+            run.step();
+        }
+
+        let costs = billed.0.lock().unwrap();
+        assert_eq!(run.stopped, Some("cost_budget"));
+        assert_eq!(costs.len(), run.swarm.evaluations);
+        assert!(costs.iter().any(|&c| c > 1.0) && costs.iter().any(|&c| c < 1.0));
+        let spent: f64 = costs.iter().sum();
+        assert!((run.hooks.cost - spent).abs() < 1e-9);
+        // the budget ran out in the last iteration, not before
+        let before: f64 = costs[..costs.len() - 10].iter().sum();
+        assert!(
+            before < 200.0 && spent >= 200.0,
+            "{} then {}",
+            before,
+            spent
+        );
+    }
+
+    #[test]
+    fn a_run_that_cannot_start_returns_why() {
+        let path = std::env::temp_dir().join("pso_missing_checkpoint.toml");
+        let _ = std::fs::remove_file(&path);
+        let options = RunOptions {
+            resume: Some(path.to_str().unwrap().to_string()),
+            ..RunOptions::default()
+        };
+        let error = Run::new(Config::default(), &options).err().unwrap();
+        assert!(error.starts_with("cannot read checkpoint"), "{}", error);
+    }
+
+    #[test]
+    fn a_given_up_external_program_stops_the_run() {
+        let path = std::env::temp_dir().join("pso_given_up_command.toml");
+        std::fs::write(
+            &path,
+            "dimensions = 2\n[command]\nprogram = \"false\"\ntimeout = 5.0\n",
+        )
+        .unwrap();
+        let mut config = Config::default();
+        config.particles = 4;
+        config.iterations = 20;
+        let problem = crate::problem::Problem::load(path.to_str().unwrap(), &config).unwrap();
+        config.apply_problem(problem).unwrap();
+        let options = RunOptions::default();
+        let mut run = Run::new(config, &options).unwrap();
+        while !run.is_finished() {
+            run.step();
+        }
+        assert_eq!(run.stopped, Some("objective_failed"));
+        assert!(run.iteration < 20);
+        assert!(run.config.objective_failure().is_some());
+    }
+
+    #[test]
+    fn clerc_constriction_matches_the_usual_constant() {
+        assert!((clerc_constriction(4.1) - 0.729843788).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,149 @@
+// Desc: Repair by projection (`linear_repair`): a particle that violates the
+// linear constraints moves to the nearest point that satisfies all of them
+// within the bounds, instead of carrying a penalty. Nonlinear constraints
+// are left to the comparator.
+//
+// The nearest point of the intersection of half-spaces, hyperplanes and the
+// box is found with Dykstra's alternating projections, each of which is
+// exact; the box comes last, so a repaired point never leaves the bounds.
+use crate::config::Config;
+use crate::constraint::{Constraint, ConstraintKind, Linear};
+
+const SWEEPS: usize = 1000; // most rounds of projections onto every set
+const TOLERANCE: f64 = 1e-12; // change in a round, relative to the box, that ends the repair
+
+pub struct Polytope {
+    constraints: Vec<(Linear, ConstraintKind)>,
+    bounds: Vec<(f64, f64)>,
+    pub nonlinear: usize, // constraints the repair cannot project onto
+}
+
+impl Polytope {
+    // The linear constraints of the problem file and the caller, None without any
+    pub fn of(config: &Config) -> Option<Polytope> {
+        let d = config.dimensions;
+        let problem = config.problem.iter().flat_map(|p| &p.constraints);
+        let all: Vec<&dyn Constraint> = problem
+            .map(|c| c as &dyn Constraint)
+            .chain(config.constraints.iter().map(|c| c.as_ref()))
+            .collect();
+        let constraints: Vec<(Linear, ConstraintKind)> = all
+            .iter()
+            .filter_map(|c| c.linear(d).map(|linear| (linear, c.kind())))
+            .filter(|(linear, _)| linear.a.iter().any(|&ai| ai != 0.0))
+            .collect();
+        (!constraints.is_empty()).then(|| Polytope {
+            nonlinear: all.len() - constraints.len(),
+            constraints,
+            bounds: (0..d).map(|pos| config.bounds_of(pos)).collect(),
+        })
+    }
+
+    pub fn linear(&self) -> usize {
+        self.constraints.len()
+    }
+
+    fn violated(&self, x: &[f64]) -> bool {
+        self.constraints.iter().any(|(linear, kind)| {
+            let g = dot(&linear.a, x) - linear.b;
+            match kind {
+                ConstraintKind::Inequality => g > 0.0,
+                ConstraintKind::Equality { tolerance } => g.abs() > *tolerance,
+            }
+        })
+    }
+
+    // Moves `x` onto the feasible region when it is outside; whether it moved
+    pub fn repair(&self, x: &mut [f64]) -> bool {
+        if !self.violated(x) {
+            return false;
+        }
+        let scale: f64 = self.bounds.iter().map(|(l, u)| (u - l) * (u - l)).sum();
+        let sets = self.constraints.len() + 1;
+        let mut increments = vec![vec![0.0; x.len()]; sets];
+        for _ in 0..SWEEPS {
+            // the iterate can come back to where a round began while the
+            // increments still change, so it is their change that ends it
+            let mut change = 0.0;
+            for (k, increment) in increments.iter_mut().enumerate() {
+                let mut z: Vec<f64> = x.iter().zip(increment.iter()).map(|(a, b)| a + b).collect();
+                let before = z.clone();
+                match self.constraints.get(k) {
+                    Some((linear, kind)) => onto_constraint(linear, *kind, &mut z),
+                    None => {
+                        for (zi, (lower, upper)) in z.iter_mut().zip(&self.bounds) {
+                            *zi = zi.clamp(*lower, *upper);
+                        }
+                    }
+                }
+                for ((inc, b), zi) in increment.iter_mut().zip(&before).zip(&z) {
+                    change += (b - zi - *inc) * (b - zi - *inc);
+                    *inc = b - zi;
+                }
+                x.copy_from_slice(&z);
+            }
+            if change <= TOLERANCE * TOLERANCE * scale {
+                break;
+            }
+        }
+        true
+    }
+}
+
+fn dot(a: &[f64], x: &[f64]) -> f64 {
+    a.iter().zip(x).map(|(ai, xi)| ai * xi).sum()
+}
+
+// Nearest point to `z` on the hyperplane a·x = b, or in the half-space
+// a·x <= b of an inequality
+fn onto_constraint(linear: &Linear, kind: ConstraintKind, z: &mut [f64]) {
+    let excess = dot(&linear.a, z) - linear.b;
+    if kind == ConstraintKind::Inequality && excess <= 0.0 {
+        return;
+    }
+    let norm = dot(&linear.a, &linear.a);
+    for (zi, ai) in z.iter_mut().zip(&linear.a) {
+        *zi -= excess / norm * ai;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repair_finds_the_nearest_feasible_point() {
+        let polytope = Polytope {
+            constraints: vec![
+                // x + y >= 1, as -x - y <= -1
+                (
+                    Linear {
+                        a: vec![-1.0, -1.0],
+                        b: -1.0,
+                    },
+                    ConstraintKind::Inequality,
+                ),
+                // y == 0.8
+                (
+                    Linear {
+                        a: vec![0.0, 1.0],
+                        b: 0.8,
+                    },
+                    ConstraintKind::Equality { tolerance: 0.0 },
+                ),
+            ],
+            bounds: vec![(-1.0, 1.0), (-1.0, 1.0)],
+            nonlinear: 0,
+        };
+        let mut feasible = [0.5, 0.8];
+        assert!(!polytope.repair(&mut feasible));
+
+        let mut x = [-0.5, 0.0];
+        assert!(polytope.repair(&mut x));
+        assert!((x[0] - 0.2).abs() < 1e-9 && (x[1] - 0.8).abs() < 1e-9);
+        // the bounds win over the projection direction
+        let mut y = [-3.0, 3.0];
+        polytope.repair(&mut y);
+        assert!(y[0] >= -1.0 && (y[1] - 0.8).abs() < 1e-9 && y[0] + y[1] >= 1.0 - 1e-9);
+    }
+}
@@ -0,0 +1,164 @@
+// Desc: Interactive command loop for pausing, inspecting and tuning a run
+use std::io::BufRead;
+use std::sync::mpsc::{self, Receiver};
+
+use crate::diagnostics;
+use crate::pso::Run;
+use crate::schedule::Param;
+use crate::topology::Topology;
+
+const HELP: &str = "Commands:
+  show               print iteration, gbest, diversity and coefficients
+  step [N]           run N iterations (default 1) and pause again
+  resume             keep running until the next pause or the end
+  pause              pause after the current iteration
+  set <w|c1|c2> V    fix a coefficient (replacing any schedule) from now on
+  set topology T     switch to topology T (global, ring, von_neumann, random)
+  quit               stop the run and print the final report
+  help               show this message";
+
+enum Action {
+    Stay,
+    Step(usize),
+    Resume,
+    Pause,
+    Quit,
+}
+
+// Commands are read on a separate thread and handed over through a channel,
+// so the run state itself is only ever touched by the optimizer thread.
+fn spawn_reader() -> Receiver<String> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let stdin = std::io::stdin();
+        for line in stdin.lock().lines() {
+            let Ok(line) = line else { break };
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+fn show(run: &Run) {
+    println!(
+        "iteration {}/{}, evaluations {}, gbest {}, diversity {:.6}",
+        run.iteration,
+        run.config.iterations,
+        run.swarm.evaluations,
+        run.config.reported(run.swarm.gbest),
+        diagnostics::diversity(&run.swarm.particles, run.config.dimensions)
+    );
+    let coef = run.coefficients();
+    println!("w = {}, c1 = {}, c2 = {}", coef.w, coef.c1, coef.c2);
+}
+
+fn execute(run: &mut Run, line: &str) -> Action {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    match words.as_slice() {
+        [] => Action::Stay,
+        ["help"] => {
+            println!("{}", HELP);
+            Action::Stay
+        }
+        ["show"] => {
+            show(run);
+            Action::Stay
+        }
+        ["step"] => Action::Step(1),
+        ["step", n] => match n.parse() {
+            Ok(n) => Action::Step(n),
+            Err(_) => {
+                println!("step expects a number of iterations");
+                Action::Stay
+            }
+        },
+        ["resume"] | ["continue"] => Action::Resume,
+        ["pause"] => Action::Pause,
+        ["quit"] | ["exit"] => Action::Quit,
+        ["set", "topology", name] => {
+            match serde_json::from_value::<Topology>(serde_json::json!({ "type": name })) {
+                Ok(topology) => run.set_topology(topology),
+                Err(_) => println!(
+                    "unknown topology `{}` (expected global, ring, von_neumann or random)",
+                    name
+                ),
+            }
+            Action::Stay
+        }
+        ["set", name, value] => {
+            let Ok(value) = value.parse::<f64>() else {
+                println!("set expects a numeric value");
+                return Action::Stay;
+            };
+            match *name {
+                "w" => {
+                    run.config.w = Param::Value(value);
+                    run.config.inertia_schedule = None;
+                }
+                "c1" => run.config.c1 = Param::Value(value),
+                "c2" => run.config.c2 = Param::Value(value),
+                _ => println!("unknown parameter `{}` (expected w, c1 or c2)", name),
+            }
+            Action::Stay
+        }
+        _ => {
+            println!("unknown command `{}` (type `help`)", line.trim());
+            Action::Stay
+        }
+    }
+}
+
+// Drives the run from stdin commands. The run starts paused; closing stdin
+// lets it continue to the end.
+pub fn drive(run: &mut Run) {
+    let commands = spawn_reader();
+    let mut paused = true;
+    let mut pending_steps = 0;
+
+    println!("{}", HELP);
+    show(run);
+
+    while !run.is_finished() {
+        // queued steps run to completion before the next command is read
+        if pending_steps == 0 {
+            let line = if paused {
+                match commands.recv() {
+                    Ok(line) => Some(line),
+                    Err(_) => {
+                        paused = false;
+                        None
+                    }
+                }
+            } else {
+                commands.try_recv().ok()
+            };
+
+            if let Some(line) = line {
+                match execute(run, &line) {
+                    Action::Stay => {}
+                    Action::Step(n) => {
+                        paused = true;
+                        pending_steps = n;
+                    }
+                    Action::Resume => paused = false,
+                    Action::Pause => {
+                        paused = true;
+                        show(run);
+                    }
+                    Action::Quit => return,
+                }
+                continue;
+            }
+        }
+
+        run.step();
+        if pending_steps > 0 {
+            pending_steps -= 1;
+            if pending_steps == 0 {
+                show(run);
+            }
+        }
+    }
+}
@@ -0,0 +1,45 @@
+// Desc: Process resource usage (CPU time, peak memory) for the run summary
+use std::time::Duration;
+
+pub struct ResourceUsage {
+    pub user_time: Duration,
+    pub system_time: Duration,
+    pub peak_rss_bytes: u64,
+}
+
+impl ResourceUsage {
+    pub fn cpu_time(&self) -> Duration {
+        self.user_time + self.system_time
+    }
+}
+
+#[cfg(unix)]
+pub fn current() -> Option<ResourceUsage> {
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    if unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) } != 0 {
+        return None;
+    }
+
+    let to_duration = |tv: libc::timeval| {
+        Duration::from_secs(tv.tv_sec as u64) + Duration::from_micros(tv.tv_usec as u64)
+    };
+
+    // ru_maxrss is reported in bytes on macOS and in kilobytes elsewhere
+    let maxrss = usage.ru_maxrss as u64;
+    let peak_rss_bytes = if cfg!(target_os = "macos") {
+        maxrss
+    } else {
+        maxrss * 1024
+    };
+
+    Some(ResourceUsage {
+        user_time: to_duration(usage.ru_utime),
+        system_time: to_duration(usage.ru_stime),
+        peak_rss_bytes,
+    })
+}
+
+#[cfg(not(unix))]
+pub fn current() -> Option<ResourceUsage> {
+    None
+}
@@ -0,0 +1,35 @@
+// Desc: Random rotations of the search space, to expose coordinate-aligned search bias
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_distr::StandardNormal;
+
+// Uniformly random orthogonal matrix (rows), from Gram-Schmidt on a Gaussian
+// matrix; the same seed always gives the same rotation
+pub fn random_orthogonal(dimensions: usize, seed: u64) -> Vec<Vec<f64>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut rows: Vec<Vec<f64>> = Vec::with_capacity(dimensions);
+
+    while rows.len() < dimensions {
+        let mut v: Vec<f64> = (0..dimensions)
+            .map(|_| rng.sample(StandardNormal))
+            .collect();
+        for r in &rows {
+            let dot: f64 = v.iter().zip(r).map(|(a, b)| a * b).sum();
+            for (vi, ri) in v.iter_mut().zip(r) {
+                *vi -= dot * ri;
+            }
+        }
+        let norm = v.iter().map(|vi| vi * vi).sum::<f64>().sqrt();
+        if norm > 1e-9 {
+            rows.push(v.into_iter().map(|vi| vi / norm).collect());
+        }
+    }
+    rows
+}
+
+pub fn rotate(matrix: &[Vec<f64>], x: &[f64]) -> Vec<f64> {
+    matrix
+        .iter()
+        .map(|row| row.iter().zip(x).map(|(a, b)| a * b).sum())
+        .collect()
+}
@@ -0,0 +1,92 @@
+// Desc: Points of the search box, for initialization, restarts and every
+// other random draw of a position: uniform within each coordinate's bounds,
+// over the integers of an integer variable, and at its value for a fixed one
+use rand::Rng;
+
+use crate::config::Config;
+use crate::problem::VariableType;
+
+// A uniform value of coordinate `pos`; strategy coordinates after the
+// objective's are uniform within their ranges
+pub fn coordinate(config: &Config, pos: usize, rng: &mut impl Rng) -> f64 {
+    let (lower, upper) = config.bounds_of(pos);
+    if let Some(fixed) = config.fixed_value(pos) {
+        return fixed;
+    }
+    let variable = match &config.problem {
+        Some(problem) if pos < config.dimensions => Some(&problem.variables[pos]),
+        _ => None,
+    };
+    match variable {
+        Some(v) if v.kind == VariableType::Integer => {
+            rng.gen_range(lower.ceil() as i64..=upper.floor() as i64) as f64
+        }
+        _ => rng.gen_range(lower..upper),
+    }
+}
+
+// A uniform point over the objective's coordinates
+pub fn point(config: &Config, rng: &mut impl Rng) -> Vec<f64> {
+    (0..config.dimensions)
+        .map(|pos| coordinate(config, pos, rng))
+        .collect()
+}
+
+// `x` moved into coordinate `pos`'s bounds and onto a value its variable allows
+pub fn confine(config: &Config, pos: usize, x: f64) -> f64 {
+    let (lower, upper) = config.bounds_of(pos);
+    config.snap(pos, x.clamp(lower, upper))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::problem::{Problem, Variable};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha12Rng;
+
+    #[test]
+    fn points_respect_bounds_integers_and_fixed_variables() {
+        let variable = |name: &str, bounds: (f64, f64), kind, fixed| Variable {
+            name: name.to_string(),
+            lower_bound: bounds.0,
+            upper_bound: bounds.1,
+            kind,
+            fixed,
+        };
+        let problem = Problem {
+            path: "test".to_string(),
+            objective: None,
+            expression: None,
+            model: None,
+            command: None,
+            plugin: None,
+            portfolio: None,
+            fit: None,
+            optimum: None,
+            variables: vec![
+                variable("x", (-1.0, 2.0), VariableType::Continuous, None),
+                variable("n", (0.5, 3.5), VariableType::Integer, None),
+                variable("z", (-5.0, 5.0), VariableType::Continuous, Some(0.25)),
+            ],
+            constraints: Vec::new(),
+            equality_tolerance: 0.0,
+        };
+        let mut config = Config::default();
+        config.apply_problem(problem).unwrap();
+
+        let mut rng = ChaCha12Rng::seed_from_u64(1);
+        let mut integers = [false; 4];
+        for _ in 0..200 {
+            let x = point(&config, &mut rng);
+            assert!((-1.0..2.0).contains(&x[0]));
+            assert!((1.0..=3.0).contains(&x[1]) && x[1].fract() == 0.0);
+            integers[x[1] as usize] = true;
+            assert_eq!(x[2], 0.25);
+        }
+        // both end integers are drawn, not only those rounding leaves
+        assert_eq!(integers, [false, true, true, true]);
+        assert_eq!(confine(&config, 1, 9.0), 3.0);
+        assert_eq!(confine(&config, 2, -3.0), 0.25);
+    }
+}
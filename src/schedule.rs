@@ -0,0 +1,209 @@
+// Desc: Time-varying parameter schedules
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+// A parameter value that changes over the course of a run. `iteration` counts
+// from 0 and `iterations` is the planned length of the run.
+pub trait Schedule {
+    fn value(&self, iteration: usize, iterations: usize) -> f64;
+}
+
+// Fraction of the run completed, in [0, 1]
+pub fn progress(iteration: usize, iterations: usize) -> f64 {
+    if iterations <= 1 {
+        return 1.0;
+    }
+    (iteration as f64 / (iterations - 1) as f64).min(1.0)
+}
+
+/// Fixed value for the whole run
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Constant {
+    pub value: f64,
+}
+
+impl Schedule for Constant {
+    fn value(&self, _iteration: usize, _iterations: usize) -> f64 {
+        self.value
+    }
+}
+
+/// Straight line from `start` to `end`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Linear {
+    pub start: f64,
+    pub end: f64,
+}
+
+impl Schedule for Linear {
+    fn value(&self, iteration: usize, iterations: usize) -> f64 {
+        self.start + (self.end - self.start) * progress(iteration, iterations)
+    }
+}
+
+/// Geometric interpolation from `start` to `end` (both must be positive)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Exponential {
+    pub start: f64,
+    pub end: f64,
+}
+
+impl Schedule for Exponential {
+    fn value(&self, iteration: usize, iterations: usize) -> f64 {
+        self.start * (self.end / self.start).powf(progress(iteration, iterations))
+    }
+}
+
+/// Half-cosine annealing from `start` to `end`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Cosine {
+    pub start: f64,
+    pub end: f64,
+}
+
+impl Schedule for Cosine {
+    fn value(&self, iteration: usize, iterations: usize) -> f64 {
+        let t = progress(iteration, iterations);
+        self.end + (self.start - self.end) * 0.5 * (1.0 + (std::f64::consts::PI * t).cos())
+    }
+}
+
+/// Starts at `start` and is multiplied by `factor` every `every` iterations
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Step {
+    pub start: f64,
+    pub factor: f64,
+    #[schemars(range(min = 1))]
+    pub every: usize,
+}
+
+impl Schedule for Step {
+    fn value(&self, iteration: usize, _iterations: usize) -> f64 {
+        self.start * self.factor.powi((iteration / self.every.max(1)) as i32)
+    }
+}
+
+/// Linear interpolation between `(iteration, value)` breakpoints, held
+/// constant before the first and after the last one
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Piecewise {
+    pub points: Vec<(usize, f64)>,
+}
+
+impl Schedule for Piecewise {
+    fn value(&self, iteration: usize, _iterations: usize) -> f64 {
+        let points = &self.points;
+        if iteration <= points[0].0 {
+            return points[0].1;
+        }
+        for pair in points.windows(2) {
+            let ((i0, v0), (i1, v1)) = (pair[0], pair[1]);
+            if iteration <= i1 {
+                let t = (iteration - i0) as f64 / (i1 - i0) as f64;
+                return v0 + (v1 - v0) * t;
+            }
+        }
+        points[points.len() - 1].1
+    }
+}
+
+/// Schedule as written in the config file, tagged by `type`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ScheduleSpec {
+    Constant(Constant),
+    Linear(Linear),
+    Exponential(Exponential),
+    Cosine(Cosine),
+    Step(Step),
+    Piecewise(Piecewise),
+}
+
+impl Schedule for ScheduleSpec {
+    fn value(&self, iteration: usize, iterations: usize) -> f64 {
+        match self {
+            ScheduleSpec::Constant(s) => s.value(iteration, iterations),
+            ScheduleSpec::Linear(s) => s.value(iteration, iterations),
+            ScheduleSpec::Exponential(s) => s.value(iteration, iterations),
+            ScheduleSpec::Cosine(s) => s.value(iteration, iterations),
+            ScheduleSpec::Step(s) => s.value(iteration, iterations),
+            ScheduleSpec::Piecewise(s) => s.value(iteration, iterations),
+        }
+    }
+}
+
+impl ScheduleSpec {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            ScheduleSpec::Exponential(s) if !(s.start > 0.0 && s.end > 0.0) => {
+                Err("exponential schedule needs positive start and end".to_string())
+            }
+            ScheduleSpec::Step(s) if s.every == 0 => {
+                Err("step schedule needs `every` of at least 1".to_string())
+            }
+            ScheduleSpec::Piecewise(s) if s.points.is_empty() => {
+                Err("piecewise schedule needs at least one point".to_string())
+            }
+            ScheduleSpec::Piecewise(s) if s.points.windows(2).any(|w| w[0].0 >= w[1].0) => {
+                Err("piecewise schedule points must have increasing iterations".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            ScheduleSpec::Constant(s) => s.value.to_string(),
+            ScheduleSpec::Linear(s) => format!("linear {} -> {}", s.start, s.end),
+            ScheduleSpec::Exponential(s) => format!("exponential {} -> {}", s.start, s.end),
+            ScheduleSpec::Cosine(s) => format!("cosine {} -> {}", s.start, s.end),
+            ScheduleSpec::Step(s) => {
+                format!(
+                    "step {} x{} every {} iterations",
+                    s.start, s.factor, s.every
+                )
+            }
+            ScheduleSpec::Piecewise(s) => {
+                let points: Vec<String> = s
+                    .points
+                    .iter()
+                    .map(|(i, v)| format!("{}@{}", v, i))
+                    .collect();
+                format!("piecewise {}", points.join(" "))
+            }
+        }
+    }
+}
+
+/// A parameter given either as a plain number or as a schedule object
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum Param {
+    Value(f64),
+    Schedule(ScheduleSpec),
+}
+
+impl Schedule for Param {
+    fn value(&self, iteration: usize, iterations: usize) -> f64 {
+        match self {
+            Param::Value(v) => *v,
+            Param::Schedule(s) => s.value(iteration, iterations),
+        }
+    }
+}
+
+impl Param {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Param::Value(_) => Ok(()),
+            Param::Schedule(s) => s.validate(),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Param::Value(v) => v.to_string(),
+            Param::Schedule(s) => s.describe(),
+        }
+    }
+}
@@ -0,0 +1,83 @@
+// Desc: Selection operators shared by the evolutionary hybrids
+use rand::Rng;
+
+use crate::comparator::{Comparator, Score};
+
+// Truncation selection: indices of the `n` best scores, best first.
+// NaN fitness sorts last, so it only survives when nothing else is left.
+pub fn best_indices(scores: &[Score], n: usize, comparator: &Comparator) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..scores.len()).collect();
+    order.sort_by(|&a, &b| comparator.ordering(scores[a], scores[b]));
+    order.truncate(n);
+    order
+}
+
+// Truncation selection in the comparator's ranking. Under stochastic ranking
+// that is up to one bubble-sort sweep per candidate, comparing neighbours by
+// fitness when both are feasible or with probability pf and by violation
+// otherwise, until a sweep swaps nothing; other comparators sort as
+// `best_indices` and draw nothing from `rng`.
+pub fn ranked_indices(
+    scores: &[Score],
+    n: usize,
+    comparator: &Comparator,
+    rng: &mut impl Rng,
+) -> Vec<usize> {
+    let Comparator::StochasticRanking { pf } = *comparator else {
+        return best_indices(scores, n, comparator);
+    };
+    let mut order: Vec<usize> = (0..scores.len()).collect();
+    for _ in 0..order.len() {
+        let mut swapped = false;
+        for j in 1..order.len() {
+            let (a, b) = (scores[order[j - 1]], scores[order[j]]);
+            let by_fitness = (a.violation == 0.0 && b.violation == 0.0) || rng.gen::<f64>() < pf;
+            let behind = if by_fitness {
+                Comparator::Strict.ordering(a, b).is_gt()
+            } else {
+                a.violation > b.violation
+            };
+            if behind {
+                order.swap(j - 1, j);
+                swapped = true;
+            }
+        }
+        if !swapped {
+            break;
+        }
+    }
+    order.truncate(n);
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    // pf = 0 ranks infeasible points by violation alone, pf = 1 ignores it
+    #[test]
+    fn stochastic_ranking_trades_fitness_against_violation_by_pf() {
+        let scores = [
+            Score {
+                fitness: 1.0,
+                violation: 2.0,
+            },
+            Score {
+                fitness: 3.0,
+                violation: 0.0,
+            },
+            Score {
+                fitness: 2.0,
+                violation: 1.0,
+            },
+        ];
+        let mut rng = StdRng::seed_from_u64(1);
+        let rank = |pf: f64, rng: &mut StdRng| {
+            ranked_indices(&scores, 3, &Comparator::StochasticRanking { pf }, rng)
+        };
+        assert_eq!(rank(0.0, &mut rng), vec![1, 2, 0]);
+        assert_eq!(rank(1.0, &mut rng), vec![0, 2, 1]);
+    }
+}
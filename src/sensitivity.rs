@@ -0,0 +1,141 @@
+// Desc: `sensitivity` experiment: the same configuration started from many
+// seeds, each drawing its own initial swarm (or start point), and how much
+// the final result depends on where a run starts. The final solutions are
+// clustered into the distinct optima reached; the share of starts that find
+// the best of them tells whether one run is enough.
+use std::io::Write;
+
+use crate::cluster;
+use crate::config::Config;
+use crate::experiment::RunRecord;
+use crate::handle::CancelToken;
+use crate::multirun;
+use crate::options::RunOptions;
+use crate::stats;
+
+const CONFIDENCE: f64 = 0.95; // for the runs needed to reach the best optimum
+
+pub fn run(
+    config: &Config,
+    starts: usize,
+    eps: f64,
+    jobs: Option<u64>,
+    output: Option<&str>,
+    token: &CancelToken,
+) -> Result<(), String> {
+    if starts < 2 {
+        return Err("sensitivity needs at least 2 starts".to_string());
+    }
+    if !(eps > 0.0 && eps.is_finite()) {
+        return Err(format!("--eps must be positive, got {}", eps));
+    }
+    let base = config.seed.unwrap_or_else(rand::random);
+    let tasks: Vec<(String, Config)> = (1..=starts)
+        .map(|i| {
+            let mut config = config.clone();
+            config.seed = Some(base.wrapping_add(i as u64 - 1));
+            config.run = i;
+            (format!("Start {}", i), config)
+        })
+        .collect();
+    let (seeds, records): (Vec<u64>, Vec<RunRecord>) = multirun::run_all(
+        &tasks,
+        &RunOptions::default(),
+        multirun::jobs(jobs, config.evaluation_threads()),
+        token,
+        |_, _| {},
+    )
+    .into_iter()
+    .zip(&tasks)
+    .filter_map(|(record, (_, config))| Some((config.seed?, record?)))
+    .unzip();
+    if records.len() < 2 {
+        return Err("fewer than 2 starts completed".to_string());
+    }
+
+    let (clusters, noise) = cluster::of_runs(config, &records, eps, 1);
+    let mut label = vec![0; records.len()];
+    for (k, c) in clusters.iter().enumerate() {
+        for &i in &c.members {
+            label[i] = k + 1;
+        }
+    }
+
+    let mut finals: Vec<f64> = records.iter().map(|r| config.reported(r.best)).collect();
+    finals.sort_by(f64::total_cmp);
+    let reported = |v: f64| config.reported(v);
+    println!(
+        "Starting-point sensitivity of {} ({} dimensions, {} starts, seeds {} to {}):",
+        config.objective_name(),
+        config.dimensions,
+        records.len(),
+        base,
+        base.wrapping_add(starts as u64 - 1)
+    );
+    if token.is_cancelled() {
+        println!("  Cancelled: the analysis covers the completed starts only");
+    }
+    println!(
+        "  Final fitness: best {}, median {}, worst {}",
+        reported(finals[0]),
+        reported(stats::median(&finals)),
+        reported(finals[finals.len() - 1])
+    );
+    println!(
+        "  Interquartile range: {} to {}, std {}",
+        reported(stats::quantile_sorted(&finals, 0.25)),
+        reported(stats::quantile_sorted(&finals, 0.75)),
+        stats::std_dev(&finals)
+    );
+    cluster::print(&records, &clusters, &noise, eps);
+
+    let share = clusters[0].members.len() as f64 / records.len() as f64;
+    println!(
+        "  Starts reaching the best solution: {} of {} ({:.0}%)",
+        clusters[0].members.len(),
+        records.len(),
+        share * 100.0
+    );
+    if clusters.len() == 1 {
+        println!("  Every start ended at the same solution: one run is enough for this problem");
+    } else {
+        let needed = ((1.0 - CONFIDENCE).ln() / (1.0 - share).ln())
+            .ceil()
+            .max(1.0);
+        println!(
+            "  The result depends on the start: {} independent run(s) reach the best \
+             solution with {:.0}% probability",
+            needed,
+            CONFIDENCE * 100.0
+        );
+    }
+
+    if let Some(path) = output {
+        save(path, &seeds, &records, &label)?;
+    }
+    Ok(())
+}
+
+// One row per start: its seed, final fitness, cluster and final position
+fn save(path: &str, seeds: &[u64], records: &[RunRecord], label: &[usize]) -> Result<(), String> {
+    let mut file =
+        std::fs::File::create(path).map_err(|e| format!("cannot create {}: {}", path, e))?;
+    let dimensions = records.first().map_or(0, |r| r.position.len());
+    let columns: Vec<String> = (1..=dimensions).map(|i| format!("x{}", i)).collect();
+    let mut written = writeln!(file, "start,seed,best,cluster,{}", columns.join(","));
+    for (i, ((seed, record), cluster)) in seeds.iter().zip(records).zip(label).enumerate() {
+        let position: Vec<String> = record.position.iter().map(f64::to_string).collect();
+        written = written.and_then(|_| {
+            writeln!(
+                file,
+                "{},{},{},{},{}",
+                i + 1,
+                seed,
+                record.best,
+                cluster,
+                position.join(",")
+            )
+        });
+    }
+    written.map_err(|e| format!("cannot write {}: {}", path, e))
+}
@@ -0,0 +1,151 @@
+// Desc: Nelder-Mead simplex search, reusable by any optimizer that refines a point
+use std::cmp::Ordering;
+
+use crate::comparator::{Comparator, Score};
+
+const REFLECTION: f64 = 1.0;
+const EXPANSION: f64 = 2.0;
+const CONTRACTION: f64 = 0.5;
+const SHRINK: f64 = 0.5;
+
+// What a local search needs from the run it works for
+pub trait Probe {
+    fn comparator(&self) -> &Comparator;
+    // Maps a point into the feasible box (clamping, integer snapping)
+    fn project(&self, x: Vec<f64>) -> Vec<f64>;
+    // Scores a point; `id` tells the evaluations of one iteration apart
+    fn evaluate(&mut self, id: usize, x: &[f64]) -> Score;
+}
+
+// n + 1 vertices in n dimensions, kept best first; every new vertex is
+// projected into the feasible box before it is evaluated
+pub struct Simplex {
+    vertices: Vec<(Vec<f64>, Score)>,
+}
+
+impl Simplex {
+    // The start point plus one vertex per axis, `steps[i]` away along axis i
+    pub fn around(start: &[f64], steps: &[f64], probe: &mut impl Probe) -> Self {
+        let mut points = vec![probe.project(start.to_vec())];
+        for (i, step) in steps.iter().enumerate() {
+            let mut x = start.to_vec();
+            x[i] += step;
+            let mut x = probe.project(x);
+            if x == points[0] {
+                // pinned at a bound: step inwards instead
+                x = start.to_vec();
+                x[i] -= step;
+                x = probe.project(x);
+            }
+            points.push(x);
+        }
+        let vertices = points
+            .into_iter()
+            .enumerate()
+            .map(|(id, x)| {
+                let score = probe.evaluate(id, &x);
+                (x, score)
+            })
+            .collect();
+
+        let mut simplex = Simplex { vertices };
+        simplex.sort(probe.comparator());
+        simplex
+    }
+
+    fn sort(&mut self, comparator: &Comparator) {
+        self.vertices
+            .sort_by(|(_, a), (_, b)| comparator.ordering(*a, *b));
+    }
+
+    // Largest distance of a vertex from the best one along any axis, relative
+    // to `ranges`; the simplex has collapsed when this gets tiny
+    pub fn diameter(&self, ranges: &[f64]) -> f64 {
+        let best = &self.vertices[0].0;
+        self.vertices[1..]
+            .iter()
+            .flat_map(|(x, _)| {
+                x.iter()
+                    .zip(best)
+                    .zip(ranges)
+                    .map(|((a, b), r)| (a - b).abs() / r)
+            })
+            .fold(0.0, f64::max)
+    }
+
+    // One Nelder-Mead iteration: reflect the worst vertex through the centroid
+    // of the others, then expand, contract or shrink the whole simplex.
+    // Returns the number of evaluations it took.
+    pub fn step(&mut self, probe: &mut impl Probe) -> usize {
+        let n = self.vertices.len() - 1;
+        let comparator = probe.comparator().clone();
+        let better = |a: Score, b: Score| comparator.ordering(a, b) == Ordering::Less;
+
+        let mut centroid = vec![0.0; self.vertices[0].0.len()];
+        for (x, _) in &self.vertices[..n] {
+            for (c, xi) in centroid.iter_mut().zip(x) {
+                *c += xi / n as f64;
+            }
+        }
+        // centroid + t * (centroid - worst)
+        let along = |worst: &[f64], t: f64| -> Vec<f64> {
+            centroid
+                .iter()
+                .zip(worst)
+                .map(|(c, w)| c + t * (c - w))
+                .collect()
+        };
+
+        let (best, second_worst, worst) = (
+            self.vertices[0].1,
+            self.vertices[n - 1].1,
+            self.vertices[n].clone(),
+        );
+        let reflected = probe.project(along(&worst.0, REFLECTION));
+        let reflected_score = probe.evaluate(n, &reflected);
+        let mut evaluations = 1;
+
+        let replacement = if better(reflected_score, best) {
+            let expanded = probe.project(along(&worst.0, EXPANSION));
+            let expanded_score = probe.evaluate(n, &expanded);
+            evaluations += 1;
+            if better(expanded_score, reflected_score) {
+                Some((expanded, expanded_score))
+            } else {
+                Some((reflected, reflected_score))
+            }
+        } else if better(reflected_score, second_worst) {
+            Some((reflected, reflected_score))
+        } else {
+            // contract outside when the reflection beat the worst vertex, inside otherwise
+            let (t, bar) = if better(reflected_score, worst.1) {
+                (CONTRACTION, reflected_score)
+            } else {
+                (-CONTRACTION, worst.1)
+            };
+            let contracted = probe.project(along(&worst.0, t));
+            let contracted_score = probe.evaluate(n, &contracted);
+            evaluations += 1;
+            (!better(bar, contracted_score)).then_some((contracted, contracted_score))
+        };
+
+        match replacement {
+            Some(vertex) => self.vertices[n] = vertex,
+            None => {
+                let best_x = self.vertices[0].0.clone();
+                for (id, (x, score)) in self.vertices.iter_mut().enumerate().skip(1) {
+                    let shrunk = best_x
+                        .iter()
+                        .zip(x.iter())
+                        .map(|(b, xi)| b + SHRINK * (xi - b))
+                        .collect();
+                    *x = probe.project(shrunk);
+                    *score = probe.evaluate(id, x);
+                    evaluations += 1;
+                }
+            }
+        }
+        self.sort(&comparator);
+        evaluations
+    }
+}
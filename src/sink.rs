@@ -0,0 +1,177 @@
+// Desc: Log files that survive a failing disk: after the first failed write
+// (disk full, permissions) the output is kept in memory, with one warning, and
+// written out when the sink is dropped, to its own file if that works again or
+// to the temporary directory otherwise. The run goes on either way.
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+pub struct Sink {
+    path: String,
+    file: Option<File>, // None once opening or writing it failed
+    empty: bool,        // the file had no content when opened
+    pending: Vec<u8>,   // output not written to the file
+}
+
+impl Sink {
+    // Replaces the file at `path`
+    pub fn create(path: &str) -> Self {
+        Sink::open(
+            path,
+            OpenOptions::new().create(true).write(true).truncate(true),
+        )
+    }
+
+    // Continues the file at `path`
+    pub fn append(path: &str) -> Self {
+        Sink::open(path, OpenOptions::new().create(true).append(true))
+    }
+
+    fn open(path: &str, options: &OpenOptions) -> Self {
+        let mut sink = Sink {
+            path: path.to_string(),
+            file: None,
+            empty: true,
+            pending: Vec::new(),
+        };
+        match options.open(path) {
+            Ok(file) => {
+                sink.empty = file.metadata().map_or(true, |m| m.len() == 0);
+                sink.file = Some(file);
+            }
+            Err(e) => sink.fail(&e),
+        }
+        sink
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.empty
+    }
+
+    fn fail(&mut self, error: &io::Error) {
+        eprintln!(
+            "Warning: cannot write {} ({}); keeping its output in memory until the run ends",
+            self.path, error
+        );
+        self.file = None;
+    }
+
+    // Where the buffered output goes when the file still cannot take it
+    fn fallback(&self) -> PathBuf {
+        let name = std::path::Path::new(&self.path)
+            .file_name()
+            .map_or_else(|| "output".into(), |n| n.to_os_string());
+        std::env::temp_dir().join(name)
+    }
+}
+
+impl Write for Sink {
+    // Never fails: what the file does not take is buffered
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut written = 0;
+        if let Some(file) = self.file.as_mut() {
+            while written < buf.len() {
+                match file.write(&buf[written..]) {
+                    Ok(0) => {
+                        self.fail(&io::ErrorKind::WriteZero.into());
+                        break;
+                    }
+                    Ok(n) => written += n,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(e) => {
+                        self.fail(&e);
+                        break;
+                    }
+                }
+            }
+        }
+        self.pending.extend_from_slice(&buf[written..]);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(Err(e)) = self.file.as_mut().map(|file| file.flush()) {
+            self.fail(&e);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Sink {
+    fn drop(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let retried = OpenOptions::new()
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(&self.pending));
+        if retried.is_ok() {
+            eprintln!(
+                "Warning: the output buffered for {} was written to it at the end of the run",
+                self.path
+            );
+            return;
+        }
+        let fallback = self.fallback();
+        match std::fs::write(&fallback, &self.pending) {
+            Ok(()) => eprintln!(
+                "Warning: {} is incomplete; its output from the failure on is in {}",
+                self.path,
+                fallback.display()
+            ),
+            Err(e) => eprintln!(
+                "Warning: lost the buffered output of {}: cannot write {} either ({})",
+                self.path,
+                fallback.display(),
+                e
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_of_a_failed_write_reaches_the_file_at_drop() {
+        let path = std::env::temp_dir().join("pso_sink_recovered.csv");
+        let path = path.to_str().unwrap();
+        let mut sink = Sink::create(path);
+        writeln!(sink, "first").unwrap();
+        // a handle opened for reading fails every write, as a full disk would
+        sink.file = Some(File::open(path).unwrap());
+        writeln!(sink, "second").unwrap();
+        assert!(sink.file.is_none());
+        writeln!(sink, "third").unwrap();
+        assert_eq!(sink.pending, b"second\nthird\n");
+        drop(sink);
+        assert_eq!(
+            std::fs::read_to_string(path).unwrap(),
+            "first\nsecond\nthird\n"
+        );
+    }
+
+    #[test]
+    fn an_unwritable_path_falls_back_to_the_temporary_directory() {
+        // a directory cannot be opened as a file, neither at the start nor at drop
+        let blocked = std::env::temp_dir().join("pso_sink_blocked");
+        let path = blocked.join("pso_sink_fallback.csv");
+        std::fs::create_dir_all(&path).unwrap();
+        let fallback = std::env::temp_dir().join("pso_sink_fallback.csv");
+        let _ = std::fs::remove_file(&fallback);
+
+        let mut sink = Sink::create(path.to_str().unwrap());
+        assert!(sink.file.is_none());
+        writeln!(sink, "kept").unwrap();
+        sink.flush().unwrap();
+        assert_eq!(sink.fallback(), fallback);
+        drop(sink);
+        assert_eq!(std::fs::read_to_string(&fallback).unwrap(), "kept\n");
+    }
+}
@@ -0,0 +1,238 @@
+// Desc: Summary statistics over the results of several runs
+use rand::Rng;
+
+use crate::config::Objective;
+
+pub fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+// Sample standard deviation (n - 1 in the denominator)
+pub fn std_dev(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let m = mean(values);
+    let var = values.iter().map(|v| (v - m).powi(2)).sum::<f64>() / (values.len() - 1) as f64;
+    var.sqrt()
+}
+
+// Quantile function of the standard normal distribution (Acklam's rational
+// approximation, relative error below 1.2e-9)
+pub fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e+01,
+        2.209460984245205e+02,
+        -2.759285104469687e+02,
+        1.38357751867269e+02,
+        -3.066479806614716e+01,
+        2.506628277459239e+00,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e+01,
+        1.615858368580409e+02,
+        -1.556989798598866e+02,
+        6.680131188771972e+01,
+        -1.328068155288572e+01,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-03,
+        -3.223964580411365e-01,
+        -2.400758277161838e+00,
+        -2.549732539343734e+00,
+        4.374664141464968e+00,
+        2.938163982698783e+00,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-03,
+        3.224671290700398e-01,
+        2.445134137142996e+00,
+        3.754408661907416e+00,
+    ];
+    const P_LOW: f64 = 0.02425;
+
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+
+    if p < P_LOW {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p <= 1.0 - P_LOW {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    } else {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    }
+}
+
+// Cohen's d between two samples, using the pooled standard deviation
+pub fn cohens_d(a: &[f64], b: &[f64]) -> f64 {
+    let (na, nb) = (a.len() as f64, b.len() as f64);
+    let pooled = (((na - 1.0) * std_dev(a).powi(2) + (nb - 1.0) * std_dev(b).powi(2))
+        / (na + nb - 2.0))
+        .sqrt();
+    (mean(a) - mean(b)).abs() / pooled
+}
+
+// Runs per configuration needed by a two-sided two-sample t-test to detect a
+// standardized effect size `d` with the given significance level and power.
+// Normal approximation plus the usual z^2/4 small-sample correction.
+pub fn required_runs(d: f64, alpha: f64, power: f64) -> usize {
+    let z_alpha = normal_quantile(1.0 - alpha / 2.0);
+    let z_beta = normal_quantile(power);
+    let n = 2.0 * ((z_alpha + z_beta) / d).powi(2) + z_alpha.powi(2) / 4.0;
+    (n.ceil() as usize).max(2)
+}
+
+// Quantile with linear interpolation between order statistics; `sorted` must be ascending
+pub fn quantile_sorted(sorted: &[f64], q: f64) -> f64 {
+    let pos = q.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let (lo, hi) = (pos.floor() as usize, pos.ceil() as usize);
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (pos - lo as f64)
+}
+
+pub fn median(values: &[f64]) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    quantile_sorted(&sorted, 0.5)
+}
+
+// Percentile bootstrap confidence interval of `statistic`
+pub fn bootstrap_ci(
+    values: &[f64],
+    statistic: fn(&[f64]) -> f64,
+    resamples: usize,
+    confidence: f64,
+    rng: &mut impl Rng,
+) -> (f64, f64) {
+    let mut sample = vec![0.0; values.len()];
+    let mut estimates: Vec<f64> = (0..resamples)
+        .map(|_| {
+            for s in sample.iter_mut() {
+                *s = values[rng.gen_range(0..values.len())];
+            }
+            statistic(&sample)
+        })
+        .collect();
+    estimates.sort_by(f64::total_cmp);
+
+    let tail = (1.0 - confidence) / 2.0;
+    (
+        quantile_sorted(&estimates, tail),
+        quantile_sorted(&estimates, 1.0 - tail),
+    )
+}
+
+pub struct Summary {
+    pub runs: usize,
+    pub mean: f64,
+    pub median: f64,
+    pub std: f64,
+    pub best: f64,
+    pub worst: f64,
+    pub mean_ci: (f64, f64),
+    pub median_ci: (f64, f64),
+    pub confidence: f64,
+}
+
+impl Summary {
+    pub fn of(values: &[f64], resamples: usize, confidence: f64, rng: &mut impl Rng) -> Self {
+        Summary {
+            runs: values.len(),
+            mean: mean(values),
+            median: median(values),
+            std: std_dev(values),
+            best: values.iter().copied().fold(f64::INFINITY, f64::min),
+            worst: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            mean_ci: bootstrap_ci(values, mean, resamples, confidence, rng),
+            median_ci: bootstrap_ci(values, median, resamples, confidence, rng),
+            confidence,
+        }
+    }
+
+    // Of the final values of a maximized objective, whose best is the largest
+    pub fn maximized(mut self) -> Self {
+        std::mem::swap(&mut self.best, &mut self.worst);
+        self
+    }
+
+    // Best and worst as the objective's direction ranks them
+    pub fn toward(self, objective: Objective) -> Self {
+        match objective {
+            Objective::Minimize => self,
+            Objective::Maximize => self.maximized(),
+        }
+    }
+
+    pub fn print(&self) {
+        let level = self.confidence * 100.0;
+        println!("  Runs: {}", self.runs);
+        println!(
+            "  Mean: {} ({}% CI [{}, {}])",
+            self.mean, level, self.mean_ci.0, self.mean_ci.1
+        );
+        println!(
+            "  Median: {} ({}% CI [{}, {}])",
+            self.median, level, self.median_ci.0, self.median_ci.1
+        );
+        println!("  Std: {}", self.std);
+        println!("  Best: {}, worst: {}", self.best, self.worst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    // a fixed, skewed sample of `n` values
+    fn sample(n: usize) -> Vec<f64> {
+        (0..n)
+            .map(|i| ((i * 37) % 101) as f64 / 10.0)
+            .map(|v| v * v)
+            .collect()
+    }
+
+    #[test]
+    fn bootstrap_intervals_cover_the_estimate_and_narrow() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(7);
+        let width = |ci: (f64, f64)| ci.1 - ci.0;
+        let small = Summary::of(&sample(25), 2000, 0.95, &mut rng);
+        let large = Summary::of(&sample(400), 2000, 0.95, &mut rng);
+        for s in [&small, &large] {
+            assert!(s.mean_ci.0 <= s.mean && s.mean <= s.mean_ci.1);
+            assert!(s.median_ci.0 <= s.median && s.median <= s.median_ci.1);
+        }
+        // more data, a tighter interval
+        assert!(width(large.mean_ci) < width(small.mean_ci) / 2.0);
+        assert!(width(large.median_ci) < width(small.median_ci));
+
+        // more resamples, less disagreement between the intervals of two streams
+        let values = sample(25);
+        let mut spread = |resamples: usize| -> f64 {
+            (0..10)
+                .map(|_| {
+                    let a = bootstrap_ci(&values, mean, resamples, 0.95, &mut rng);
+                    let b = bootstrap_ci(&values, mean, resamples, 0.95, &mut rng);
+                    (a.0 - b.0).abs() + (a.1 - b.1).abs()
+                })
+                .sum()
+        };
+        let few = spread(20);
+        let many = spread(2000);
+        assert!(many < few / 4.0, "{} vs {}", many, few);
+    }
+
+    #[test]
+    fn maximized_summaries_rank_the_largest_value_best() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(1);
+        let values = [1.0, 5.0, 9.0];
+        let s = Summary::of(&values, 100, 0.95, &mut rng).toward(Objective::Maximize);
+        assert_eq!((s.best, s.worst), (9.0, 1.0));
+        let s = Summary::of(&values, 100, 0.95, &mut rng).toward(Objective::Minimize);
+        assert_eq!((s.best, s.worst), (1.0, 9.0));
+    }
+}
@@ -0,0 +1,199 @@
+// Desc: Early stop and restart conditions checked between iterations
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::comparator::Score;
+use crate::config::{Config, StopAction};
+use crate::diagnostics;
+use crate::pso::Swarm;
+
+// A stop condition that has been met
+pub struct Trigger {
+    pub condition: &'static str,
+    pub action: StopAction,
+    pub detail: String,
+}
+
+// Mean over particles of the velocity's Euclidean norm, objective dimensions only
+pub fn mean_speed(swarm: &Swarm, dimensions: usize) -> f64 {
+    diagnostics::velocity_norms(&swarm.particles, dimensions).0
+}
+
+// Tracks how long each condition has held; conditions are combined with "any"
+pub struct StopMonitor {
+    started: Instant,
+    collapsed_for: usize,
+    reference: Option<f64>, // best fitness at the last improvement that counted
+    stagnant_for: usize,
+}
+
+// What a checkpoint keeps of the monitor: the counters and the time spent
+#[derive(Default, Serialize, Deserialize)]
+pub struct StopState {
+    seconds: f64,
+    collapsed_for: usize,
+    reference: Option<f64>,
+    stagnant_for: usize,
+}
+
+// The wall clock starts when the monitor is created, along with the run
+impl Default for StopMonitor {
+    fn default() -> Self {
+        StopMonitor {
+            started: Instant::now(),
+            collapsed_for: 0,
+            reference: None,
+            stagnant_for: 0,
+        }
+    }
+}
+
+impl StopMonitor {
+    // Wall-clock seconds since the run started
+    pub fn elapsed(&self) -> f64 {
+        self.started.elapsed().as_secs_f64()
+    }
+
+    pub fn state(&self) -> StopState {
+        StopState {
+            seconds: self.elapsed(),
+            collapsed_for: self.collapsed_for,
+            reference: self.reference,
+            stagnant_for: self.stagnant_for,
+        }
+    }
+
+    // A resumed run's clock goes on from the time the checkpoint was taken
+    pub fn resume(state: &StopState) -> Self {
+        let now = Instant::now();
+        StopMonitor {
+            started: now
+                .checked_sub(Duration::from_secs_f64(state.seconds))
+                .unwrap_or(now),
+            collapsed_for: state.collapsed_for,
+            reference: state.reference,
+            stagnant_for: state.stagnant_for,
+        }
+    }
+
+    // The swarm's conditions and the ones every algorithm has; `spent` is
+    // the cost of the swarm's objective evaluations so far
+    pub fn check(&mut self, config: &Config, swarm: &Swarm, spent: f64) -> Option<Trigger> {
+        let conditions = &config.stop;
+
+        if let Some(budget) = conditions.cost_budget {
+            let cost = config.cost_of(spent, swarm.low_evaluations);
+            if cost >= budget {
+                return Some(Trigger {
+                    condition: "cost_budget",
+                    action: StopAction::Stop,
+                    detail: format!("spent {} of budget {}", cost, budget),
+                });
+            }
+        }
+
+        if let Some(collapse) = &conditions.velocity_collapse {
+            let speed = mean_speed(swarm, config.dimensions);
+            if speed < collapse.threshold {
+                self.collapsed_for += 1;
+            } else {
+                self.collapsed_for = 0;
+            }
+            if self.collapsed_for >= collapse.iterations {
+                self.collapsed_for = 0;
+                return Some(Trigger {
+                    condition: "velocity_collapse",
+                    action: collapse.action,
+                    detail: format!(
+                        "mean speed {:e} below {:e} for {} iterations",
+                        speed, collapse.threshold, collapse.iterations
+                    ),
+                });
+            }
+        }
+
+        let best = Score {
+            fitness: swarm.gbest,
+            violation: swarm.gbest_violation,
+        };
+        self.progress(config, best, swarm.evaluations)
+    }
+
+    // Conditions on the best-so-far, the evaluations and the elapsed time,
+    // which apply to any algorithm; they only stop the run
+    pub fn progress(
+        &mut self,
+        config: &Config,
+        best: Score,
+        evaluations: usize,
+    ) -> Option<Trigger> {
+        let conditions = &config.stop;
+        let stop = |condition, detail| {
+            Some(Trigger {
+                condition,
+                action: StopAction::Stop,
+                detail,
+            })
+        };
+
+        if let Some(failure) = config.objective_failure() {
+            return stop("objective_failed", failure);
+        }
+
+        if let Some(target) = &conditions.target {
+            let fitness = config.reported(best.fitness);
+            let shortfall = config.objective.shortfall(fitness, target.fitness);
+            if best.violation <= 0.0 && shortfall <= target.tolerance {
+                return stop(
+                    "target",
+                    format!(
+                        "fitness {} within {} of target {}",
+                        fitness, target.tolerance, target.fitness
+                    ),
+                );
+            }
+        }
+
+        if let Some(limit) = conditions.max_evaluations {
+            if evaluations >= limit {
+                return stop(
+                    "max_evaluations",
+                    format!("spent {} of {} evaluations", evaluations, limit),
+                );
+            }
+        }
+
+        if let Some(limit) = conditions.max_seconds {
+            let elapsed = self.elapsed();
+            if elapsed >= limit {
+                return stop(
+                    "max_seconds",
+                    format!("ran {:.3} s of {} s", elapsed, limit),
+                );
+            }
+        }
+
+        if let Some(stagnation) = &conditions.stagnation {
+            match self.reference {
+                Some(reference) if reference - best.fitness <= stagnation.min_improvement => {
+                    self.stagnant_for += 1;
+                }
+                _ => {
+                    self.reference = Some(best.fitness);
+                    self.stagnant_for = 0;
+                }
+            }
+            if self.stagnant_for >= stagnation.iterations {
+                return stop(
+                    "stagnation",
+                    format!(
+                        "best fitness {} not improved by more than {} for {} iterations",
+                        best.fitness, stagnation.min_improvement, stagnation.iterations
+                    ),
+                );
+            }
+        }
+        None
+    }
+}
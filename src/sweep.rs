@@ -0,0 +1,399 @@
+// Desc: Parameter sweeps (`sweep`): every combination of the given c1, c2,
+// inertia and swarm size values, each run several times in parallel, ranked
+// by mean final fitness and then by the evaluations needed to reach the target
+//
+//   let base = PsoBuilder::new().seed(1).target_fitness(0.0, 1e-6).build()?;
+//   let ranked = Sweep::new(base, 10).c1(&[1.0, 1.5, 2.0]).inertia(&[0.5, 0.7])
+//       .run(|x| x.iter().map(|v| v * v).sum())?;
+//   println!("{:?}", ranked[0]);
+use std::io::Write;
+use std::sync::Arc;
+
+use crate::api::PsoConfig;
+use crate::config::Config;
+use crate::experiment::{ExperimentSummary, RunRecord};
+use crate::functions::ObjectiveFunction;
+use crate::handle::CancelToken;
+use crate::multirun;
+use crate::options::RunOptions;
+use crate::schedule::{Param, Schedule};
+
+// Values to try per parameter; an empty list keeps the base config's value
+#[derive(Clone, Debug, Default)]
+pub struct SweepGrid {
+    pub c1: Vec<f64>,
+    pub c2: Vec<f64>,
+    pub w: Vec<f64>,
+    pub particles: Vec<usize>,
+}
+
+// One combination of the sweep and the statistics of its runs
+#[derive(Clone, Debug)]
+pub struct SweepRow {
+    pub c1: f64,
+    pub c2: f64,
+    pub w: f64,
+    pub particles: usize,
+    pub summary: ExperimentSummary,
+    pub mean_evaluations_to_target: Option<f64>, // over the runs that reached the target
+}
+
+// The base config with every combination of the grid's values, in grid order
+fn combinations(base: &Config, grid: &SweepGrid) -> Result<Vec<Config>, String> {
+    if !grid.w.is_empty() && base.inertia_schedule.is_some() {
+        return Err("the config's inertia_schedule replaces w, which cannot be swept".to_string());
+    }
+    let or_base = |values: &[f64], base: &Param| match values {
+        [] => vec![base.value(0, 1)],
+        _ => values.to_vec(),
+    };
+    let particles = match &grid.particles[..] {
+        [] => vec![base.particles],
+        values => values.to_vec(),
+    };
+    let mut configs = Vec::new();
+    for &c1 in &or_base(&grid.c1, &base.c1) {
+        for &c2 in &or_base(&grid.c2, &base.c2) {
+            for &w in &or_base(&grid.w, &base.w) {
+                for &n in &particles {
+                    let mut config = base.clone();
+                    if !grid.c1.is_empty() {
+                        config.c1 = Param::Value(c1);
+                    }
+                    if !grid.c2.is_empty() {
+                        config.c2 = Param::Value(c2);
+                    }
+                    if !grid.w.is_empty() {
+                        config.w = Param::Value(w);
+                    }
+                    config.particles = n;
+                    config.validate().map_err(|e| {
+                        format!("c1 {}, c2 {}, w {}, {} particles: {}", c1, c2, w, n, e)
+                    })?;
+                    configs.push(config);
+                }
+            }
+        }
+    }
+    Ok(configs)
+}
+
+// Evaluations after which the run's best-so-far first met the config's target
+fn evaluations_to_target(config: &Config, record: &RunRecord) -> Option<usize> {
+    let target = config.stop.target.as_ref()?;
+    record
+        .trace
+        .iter()
+        .find(|&&(_, best)| config.objective.shortfall(best, target.fitness) <= target.tolerance)
+        .map(|&(evaluations, _)| evaluations)
+}
+
+// Runs every combination `runs` times on `jobs` threads and returns the rows,
+// best first. A seeded base gives every combination the same run seeds, so
+// they are compared on the same random draws. Runs cancelled by `token` are
+// left out; combinations without a completed run are dropped.
+pub fn run_grid(
+    base: &Config,
+    grid: &SweepGrid,
+    runs: usize,
+    jobs: usize,
+    token: &CancelToken,
+) -> Result<Vec<SweepRow>, String> {
+    if runs == 0 {
+        return Err("a sweep needs at least 1 run per combination".to_string());
+    }
+    let configs = combinations(base, grid)?;
+    let tasks: Vec<(String, Config)> = configs
+        .iter()
+        .flat_map(|config| {
+            (1..=runs).map(move |i| {
+                let mut config = config.clone();
+                config.seed = config.seed.map(|seed| seed.wrapping_add(i as u64 - 1));
+                config.run = i;
+                let label = format!(
+                    "c1 {}, c2 {}, w {}, {} particles, run {}",
+                    config.c1.describe(),
+                    config.c2.describe(),
+                    config.w.describe(),
+                    config.particles,
+                    i
+                );
+                (label, config)
+            })
+        })
+        .collect();
+    let records = multirun::run_all(&tasks, &RunOptions::default(), jobs, token, |_, _| {});
+
+    let mut rows: Vec<SweepRow> = configs
+        .iter()
+        .zip(records.chunks(runs))
+        .filter_map(|(config, records)| {
+            let records: Vec<RunRecord> = records.iter().flatten().cloned().collect();
+            if records.is_empty() {
+                return None;
+            }
+            let hits: Vec<f64> = records
+                .iter()
+                .filter_map(|r| evaluations_to_target(config, r))
+                .map(|e| e as f64)
+                .collect();
+            Some(SweepRow {
+                c1: config.c1.value(0, 1),
+                c2: config.c2.value(0, 1),
+                w: config.w.value(0, 1),
+                particles: config.particles,
+                summary: ExperimentSummary::of(&records, config),
+                mean_evaluations_to_target: (!hits.is_empty())
+                    .then(|| hits.iter().sum::<f64>() / hits.len() as f64),
+            })
+        })
+        .collect();
+    let sign = base.objective.sign();
+    rows.sort_by(|a, b| {
+        (sign * a.summary.mean)
+            .total_cmp(&(sign * b.summary.mean))
+            .then_with(|| {
+                let to_target =
+                    |r: &SweepRow| r.mean_evaluations_to_target.unwrap_or(f64::INFINITY);
+                to_target(a).total_cmp(&to_target(b))
+            })
+    });
+    Ok(rows)
+}
+
+// A list of values (`1,1.5,2`) or an inclusive range with a step (`0.5:2:0.5`)
+pub fn parse_values(s: &str) -> Result<Vec<f64>, String> {
+    let number = |v: &str| {
+        v.trim()
+            .parse::<f64>()
+            .map_err(|_| format!("invalid number '{}' in '{}'", v, s))
+    };
+    let parts: Vec<&str> = s.split(':').collect();
+    match parts[..] {
+        [start, end, step] => {
+            let (start, end, step) = (number(start)?, number(end)?, number(step)?);
+            if step.partial_cmp(&0.0) != Some(std::cmp::Ordering::Greater) || end < start {
+                return Err(format!("'{}' needs START <= END and a positive STEP", s));
+            }
+            // the tolerance keeps END when the steps do not land on it exactly
+            let steps = ((end - start) / step + 1e-9).floor() as usize;
+            Ok((0..=steps).map(|k| start + k as f64 * step).collect())
+        }
+        [_] => s.split(',').map(number).collect(),
+        _ => Err(format!("'{}' is neither a list nor START:END:STEP", s)),
+    }
+}
+
+// Swarm sizes, in the same forms as `parse_values`
+pub fn parse_counts(s: &str) -> Result<Vec<usize>, String> {
+    parse_values(s)?
+        .into_iter()
+        .map(|v| {
+            if v >= 1.0 && v.fract() == 0.0 {
+                Ok(v as usize)
+            } else {
+                Err(format!(
+                    "particles must be whole numbers of at least 1, not {}",
+                    v
+                ))
+            }
+        })
+        .collect()
+}
+
+fn print(rows: &[SweepRow]) {
+    println!(
+        "{:>4} {:>8} {:>8} {:>8} {:>9} {:>14} {:>14} {:>8} {:>15}",
+        "rank", "c1", "c2", "w", "particles", "mean", "median", "success", "evals_to_target"
+    );
+    for (rank, row) in rows.iter().enumerate() {
+        let success = row
+            .summary
+            .success_rate
+            .map_or("-".to_string(), |r| format!("{:.0}%", r * 100.0));
+        let to_target = row
+            .mean_evaluations_to_target
+            .map_or("-".to_string(), |e| format!("{:.0}", e));
+        println!(
+            "{:>4} {:>8} {:>8} {:>8} {:>9} {:>14.6e} {:>14.6e} {:>8} {:>15}",
+            rank + 1,
+            row.c1,
+            row.c2,
+            row.w,
+            row.particles,
+            row.summary.mean,
+            row.summary.median,
+            success,
+            to_target
+        );
+    }
+}
+
+fn save(path: &str, rows: &[SweepRow]) -> Result<(), String> {
+    let mut file =
+        std::fs::File::create(path).map_err(|e| format!("cannot create {}: {}", path, e))?;
+    let mut written = writeln!(
+        file,
+        "rank,c1,c2,w,particles,runs,mean,median,std,best,worst,success_rate,\
+         mean_evaluations_to_target,mean_evaluations"
+    );
+    for (rank, row) in rows.iter().enumerate() {
+        let s = &row.summary;
+        let optional = |v: Option<f64>| v.map_or(String::new(), |v| v.to_string());
+        written = written.and_then(|_| {
+            writeln!(
+                file,
+                "{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                rank + 1,
+                row.c1,
+                row.c2,
+                row.w,
+                row.particles,
+                s.runs,
+                s.mean,
+                s.median,
+                s.std,
+                s.best,
+                s.worst,
+                optional(s.success_rate),
+                optional(row.mean_evaluations_to_target),
+                s.mean_evaluations
+            )
+        });
+    }
+    written.map_err(|e| format!("cannot write {}: {}", path, e))
+}
+
+// `sweep`: runs the grid over `config` until `token` is cancelled, prints the
+// ranking and writes it to `output`
+pub fn run(
+    config: &Config,
+    grid: &SweepGrid,
+    runs: usize,
+    jobs: Option<u64>,
+    output: Option<&str>,
+    token: &CancelToken,
+) -> Result<(), String> {
+    let jobs = multirun::jobs(jobs, config.evaluation_threads());
+    let combinations = combinations(config, grid)?.len();
+    println!(
+        "Sweeping {} combination(s) x {} run(s) on {} thread(s)",
+        combinations,
+        runs,
+        jobs.min(combinations * runs)
+    );
+    let rows = run_grid(config, grid, runs, jobs, token)?;
+    if token.is_cancelled() {
+        println!("Cancelled: the ranking covers the completed runs only");
+    }
+    println!();
+    print(&rows);
+    if let Some(path) = output {
+        save(path, &rows)?;
+    }
+    Ok(())
+}
+
+// Library form of `sweep` over a `PsoConfig`, without console or file output
+pub struct Sweep {
+    config: PsoConfig,
+    runs: usize,
+    grid: SweepGrid,
+    jobs: Option<usize>,
+}
+
+impl Sweep {
+    pub fn new(config: PsoConfig, runs: usize) -> Self {
+        Sweep {
+            config,
+            runs,
+            grid: SweepGrid::default(),
+            jobs: None,
+        }
+    }
+
+    pub fn c1(mut self, values: &[f64]) -> Self {
+        self.grid.c1 = values.to_vec();
+        self
+    }
+
+    pub fn c2(mut self, values: &[f64]) -> Self {
+        self.grid.c2 = values.to_vec();
+        self
+    }
+
+    pub fn inertia(mut self, values: &[f64]) -> Self {
+        self.grid.w = values.to_vec();
+        self
+    }
+
+    pub fn particles(mut self, values: &[usize]) -> Self {
+        self.grid.particles = values.to_vec();
+        self
+    }
+
+    // Worker threads; one per CPU core by default
+    pub fn jobs(mut self, jobs: usize) -> Self {
+        self.jobs = Some(jobs);
+        self
+    }
+
+    // Runs every combination on `objective` and returns the rows, best first
+    pub fn run(
+        &self,
+        objective: impl ObjectiveFunction + 'static,
+    ) -> Result<Vec<SweepRow>, String> {
+        let mut config = self.config.to_config();
+        config.prepare();
+        config.objective_fn = Some(Arc::new(objective));
+        config.quiet = true;
+
+        let jobs = self
+            .jobs
+            .unwrap_or_else(|| multirun::jobs(None, config.evaluation_threads()))
+            .max(1);
+        run_grid(
+            &config,
+            &self.grid,
+            self.runs,
+            jobs,
+            &CancelToken::default(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::PsoBuilder;
+
+    #[test]
+    fn sweeps_rank_every_combination_by_mean_fitness() {
+        assert_eq!(parse_values("0.5:2:0.5"), Ok(vec![0.5, 1.0, 1.5, 2.0]));
+        assert_eq!(parse_values("1, 1.5"), Ok(vec![1.0, 1.5]));
+        assert!(parse_counts("10,2.5").is_err());
+
+        let base = PsoBuilder::new()
+            .dimensions(3)
+            .iterations(60)
+            .seed(2)
+            .target_fitness(0.0, 1e-2)
+            .build()
+            .unwrap();
+        let rows = Sweep::new(base, 3)
+            .c1(&[0.5, 1.5])
+            .particles(&[5, 20])
+            .jobs(2)
+            .run(|x: &[f64]| x.iter().map(|v| v * v).sum())
+            .unwrap();
+
+        assert_eq!(rows.len(), 4);
+        assert!(rows
+            .windows(2)
+            .all(|w| w[0].summary.mean <= w[1].summary.mean));
+        assert!(rows.iter().all(|r| r.summary.runs == 3));
+        let mut grid: Vec<(f64, usize)> = rows.iter().map(|r| (r.c1, r.particles)).collect();
+        grid.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(grid, vec![(0.5, 5), (0.5, 20), (1.5, 5), (1.5, 20)]);
+    }
+}
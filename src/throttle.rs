@@ -0,0 +1,48 @@
+// Desc: Rate limit on objective evaluations, for objectives behind a throttled remote service
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+pub struct Throttle {
+    interval: Duration,
+    next: Mutex<Instant>, // earliest time the next evaluation may start
+}
+
+// Throttles handed out by `shared`, one per rate
+static SHARED: Mutex<Vec<(u64, Arc<Throttle>)>> = Mutex::new(Vec::new());
+
+impl Throttle {
+    pub fn new(per_second: f64) -> Self {
+        Throttle {
+            interval: Duration::from_secs_f64(1.0 / per_second),
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    // The throttle of `per_second` that every run of the process shares, so
+    // parallel runs and threads stay within the limit together
+    pub fn shared(per_second: f64) -> Arc<Throttle> {
+        let mut shared = SHARED.lock().unwrap_or_else(|e| e.into_inner());
+        let key = per_second.to_bits();
+        if let Some((_, throttle)) = shared.iter().find(|(k, _)| *k == key) {
+            return throttle.clone();
+        }
+        let throttle = Arc::new(Throttle::new(per_second));
+        shared.push((key, throttle.clone()));
+        throttle
+    }
+
+    // Sleeps until the next evaluation slot. Slots are spaced evenly, without
+    // bursts after idle periods; each caller takes its own.
+    pub fn wait(&self) {
+        let slot = {
+            let mut next = self.next.lock().unwrap_or_else(|e| e.into_inner());
+            let slot = (*next).max(Instant::now());
+            *next = slot + self.interval;
+            slot
+        };
+        let now = Instant::now();
+        if slot > now {
+            std::thread::sleep(slot - now);
+        }
+    }
+}
@@ -0,0 +1,163 @@
+// Desc: Swarm topologies: which personal bests guide each particle's social term
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::comparator::Comparator;
+use crate::pso::Particle;
+
+/// Neighbourhood whose best personal best each particle follows in the social term
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Topology {
+    /// Every particle follows the global best (gbest PSO)
+    #[default]
+    Global,
+    /// Fixed ring: the `k` particles on each side by index, plus itself
+    Ring {
+        #[serde(default = "default_ring_k")]
+        k: usize,
+    },
+    /// Fixed torus grid, as square as the swarm size allows: the particles
+    /// above, below, left and right, plus itself
+    VonNeumann,
+    /// Adaptive random (SPSO-2011): every particle informs itself and `k`
+    /// random others; the links are redrawn after any iteration that did not
+    /// improve the global best
+    Random {
+        #[serde(default = "default_random_k")]
+        k: usize,
+    },
+}
+
+fn default_ring_k() -> usize {
+    1
+}
+
+fn default_random_k() -> usize {
+    3
+}
+
+impl Topology {
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            Topology::Ring { k: 0 } => Err("ring topology needs k of at least 1".to_string()),
+            Topology::Random { k: 0 } => Err("random topology needs k of at least 1".to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Topology::Global => "global best".to_string(),
+            Topology::Ring { k } => format!("ring, {} neighbours on each side", k),
+            Topology::VonNeumann => "von Neumann grid".to_string(),
+            Topology::Random { k } => format!("adaptive random, {} informants each", k),
+        }
+    }
+}
+
+// For every particle, the particles whose personal bests it sees (itself included)
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Neighbourhoods {
+    informants: Vec<Vec<usize>>,
+}
+
+impl Neighbourhoods {
+    // None for the global topology, whose single neighbourhood is the gbest
+    pub fn build(topology: &Topology, n: usize, rng: &mut impl Rng) -> Option<Self> {
+        let informants = match *topology {
+            Topology::Global => return None,
+            Topology::Ring { k } => (0..n)
+                .map(|i| {
+                    let mut near: Vec<usize> =
+                        (0..=2 * k).map(|d| (i + n * k + d - k) % n).collect();
+                    near.sort_unstable();
+                    near.dedup();
+                    near
+                })
+                .collect(),
+            Topology::VonNeumann => {
+                let rows = (1..=n)
+                    .take_while(|r| r * r <= n)
+                    .filter(|r| n.is_multiple_of(*r))
+                    .last()
+                    .unwrap_or(1);
+                let cols = n / rows;
+                (0..n)
+                    .map(|i| {
+                        let (r, c) = (i / cols, i % cols);
+                        let mut near = vec![
+                            i,
+                            ((r + rows - 1) % rows) * cols + c,
+                            ((r + 1) % rows) * cols + c,
+                            r * cols + (c + cols - 1) % cols,
+                            r * cols + (c + 1) % cols,
+                        ];
+                        near.sort_unstable();
+                        near.dedup();
+                        near
+                    })
+                    .collect()
+            }
+            Topology::Random { k } => {
+                let mut informants: Vec<Vec<usize>> = (0..n).map(|i| vec![i]).collect();
+                for i in 0..n {
+                    for _ in 0..k {
+                        let j = rng.gen_range(0..n);
+                        if !informants[j].contains(&i) {
+                            informants[j].push(i);
+                        }
+                    }
+                }
+                informants
+            }
+        };
+        Some(Neighbourhoods { informants })
+    }
+
+    // Best personal best among the informants of particle `i`
+    pub fn best<'p>(
+        &self,
+        i: usize,
+        particles: &'p [Particle],
+        comparator: &Comparator,
+    ) -> &'p [f64] {
+        let best = self.informants[i]
+            .iter()
+            .map(|&j| &particles[j])
+            .reduce(|a, b| {
+                if comparator.better(b.pbest_score(), a.pbest_score()) {
+                    b
+                } else {
+                    a
+                }
+            })
+            .expect("a neighbourhood includes its own particle");
+        &best.pbest_x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn fixed_neighbourhoods_are_symmetric_and_include_the_particle() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for (topology, n, size) in [
+            (Topology::Ring { k: 1 }, 10, 3),
+            (Topology::Ring { k: 2 }, 10, 5),
+            (Topology::VonNeumann, 12, 5),
+        ] {
+            let nb = Neighbourhoods::build(&topology, n, &mut rng).unwrap();
+            for (i, near) in nb.informants.iter().enumerate() {
+                assert_eq!(near.len(), size, "{:?} around {}", topology, i);
+                assert!(near.contains(&i));
+                assert!(near.iter().all(|&j| nb.informants[j].contains(&i)));
+            }
+        }
+    }
+}
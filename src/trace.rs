@@ -0,0 +1,80 @@
+// Desc: Debug trace of every objective evaluation, in call order
+use crate::privacy::Privacy;
+use crate::sink::Sink;
+
+pub struct EvalTrace {
+    writer: csv::Writer<Sink>,
+    hashed: bool,
+    privacy: Option<Privacy>,
+}
+
+// FNV-1a over the raw bit patterns, so the hash is stable across platforms
+// and toolchains and only identical points collide
+pub fn hash_point(x: &[f64]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for xi in x {
+        for byte in xi.to_bits().to_le_bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+impl EvalTrace {
+    pub fn create(path: &str, hashed: bool, dimensions: usize, privacy: Option<Privacy>) -> Self {
+        let mut writer = csv::Writer::from_writer(Sink::create(path));
+
+        let mut header = vec![
+            "evaluation".to_string(),
+            "particle".to_string(),
+            "fitness".to_string(),
+        ];
+        if hashed {
+            header.push("point_hash".to_string());
+        } else {
+            header.extend((1..=dimensions).map(|d| format!("x{}", d)));
+        }
+        writer
+            .write_record(&header)
+            .expect("Error writing trace file");
+
+        EvalTrace {
+            writer,
+            hashed,
+            privacy,
+        }
+    }
+
+    pub fn record(&mut self, evaluation: usize, particle: Option<usize>, x: &[f64], fitness: f64) {
+        let noisy;
+        let (x, fitness) = match &self.privacy {
+            Some(privacy) => {
+                noisy = privacy.apply_all(x);
+                (noisy.as_slice(), privacy.apply(fitness))
+            }
+            None => (x, fitness),
+        };
+
+        // {:?} keeps the shortest representation that round-trips exactly
+        let mut record = vec![
+            evaluation.to_string(),
+            particle.map_or(String::new(), |p| p.to_string()),
+            format!("{:?}", fitness),
+        ];
+        if self.hashed {
+            record.push(format!("{:016x}", hash_point(x)));
+        } else {
+            record.extend(x.iter().map(|xi| format!("{:?}", xi)));
+        }
+        self.writer
+            .write_record(&record)
+            .expect("Error writing trace file");
+    }
+}
+
+impl Drop for EvalTrace {
+    fn drop(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
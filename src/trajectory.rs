@@ -0,0 +1,55 @@
+// Desc: Streaming reader of trajectory files (CSV logs, convergence traces,
+// result lists) for the analysis subcommands: the file is read one line at a
+// time through a buffer, so a history of any size takes constant memory
+// beyond what the caller keeps of it.
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+pub struct Trajectory {
+    path: String,
+    reader: BufReader<File>,
+    first: Option<String>, // the first line, read ahead to tell the format
+}
+
+impl Trajectory {
+    pub fn open(path: &str) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        let mut trajectory = Trajectory {
+            path: path.to_string(),
+            reader: BufReader::new(file),
+            first: None,
+        };
+        trajectory.first = trajectory.read_line()?;
+        Ok(trajectory)
+    }
+
+    // The first line, usually a header
+    pub fn header(&self) -> Option<&str> {
+        self.first.as_deref()
+    }
+
+    // Calls `row` with the comma-separated fields of every line, the first
+    // one included, in file order
+    pub fn rows(mut self, mut row: impl FnMut(&[&str])) -> Result<(), String> {
+        let mut line = self.first.take();
+        while let Some(text) = line {
+            let fields: Vec<&str> = text.split(',').collect();
+            row(&fields);
+            line = self.read_line()?;
+        }
+        Ok(())
+    }
+
+    fn read_line(&mut self) -> Result<Option<String>, String> {
+        let mut line = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) => Ok(None),
+            Ok(_) => {
+                let end = line.trim_end_matches(['\n', '\r']).len();
+                line.truncate(end);
+                Ok(Some(line))
+            }
+            Err(e) => Err(format!("cannot read {}: {}", self.path, e)),
+        }
+    }
+}
@@ -0,0 +1,88 @@
+// Desc: Read-only view of a running swarm that other threads can poll
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use crate::pso::Swarm;
+
+// The swarm as it was at the end of one iteration
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub iteration: usize, // iterations completed
+    pub evaluations: usize,
+    pub gbest: f64,
+    pub gbest_x: Vec<f64>,
+    pub positions: Vec<Vec<f64>>,
+    pub finished: bool,
+}
+
+struct Shared {
+    version: AtomicU64, // bumped after every change, to detect updates cheaply
+    latest: RwLock<Arc<Snapshot>>,
+    history: RwLock<Vec<f64>>, // gbest after every iteration
+}
+
+// The optimizer publishes into the view and never waits on readers: a
+// snapshot is built outside the lock and swapped in, and readers only hold
+// the lock long enough to clone an Arc or copy a slice of the history.
+#[derive(Clone)]
+pub struct SwarmView(Arc<Shared>);
+
+impl SwarmView {
+    pub(crate) fn new() -> Self {
+        SwarmView(Arc::new(Shared {
+            version: AtomicU64::new(0),
+            latest: RwLock::new(Arc::new(Snapshot {
+                iteration: 0,
+                evaluations: 0,
+                gbest: f64::INFINITY,
+                gbest_x: Vec::new(),
+                positions: Vec::new(),
+                finished: false,
+            })),
+            history: RwLock::new(Vec::new()),
+        }))
+    }
+
+    // `gbest` is the swarm's, in the objective's own sign
+    pub(crate) fn publish(&self, iteration: usize, gbest: f64, swarm: &Swarm) {
+        let snapshot = Arc::new(Snapshot {
+            iteration,
+            evaluations: swarm.evaluations,
+            gbest,
+            gbest_x: swarm.gbest_x.clone(),
+            positions: swarm.particles.iter().map(|p| p.x.clone()).collect(),
+            finished: false,
+        });
+        self.0.history.write().unwrap().push(gbest);
+        *self.0.latest.write().unwrap() = snapshot;
+        self.0.version.fetch_add(1, Ordering::Release);
+    }
+
+    pub(crate) fn finish(&self) {
+        let mut latest = self.0.latest.write().unwrap();
+        *latest = Arc::new(Snapshot {
+            iteration: latest.iteration,
+            evaluations: latest.evaluations,
+            gbest: latest.gbest,
+            gbest_x: latest.gbest_x.clone(),
+            positions: latest.positions.clone(),
+            finished: true,
+        });
+        drop(latest);
+        self.0.version.fetch_add(1, Ordering::Release);
+    }
+
+    pub fn version(&self) -> u64 {
+        self.0.version.load(Ordering::Acquire)
+    }
+
+    pub fn latest(&self) -> Arc<Snapshot> {
+        Arc::clone(&self.0.latest.read().unwrap())
+    }
+
+    // gbest history entries from `start` on
+    pub fn history_since(&self, start: usize) -> Vec<f64> {
+        let history = self.0.history.read().unwrap();
+        history.get(start..).unwrap_or_default().to_vec()
+    }
+}
@@ -0,0 +1,126 @@
+// Desc: Local web page with a live convergence chart and swarm scatter (server-sent events)
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::view::SwarmView;
+
+const PAGE: &str = include_str!("web.html");
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+#[derive(Serialize)]
+struct Update<'a> {
+    iteration: usize, // index of the first entry in `gbest`
+    gbest: &'a [f64], // new history entries since the previous update
+    completed: usize, // iterations completed
+    evaluations: usize,
+    best_x: &'a [f64],
+    scatter: &'a [(f64, f64)], // first two coordinates of every particle
+    bounds: (f64, f64),
+    finished: bool,
+}
+
+// Serves the live page from a background thread; every client polls `view`
+pub fn serve(port: u16, bounds: (f64, f64), view: SwarmView) -> std::io::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    println!("Live view at http://127.0.0.1:{}/", port);
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let view = view.clone();
+            std::thread::spawn(move || {
+                let _ = handle(stream, view, bounds);
+            });
+        }
+    });
+    Ok(())
+}
+
+fn handle(mut stream: TcpStream, view: SwarmView, bounds: (f64, f64)) -> std::io::Result<()> {
+    let mut request_line = String::new();
+    let mut reader = BufReader::new(stream.try_clone()?);
+    reader.read_line(&mut request_line)?;
+    // drain the headers; nothing in them matters here
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+    match path {
+        "/" => {
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\n\
+                 Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                PAGE.len(),
+                PAGE
+            )
+        }
+        "/events" => stream_events(stream, view, bounds),
+        _ => write!(
+            stream,
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+        ),
+    }
+}
+
+fn stream_events(
+    mut stream: TcpStream,
+    view: SwarmView,
+    bounds: (f64, f64),
+) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\
+         Cache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n"
+    )?;
+
+    let mut sent_version = 0;
+    let mut sent_history = 0;
+    let mut last_write = Instant::now();
+
+    loop {
+        let version = view.version();
+        if version == sent_version {
+            if last_write.elapsed() >= KEEP_ALIVE {
+                write!(stream, ": keep-alive\n\n")?;
+                stream.flush()?;
+                last_write = Instant::now();
+            }
+            std::thread::sleep(POLL_INTERVAL);
+            continue;
+        }
+
+        let snapshot = view.latest();
+        let gbest = view.history_since(sent_history);
+        let scatter: Vec<(f64, f64)> = snapshot
+            .positions
+            .iter()
+            .map(|x| (x[0], x.get(1).copied().unwrap_or(0.0)))
+            .collect();
+        let update = Update {
+            iteration: sent_history,
+            gbest: &gbest,
+            completed: snapshot.iteration,
+            evaluations: snapshot.evaluations,
+            best_x: &snapshot.gbest_x,
+            scatter: &scatter,
+            bounds,
+            finished: snapshot.finished,
+        };
+        let json = serde_json::to_string(&update).expect("Error serializing update");
+        sent_version = version;
+        sent_history += gbest.len();
+
+        write!(stream, "data: {}\n\n", json)?;
+        stream.flush()?;
+        last_write = Instant::now();
+        if snapshot.finished {
+            return Ok(());
+        }
+    }
+}